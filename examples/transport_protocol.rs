@@ -5,7 +5,7 @@ use saelient::{
 
 fn main() {
     // Request to send received from the sender.
-    let rts = RequestToSend::new(128, Some(1), Pgn::ProprietaryA);
+    let rts = RequestToSend::try_new(128, Some(1), Pgn::ProprietaryA).unwrap();
 
     // We then use the RTS to start the transfer.
     let mut transfer = Transfer::new(rts);
@@ -0,0 +1,22 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use saelient::{Id, IdBuilder, PduFormat, Pgn};
+
+fuzz_target!(|id: Id| {
+    assert_eq!(Id::new(id.as_raw()), id);
+
+    let pgn = id.pgn();
+    assert_eq!(Pgn::from(u32::from(pgn)), pgn);
+
+    let mut builder = IdBuilder::new()
+        .priority(id.priority())
+        .pgn(pgn)
+        .sa(id.sa())
+        .dp(id.dp())
+        .edp(id.edp());
+    if let PduFormat::Pdu1(_) = id.pf() {
+        builder = builder.da(id.ps());
+    }
+    assert_eq!(builder.build(), Some(id));
+});
@@ -0,0 +1,24 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use saelient::diagnostic::{MemoryAccessRequest, MemoryAccessResponse};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(request) = MemoryAccessRequest::try_from(data) {
+        let rebuilt = MemoryAccessRequest::try_new(
+            request.command(),
+            request.pointer(),
+            request.length(),
+            request.key_or_user_level(),
+        ).unwrap();
+        assert_eq!(rebuilt.command(), request.command());
+        assert_eq!(rebuilt.pointer(), request.pointer());
+        assert_eq!(rebuilt.length(), request.length());
+        assert_eq!(<[u8; 8]>::from(&request), data);
+    }
+
+    if let Ok(response) = MemoryAccessResponse::try_from(data) {
+        let _ = (response.status(), response.error_indicator(), response.seed());
+        assert_eq!(<[u8; 8]>::from(&response), data);
+    }
+});
@@ -0,0 +1,38 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use saelient::transport::{
+    BroadcastAnnounce, ClearToSend, ConnectionAbort, ControlMessage, DataTransfer,
+    EndOfMessageAck,
+};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(msg) = ControlMessage::try_from(data) {
+        let raw: [u8; 8] = match &msg {
+            ControlMessage::RequestToSend(rts) => rts.clone().into(),
+            ControlMessage::ClearToSend(cts) => cts.into(),
+            ControlMessage::EndOfMessageAck(end) => end.into(),
+            ControlMessage::BroadcastAnnounce(bam) => bam.into(),
+            ControlMessage::ConnectionAbort(abort) => abort.into(),
+        };
+
+        // serializing a parsed message is stable
+        let again: [u8; 8] = match ControlMessage::try_from(&raw[..]).unwrap() {
+            ControlMessage::RequestToSend(rts) => rts.into(),
+            ControlMessage::ClearToSend(cts) => <[u8; 8]>::from(&cts),
+            ControlMessage::EndOfMessageAck(end) => <[u8; 8]>::from(&end),
+            ControlMessage::BroadcastAnnounce(bam) => <[u8; 8]>::from(&bam),
+            ControlMessage::ConnectionAbort(abort) => <[u8; 8]>::from(&abort),
+        };
+        assert_eq!(raw, again);
+    }
+
+    let _ = ClearToSend::try_from(data);
+    let _ = EndOfMessageAck::try_from(data);
+    let _ = BroadcastAnnounce::try_from(data);
+    let _ = ConnectionAbort::try_from(data);
+
+    if let Ok(dt) = DataTransfer::try_from(data) {
+        assert_eq!(<[u8; 8]>::from(&dt), data);
+    }
+});
@@ -0,0 +1,22 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use saelient::transport::{DataTransfer, RequestToSend, Transfer};
+
+fuzz_target!(|input: (RequestToSend, bool, Vec<DataTransfer>)| {
+    let (rts, borrowed, frames) = input;
+    let mut storage = [0; 1785];
+
+    let mut transfer = if borrowed {
+        Transfer::new_with_storage(rts, &mut storage[..])
+    } else {
+        Transfer::new(rts)
+    };
+
+    for dt in frames {
+        if transfer.next(dt).is_err() {
+            break;
+        }
+        let _ = transfer.finished();
+    }
+});
@@ -0,0 +1,60 @@
+//! Compile test of the API without `alloc`
+//!
+//! Everything here has to build for `thumbv6m-none-eabi`, which has no
+//! allocator and no standard library.
+
+#![no_std]
+
+use embedded_storage::nor_flash::NorFlash;
+use saelient::flash::{FlashTarget, Region};
+use saelient::gateway::{Gateway, Mode};
+use saelient::node::Node;
+use saelient::request::Responder;
+use saelient::router::{Filter, Router};
+use saelient::time::Instant;
+use saelient::transport::{FlashStorage, RequestToSend, Transfer, TransportManager};
+use saelient::{Id, Pgn};
+
+/// Reassemble and answer transport sessions into a borrowed buffer.
+pub fn node(storage: &mut [u8], id: Id, data: &[u8], now: Instant) -> usize {
+    let mut node = Node::new(0x80, storage);
+    node.poll(Some((id, data)), now).count()
+}
+
+/// Concurrent transport sessions over a pool of borrowed buffers.
+pub fn manager(pool: [&mut [u8]; 2], id: Id, data: &[u8; 8], now: Instant) -> bool {
+    let mut manager = TransportManager::new(0x80, pool);
+    manager.handle(id, data, now).is_some()
+}
+
+/// Fixed-capacity dispatch.
+pub fn dispatch(id: Id, data: &[u8]) -> bool {
+    let mut handler = |_: u8, _: Pgn, _: &[u8]| {};
+    let mut router = Router::<'_, 4>::new();
+    let _ = router.register(Filter::Pgn(id.pgn()), &mut handler);
+
+    let gateway = Gateway::<'_, 4>::new(Mode::Deny);
+    let responder = Responder::<'_, 4>::new();
+    router.dispatch(id.sa(), id.pgn(), data)
+        && gateway.allows(id.pgn())
+        && !responder.supports(id.pgn())
+}
+
+/// Reassemble into a fixed-capacity vector.
+pub fn heapless(rts: RequestToSend) -> Transfer<'static, heapless::Vec<u8, 1785>> {
+    Transfer::with_storage(rts, heapless::Vec::new())
+}
+
+/// Reassemble straight into flash.
+pub fn flash<F: NorFlash>(
+    flash: F,
+    rts: RequestToSend,
+) -> Option<Transfer<'static, FlashStorage<F>>> {
+    let storage = FlashStorage::new(flash, 0, 0x1_0000)?;
+    Some(Transfer::with_storage(rts, storage))
+}
+
+/// Bootloader target over a region map.
+pub fn target<F: NorFlash>(flash: F, regions: &[Region]) -> Option<FlashTarget<'_, F>> {
+    FlashTarget::new(flash, 0x00, regions)
+}
@@ -0,0 +1,316 @@
+//! Address claiming (J1939-81)
+//!
+//! [`AddressManager`] claims a source address for a NAME and defends it
+//! against other controller applications claiming the same address.
+
+use crate::error::exact;
+use crate::id::{Id, IdBuilder, Pgn};
+use crate::logging::debug;
+use crate::name::Name;
+use crate::request::Request;
+use crate::time::{Duration, Instant};
+use core::ops::RangeInclusive;
+
+const GLOBAL: u8 = 0xFF;
+
+/// Source address of a node without an address.
+pub const NULL: u8 = 0xFE;
+
+/// Time a claim has to stand uncontested before the address is used.
+pub const CLAIM_TIMEOUT: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum State {
+    /// Address held without claiming it.
+    Fixed,
+    /// Claim to send on the next poll.
+    Pending,
+    /// Claim sent at the given time.
+    Claiming(Instant),
+    Claimed,
+    /// No address left to claim.
+    Lost,
+}
+
+/// Claims and defends a source address.
+///
+/// The preferred address is claimed first. On losing it to a lower NAME, the
+/// next free address of the range is claimed, and the cannot claim message
+/// is sent once none is left.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AddressManager {
+    name: Name,
+    first: u8,
+    last: u8,
+    address: u8,
+    state: State,
+    /// Addresses claimed by other controller applications, one bit each.
+    used: [u32; 8],
+}
+
+impl AddressManager {
+    /// Create a manager claiming `preferred` for `name`, then other addresses
+    /// of `range`.
+    pub fn new(name: Name, preferred: u8, range: RangeInclusive<u8>) -> Self {
+        Self {
+            name,
+            first: *range.start(),
+            last: *range.end(),
+            address: preferred,
+            state: State::Pending,
+            used: [0; 8],
+        }
+    }
+
+    /// Create a manager holding `address` without taking part in address
+    /// claiming.
+    pub fn fixed(address: u8) -> Self {
+        Self {
+            name: Name::default(),
+            first: address,
+            last: address,
+            address,
+            state: State::Fixed,
+            used: [0; 8],
+        }
+    }
+
+    /// NAME claiming the address.
+    pub fn name(&self) -> Name {
+        self.name
+    }
+
+    /// Address that may be used, once claimed.
+    pub fn address(&self) -> Option<u8> {
+        matches!(self.state, State::Fixed | State::Claimed).then_some(self.address)
+    }
+
+    /// Source address of the frames sent by the node.
+    ///
+    /// The address being claimed until it is, and [`NULL`] once no address
+    /// could be claimed.
+    pub fn source_address(&self) -> u8 {
+        match self.state {
+            State::Lost => NULL,
+            _ => self.address,
+        }
+    }
+
+    /// Time at which [`AddressManager::poll`] has to be called, if any.
+    ///
+    /// A claim that has yet to be sent is due immediately.
+    pub fn deadline(&self) -> Option<Instant> {
+        match self.state {
+            State::Pending => Some(Instant::from_millis(0)),
+            State::Claiming(since) => Some(since + CLAIM_TIMEOUT),
+            _ => None,
+        }
+    }
+
+    /// Send the initial claim and complete claims that went uncontested.
+    ///
+    /// Returns the frame to send, if any.
+    pub fn poll(&mut self, now: Instant) -> Option<(Id, [u8; 8])> {
+        match self.state {
+            State::Pending if self.is_used(self.address) => self.claim_next(now),
+            State::Pending => {
+                self.state = State::Claiming(now);
+                self.frame(self.address)
+            }
+            State::Claiming(since) if now >= since + CLAIM_TIMEOUT => {
+                debug!("claimed address {:#04x}", self.address);
+                self.state = State::Claimed;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Handle a received address claim or request for address claimed.
+    ///
+    /// Returns the frame to send in response, if any.
+    pub fn handle(&mut self, id: Id, data: &[u8], now: Instant) -> Option<(Id, [u8; 8])> {
+        if matches!(self.state, State::Fixed | State::Pending) {
+            if id.pgn() == Pgn::AddressClaimed && id.sa() != NULL {
+                self.mark(id.sa());
+            }
+            return None;
+        }
+
+        match id.pgn() {
+            Pgn::AddressClaimed => {
+                let name = Name::new(u64::from_le_bytes(*exact(data).ok()?));
+                self.contend(id.sa(), name, now)
+            }
+            Pgn::Request => {
+                let da = id.da()?;
+                if Request::try_from(data).ok()?.pgn() != Pgn::AddressClaimed {
+                    return None;
+                }
+                if da != GLOBAL && da != self.source_address() {
+                    return None;
+                }
+                self.frame(self.source_address())
+            }
+            _ => None,
+        }
+    }
+
+    /// Resolve a claim of `sa` by `name`.
+    fn contend(&mut self, sa: u8, name: Name, now: Instant) -> Option<(Id, [u8; 8])> {
+        if sa == NULL || name == self.name {
+            return None;
+        }
+        if sa != self.address || self.state == State::Lost {
+            self.mark(sa);
+            return None;
+        }
+
+        // the lower NAME keeps the address
+        if self.name < name {
+            debug!("defending address {sa:#04x}");
+            return self.frame(self.address);
+        }
+
+        debug!("lost address {sa:#04x}");
+        self.mark(sa);
+        self.claim_next(now)
+    }
+
+    /// Claim the next free address of the range, or give up.
+    fn claim_next(&mut self, now: Instant) -> Option<(Id, [u8; 8])> {
+        let free = (self.first..=self.last)
+            .cycle()
+            .skip_while(|&a| a != self.address)
+            .skip(1)
+            .take(usize::from(self.last - self.first) + 1)
+            .find(|&a| !self.is_used(a));
+
+        match free {
+            Some(address) => {
+                self.address = address;
+                self.state = State::Claiming(now);
+                self.frame(address)
+            }
+            None => {
+                debug!("no address left to claim");
+                self.state = State::Lost;
+                self.frame(NULL)
+            }
+        }
+    }
+
+    fn is_used(&self, address: u8) -> bool {
+        self.used[usize::from(address / 32)] & (1 << (address % 32)) != 0
+    }
+
+    fn mark(&mut self, address: u8) {
+        self.used[usize::from(address / 32)] |= 1 << (address % 32);
+    }
+
+    /// Address claimed message sent from `sa`.
+    fn frame(&self, sa: u8) -> Option<(Id, [u8; 8])> {
+        let id = IdBuilder::new()
+            .priority(6)
+            .pgn(Pgn::AddressClaimed)
+            .sa(sa)
+            .da(GLOBAL)
+            .build()?;
+        Some((id, self.name.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claim(sa: u8, name: u64) -> (Id, [u8; 8]) {
+        let id = IdBuilder::new()
+            .pgn(Pgn::AddressClaimed)
+            .sa(sa)
+            .da(GLOBAL)
+            .build()
+            .unwrap();
+        (id, name.to_le_bytes())
+    }
+
+    #[test]
+    fn claim_and_defend() {
+        let name = Name::new((1 << 63) | 0x100);
+        let mut manager = AddressManager::new(name, 0x80, 0x80..=0x82);
+        let now = Instant::from_millis(0);
+        assert_eq!(manager.deadline(), Some(now));
+
+        let (id, data) = manager.poll(now).unwrap();
+        assert_eq!(id.pgn(), Pgn::AddressClaimed);
+        assert_eq!((id.sa(), id.da()), (0x80, Some(GLOBAL)));
+        assert_eq!(data, name.as_raw().to_le_bytes());
+        assert_eq!(manager.address(), None);
+
+        assert!(manager.poll(now + Duration::from_millis(249)).is_none());
+        assert!(manager.poll(now + CLAIM_TIMEOUT).is_none());
+        assert_eq!(manager.address(), Some(0x80));
+        assert_eq!(manager.deadline(), None);
+
+        // a higher NAME is answered with our claim
+        let (id, data) = claim(0x80, (1 << 63) | 0x200);
+        let (id, _) = manager.handle(id, &data, now).unwrap();
+        assert_eq!(id.sa(), 0x80);
+        assert_eq!(manager.address(), Some(0x80));
+
+        // claims of other addresses are noted
+        let (id, data) = claim(0x81, 0x10);
+        assert!(manager.handle(id, &data, now).is_none());
+
+        // a lower NAME takes the address, 0x81 is in use
+        let (id, data) = claim(0x80, 0x10);
+        let (id, _) = manager.handle(id, &data, now).unwrap();
+        assert_eq!(id.sa(), 0x82);
+        assert_eq!(manager.address(), None);
+        assert_eq!(manager.source_address(), 0x82);
+
+        let (id, data) = claim(0x82, 0x10);
+        let (id, data) = manager.handle(id, &data, now).unwrap();
+        assert_eq!(id.sa(), NULL);
+        assert_eq!(data, name.as_raw().to_le_bytes());
+        assert_eq!(manager.source_address(), NULL);
+        assert_eq!(manager.address(), None);
+
+        // requests are answered with the cannot claim message
+        let (id, data) = Request::new(Pgn::AddressClaimed)
+            .to_frame(0x10, None)
+            .unwrap();
+        let (id, _) = manager.handle(id, &data, now).unwrap();
+        assert_eq!(id.sa(), NULL);
+    }
+
+    #[test]
+    fn single_address() {
+        let mut manager = AddressManager::new(Name::new(0x100), 0x80, 0x80..=0x80);
+        let now = Instant::from_millis(0);
+        manager.poll(now).unwrap();
+
+        let (id, data) = Request::new(Pgn::AddressClaimed)
+            .to_frame(0x10, Some(0x80))
+            .unwrap();
+        let (id, _) = manager.handle(id, &data, now).unwrap();
+        assert_eq!(id.sa(), 0x80);
+
+        let (id, data) = claim(0x80, 0x10);
+        let (id, _) = manager.handle(id, &data, now).unwrap();
+        assert_eq!(id.sa(), NULL);
+    }
+
+    #[test]
+    fn fixed() {
+        let mut manager = AddressManager::fixed(0x80);
+        assert_eq!(manager.address(), Some(0x80));
+        assert!(manager.poll(Instant::from_millis(0)).is_none());
+
+        let (id, data) = claim(0x80, 0);
+        assert!(manager.handle(id, &data, Instant::from_millis(0)).is_none());
+        assert_eq!(manager.address(), Some(0x80));
+    }
+}
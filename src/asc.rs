@@ -0,0 +1,326 @@
+//! Vector ASC log files
+//!
+//! Reads the text logs written by CANalyzer and CANoe, one event per line:
+//!
+//! ```text
+//! base hex  timestamps absolute
+//! Begin Triggerblock
+//!    0.010000 1  18FEF100x       Rx   d 8 01 02 03 04 05 06 07 08
+//!    0.020000 CANFD   2 Rx 18EF5500x  1 0 9 12 00 01 02 03 04 05 06 07 08 09 0A 0B
+//! End TriggerBlock
+//! ```
+//!
+//! Header lines select the number base and whether timestamps are absolute
+//! or relative to the previous event. Events other than data frames with
+//! extended identifiers are skipped, so records can be replayed into a
+//! [`Node`](crate::node::Node) like [`candump`](crate::candump) records.
+
+use crate::id::Id;
+use crate::payload::{MAX_LEN, Payload};
+use crate::time::Instant;
+use std::io::{self, BufRead};
+use std::string::String;
+use std::time::Duration;
+
+/// Direction of a logged frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    Rx,
+    Tx,
+}
+
+/// A frame read from a log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// Time since the start of the measurement.
+    pub timestamp: Duration,
+    /// Channel number, starting at 1.
+    pub channel: u8,
+    pub direction: Direction,
+    pub id: Id,
+    pub data: Payload,
+}
+
+impl Record {
+    /// Timestamp as an [`Instant`] for driving the stack.
+    pub fn instant(&self) -> Instant {
+        Instant::from_millis(self.timestamp.as_millis() as u64)
+    }
+}
+
+/// Error reading a log.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// Malformed line, numbered from 1.
+    Parse(usize),
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// Parse a single event line of a log with hexadecimal numbers.
+///
+/// The timestamp is taken as is. Returns `Ok(None)` for events that are not
+/// data frames with an extended identifier, and the line if it is malformed.
+pub fn parse_line(line: &str) -> Result<Option<Record>, &str> {
+    parse(line, 16).map_err(|_| line)
+}
+
+fn parse(line: &str, radix: u32) -> Result<Option<Record>, ()> {
+    let mut fields = line.split_whitespace();
+
+    let Some(timestamp) = fields.next().and_then(|t| t.parse::<f64>().ok()) else {
+        // header or trigger block line
+        return Ok(None);
+    };
+    let timestamp = Duration::try_from_secs_f64(timestamp).map_err(|_| ())?;
+
+    match fields.next() {
+        Some("CANFD") => parse_fd(timestamp, fields, radix),
+        // classic frames list the channel first
+        Some(channel) if channel.parse::<u8>().is_ok() => {
+            parse_classic(timestamp, channel, fields, radix)
+        }
+        _ => Ok(None),
+    }
+}
+
+fn parse_fd<'a>(
+    timestamp: Duration,
+    mut fields: impl Iterator<Item = &'a str>,
+    radix: u32,
+) -> Result<Option<Record>, ()> {
+    let channel: u8 = fields.next().ok_or(())?.parse().map_err(|_| ())?;
+    let direction = direction(fields.next().ok_or(())?)?;
+    let Some(id) = extended(fields.next().ok_or(())?, radix)? else {
+        return Ok(None);
+    };
+
+    // an optional symbolic message name precedes the bit rate switch
+    if !matches!(fields.next(), Some("0" | "1")) {
+        fields.next().ok_or(())?;
+    }
+    // error state indicator and DLC
+    fields.next().ok_or(())?;
+    fields.next().ok_or(())?;
+    let len: usize = fields.next().ok_or(())?.parse().map_err(|_| ())?;
+
+    let data = bytes(&mut fields, len, radix)?;
+    Ok(Some(Record {
+        timestamp,
+        channel,
+        direction,
+        id,
+        data,
+    }))
+}
+
+fn parse_classic<'a>(
+    timestamp: Duration,
+    channel: &str,
+    mut fields: impl Iterator<Item = &'a str>,
+    radix: u32,
+) -> Result<Option<Record>, ()> {
+    let channel: u8 = channel.parse().map_err(|_| ())?;
+
+    let id = fields.next().ok_or(())?;
+    let Some(direction) = fields.next().and_then(|d| direction(d).ok()) else {
+        // error frames and other events
+        return Ok(None);
+    };
+    let Some(id) = extended(id, radix)? else {
+        return Ok(None);
+    };
+
+    match fields.next() {
+        Some("d") => {}
+        Some("r") => return Ok(None),
+        _ => return Err(()),
+    }
+    let len: usize = fields.next().ok_or(())?.parse().map_err(|_| ())?;
+    if len > 8 {
+        return Err(());
+    }
+
+    let data = bytes(&mut fields, len, radix)?;
+    Ok(Some(Record {
+        timestamp,
+        channel,
+        direction,
+        id,
+        data,
+    }))
+}
+
+fn direction(field: &str) -> Result<Direction, ()> {
+    match field {
+        "Rx" => Ok(Direction::Rx),
+        "Tx" => Ok(Direction::Tx),
+        _ => Err(()),
+    }
+}
+
+/// Parse an identifier, `None` if it is a standard identifier.
+fn extended(field: &str, radix: u32) -> Result<Option<Id>, ()> {
+    let Some(id) = field.strip_suffix('x') else {
+        return Ok(None);
+    };
+    let id = u32::from_str_radix(id, radix).map_err(|_| ())?;
+    if id > 0x1FFF_FFFF {
+        return Err(());
+    }
+    Ok(Some(Id::new(id)))
+}
+
+fn bytes<'a>(
+    fields: &mut impl Iterator<Item = &'a str>,
+    len: usize,
+    radix: u32,
+) -> Result<Payload, ()> {
+    let mut bytes = [0; MAX_LEN];
+    for byte in bytes.get_mut(..len).ok_or(())? {
+        *byte = u8::from_str_radix(fields.next().ok_or(())?, radix).map_err(|_| ())?;
+    }
+    Payload::new(bytes.get(..len).ok_or(())?).ok_or(())
+}
+
+/// Iterator over the frames of a log.
+#[derive(Debug)]
+pub struct Reader<R> {
+    inner: R,
+    line: usize,
+    buf: String,
+    radix: u32,
+    relative: bool,
+    elapsed: Duration,
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Create a new reader.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            line: 0,
+            buf: String::new(),
+            radix: 16,
+            relative: false,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Apply a `base ... timestamps ...` header line.
+    fn header(&mut self) {
+        let mut fields = self.buf.split_whitespace();
+        if fields.next() != Some("base") {
+            return;
+        }
+
+        match fields.next() {
+            Some("dec") => self.radix = 10,
+            Some("hex") => self.radix = 16,
+            _ => {}
+        }
+        if fields.next() == Some("timestamps") {
+            self.relative = fields.next() == Some("relative");
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = Result<Record, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.inner.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => self.line += 1,
+                Err(e) => return Some(Err(e.into())),
+            }
+
+            self.header();
+
+            match parse(&self.buf, self.radix) {
+                Ok(Some(mut record)) => {
+                    if self.relative {
+                        self.elapsed += record.timestamp;
+                        record.timestamp = self.elapsed;
+                    }
+                    return Some(Ok(record));
+                }
+                Ok(None) => continue,
+                Err(_) => return Some(Err(Error::Parse(self.line))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "\
+date Wed Jul 3 10:29:06.000 am 2024
+base hex  timestamps absolute
+internal events logged
+// version 9.0.0
+Begin Triggerblock Wed Jul 3 10:29:06.000 am 2024
+   0.000000 Start of measurement
+   0.010000 1  18FEF100x       Rx   d 8 01 02 03 04 05 06 07 08  Length = 0 BitCount = 0
+   0.011000 1  123             Rx   d 2 01 02
+   0.012000 1  18FEF100x       Rx   r
+   0.013000 1  ErrorFrame
+   0.020000 CANFD   2 Rx 18EF5500x  Message 1 0 9 12 00 01 02 03 04 05 06 07 08 09 0A 0B
+End TriggerBlock
+";
+
+    #[test]
+    fn read() {
+        let records: std::vec::Vec<_> = Reader::new(LOG.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+
+        assert_eq!(records[0].id, Id::new(0x18FEF100));
+        assert_eq!(records[0].channel, 1);
+        assert_eq!(records[0].direction, Direction::Rx);
+        assert_eq!(records[0].data.as_slice(), &[1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(records[0].instant(), Instant::from_millis(10));
+
+        assert_eq!(records[1].channel, 2);
+        assert!(records[1].data.is_fd());
+        assert_eq!(
+            records[1].data.as_slice()[..12],
+            [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]
+        );
+
+        let mut reader = Reader::new("0.1 1 18FEF100x Rx d 8 01".as_bytes());
+        assert!(matches!(reader.next(), Some(Err(Error::Parse(1)))));
+    }
+
+    #[test]
+    fn relative_decimal() {
+        let log = "\
+base dec  timestamps relative
+   0.5 1  419361024x  Tx   d 1 255
+   0.25 1  419361024x  Tx   d 1 16
+";
+        let records: std::vec::Vec<_> = Reader::new(log.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records[0].id, Id::new(0x18FEF100));
+        assert_eq!(records[0].direction, Direction::Tx);
+        assert_eq!(records[0].data.as_slice(), &[0xFF]);
+        assert_eq!(records[1].instant(), Instant::from_millis(750));
+        assert_eq!(records[1].data.as_slice(), &[0x10]);
+
+        assert!(parse_line("   0.1 1  18FEF100x  Rx   d 1 FF").is_ok());
+        assert!(parse_line("   0.1 1  18FEF100x  Rx   d 9").is_err());
+    }
+}
@@ -0,0 +1,277 @@
+//! In-memory virtual bus
+//!
+//! Connects several [`Node`]s for integration testing without hardware, with
+//! configurable latency and frame loss.
+
+use crate::Id;
+use crate::id::Pgn;
+use crate::node::Node;
+use crate::time::{Clock, Duration, Instant};
+use std::collections::VecDeque;
+use std::vec::Vec;
+
+/// A frame waiting for delivery.
+#[derive(Debug)]
+struct InFlight {
+    at: Instant,
+    from: usize,
+    id: Id,
+    data: [u8; 8],
+}
+
+/// A message completed by one of the nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Received {
+    /// Index of the receiving node.
+    pub node: usize,
+    pub pgn: Pgn,
+    /// Source address of the sender.
+    pub sa: u8,
+    pub data: Vec<u8>,
+}
+
+/// A virtual CAN bus.
+///
+/// Time only advances through [`VirtualBus::step`], making test runs fully
+/// deterministic.
+#[derive(Debug)]
+pub struct VirtualBus<'a> {
+    nodes: Vec<Node<'a>>,
+    in_flight: VecDeque<InFlight>,
+    received: Vec<Received>,
+    latency: Duration,
+    loss: f32,
+    seed: u64,
+    now: Instant,
+}
+
+impl<'a> VirtualBus<'a> {
+    /// Create a new bus without latency or frame loss.
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            in_flight: VecDeque::new(),
+            received: Vec::new(),
+            latency: Duration::from_millis(0),
+            loss: 0.0,
+            seed: 1,
+            now: Instant::from_millis(0),
+        }
+    }
+
+    /// Delay between sending and delivering a frame.
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Probability of a frame being lost, between 0 and 1.
+    ///
+    /// Losses are drawn from a pseudo-random sequence started from `seed`.
+    pub fn loss(mut self, probability: f32, seed: u64) -> Self {
+        self.loss = probability.clamp(0.0, 1.0);
+        self.seed = seed.max(1);
+        self
+    }
+
+    /// Attach a node and return its index.
+    pub fn attach(&mut self, node: Node<'a>) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    /// Access an attached node.
+    pub fn node(&self, index: usize) -> Option<&Node<'a>> {
+        self.nodes.get(index)
+    }
+
+    /// Send a frame on behalf of `from`.
+    ///
+    /// `from` does not need to be an attached node, which allows injecting
+    /// traffic from the test itself.
+    pub fn send(&mut self, from: usize, id: Id, data: [u8; 8]) {
+        if self.lost() {
+            return;
+        }
+
+        self.in_flight.push_back(InFlight {
+            at: self.now + self.latency,
+            from,
+            id,
+            data,
+        });
+    }
+
+    /// Advance time by `duration`, delivering due frames.
+    pub fn step(&mut self, duration: Duration) {
+        let end = self.now + duration;
+
+        loop {
+            let next = self.in_flight.iter().map(|f| f.at).min();
+            match next {
+                Some(at) if at <= end => {
+                    self.now = self.now.max(at);
+                    let Some(index) = self.in_flight.iter().position(|f| f.at == at) else {
+                        break;
+                    };
+                    if let Some(frame) = self.in_flight.remove(index) {
+                        self.deliver(frame);
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        self.now = end;
+
+        // let nodes act on their deadlines
+        for index in 0..self.nodes.len() {
+            let frames: Vec<_> = self.nodes[index].poll(None, self.now).collect();
+            for (id, data) in frames {
+                self.send(index, id, data);
+            }
+        }
+    }
+
+    /// Take the messages completed so far.
+    pub fn take_received(&mut self) -> Vec<Received> {
+        core::mem::take(&mut self.received)
+    }
+
+    fn deliver(&mut self, frame: InFlight) {
+        for index in 0..self.nodes.len() {
+            if index == frame.from {
+                continue;
+            }
+
+            let node = &mut self.nodes[index];
+            let frames: Vec<_> = node.poll(Some((frame.id, &frame.data)), self.now).collect();
+
+            if let Some((pgn, sa, data)) = node.received() {
+                self.received.push(Received {
+                    node: index,
+                    pgn,
+                    sa,
+                    data: data.to_vec(),
+                });
+            }
+
+            for (id, data) in frames {
+                self.send(index, id, data);
+            }
+        }
+    }
+
+    /// Draw from the loss sequence (xorshift64).
+    fn lost(&mut self) -> bool {
+        if self.loss <= 0.0 {
+            return false;
+        }
+
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+
+        (self.seed as f64 / u64::MAX as f64) < self.loss as f64
+    }
+}
+
+impl Clock for VirtualBus<'_> {
+    /// Current bus time.
+    fn now(&self) -> Instant {
+        self.now
+    }
+}
+
+impl Default for VirtualBus<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IdBuilder;
+    use crate::transport::RequestToSend;
+
+    const TESTER: usize = usize::MAX;
+
+    fn id(pgn: Pgn) -> Id {
+        IdBuilder::new()
+            .priority(7)
+            .pgn(pgn)
+            .sa(0x10)
+            .da(0x20)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn transport_with_latency() {
+        let mut bus = VirtualBus::new().latency(Duration::from_millis(5));
+        bus.attach(Node::new(0x20, std::vec![]));
+
+        let rts = RequestToSend::try_new(9, None, Pgn::ProprietaryA).unwrap();
+        bus.send(
+            TESTER,
+            id(Pgn::TransportProtocolConnectionManagement),
+            rts.into(),
+        );
+        bus.send(
+            TESTER,
+            id(Pgn::TransportProtocolDataTransfer),
+            [1, 1, 2, 3, 4, 5, 6, 7],
+        );
+        bus.send(
+            TESTER,
+            id(Pgn::TransportProtocolDataTransfer),
+            [2, 8, 9, 0, 0, 0, 0, 0],
+        );
+
+        bus.step(Duration::from_millis(4));
+        assert!(bus.take_received().is_empty());
+
+        bus.step(Duration::from_millis(1));
+        let received = bus.take_received();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].node, 0);
+        assert_eq!(received[0].sa, 0x10);
+        assert_eq!(received[0].data, [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn stalled_transfer_times_out() {
+        let mut bus = VirtualBus::new();
+        bus.attach(Node::new(0x20, std::vec![]));
+
+        let rts = RequestToSend::try_new(9, None, Pgn::ProprietaryA).unwrap();
+        bus.send(
+            TESTER,
+            id(Pgn::TransportProtocolConnectionManagement),
+            rts.into(),
+        );
+        bus.step(Duration::from_millis(1));
+        assert!(bus.node(0).unwrap().deadline().is_some());
+
+        bus.step(Duration::from_millis(2000));
+        assert!(bus.take_received().is_empty());
+        assert!(bus.node(0).unwrap().deadline().is_none());
+    }
+
+    #[test]
+    fn loss() {
+        let mut bus = VirtualBus::new().loss(1.0, 42);
+        bus.attach(Node::new(0x20, std::vec![]));
+
+        let rts = RequestToSend::try_new(9, None, Pgn::ProprietaryA).unwrap();
+        bus.send(
+            TESTER,
+            id(Pgn::TransportProtocolConnectionManagement),
+            rts.into(),
+        );
+        bus.step(Duration::from_millis(1));
+        assert!(bus.node(0).unwrap().deadline().is_none());
+    }
+}
@@ -0,0 +1,310 @@
+//! `embedded-can` driver glue
+//!
+//! Converts between [`embedded_can::Frame`]s and the `(Id, data)` pairs used
+//! throughout this crate, and provides a concrete [`Frame`] type.
+
+use crate::Id;
+use crate::payload::Payload;
+
+/// A J1939 data frame.
+///
+/// Always uses an extended identifier and never a remote request, so it can
+/// be handed to any `embedded-can` driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Frame {
+    id: Id,
+    data: [u8; 8],
+    dlc: u8,
+}
+
+impl Frame {
+    /// Build a frame carrying a typed message.
+    pub fn from_message<M>(id: Id, msg: &M) -> Self
+    where
+        for<'m> [u8; 8]: From<&'m M>,
+    {
+        Self {
+            id,
+            data: <[u8; 8]>::from(msg),
+            dlc: 8,
+        }
+    }
+
+    /// Identifier and payload padded to 8 bytes with `0xFF`.
+    pub fn parts(&self) -> (Id, [u8; 8]) {
+        let mut data = [0xFF; 8];
+        data[..self.dlc as usize].copy_from_slice(&self.data[..self.dlc as usize]);
+        (self.id, data)
+    }
+}
+
+impl From<(Id, [u8; 8])> for Frame {
+    fn from((id, data): (Id, [u8; 8])) -> Self {
+        Self { id, data, dlc: 8 }
+    }
+}
+
+impl embedded_can::Frame for Frame {
+    /// Returns `None` for standard identifiers or payloads longer than 8
+    /// bytes.
+    fn new(id: impl Into<embedded_can::Id>, data: &[u8]) -> Option<Self> {
+        let embedded_can::Id::Extended(id) = id.into() else {
+            return None;
+        };
+
+        let mut frame = Self {
+            id: Id::from(id),
+            data: [0; 8],
+            dlc: data.len() as u8,
+        };
+        frame.data.get_mut(..data.len())?.copy_from_slice(data);
+        Some(frame)
+    }
+
+    /// Always returns `None`, remote frames are not used by J1939.
+    fn new_remote(_id: impl Into<embedded_can::Id>, _dlc: usize) -> Option<Self> {
+        None
+    }
+
+    fn is_extended(&self) -> bool {
+        true
+    }
+
+    fn is_remote_frame(&self) -> bool {
+        false
+    }
+
+    fn id(&self) -> embedded_can::Id {
+        embedded_can::Id::from(self.id)
+    }
+
+    fn dlc(&self) -> usize {
+        self.dlc as usize
+    }
+
+    fn data(&self) -> &[u8] {
+        &self.data[..self.dlc as usize]
+    }
+}
+
+/// Build a driver frame from an identifier and payload.
+///
+/// Returns `None` if the payload does not fit the frame type.
+pub fn to_frame<F: embedded_can::Frame>(id: Id, data: &[u8]) -> Option<F> {
+    F::new(embedded_can::Id::from(id), data)
+}
+
+/// Split a received driver frame into identifier and payload.
+///
+/// Payloads shorter than 8 bytes are padded with `0xFF`. Returns `None` for
+/// standard identifier and remote frames, which are not used by J1939.
+pub fn from_frame<F: embedded_can::Frame>(frame: &F) -> Option<(Id, [u8; 8])> {
+    let embedded_can::Id::Extended(id) = frame.id() else {
+        return None;
+    };
+
+    if frame.is_remote_frame() {
+        return None;
+    }
+
+    let mut data = [0xFF; 8];
+    let len = frame.data().len().min(8);
+    data[..len].copy_from_slice(&frame.data()[..len]);
+
+    Some((Id::from(id), data))
+}
+
+/// Split a received classic or FD driver frame into identifier and payload.
+///
+/// Returns `None` for standard identifier and remote frames, or payloads
+/// longer than 64 bytes.
+pub fn from_fd_frame<F: embedded_can::Frame>(frame: &F) -> Option<(Id, Payload)> {
+    let embedded_can::Id::Extended(id) = frame.id() else {
+        return None;
+    };
+
+    if frame.is_remote_frame() {
+        return None;
+    }
+
+    Some((Id::from(id), Payload::new(frame.data())?))
+}
+
+/// Error from a blocking driver operation.
+#[cfg(feature = "embedded-can-blocking")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Error<E> {
+    /// Driver error.
+    Can(E),
+    /// Payload does not fit into a driver frame.
+    Payload,
+    /// Received frame is not a J1939 data frame.
+    Frame,
+}
+
+/// Transmit a payload through a blocking driver.
+#[cfg(feature = "embedded-can-blocking")]
+pub fn transmit<C: embedded_can::blocking::Can>(
+    can: &mut C,
+    id: Id,
+    data: &[u8],
+) -> Result<(), Error<C::Error>> {
+    let frame = to_frame(id, data).ok_or(Error::Payload)?;
+    can.transmit(&frame).map_err(Error::Can)
+}
+
+/// Transmit a typed message through a blocking driver.
+#[cfg(feature = "embedded-can-blocking")]
+pub fn send<C, M>(can: &mut C, id: Id, msg: &M) -> Result<(), Error<C::Error>>
+where
+    C: embedded_can::blocking::Can,
+    for<'m> [u8; 8]: From<&'m M>,
+{
+    let data = <[u8; 8]>::from(msg);
+    transmit(can, id, &data)
+}
+
+/// Receive the next frame from a blocking driver.
+#[cfg(feature = "embedded-can-blocking")]
+pub fn receive<C: embedded_can::blocking::Can>(
+    can: &mut C,
+) -> Result<(Id, [u8; 8]), Error<C::Error>> {
+    let frame = can.receive().map_err(Error::Can)?;
+    from_frame(&frame).ok_or(Error::Frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_can::{ExtendedId, Frame as _, StandardId};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestFrame {
+        id: embedded_can::Id,
+        remote: bool,
+        data: [u8; 8],
+        dlc: usize,
+    }
+
+    impl embedded_can::Frame for TestFrame {
+        fn new(id: impl Into<embedded_can::Id>, data: &[u8]) -> Option<Self> {
+            let mut frame = Self::new_remote(id, data.len())?;
+            frame.remote = false;
+            frame.data[..data.len()].copy_from_slice(data);
+            Some(frame)
+        }
+
+        fn new_remote(id: impl Into<embedded_can::Id>, dlc: usize) -> Option<Self> {
+            if dlc > 8 {
+                return None;
+            }
+
+            Some(Self {
+                id: id.into(),
+                remote: true,
+                data: [0; 8],
+                dlc,
+            })
+        }
+
+        fn is_extended(&self) -> bool {
+            matches!(self.id, embedded_can::Id::Extended(_))
+        }
+
+        fn is_remote_frame(&self) -> bool {
+            self.remote
+        }
+
+        fn id(&self) -> embedded_can::Id {
+            self.id
+        }
+
+        fn dlc(&self) -> usize {
+            self.dlc
+        }
+
+        fn data(&self) -> &[u8] {
+            &self.data[..self.dlc]
+        }
+    }
+
+    #[test]
+    fn frame_conversion() {
+        let id = Id::new(0x18FEF100);
+
+        let frame: TestFrame = to_frame(id, &[1, 2, 3]).unwrap();
+        assert_eq!(
+            from_frame(&frame),
+            Some((id, [1, 2, 3, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]))
+        );
+
+        assert!(to_frame::<TestFrame>(id, &[0; 9]).is_none());
+
+        let standard = TestFrame::new(StandardId::new(0x100).unwrap(), &[]).unwrap();
+        assert!(from_frame(&standard).is_none());
+
+        let remote = TestFrame::new_remote(ExtendedId::new(0x18FEF100).unwrap(), 8).unwrap();
+        assert!(from_frame(&remote).is_none());
+        assert!(from_fd_frame(&remote).is_none());
+
+        let (_, payload) = from_fd_frame(&frame).unwrap();
+        assert_eq!(payload.as_slice(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn j1939_frame() {
+        let id = Id::new(0x18FEC1F9);
+        let vd = crate::messages::VehicleDistance::try_from([0; 8].as_ref()).unwrap();
+
+        let frame = Frame::from_message(id, &vd);
+        assert_eq!(frame.id(), embedded_can::Id::from(id));
+        assert_eq!(frame.data(), &[0; 8]);
+        assert_eq!(from_frame(&frame), Some((id, [0; 8])));
+
+        let frame: Frame = to_frame(id, &[1, 2]).unwrap();
+        assert_eq!(frame.dlc(), 2);
+        assert_eq!(
+            frame.parts(),
+            (id, [1, 2, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF])
+        );
+
+        assert!(Frame::new(StandardId::new(0x100).unwrap(), &[]).is_none());
+        assert!(Frame::new(ExtendedId::new(0x100).unwrap(), &[0; 9]).is_none());
+        assert!(Frame::new_remote(ExtendedId::new(0x100).unwrap(), 0).is_none());
+    }
+
+    #[cfg(feature = "embedded-can-blocking")]
+    #[test]
+    fn blocking() {
+        struct Loopback(Option<TestFrame>);
+
+        impl embedded_can::blocking::Can for Loopback {
+            type Frame = TestFrame;
+            type Error = embedded_can::ErrorKind;
+
+            fn transmit(&mut self, frame: &Self::Frame) -> Result<(), Self::Error> {
+                self.0 = Some(frame.clone());
+                Ok(())
+            }
+
+            fn receive(&mut self) -> Result<Self::Frame, Self::Error> {
+                self.0.take().ok_or(embedded_can::ErrorKind::Other)
+            }
+        }
+
+        let mut can = Loopback(None);
+        let id = Id::new(0x18FEC1F9);
+        let vd = crate::messages::VehicleDistance::try_from([0; 8].as_ref()).unwrap();
+
+        send(&mut can, id, &vd).unwrap();
+        assert_eq!(receive(&mut can), Ok((id, [0; 8])));
+        assert_eq!(
+            receive(&mut can),
+            Err(Error::Can(embedded_can::ErrorKind::Other))
+        );
+    }
+}
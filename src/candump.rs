@@ -0,0 +1,247 @@
+//! candump log files
+//!
+//! Reads and writes the `.log` format of `candump -l`, one frame per line:
+//!
+//! ```text
+//! (1436509052.249713) can0 18EF5500#0102030405060708
+//! ```
+//!
+//! CAN FD frames use `##` followed by a flags nibble. Recorded captures can
+//! be replayed into a [`Node`](crate::node::Node) for regression testing.
+
+use crate::id::Id;
+use crate::payload::Payload;
+use crate::time::Instant;
+use std::io::{self, BufRead, Write};
+use std::string::String;
+use std::time::Duration;
+
+/// A frame read from a log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// Time since the Unix epoch.
+    pub timestamp: Duration,
+    /// Interface name, such as `can0`.
+    pub interface: String,
+    pub id: Id,
+    pub data: Payload,
+}
+
+impl Record {
+    /// Timestamp as an [`Instant`] for driving the stack.
+    pub fn instant(&self) -> Instant {
+        Instant::from_millis(self.timestamp.as_millis() as u64)
+    }
+}
+
+/// Error reading a log.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// Malformed line, numbered from 1.
+    Parse(usize),
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// Parse a single log line.
+///
+/// Returns `Ok(None)` for frames not used by J1939, such as standard
+/// identifier and remote frames, and the line if it is malformed.
+pub fn parse_line(line: &str) -> Result<Option<Record>, &str> {
+    parse(line).map_err(|_| line)
+}
+
+fn parse(line: &str) -> Result<Option<Record>, ()> {
+    let mut fields = line.split_whitespace();
+
+    let timestamp = fields
+        .next()
+        .and_then(|t| t.strip_prefix('('))
+        .and_then(|t| t.strip_suffix(')'))
+        .ok_or(())?;
+    let interface = fields.next().ok_or(())?;
+    let frame = fields.next().ok_or(())?;
+
+    let (secs, micros) = timestamp.split_once('.').ok_or(())?;
+    let secs: u64 = secs.parse().map_err(|_| ())?;
+    let micros: u32 = micros.parse().map_err(|_| ())?;
+    let timestamp = Duration::new(secs, micros.saturating_mul(1000));
+
+    let (id, data) = frame.split_once('#').ok_or(())?;
+    // standard identifiers are written with 3 digits
+    if id.len() != 8 {
+        return Ok(None);
+    }
+    let id = u32::from_str_radix(id, 16).map_err(|_| ())?;
+
+    let data = match data.strip_prefix('#') {
+        // FD flags nibble precedes the data
+        Some(fd) => fd.get(1..).ok_or(())?,
+        None if data.starts_with('R') => return Ok(None),
+        None => data,
+    };
+
+    if data.len() % 2 != 0 {
+        return Err(());
+    }
+    let mut bytes = [0; crate::payload::MAX_LEN];
+    let len = data.len() / 2;
+    for (i, byte) in bytes.iter_mut().take(len).enumerate() {
+        *byte = u8::from_str_radix(data.get(i * 2..i * 2 + 2).ok_or(())?, 16).map_err(|_| ())?;
+    }
+
+    Ok(Some(Record {
+        timestamp,
+        interface: interface.into(),
+        id: Id::new(id & 0x1FFF_FFFF),
+        data: Payload::new(bytes.get(..len).ok_or(())?).ok_or(())?,
+    }))
+}
+
+/// Iterator over the frames of a log.
+#[derive(Debug)]
+pub struct Reader<R> {
+    inner: R,
+    line: usize,
+    buf: String,
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Create a new reader.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            line: 0,
+            buf: String::new(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = Result<Record, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.inner.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => self.line += 1,
+                Err(e) => return Some(Err(e.into())),
+            }
+
+            if self.buf.trim().is_empty() {
+                continue;
+            }
+
+            match parse_line(&self.buf) {
+                Ok(Some(record)) => return Some(Ok(record)),
+                Ok(None) => continue,
+                Err(_) => return Some(Err(Error::Parse(self.line))),
+            }
+        }
+    }
+}
+
+/// Writes frames as a log.
+#[derive(Debug)]
+pub struct Writer<W> {
+    inner: W,
+    interface: String,
+}
+
+impl<W: Write> Writer<W> {
+    /// Create a new writer recording frames as received on `interface`.
+    pub fn new(inner: W, interface: &str) -> Self {
+        Self {
+            inner,
+            interface: interface.into(),
+        }
+    }
+
+    /// Write a frame.
+    pub fn write(&mut self, timestamp: Duration, id: Id, data: &[u8]) -> io::Result<()> {
+        write!(
+            self.inner,
+            "({}.{:06}) {} {:08X}#",
+            timestamp.as_secs(),
+            timestamp.subsec_micros(),
+            self.interface,
+            id.as_raw()
+        )?;
+
+        if data.len() > 8 {
+            write!(self.inner, "#0")?;
+        }
+
+        for byte in data {
+            write!(self.inner, "{byte:02X}")?;
+        }
+
+        writeln!(self.inner)
+    }
+
+    /// Unwrap the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "\
+(1436509052.249713) can0 18EF5500#0102030405060708
+(1436509052.250000) can0 123#0102
+(1436509052.251000) can0 18FEF100#R
+
+(1436509052.252000) can1 18EF5500##1000102030405060708090A0B
+";
+
+    #[test]
+    fn read() {
+        let records: std::vec::Vec<_> = Reader::new(LOG.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+
+        assert_eq!(records[0].id, Id::new(0x18EF5500));
+        assert_eq!(records[0].data.as_slice(), &[1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(records[0].instant(), Instant::from_millis(1436509052249));
+
+        assert_eq!(records[1].interface, "can1");
+        assert!(records[1].data.is_fd());
+        assert_eq!(
+            records[1].data.as_slice()[..12],
+            [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]
+        );
+
+        let mut reader = Reader::new("(1.0) can0 18EF5500#0".as_bytes());
+        assert!(matches!(reader.next(), Some(Err(Error::Parse(1)))));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut writer = Writer::new(std::vec::Vec::new(), "can0");
+        writer
+            .write(
+                Duration::new(1436509052, 249713000),
+                Id::new(0x18EF5500),
+                &[1, 2, 3, 4, 5, 6, 7, 8],
+            )
+            .unwrap();
+        let log = writer.into_inner();
+        assert_eq!(
+            std::str::from_utf8(&log).unwrap(),
+            "(1436509052.249713) can0 18EF5500#0102030405060708\n"
+        );
+
+        let record = Reader::new(&log[..]).next().unwrap().unwrap();
+        assert_eq!(record.timestamp, Duration::new(1436509052, 249713000));
+    }
+}
@@ -0,0 +1,408 @@
+//! Multi-channel stacks
+//!
+//! [`Channels`] coordinates one [`Node`] per CAN channel, each claiming its
+//! own source address, and routes parameter groups between channels according to
+//! PGN-level rules. Common in gateways and ISOBUS tractors.
+
+use crate::gateway::Forward;
+use crate::id::{Id, IdBuilder, Pgn};
+use crate::node::{Frames, Node};
+use crate::router::Filter;
+use crate::time::Instant;
+use crate::transport::BroadcastTransfer;
+
+const GLOBAL: u8 = 0xFF;
+
+/// Forward messages matching `filter` from one channel to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Route {
+    /// Receiving channel.
+    pub from: usize,
+    /// Sending channel.
+    pub to: usize,
+    pub filter: Filter,
+}
+
+/// Messages to send on other channels.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Forwards<'a, const R: usize> {
+    targets: [Option<(usize, Id)>; R],
+    index: usize,
+    data: &'a [u8],
+    /// Payload reassembled by the channel node.
+    reassembled: bool,
+}
+
+impl<'a, const R: usize> Forwards<'a, R> {
+    fn empty() -> Self {
+        Self {
+            targets: [None; R],
+            index: 0,
+            data: &[],
+            reassembled: false,
+        }
+    }
+
+    /// Message sent as `id` on the destination channel.
+    fn forward(&self, id: Id) -> Option<Forward<'a>> {
+        if !self.reassembled || self.data.len() <= 8 {
+            return Some(Forward::Frame(id, self.data));
+        }
+
+        let cm = IdBuilder::new()
+            .priority(id.priority())
+            .pgn(Pgn::TransportProtocolConnectionManagement)
+            .sa(id.sa())
+            .da(GLOBAL)
+            .build()?;
+        let transfer = BroadcastTransfer::new(self.data, id.pgn()).ok()?;
+        Some(Forward::Broadcast(cm, transfer))
+    }
+}
+
+impl<'a, const R: usize> Iterator for Forwards<'a, R> {
+    /// Channel and message to send on it.
+    ///
+    /// Reassembled payloads longer than 8 bytes are re-originated as
+    /// broadcast transfers.
+    type Item = (usize, Forward<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(target) = self.targets.get_mut(self.index) {
+            self.index += 1;
+            if let Some((channel, id)) = target.take()
+                && let Some(forward) = self.forward(id)
+            {
+                return Some((channel, forward));
+            }
+        }
+
+        None
+    }
+}
+
+/// Result of handling a received frame.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Handled<'a, const R: usize> {
+    /// Frames to send back on the receiving channel.
+    pub responses: Frames,
+    pub forwards: Forwards<'a, R>,
+}
+
+/// A stack attached to `C` channels with up to `R` routes.
+#[derive(Debug)]
+pub struct Channels<'a, const C: usize, const R: usize> {
+    nodes: [Node<'a>; C],
+    routes: [Option<Route>; R],
+}
+
+impl<'a, const C: usize, const R: usize> Channels<'a, C, R> {
+    /// Create a new stack with one node per channel, without routes.
+    pub fn new(nodes: [Node<'a>; C]) -> Self {
+        Self {
+            nodes,
+            routes: [None; R],
+        }
+    }
+
+    /// Add a route.
+    ///
+    /// Returns the route if either channel does not exist or the stack is
+    /// full.
+    pub fn route(&mut self, route: Route) -> Result<(), Route> {
+        if route.from >= C || route.to >= C || route.from == route.to {
+            return Err(route);
+        }
+
+        match self.routes.iter_mut().find(|r| r.is_none()) {
+            Some(slot) => {
+                *slot = Some(route);
+                Ok(())
+            }
+            None => Err(route),
+        }
+    }
+
+    /// Time at which [`Channels::poll`] has to be called, if any.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.nodes.iter().filter_map(Node::deadline).min()
+    }
+
+    /// Poll the node of `channel` without a received frame, claiming its
+    /// address and sending its transfers.
+    ///
+    /// Returns the frames to send on `channel`.
+    pub fn poll(&mut self, channel: usize, now: Instant) -> Frames {
+        match self.nodes.get_mut(channel) {
+            Some(node) => node.poll(None, now),
+            None => Frames::default(),
+        }
+    }
+
+    /// Node of `channel`.
+    pub fn node(&self, channel: usize) -> Option<&Node<'a>> {
+        self.nodes.get(channel)
+    }
+
+    /// Node of `channel`.
+    pub fn node_mut(&mut self, channel: usize) -> Option<&mut Node<'a>> {
+        self.nodes.get_mut(channel)
+    }
+
+    /// Handle a frame received on `channel`.
+    ///
+    /// Broadcast messages and messages reassembled by the channel node are
+    /// forwarded along matching routes, sent from the address of the node on
+    /// the destination channel once it claimed one. Messages addressed to a
+    /// specific node are not forwarded.
+    pub fn handle<'s>(
+        &'s mut self,
+        channel: usize,
+        id: Id,
+        data: &'s [u8],
+        now: Instant,
+    ) -> Handled<'s, R> {
+        let Self { nodes, routes } = self;
+        let mut targets = [None; R];
+        let addresses: [Option<u8>; C] =
+            core::array::from_fn(|i| nodes[i].address_manager().address());
+
+        let Some(node) = nodes.get_mut(channel) else {
+            return Handled {
+                responses: Frames::default(),
+                forwards: Forwards::empty(),
+            };
+        };
+
+        let responses = node.poll(Some((id, data)), now);
+        let reassembled = node.received();
+
+        let (base, data) = match reassembled {
+            // re-originated globally, as the original destination was the node
+            Some((pgn, _, data)) => {
+                let builder = IdBuilder::new().priority(id.priority()).pgn(pgn).da(GLOBAL);
+                (Some((pgn, builder)), data)
+            }
+            None => {
+                let transport = matches!(
+                    id.pgn(),
+                    Pgn::TransportProtocolConnectionManagement | Pgn::TransportProtocolDataTransfer
+                );
+                let broadcast = id.da().is_none_or(|da| da == GLOBAL);
+                let base = (!transport && broadcast).then(|| {
+                    let builder = IdBuilder::new()
+                        .priority(id.priority())
+                        .pgn(id.pgn())
+                        .edp(id.edp())
+                        .da(GLOBAL);
+                    (id.pgn(), builder)
+                });
+                (base, data)
+            }
+        };
+
+        if let Some((pgn, builder)) = base {
+            let routes = routes
+                .iter()
+                .flatten()
+                .filter(|r| r.from == channel && r.filter.matches(pgn));
+
+            for (target, route) in targets.iter_mut().zip(routes) {
+                *target = addresses[route.to]
+                    .and_then(|sa| builder.sa(sa).build())
+                    .map(|id| (route.to, id));
+            }
+        }
+
+        Handled {
+            responses,
+            forwards: Forwards {
+                targets,
+                index: 0,
+                data,
+                reassembled: reassembled.is_some(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::CLAIM_TIMEOUT;
+    use crate::config::NodeConfig;
+    use crate::name::Name;
+    use crate::transport::{BroadcastAnnounce, RequestToSend};
+
+    fn frame(forward: (usize, Forward<'_>)) -> (usize, Id, &[u8]) {
+        match forward {
+            (channel, Forward::Frame(id, data)) => (channel, id, data),
+            _ => panic!("Expected frame"),
+        }
+    }
+
+    #[test]
+    fn forward_between_channels() {
+        let mut channels = Channels::<3, 2>::new([
+            Node::new(0x80, std::vec![]),
+            Node::new(0x81, std::vec![]),
+            Node::new(0x82, std::vec![]),
+        ]);
+        let route = |to, filter| Route {
+            from: 0,
+            to,
+            filter,
+        };
+        assert!(channels.route(route(1, Filter::Pgn(Pgn::TimeDate))).is_ok());
+        assert!(channels.route(route(2, Filter::Range(0, 0x3FFFF))).is_ok());
+        assert!(
+            channels
+                .route(route(2, Filter::Pgn(Pgn::TimeDate)))
+                .is_err()
+        );
+
+        let now = Instant::default();
+        let td = IdBuilder::new()
+            .pgn(Pgn::TimeDate)
+            .sa(0x10)
+            .build()
+            .unwrap();
+        let forwards: std::vec::Vec<_> = channels
+            .handle(0, td, &[0; 8], now)
+            .forwards
+            .map(frame)
+            .collect();
+        assert_eq!(forwards.len(), 2);
+        assert_eq!((forwards[0].0, forwards[0].1.sa()), (1, 0x81));
+        assert_eq!((forwards[1].0, forwards[1].1.sa()), (2, 0x82));
+        assert_eq!(forwards[1].1.pgn(), Pgn::TimeDate);
+
+        // no routes from channel 1
+        assert!(
+            channels
+                .handle(1, td, &[0; 8], now)
+                .forwards
+                .next()
+                .is_none()
+        );
+
+        // reassembled message addressed to the channel node
+        let id = |pgn| {
+            IdBuilder::new()
+                .priority(7)
+                .pgn(pgn)
+                .sa(0x10)
+                .da(0x80)
+                .build()
+                .unwrap()
+        };
+        let rts: [u8; 8] = RequestToSend::try_new(9, None, Pgn::ProprietaryA)
+            .unwrap()
+            .into();
+        let cm = id(Pgn::TransportProtocolConnectionManagement);
+        let dt = id(Pgn::TransportProtocolDataTransfer);
+        assert!(channels.handle(0, cm, &rts, now).responses.next().is_some());
+        assert!(
+            channels
+                .handle(0, dt, &[1; 8], now)
+                .forwards
+                .next()
+                .is_none()
+        );
+
+        let mut forwards = channels.handle(0, dt, &[2; 8], now).forwards;
+        let Some((2, Forward::Broadcast(id, mut transfer))) = forwards.next() else {
+            panic!("Expected broadcast transfer");
+        };
+        assert_eq!(id.pgn(), Pgn::TransportProtocolConnectionManagement);
+        assert_eq!((id.priority(), id.sa(), id.da()), (7, 0x82, Some(0xFF)));
+        let bam = transfer.announce(now);
+        assert_eq!((bam.pgn(), bam.total_size()), (Pgn::ProprietaryA, 9));
+        assert!(forwards.next().is_none());
+    }
+
+    #[test]
+    fn claim_and_forward_broadcast_transfers() {
+        let config = |sa| NodeConfig::new(Name::new(0x100 + u64::from(sa)), sa);
+        let mut channels = Channels::<2, 1>::new([
+            Node::with_config(config(0x80), std::vec![]).unwrap(),
+            Node::with_config(config(0x81), std::vec![]).unwrap(),
+        ]);
+        let route = Route {
+            from: 0,
+            to: 1,
+            filter: Filter::Pgn(Pgn::ActiveDiagnosticTroubleCodes),
+        };
+        channels.route(route).unwrap();
+        let now = Instant::default();
+
+        // each channel node claims its address
+        assert_eq!(channels.deadline(), Some(now));
+        for channel in 0..2 {
+            let (id, _) = channels.poll(channel, now).next().unwrap();
+            assert_eq!(id.pgn(), Pgn::AddressClaimed);
+            assert_eq!(id.sa(), 0x80 + channel as u8);
+        }
+        assert_eq!(channels.deadline(), Some(now + CLAIM_TIMEOUT));
+
+        // nothing forwarded to a channel without an address
+        let dm1 = IdBuilder::new()
+            .pgn(Pgn::ActiveDiagnosticTroubleCodes)
+            .sa(0x10)
+            .build()
+            .unwrap();
+        assert!(
+            channels
+                .handle(0, dm1, &[0; 8], now)
+                .forwards
+                .next()
+                .is_none()
+        );
+
+        let later = now + CLAIM_TIMEOUT;
+        channels.poll(0, later);
+        channels.poll(1, later);
+        assert_eq!(channels.deadline(), None);
+
+        // DM1 broadcast with the transport protocol
+        let id = |pgn| {
+            IdBuilder::new()
+                .priority(7)
+                .pgn(pgn)
+                .sa(0x10)
+                .da(GLOBAL)
+                .build()
+                .unwrap()
+        };
+        let cm = id(Pgn::TransportProtocolConnectionManagement);
+        let dt = id(Pgn::TransportProtocolDataTransfer);
+        let bam = BroadcastAnnounce::try_new(10, Pgn::ActiveDiagnosticTroubleCodes)
+            .unwrap()
+            .to_bytes();
+        assert!(
+            channels
+                .handle(0, cm, &bam, later)
+                .forwards
+                .next()
+                .is_none()
+        );
+        channels.handle(0, dt, &[1, 0, 0xFF, 1, 0, 1, 1, 2], later);
+
+        let mut forwards = channels
+            .handle(0, dt, &[2, 0, 2, 1, 0xFF, 0xFF, 0xFF, 0xFF], later)
+            .forwards;
+        let Some((1, Forward::Broadcast(id, mut transfer))) = forwards.next() else {
+            panic!("Expected broadcast transfer");
+        };
+        assert_eq!((id.sa(), id.da()), (0x81, Some(GLOBAL)));
+        let bam = transfer.announce(later);
+        assert_eq!(
+            (bam.pgn(), bam.total_size()),
+            (Pgn::ActiveDiagnosticTroubleCodes, 10)
+        );
+    }
+}
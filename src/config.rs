@@ -0,0 +1,233 @@
+//! Node configuration
+//!
+//! [`NodeConfig`] declares the J1939 personality of a node in one place and
+//! is validated up front by [`Node::with_config`](crate::node::Node::with_config).
+
+use crate::id::Pgn;
+use crate::name::Name;
+use crate::time::Duration;
+use core::ops::RangeInclusive;
+
+/// A parameter group sent at a fixed rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Periodic {
+    pub pgn: Pgn,
+    /// Transmission interval.
+    pub interval: Duration,
+    /// Priority between 0 and 7.
+    pub priority: u8,
+}
+
+/// Invalid configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConfigError {
+    /// Preferred address is outside the address range.
+    PreferredAddress,
+    /// Address range is empty, includes the null or global address, or
+    /// spans several addresses without an arbitrary address capable NAME.
+    AddressRange,
+    /// Periodic entry with a zero interval or a priority above 7.
+    Periodic(Pgn),
+    /// Maximum packets per CTS of zero.
+    MaxPacketsPerCts,
+    /// Maximum message size outside of 9 to 1785 bytes.
+    MaxMessageSize,
+}
+
+/// Configuration of a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NodeConfig<'a> {
+    name: Name,
+    preferred_address: u8,
+    address_range: (u8, u8),
+    requests: &'a [Pgn],
+    periodic: &'a [Periodic],
+    max_packets_per_cts: Option<u8>,
+    max_message_size: u16,
+}
+
+impl<'a> NodeConfig<'a> {
+    /// Create a configuration claiming only `preferred_address`.
+    pub fn new(name: Name, preferred_address: u8) -> Self {
+        Self {
+            name,
+            preferred_address,
+            address_range: (preferred_address, preferred_address),
+            requests: &[],
+            periodic: &[],
+            max_packets_per_cts: None,
+            max_message_size: 1785,
+        }
+    }
+
+    /// Inclusive range of addresses the node may claim.
+    pub fn address_range(mut self, first: u8, last: u8) -> Self {
+        self.address_range = (first, last);
+        self
+    }
+
+    /// PGNs the node responds to when requested, with the data of
+    /// [`Events::on_request`](crate::events::Events::on_request).
+    pub fn requests(mut self, requests: &'a [Pgn]) -> Self {
+        self.requests = requests;
+        self
+    }
+
+    /// Parameter groups the node sends periodically once its address is
+    /// claimed, with the data of
+    /// [`Events::on_periodic`](crate::events::Events::on_periodic).
+    pub fn periodic(mut self, periodic: &'a [Periodic]) -> Self {
+        self.periodic = periodic;
+        self
+    }
+
+    /// Limit the number of packets allowed per CTS in transport sessions.
+    ///
+    /// Defaults to the number requested by the sender.
+    pub fn max_packets_per_cts(mut self, max: u8) -> Self {
+        self.max_packets_per_cts = Some(max);
+        self
+    }
+
+    /// Largest message accepted through transport sessions.
+    ///
+    /// Default is 1785 bytes.
+    pub fn max_message_size(mut self, size: u16) -> Self {
+        self.max_message_size = size;
+        self
+    }
+
+    /// Check the configuration for consistency.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let (first, last) = self.address_range;
+        if first > last || last > 253 {
+            return Err(ConfigError::AddressRange);
+        }
+        if first != last && !self.name.arbitrary_address_capable() {
+            return Err(ConfigError::AddressRange);
+        }
+        if !(first..=last).contains(&self.preferred_address) {
+            return Err(ConfigError::PreferredAddress);
+        }
+
+        if let Some(p) = self
+            .periodic
+            .iter()
+            .find(|p| p.interval.as_millis() == 0 || p.priority > 7)
+        {
+            return Err(ConfigError::Periodic(p.pgn));
+        }
+
+        if self.max_packets_per_cts == Some(0) {
+            return Err(ConfigError::MaxPacketsPerCts);
+        }
+        if !(9..=1785).contains(&self.max_message_size) {
+            return Err(ConfigError::MaxMessageSize);
+        }
+
+        Ok(())
+    }
+
+    /// NAME of the node.
+    pub fn name(&self) -> Name {
+        self.name
+    }
+
+    /// Address claimed first.
+    pub fn preferred_address(&self) -> u8 {
+        self.preferred_address
+    }
+
+    /// Addresses the node may claim.
+    pub fn addresses(&self) -> RangeInclusive<u8> {
+        self.address_range.0..=self.address_range.1
+    }
+
+    /// Check if the node may claim `address`.
+    pub fn allows_address(&self, address: u8) -> bool {
+        (self.address_range.0..=self.address_range.1).contains(&address)
+    }
+
+    /// Check if the node responds to requests for `pgn`.
+    pub fn supports_request(&self, pgn: Pgn) -> bool {
+        self.requests.contains(&pgn)
+    }
+
+    /// Periodically sent parameter groups.
+    pub fn periodic_table(&self) -> &'a [Periodic] {
+        self.periodic
+    }
+
+    /// Packets to allow in a CTS answering a sender asking for `requested`.
+    ///
+    /// A `requested` of 0 asks for no limit.
+    pub fn packets_per_cts(&self, requested: u8) -> u8 {
+        let requested = if requested == 0 { 255 } else { requested };
+        match self.max_packets_per_cts {
+            Some(max) => requested.min(max),
+            None => requested,
+        }
+    }
+
+    /// Largest message accepted through transport sessions.
+    pub fn message_size_limit(&self) -> u16 {
+        self.max_message_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate() {
+        let name = Name::new(0);
+        assert_eq!(NodeConfig::new(name, 0x80).validate(), Ok(()));
+        assert_eq!(
+            NodeConfig::new(name, 0x80)
+                .address_range(0x80, 0x8F)
+                .validate(),
+            Err(ConfigError::AddressRange)
+        );
+        assert_eq!(
+            NodeConfig::new(Name::new(1 << 63), 0x70)
+                .address_range(0x80, 0x8F)
+                .validate(),
+            Err(ConfigError::PreferredAddress)
+        );
+        assert_eq!(
+            NodeConfig::new(name, 0xFE).validate(),
+            Err(ConfigError::AddressRange)
+        );
+
+        let periodic = [Periodic {
+            pgn: Pgn::VehicleDistance,
+            interval: Duration::from_millis(0),
+            priority: 6,
+        }];
+        assert_eq!(
+            NodeConfig::new(name, 0x80).periodic(&periodic).validate(),
+            Err(ConfigError::Periodic(Pgn::VehicleDistance))
+        );
+        assert_eq!(
+            NodeConfig::new(name, 0x80).max_message_size(8).validate(),
+            Err(ConfigError::MaxMessageSize)
+        );
+    }
+
+    #[test]
+    fn packets_per_cts() {
+        let config = NodeConfig::new(Name::new(0), 0x80);
+        assert_eq!(config.packets_per_cts(4), 4);
+        assert_eq!(config.packets_per_cts(0), 255);
+
+        let config = config.max_packets_per_cts(2);
+        assert_eq!(config.packets_per_cts(4), 2);
+        assert_eq!(config.packets_per_cts(0), 2);
+    }
+}
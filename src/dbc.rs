@@ -0,0 +1,191 @@
+//! DBC export
+//!
+//! Writes messages and their suspect parameters as a DBC file, so that
+//! messages defined in Rust can be loaded into bus analysis tools such as
+//! CANalyzer or SavvyCAN:
+//!
+//! ```text
+//! BO_ 2364539648 EEC2: 8 Vector__XXX
+//!  SG_ AcceleratorPedalPosition1 : 8|8@1+ (0.4,0) [0|100] "%" Vector__XXX
+//! ```
+//!
+//! Each signal carries its SPN in the `SPN` attribute, following the
+//! convention of J1939 databases.
+
+use crate::id::Id;
+use crate::spn::{Registry, SpnDef};
+use std::io::{self, Write};
+use std::string::String;
+
+/// Node name used for senders and receivers that are not known.
+const NO_NODE: &str = "Vector__XXX";
+
+/// Flag marking an extended identifier in DBC message identifiers.
+const EXTENDED: u32 = 0x8000_0000;
+
+/// A message to export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Message<'a> {
+    /// Message name, such as the acronym of the parameter group.
+    pub name: &'a str,
+    /// Identifier the message is sent with.
+    pub id: Id,
+    /// Data length in bytes.
+    pub len: u8,
+}
+
+/// Write `messages` as a DBC file.
+///
+/// The signals of each message are the parameters in `registry` carried by
+/// the PGN of the message identifier.
+pub fn export<W: Write>(
+    mut out: W,
+    messages: &[Message<'_>],
+    registry: &Registry<'_>,
+) -> io::Result<()> {
+    writeln!(out, "VERSION \"\"")?;
+    writeln!(out)?;
+    writeln!(out, "NS_ :")?;
+    writeln!(out)?;
+    writeln!(out, "BS_:")?;
+    writeln!(out)?;
+    writeln!(out, "BU_:")?;
+
+    for message in messages {
+        let id = message.id.as_raw() | EXTENDED;
+        writeln!(out)?;
+        writeln!(
+            out,
+            "BO_ {} {}: {} {}",
+            id,
+            identifier(message.name),
+            message.len,
+            NO_NODE
+        )?;
+
+        for def in registry.for_pgn(message.id.pgn()) {
+            let (min, max) = range(def);
+            writeln!(
+                out,
+                " SG_ {} : {}|{}@1+ ({},{}) [{}|{}] \"{}\" {}",
+                signal_name(def),
+                def.start,
+                def.len,
+                def.scale,
+                def.offset,
+                min,
+                max,
+                def.unit,
+                NO_NODE
+            )?;
+        }
+    }
+
+    writeln!(out)?;
+    writeln!(out, "BA_DEF_ \"ProtocolType\" STRING ;")?;
+    writeln!(out, "BA_DEF_ SG_ \"SPN\" INT 0 524287;")?;
+    writeln!(out, "BA_DEF_DEF_ \"ProtocolType\" \"\";")?;
+    writeln!(out, "BA_DEF_DEF_ \"SPN\" 0;")?;
+    writeln!(out, "BA_ \"ProtocolType\" \"J1939\";")?;
+
+    for message in messages {
+        let id = message.id.as_raw() | EXTENDED;
+        for def in registry.for_pgn(message.id.pgn()) {
+            writeln!(
+                out,
+                "BA_ \"SPN\" SG_ {} {} {};",
+                id,
+                signal_name(def),
+                def.spn
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Physical range covered by the valid raw values of `def`.
+fn range(def: &SpnDef<'_>) -> (f32, f32) {
+    let max_raw: u32 = match def.len {
+        1 => 1,
+        8 => 0xFA,
+        16 => 0xFAFF,
+        32 => 0xFAFF_FFFF,
+        // short parameters reserve the top two values
+        len => (1u32 << len.min(31)).saturating_sub(3),
+    };
+
+    let a = def.offset;
+    let b = max_raw as f32 * def.scale + def.offset;
+    (a.min(b), a.max(b))
+}
+
+/// Signal name of `def`, falling back to the SPN if it has no name.
+fn signal_name(def: &SpnDef<'_>) -> String {
+    match identifier(def.name) {
+        name if name.is_empty() => std::format!("SPN{}", def.spn),
+        name => name,
+    }
+}
+
+/// Turn `name` into a DBC identifier by joining its words in camel case.
+fn identifier(name: &str) -> String {
+    let mut out = String::new();
+
+    for word in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            if out.is_empty() && first.is_ascii_digit() {
+                out.push('_');
+            }
+            out.push(first.to_ascii_uppercase());
+            out.extend(chars);
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::{IdBuilder, Pgn};
+
+    #[test]
+    fn export_builtin() {
+        let id = IdBuilder::new()
+            .priority(3)
+            .pgn(Pgn::ElectronicEngineController2)
+            .sa(0x00)
+            .build()
+            .unwrap();
+        let messages = [Message {
+            name: "EEC2",
+            id,
+            len: 8,
+        }];
+
+        let mut out = std::vec::Vec::new();
+        export(&mut out, &messages, &Registry::builtin()).unwrap();
+        let dbc = String::from_utf8(out).unwrap();
+
+        assert!(dbc.contains("BO_ 2364539648 EEC2: 8 Vector__XXX\n"));
+        assert!(dbc.contains(
+            " SG_ AcceleratorPedalPosition1 : 8|8@1+ (0.4,0) [0|100] \"%\" Vector__XXX\n"
+        ));
+        assert!(dbc.contains(" SG_ EnginePercentLoadAtCurrentSpeed : 16|8@1+"));
+        assert!(dbc.contains("BA_ \"SPN\" SG_ 2364539648 AcceleratorPedalPosition1 91;\n"));
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(
+            identifier("Battery Potential / Power Input 1"),
+            "BatteryPotentialPowerInput1"
+        );
+        assert_eq!(identifier("1st gear"), "_1stGear");
+        assert_eq!(identifier("°C"), "C");
+        assert_eq!(identifier(""), "");
+    }
+}
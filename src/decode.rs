@@ -0,0 +1,411 @@
+//! Human-readable decoding
+//!
+//! [`decode`] turns a received frame into a [`Decoded`] value that displays
+//! the parameter group, addressing and every parameter known to the crate,
+//! for building sniffers and bus monitors.
+//!
+//! With the `std` and `serde` features, [`Decoded`] serializes to a stable
+//! representation for dashboards and log pipelines:
+//!
+//! ```json
+//! {
+//!   "id": 419362048,
+//!   "priority": 6,
+//!   "pgn": 65269,
+//!   "name": "AmbientConditions",
+//!   "sa": 0,
+//!   "da": null,
+//!   "data": [200, 255, 255, 255, 255, 255, 255, 255],
+//!   "parameters": [
+//!     { "spn": 108, "name": "Barometric Pressure", "value": 100.0, "unit": "kPa" }
+//!   ],
+//!   "message": null
+//! }
+//! ```
+//!
+//! `name` is `null` for parameter groups unknown to the crate and `value` is
+//! `null` for parameters that are not available.
+
+use crate::id::{Id, Pgn};
+use crate::incoming::{Incoming, classify};
+use crate::spn::Registry;
+use core::fmt;
+use std::borrow::Cow;
+use std::format;
+use std::string::String;
+use std::vec::Vec;
+
+/// A decoded parameter value.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(all(feature = "std", feature = "serde"), derive(serde::Serialize))]
+pub struct Parameter {
+    /// Suspect parameter number.
+    pub spn: u32,
+    pub name: Cow<'static, str>,
+    /// Scaled value, `None` if not available or an error indicator.
+    pub value: Option<f32>,
+    pub unit: Cow<'static, str>,
+}
+
+/// A decoded frame.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Decoded {
+    pub id: Id,
+    pub pgn: Pgn,
+    /// Source address.
+    pub sa: u8,
+    /// Destination address of PDU1 messages.
+    pub da: Option<u8>,
+    pub data: Vec<u8>,
+    /// Parameters found in the builtin SPN registry.
+    pub parameters: Vec<Parameter>,
+    /// Contents of protocol messages such as requests, transport and
+    /// diagnostic messages.
+    pub message: Option<String>,
+}
+
+/// Decode a frame using the builtin SPN registry.
+pub fn decode(id: Id, data: &[u8]) -> Decoded {
+    decode_with(&Registry::builtin(), id, data)
+}
+
+/// Decode a frame using the parameters of `registry`.
+pub fn decode_with(registry: &Registry<'static>, id: Id, data: &[u8]) -> Decoded {
+    let pgn = id.pgn();
+
+    let parameters = registry
+        .for_pgn(pgn)
+        .map(|def| Parameter {
+            spn: def.spn,
+            name: Cow::Borrowed(def.name),
+            value: def.value(data),
+            unit: Cow::Borrowed(def.unit),
+        })
+        .collect();
+
+    let message = match classify(id, data) {
+        Incoming::Application(..) => None,
+        Incoming::TransportControl(msg) => Some(format!("{msg:?}")),
+        Incoming::TransportData(msg) => Some(format!("{msg:?}")),
+        Incoming::Diagnostic(msg) => Some(format!("{msg:?}")),
+        Incoming::Request(msg) => Some(format!("{msg:?}")),
+        Incoming::Request2(msg) => Some(format!("{msg:?}")),
+        Incoming::Transfer(msg) => Some(format!("{msg:?}")),
+        Incoming::Acknowledgement(msg) => Some(format!("{msg:?}")),
+        Incoming::Malformed(..) => Some(String::from("malformed")),
+    };
+
+    Decoded {
+        id,
+        pgn,
+        sa: id.sa(),
+        da: id.da(),
+        data: data.to_vec(),
+        parameters,
+        message,
+    }
+}
+
+/// Decode a frame using the builtin SPN registry, falling back to the
+/// signals of `dbc` for parameter groups without builtin parameters.
+///
+/// Messages are looked up by identifier first, then by PGN alone, as J1939
+/// databases list each message with a single priority and source address.
+/// The SPN of a signal is taken from its `SPN` attribute, or 0 if missing.
+#[cfg(feature = "can-dbc")]
+pub fn decode_dbc(dbc: &can_dbc::Dbc, id: Id, data: &[u8]) -> Decoded {
+    use can_dbc::{MessageId, MultiplexIndicator};
+
+    let mut decoded = decode(id, data);
+    if !decoded.parameters.is_empty() {
+        return decoded;
+    }
+
+    let message = dbc
+        .messages
+        .iter()
+        .find(|m| m.id == MessageId::Extended(id.as_raw()))
+        .or_else(|| {
+            dbc.messages.iter().find(
+                |m| matches!(m.id, MessageId::Extended(raw) if Id::new(raw).pgn() == decoded.pgn),
+            )
+        });
+    let Some(message) = message else {
+        return decoded;
+    };
+
+    let switch = message
+        .signals
+        .iter()
+        .find(|s| matches!(s.multiplexer_indicator, MultiplexIndicator::Multiplexor))
+        .and_then(|s| dbc::raw(s, data));
+
+    for signal in &message.signals {
+        let selected = match signal.multiplexer_indicator {
+            MultiplexIndicator::Plain | MultiplexIndicator::Multiplexor => true,
+            MultiplexIndicator::MultiplexedSignal(value)
+            | MultiplexIndicator::MultiplexorAndMultiplexedSignal(value) => switch == Some(value),
+        };
+        if !selected {
+            continue;
+        }
+
+        let spn = match dbc.signal_attribute(message.id, &signal.name, "SPN") {
+            Some(can_dbc::AttributeValue::Uint(spn)) => u32::try_from(*spn).unwrap_or_default(),
+            Some(can_dbc::AttributeValue::Int(spn)) => u32::try_from(*spn).unwrap_or_default(),
+            _ => 0,
+        };
+
+        decoded.parameters.push(Parameter {
+            spn,
+            name: Cow::Owned(signal.name.clone()),
+            value: dbc::value(signal, data),
+            unit: Cow::Owned(signal.unit.clone()),
+        });
+    }
+
+    decoded
+}
+
+/// Signal extraction from DBC definitions.
+#[cfg(feature = "can-dbc")]
+mod dbc {
+    use can_dbc::{ByteOrder, Signal, ValueType};
+
+    /// Raw value of `signal` within `data`, `None` if `data` is too short.
+    pub(super) fn raw(signal: &Signal, data: &[u8]) -> Option<u64> {
+        let len = signal.size;
+        if len == 0 || len > 64 {
+            return None;
+        }
+
+        let bit = |pos: u64| -> Option<u64> {
+            let byte = data.get(usize::try_from(pos / 8).ok()?)?;
+            Some(u64::from(byte >> (pos % 8)) & 1)
+        };
+
+        let mut value = 0u64;
+        match signal.byte_order {
+            ByteOrder::LittleEndian => {
+                for i in (0..len).rev() {
+                    value = (value << 1) | bit(signal.start_bit + i)?;
+                }
+            }
+            ByteOrder::BigEndian => {
+                // start bit is the most significant bit, walking down each
+                // byte before moving on to the next one
+                let mut pos = signal.start_bit;
+                for _ in 0..len {
+                    value = (value << 1) | bit(pos)?;
+                    pos = if pos.is_multiple_of(8) {
+                        pos + 15
+                    } else {
+                        pos - 1
+                    };
+                }
+            }
+        }
+
+        Some(value)
+    }
+
+    /// Scaled value of `signal` within `data`.
+    ///
+    /// Unsigned signals of up to 32 bits follow the J1939 ranges for error
+    /// indicators and not available values.
+    pub(super) fn value(signal: &Signal, data: &[u8]) -> Option<f32> {
+        let raw = raw(signal, data)?;
+
+        let value = match signal.value_type {
+            ValueType::Unsigned => {
+                if let (Ok(len), Ok(raw)) = (u8::try_from(signal.size), u32::try_from(raw))
+                    && len <= 32
+                    && !crate::spn::valid(raw, len)
+                {
+                    return None;
+                }
+                raw as f64
+            }
+            ValueType::Signed if signal.size < 64 => {
+                let shift = 64 - signal.size;
+                ((raw << shift) as i64 >> shift) as f64
+            }
+            ValueType::Signed => raw as i64 as f64,
+        };
+
+        Some((value * signal.factor + signal.offset) as f32)
+    }
+}
+
+impl fmt::Display for Decoded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:08X} {:?} ({}) {:02X} -> ",
+            self.id.as_raw(),
+            self.pgn,
+            u32::from(self.pgn),
+            self.sa
+        )?;
+
+        match self.da {
+            Some(da) => write!(f, "{da:02X}")?,
+            None => write!(f, "FF")?,
+        }
+
+        write!(f, " [")?;
+        for (i, byte) in self.data.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{byte:02X}")?;
+        }
+        write!(f, "]")?;
+
+        if let Some(message) = &self.message {
+            write!(f, "\n  {message}")?;
+        }
+
+        for p in &self.parameters {
+            match p.value {
+                Some(value) => write!(f, "\n  SPN {} {}: {} {}", p.spn, p.name, value, p.unit)?,
+                None => write!(f, "\n  SPN {} {}: n/a", p.spn, p.name)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "std", feature = "serde"))]
+impl serde::Serialize for Decoded {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let name = match self.pgn {
+            Pgn::Other(_) => None,
+            pgn => Some(format!("{pgn:?}")),
+        };
+
+        let mut s = serializer.serialize_struct("Decoded", 9)?;
+        s.serialize_field("id", &self.id.as_raw())?;
+        s.serialize_field("priority", &self.id.priority())?;
+        s.serialize_field("pgn", &u32::from(self.pgn))?;
+        s.serialize_field("name", &name)?;
+        s.serialize_field("sa", &self.sa)?;
+        s.serialize_field("da", &self.da)?;
+        s.serialize_field("data", &self.data)?;
+        s.serialize_field("parameters", &self.parameters)?;
+        s.serialize_field("message", &self.message)?;
+        s.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IdBuilder;
+    use std::string::ToString;
+
+    #[test]
+    fn display() {
+        let id = IdBuilder::new()
+            .priority(6)
+            .pgn(Pgn::AmbientConditions)
+            .sa(0x00)
+            .build()
+            .unwrap();
+        let decoded = decode(id, &[200, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(decoded.parameters.len(), 4);
+        assert_eq!(decoded.parameters[0].value, Some(100.0));
+        assert!(decoded.message.is_none());
+
+        let text = decoded.to_string();
+        assert!(text.starts_with("18FEF500 AmbientConditions (65269) 00 -> FF"));
+        assert!(text.contains("SPN 108 Barometric Pressure: 100 kPa"));
+        assert!(text.contains("SPN 171 Ambient Air Temperature: n/a"));
+    }
+
+    #[cfg(all(feature = "std", feature = "serde"))]
+    #[test]
+    fn json() {
+        let id = IdBuilder::new()
+            .priority(6)
+            .pgn(Pgn::AmbientConditions)
+            .sa(0x00)
+            .build()
+            .unwrap();
+        let decoded = decode(id, &[200, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+
+        let json = serde_json::to_value(&decoded).unwrap();
+        assert_eq!(json["id"], 0x18FEF500);
+        assert_eq!(json["priority"], 6);
+        assert_eq!(json["pgn"], 65269);
+        assert_eq!(json["name"], "AmbientConditions");
+        assert_eq!(json["da"], serde_json::Value::Null);
+        assert_eq!(json["data"][0], 200);
+        assert_eq!(json["parameters"][0]["spn"], 108);
+        assert_eq!(json["parameters"][0]["value"], 100.0);
+        assert_eq!(json["parameters"][0]["unit"], "kPa");
+        assert_eq!(json["parameters"][3]["value"], serde_json::Value::Null);
+
+        let unknown = Id::new(0x18FE0100);
+        assert_eq!(unknown.pgn(), Pgn::Other(0xFE01));
+        let json = serde_json::to_value(decode(unknown, &[])).unwrap();
+        assert_eq!(json["name"], serde_json::Value::Null);
+    }
+
+    #[cfg(feature = "can-dbc")]
+    #[test]
+    fn dbc() {
+        const DBC: &str = r#"VERSION ""
+
+NS_ :
+
+BS_:
+
+BU_:
+
+BO_ 2566852862 PROP_B: 8 Vector__XXX
+ SG_ Mux M : 0|8@1+ (1,0) [0|255] "" Vector__XXX
+ SG_ Level m1 : 8|8@1+ (0.5,0) [0|125] "%" Vector__XXX
+ SG_ Other m2 : 8|8@1+ (1,0) [0|250] "" Vector__XXX
+ SG_ Temp : 16|16@1- (0.1,0) [-3276.8|3276.7] "degC" Vector__XXX
+ SG_ Speed : 39|16@0+ (1,0) [0|64255] "rpm" Vector__XXX
+
+BA_DEF_ SG_ "SPN" INT 0 524287;
+BA_ "SPN" SG_ 2566852862 Level 520000;
+"#;
+        let dbc = can_dbc::Dbc::try_from(DBC).unwrap();
+
+        // sent with another source address than listed in the database
+        let id = IdBuilder::new()
+            .priority(6)
+            .pgn(Pgn::ProprietaryB(0x10))
+            .sa(0x20)
+            .build()
+            .unwrap();
+        let data = [1, 100, 0x18, 0xFC, 0x12, 0x34, 0xFF, 0xFF];
+        let decoded = decode_dbc(&dbc, id, &data);
+        assert_eq!(decoded.parameters.len(), 4);
+
+        let level = &decoded.parameters[1];
+        assert_eq!(level.spn, 520000);
+        assert_eq!(level.name, "Level");
+        assert_eq!(level.value, Some(50.0));
+        assert_eq!(level.unit, "%");
+
+        let temp = &decoded.parameters[2];
+        assert_eq!(temp.spn, 0);
+        assert!((temp.value.unwrap() + 100.0).abs() < 0.01);
+
+        let speed = &decoded.parameters[3];
+        assert_eq!(speed.value, Some(0x1234 as f32));
+
+        // builtin parameters take precedence
+        let amb = Id::new(0x18FEF500);
+        assert_eq!(decode_dbc(&dbc, amb, &data).parameters[0].spn, 108);
+    }
+}
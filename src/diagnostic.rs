@@ -1,8 +1,37 @@
 //! Diagnostics (J1939-73)
 
+use crate::error::{DecodeError, exact};
+use core::hash::{Hash, Hasher};
+use managed::ManagedSlice;
+
+/// Invalid diagnostic message arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DiagnosticError {
+    /// Length above the 11 bit maximum of 2047.
+    Length,
+    /// Command above the 3 bit maximum of 7.
+    Command,
+    /// Status above the 3 bit maximum of 7.
+    Status,
+    /// Error indicator above the 24 bit maximum.
+    ErrorIndicator,
+    /// SPN above the 19 bit maximum.
+    Spn,
+    /// FMI above the 5 bit maximum of 31.
+    Fmi,
+    /// Occurrence count above the 7 bit maximum of 126.
+    OccurrenceCount,
+}
+
+/// Largest length of a memory access message.
+const MAX_LENGTH: u16 = 0b111_1111_1111;
+
 /// DM14 - Memory Access Request
-#[derive(Debug, Clone, PartialEq, Eq)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MemoryAccessRequest {
     raw: [u8; 8],
 }
@@ -10,9 +39,33 @@ pub struct MemoryAccessRequest {
 impl MemoryAccessRequest {
     /// Create a new memory access request.
     ///
-    /// Panics if `length` is greater than 2^11.
+    /// # Panics
+    ///
+    /// Panics if the arguments are rejected by [`MemoryAccessRequest::try_new`].
+    #[deprecated(note = "panics on invalid arguments, use `try_new`")]
+    #[allow(clippy::panic)]
     pub fn new(command: Command, pointer: Pointer, length: u16, key_or_user_level: u16) -> Self {
-        assert!(length <= 0b11111111111);
+        match Self::try_new(command, pointer, length, key_or_user_level) {
+            Ok(request) => request,
+            Err(e) => panic!("invalid memory access request: {e:?}"),
+        }
+    }
+
+    /// Create a new memory access request.
+    ///
+    /// `length` must not exceed 2047 and `command` must fit in 3 bits.
+    pub fn try_new(
+        command: Command,
+        pointer: Pointer,
+        length: u16,
+        key_or_user_level: u16,
+    ) -> Result<Self, DiagnosticError> {
+        if length > MAX_LENGTH {
+            return Err(DiagnosticError::Length);
+        }
+        if u8::from(command) > 0b111 {
+            return Err(DiagnosticError::Command);
+        }
 
         let mut raw = [0; 8];
 
@@ -31,9 +84,48 @@ impl MemoryAccessRequest {
 
         raw[6..8].copy_from_slice(&key_or_user_level.to_le_bytes());
 
-        Self { raw }
+        Ok(Self { raw })
+    }
+
+    /// Borrowed view of this request.
+    pub fn view(&self) -> MemoryAccessRequestRef<'_> {
+        MemoryAccessRequestRef { raw: &self.raw }
+    }
+
+    /// The number of bytes to apply the memory operation to.
+    pub fn length(&self) -> u16 {
+        self.view().length()
+    }
+
+    /// The command type.
+    pub fn command(&self) -> Command {
+        self.view().command()
+    }
+
+    /// Memory address or object identifier.
+    pub fn pointer(&self) -> Pointer {
+        self.view().pointer()
+    }
+
+    /// Security key or user level, depending on context.
+    pub fn key_or_user_level(&self) -> u16 {
+        self.view().key_or_user_level()
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
     }
+}
 
+/// DM14 - Memory Access Request, interpreted in place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MemoryAccessRequestRef<'a> {
+    raw: &'a [u8; 8],
+}
+
+impl<'a> MemoryAccessRequestRef<'a> {
     /// The number of bytes to apply the memory operation to.
     pub fn length(&self) -> u16 {
         u16::from_le_bytes([self.raw[0], (self.raw[1] >> 5) & 0b111])
@@ -58,6 +150,31 @@ impl MemoryAccessRequest {
     pub fn key_or_user_level(&self) -> u16 {
         u16::from_le_bytes([self.raw[6], self.raw[7]])
     }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &'a [u8; 8] {
+        self.raw
+    }
+}
+
+impl<'a> From<&'a [u8; 8]> for MemoryAccessRequestRef<'a> {
+    fn from(raw: &'a [u8; 8]) -> Self {
+        Self { raw }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for MemoryAccessRequestRef<'a> {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self { raw: exact(value)? })
+    }
+}
+
+impl From<MemoryAccessRequestRef<'_>> for MemoryAccessRequest {
+    fn from(view: MemoryAccessRequestRef<'_>) -> Self {
+        Self { raw: *view.raw }
+    }
 }
 
 impl From<&MemoryAccessRequest> for [u8; 8] {
@@ -67,18 +184,19 @@ impl From<&MemoryAccessRequest> for [u8; 8] {
 }
 
 impl<'a> TryFrom<&'a [u8]> for MemoryAccessRequest {
-    type Error = &'a [u8];
+    type Error = DecodeError;
 
     fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
         Ok(Self {
-            raw: value.try_into().map_err(|_| value)?,
+            raw: *exact(value)?,
         })
     }
 }
 
 /// Memory access request command.
 #[derive(Debug, Clone, Copy, Eq)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Command {
     Erase,
     Read,
@@ -98,6 +216,12 @@ impl PartialEq for Command {
     }
 }
 
+impl Hash for Command {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        u8::from(*self).hash(state);
+    }
+}
+
 impl From<Command> for u8 {
     fn from(value: Command) -> Self {
         match value {
@@ -131,16 +255,18 @@ impl From<u8> for Command {
 }
 
 /// Direct or spatial memory addressing.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Pointer {
     Direct(u32),
     Spatial(u32),
 }
 
 /// DM15 - Memory Access Response
-#[derive(Debug, Clone, PartialEq, Eq)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MemoryAccessResponse {
     raw: [u8; 8],
 }
@@ -148,9 +274,37 @@ pub struct MemoryAccessResponse {
 impl MemoryAccessResponse {
     /// Create a new memory access response.
     ///
-    /// Panics if `length` is greater than 2 ^ 11.
+    /// # Panics
+    ///
+    /// Panics if the arguments are rejected by [`MemoryAccessResponse::try_new`].
+    #[deprecated(note = "panics on invalid arguments, use `try_new`")]
+    #[allow(clippy::panic)]
     pub fn new(status: Status, error_indicator: ErrorIndicator, length: u16, seed: u16) -> Self {
-        assert!(length <= 0b11111111111);
+        match Self::try_new(status, error_indicator, length, seed) {
+            Ok(response) => response,
+            Err(e) => panic!("invalid memory access response: {e:?}"),
+        }
+    }
+
+    /// Create a new memory access response.
+    ///
+    /// `length` must not exceed 2047, `status` must fit in 3 bits and
+    /// `error_indicator` in 24 bits.
+    pub fn try_new(
+        status: Status,
+        error_indicator: ErrorIndicator,
+        length: u16,
+        seed: u16,
+    ) -> Result<Self, DiagnosticError> {
+        if length > MAX_LENGTH {
+            return Err(DiagnosticError::Length);
+        }
+        if u8::from(status) > 0b111 {
+            return Err(DiagnosticError::Status);
+        }
+        if matches!(error_indicator, ErrorIndicator::Other(o) if o > 0xFFFFFF) {
+            return Err(DiagnosticError::ErrorIndicator);
+        }
 
         let mut raw = [0; 8];
 
@@ -165,9 +319,44 @@ impl MemoryAccessResponse {
 
         raw[6..8].copy_from_slice(&seed.to_le_bytes());
 
-        Self { raw }
+        Ok(Self { raw })
+    }
+
+    /// Borrowed view of this response.
+    pub fn view(&self) -> MemoryAccessResponseRef<'_> {
+        MemoryAccessResponseRef { raw: &self.raw }
+    }
+
+    pub fn length(&self) -> u16 {
+        self.view().length()
+    }
+
+    pub fn status(&self) -> Status {
+        self.view().status()
+    }
+
+    pub fn error_indicator(&self) -> ErrorIndicator {
+        self.view().error_indicator()
     }
 
+    pub fn seed(&self) -> u16 {
+        self.view().seed()
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+/// DM15 - Memory Access Response, interpreted in place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MemoryAccessResponseRef<'a> {
+    raw: &'a [u8; 8],
+}
+
+impl<'a> MemoryAccessResponseRef<'a> {
     pub fn length(&self) -> u16 {
         u16::from_le_bytes([self.raw[0], (self.raw[1] >> 5) & 0b111])
     }
@@ -184,6 +373,31 @@ impl MemoryAccessResponse {
     pub fn seed(&self) -> u16 {
         u16::from_le_bytes([self.raw[6], self.raw[7]])
     }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &'a [u8; 8] {
+        self.raw
+    }
+}
+
+impl<'a> From<&'a [u8; 8]> for MemoryAccessResponseRef<'a> {
+    fn from(raw: &'a [u8; 8]) -> Self {
+        Self { raw }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for MemoryAccessResponseRef<'a> {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self { raw: exact(value)? })
+    }
+}
+
+impl From<MemoryAccessResponseRef<'_>> for MemoryAccessResponse {
+    fn from(view: MemoryAccessResponseRef<'_>) -> Self {
+        Self { raw: *view.raw }
+    }
 }
 
 impl From<&MemoryAccessResponse> for [u8; 8] {
@@ -193,18 +407,19 @@ impl From<&MemoryAccessResponse> for [u8; 8] {
 }
 
 impl<'a> TryFrom<&'a [u8]> for MemoryAccessResponse {
-    type Error = &'a [u8];
+    type Error = DecodeError;
 
     fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
         Ok(Self {
-            raw: value.try_into().map_err(|_| value)?,
+            raw: *exact(value)?,
         })
     }
 }
 
 /// Memory access response status.
 #[derive(Debug, Clone, Copy, Eq)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Status {
     Proceed,
     Busy,
@@ -220,6 +435,12 @@ impl PartialEq for Status {
     }
 }
 
+impl Hash for Status {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        u8::from(*self).hash(state);
+    }
+}
+
 impl From<Status> for u8 {
     fn from(value: Status) -> Self {
         match value {
@@ -245,8 +466,13 @@ impl From<u8> for Status {
 }
 
 /// Error indicator state.
-#[derive(Debug, Clone, Copy, Eq)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+///
+/// Without the `descriptions` feature, `Debug` prints the raw value instead
+/// of the variant name.
+#[derive(Clone, Copy, Eq)]
+#[cfg_attr(feature = "descriptions", derive(Debug))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ErrorIndicator {
     None,
     NotIdentified,
@@ -298,7 +524,21 @@ impl PartialEq for ErrorIndicator {
     }
 }
 
+impl Hash for ErrorIndicator {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        u32::from(*self).hash(state);
+    }
+}
+
+#[cfg(not(feature = "descriptions"))]
+impl core::fmt::Debug for ErrorIndicator {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "ErrorIndicator({:#x})", u32::from(*self))
+    }
+}
+
 impl From<ErrorIndicator> for u32 {
+    /// Only the low 24 bits of [`ErrorIndicator::Other`] are kept.
     fn from(value: ErrorIndicator) -> Self {
         let result = match value {
             ErrorIndicator::None => 0x000000,
@@ -344,18 +584,14 @@ impl From<ErrorIndicator> for u32 {
             ErrorIndicator::Other(o) => o,
         };
 
-        // ensure the returned value is only 24-bits.
-        debug_assert!(result <= 0xFFFFFF);
-
-        result
+        result & 0xFFFFFF
     }
 }
 
 impl From<u32> for ErrorIndicator {
+    /// Only the low 24 bits of `value` are used.
     fn from(value: u32) -> Self {
-        debug_assert!(value <= 0xFFFFFF);
-
-        match value {
+        match value & 0xFFFFFF {
             0x000000 => Self::None,
             0x000001 => ErrorIndicator::NotIdentified,
             0x000002 => ErrorIndicator::BusyForSomeoneElse,
@@ -402,8 +638,9 @@ impl From<u32> for ErrorIndicator {
 }
 
 /// EDCP Extension State.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EdcpExtensionState {
     Completed,
     ConcatenateFollowingAsHigherOrder,
@@ -413,9 +650,67 @@ pub enum EdcpExtensionState {
     NoIndicatorAvailable,
 }
 
+/// DM16 - Binary Data Transfer
+///
+/// Single frame form carrying up to 7 bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BinaryDataTransfer {
+    raw: [u8; 8],
+}
+
+impl BinaryDataTransfer {
+    /// Largest payload of a single frame.
+    pub const MAX_LEN: usize = 7;
+
+    /// Create a new binary data transfer.
+    ///
+    /// Returns `None` if `data` is longer than [`Self::MAX_LEN`].
+    pub fn new(data: &[u8]) -> Option<Self> {
+        let mut raw = [0xFF; 8];
+        raw[0] = data.len() as u8;
+        raw.get_mut(1..=data.len())?.copy_from_slice(data);
+        Some(Self { raw })
+    }
+
+    /// Transferred bytes.
+    pub fn data(&self) -> &[u8] {
+        &self.raw[1..=(self.raw[0] as usize).min(Self::MAX_LEN)]
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&BinaryDataTransfer> for [u8; 8] {
+    fn from(value: &BinaryDataTransfer) -> Self {
+        value.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for BinaryDataTransfer {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let raw = *exact::<8>(value)?;
+        if raw[0] as usize > Self::MAX_LEN {
+            return Err(DecodeError::Value {
+                offset: 0,
+                value: raw[0],
+            });
+        }
+
+        Ok(Self { raw })
+    }
+}
+
 /// DM17 - Boot Load Data
-#[derive(Debug, Clone, PartialEq, Eq)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BootLoadData {
     raw: [u8; 8],
 }
@@ -424,6 +719,11 @@ impl BootLoadData {
     pub fn data(&self) -> [u8; 8] {
         self.raw
     }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
 }
 
 impl From<&BootLoadData> for [u8; 8] {
@@ -433,15 +733,297 @@ impl From<&BootLoadData> for [u8; 8] {
 }
 
 impl<'a> TryFrom<&'a [u8]> for BootLoadData {
-    type Error = &'a [u8];
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+/// Diagnostic trouble code
+///
+/// Identified by its SPN and FMI, the occurrence count is not part of the
+/// identity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dtc {
+    spn: u32,
+    fmi: u8,
+    occurrence_count: u8,
+}
+
+impl Dtc {
+    /// Create a new DTC.
+    ///
+    /// `spn` must fit in 19 bits, `fmi` in 5 bits and `occurrence_count` must
+    /// not exceed 126.
+    pub fn try_new(spn: u32, fmi: u8, occurrence_count: u8) -> Result<Self, DiagnosticError> {
+        if spn >= 1 << 19 {
+            return Err(DiagnosticError::Spn);
+        }
+        if fmi > 31 {
+            return Err(DiagnosticError::Fmi);
+        }
+        if occurrence_count > 126 {
+            return Err(DiagnosticError::OccurrenceCount);
+        }
+
+        Ok(Self {
+            spn,
+            fmi,
+            occurrence_count,
+        })
+    }
+
+    /// Suspect parameter number.
+    pub fn spn(&self) -> u32 {
+        self.spn
+    }
+
+    /// Failure mode identifier.
+    pub fn fmi(&self) -> u8 {
+        self.fmi
+    }
+
+    /// Number of times the DTC became active.
+    pub fn occurrence_count(&self) -> u8 {
+        self.occurrence_count
+    }
+
+    /// Check if `other` has the same SPN and FMI.
+    pub fn matches(&self, other: &Dtc) -> bool {
+        self.spn == other.spn && self.fmi == other.fmi
+    }
+}
+
+impl From<&Dtc> for [u8; 4] {
+    fn from(value: &Dtc) -> Self {
+        let spn = value.spn.to_le_bytes();
+        [
+            spn[0],
+            spn[1],
+            (spn[2] << 5) | value.fmi,
+            value.occurrence_count,
+        ]
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for Dtc {
+    type Error = DecodeError;
 
+    /// Parse a DTC, ignoring the conversion method bit.
     fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let raw = exact::<4>(value)?;
         Ok(Self {
-            raw: value.try_into().map_err(|_| value)?,
+            spn: u32::from_le_bytes([raw[0], raw[1], raw[2] >> 5, 0]),
+            fmi: raw[2] & 0x1F,
+            occurrence_count: raw[3] & 0x7F,
         })
     }
 }
 
+/// Lamp status of DM1 and DM2 messages.
+///
+/// Lamps are sent without flashing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lamps {
+    /// Malfunction indicator lamp, for emission related faults.
+    pub malfunction_indicator: bool,
+    /// Red stop lamp, for faults severe enough to stop the vehicle.
+    pub red_stop: bool,
+    /// Amber warning lamp, for faults not requiring an immediate stop.
+    pub amber_warning: bool,
+    /// Protect lamp, for faults of non-electronic subsystems.
+    pub protect: bool,
+}
+
+impl From<Lamps> for [u8; 2] {
+    fn from(value: Lamps) -> Self {
+        let status = (u8::from(value.malfunction_indicator) << 6)
+            | (u8::from(value.red_stop) << 4)
+            | (u8::from(value.amber_warning) << 2)
+            | u8::from(value.protect);
+        [status, 0xFF]
+    }
+}
+
+/// Storage entry of a [`DiagnosticServer`], empty by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DtcSlot(Option<(Dtc, bool)>);
+
+/// Active and previously active DTCs of a node.
+///
+/// Encodes DM1 and DM2 messages and applies DM3 and DM11 clears. Holds as
+/// many DTCs as its storage has slots.
+#[derive(Debug)]
+pub struct DiagnosticServer<'a> {
+    dtcs: ManagedSlice<'a, DtcSlot>,
+    lamps: Lamps,
+    /// Set by changes of the active DTCs or lamps until the next DM1.
+    changed: bool,
+}
+
+impl<'a> DiagnosticServer<'a> {
+    /// Create a new server without DTCs, holding them in `storage`.
+    pub fn new(storage: impl Into<ManagedSlice<'a, DtcSlot>>) -> Self {
+        Self {
+            dtcs: storage.into(),
+            lamps: Lamps::default(),
+            changed: false,
+        }
+    }
+
+    /// Current lamp status.
+    pub fn lamps(&self) -> Lamps {
+        self.lamps
+    }
+
+    /// Update the lamp status.
+    pub fn set_lamps(&mut self, lamps: Lamps) {
+        self.changed |= lamps != self.lamps;
+        self.lamps = lamps;
+    }
+
+    /// Mark `dtc` as active.
+    ///
+    /// A previously active DTC with the same SPN and FMI becomes active again
+    /// with its occurrence count incremented. Returns `dtc` if the storage is
+    /// full.
+    pub fn activate(&mut self, dtc: Dtc) -> Result<(), Dtc> {
+        if let Some(slot) = self
+            .dtcs
+            .iter_mut()
+            .find(|slot| slot.0.is_some_and(|(known, _)| known.matches(&dtc)))
+        {
+            if let Some((known, false)) = slot.0 {
+                let occurrence_count = (known.occurrence_count + 1).min(126);
+                *slot = DtcSlot(Some((
+                    Dtc {
+                        occurrence_count,
+                        ..known
+                    },
+                    true,
+                )));
+                self.changed = true;
+            }
+            return Ok(());
+        }
+
+        let slot = self
+            .dtcs
+            .iter_mut()
+            .find(|slot| slot.0.is_none())
+            .ok_or(dtc)?;
+        *slot = DtcSlot(Some((dtc, true)));
+        self.changed = true;
+        Ok(())
+    }
+
+    /// Mark the active DTC with `spn` and `fmi` as previously active.
+    ///
+    /// Returns the DTC, if it was active.
+    pub fn deactivate(&mut self, spn: u32, fmi: u8) -> Option<Dtc> {
+        let slot = self.dtcs.iter_mut().find(|slot| {
+            slot.0
+                .is_some_and(|(dtc, active)| active && dtc.spn == spn && dtc.fmi == fmi)
+        })?;
+        let (dtc, _) = slot.0?;
+        *slot = DtcSlot(Some((dtc, false)));
+        self.changed = true;
+        Some(dtc)
+    }
+
+    /// Active DTCs.
+    pub fn active(&self) -> impl Iterator<Item = Dtc> + '_ {
+        self.dtcs(true)
+    }
+
+    /// Previously active DTCs.
+    pub fn previously_active(&self) -> impl Iterator<Item = Dtc> + '_ {
+        self.dtcs(false)
+    }
+
+    /// Remove the active DTCs, as requested by DM11, calling `cleared` with
+    /// each.
+    pub fn clear_active(&mut self, cleared: impl FnMut(Dtc)) {
+        self.clear(true, cleared);
+    }
+
+    /// Remove the previously active DTCs, as requested by DM3, calling
+    /// `cleared` with each.
+    pub fn clear_previously_active(&mut self, cleared: impl FnMut(Dtc)) {
+        self.clear(false, cleared);
+    }
+
+    /// Encode the DM1 message into `buf`.
+    ///
+    /// Returns the length, or `None` if `buf` is too small.
+    pub fn encode_active(&self, buf: &mut [u8]) -> Option<usize> {
+        encode(self.lamps, self.active(), buf)
+    }
+
+    /// Encode the DM2 message into `buf`, with all lamps off.
+    ///
+    /// Returns the length, or `None` if `buf` is too small.
+    pub fn encode_previously_active(&self, buf: &mut [u8]) -> Option<usize> {
+        encode(Lamps::default(), self.previously_active(), buf)
+    }
+
+    /// Check if the active DTCs or the lamps changed since the last call.
+    pub(crate) fn take_changed(&mut self) -> bool {
+        core::mem::take(&mut self.changed)
+    }
+
+    fn dtcs(&self, active: bool) -> impl Iterator<Item = Dtc> + '_ {
+        self.dtcs
+            .iter()
+            .filter_map(move |slot| slot.0.filter(|(_, a)| *a == active).map(|(dtc, _)| dtc))
+    }
+
+    fn clear(&mut self, active: bool, mut cleared: impl FnMut(Dtc)) {
+        for slot in self.dtcs.iter_mut() {
+            if let Some((dtc, a)) = slot.0
+                && a == active
+            {
+                *slot = DtcSlot(None);
+                self.changed |= active;
+                cleared(dtc);
+            }
+        }
+    }
+}
+
+/// Encode a DM1 or DM2 message into `buf`.
+///
+/// Without DTCs, a zero DTC is sent. Messages shorter than a frame are
+/// padded with `0xFF`.
+fn encode(lamps: Lamps, dtcs: impl Iterator<Item = Dtc>, buf: &mut [u8]) -> Option<usize> {
+    buf.get_mut(..2)?.copy_from_slice(&<[u8; 2]>::from(lamps));
+
+    let mut len = 2;
+    for dtc in dtcs {
+        buf.get_mut(len..len + 4)?
+            .copy_from_slice(&<[u8; 4]>::from(&dtc));
+        len += 4;
+    }
+
+    if len == 2 {
+        buf.get_mut(2..6)?.fill(0);
+        len = 6;
+    }
+    if len < 8 {
+        buf.get_mut(len..8)?.fill(0xFF);
+        len = 8;
+    }
+    Some(len)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -458,12 +1040,133 @@ mod tests {
         // check we get the same result when we serialize back into bytes.
         let bytes: [u8; 8] = (&rq).into();
         assert_eq!(raw, bytes);
+        assert_eq!(rq.as_bytes(), &bytes);
+        assert_eq!(rq.view().as_bytes(), &bytes);
+    }
+
+    #[test]
+    fn views() {
+        let raw = [0x20, 0x22, 0x45, 0x23, 0x01, 0x00, 0x00, 0x00];
+        let view = MemoryAccessRequestRef::from(&raw);
+        assert_eq!(view.length(), 288);
+        assert_eq!(view.command(), Command::Read);
+        assert_eq!(MemoryAccessRequest::from(view).view(), view);
+
+        let view = MemoryAccessResponseRef::try_from(&raw[..]).unwrap();
+        assert_eq!(view.status(), Status::Busy);
+        assert_eq!(view.error_indicator(), ErrorIndicator::Other(0x012345));
+        assert!(MemoryAccessResponseRef::try_from(&raw[..7]).is_err());
+    }
+
+    #[test]
+    fn binary_data_transfer() {
+        let dm16 = BinaryDataTransfer::new(&[1, 2, 3]).unwrap();
+        let raw: [u8; 8] = (&dm16).into();
+        assert_eq!(raw, [3, 1, 2, 3, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(
+            BinaryDataTransfer::try_from(&raw[..]).unwrap().data(),
+            &[1, 2, 3]
+        );
+        assert!(BinaryDataTransfer::new(&[0; 8]).is_none());
+        assert_eq!(
+            BinaryDataTransfer::try_from(&[8; 8][..]),
+            Err(DecodeError::Value {
+                offset: 0,
+                value: 8
+            })
+        );
     }
 
     #[test]
     fn memory_access_request_spatial() {
-        let rq = MemoryAccessRequest::new(Command::Read, Pointer::Spatial(0x012345), 288, 0);
+        let rq = MemoryAccessRequest::try_new(Command::Read, Pointer::Spatial(0x012345), 288, 0)
+            .unwrap();
         let raw: &[u8] = &[0x20, 0x32, 0x45, 0x23, 0x01, 0x00, 0x00, 0x00];
         assert_eq!(rq.raw, raw);
     }
+
+    #[test]
+    fn memory_access_arguments() {
+        let pointer = Pointer::Direct(0);
+        assert_eq!(
+            MemoryAccessRequest::try_new(Command::Read, pointer, 2048, 0),
+            Err(DiagnosticError::Length)
+        );
+        assert_eq!(
+            MemoryAccessRequest::try_new(Command::Other(8), pointer, 0, 0),
+            Err(DiagnosticError::Command)
+        );
+        assert_eq!(
+            MemoryAccessResponse::try_new(Status::Other(8), ErrorIndicator::None, 0, 0),
+            Err(DiagnosticError::Status)
+        );
+        assert_eq!(
+            MemoryAccessResponse::try_new(Status::Busy, ErrorIndicator::Other(1 << 24), 0, 0),
+            Err(DiagnosticError::ErrorIndicator)
+        );
+        assert_eq!(
+            ErrorIndicator::from(0x0100_0001),
+            ErrorIndicator::NotIdentified
+        );
+    }
+
+    #[test]
+    fn dtc() {
+        let dtc = Dtc::try_new(0x7FFFF, 31, 126).unwrap();
+        let raw: [u8; 4] = (&dtc).into();
+        assert_eq!(raw, [0xFF, 0xFF, 0xFF, 0x7E]);
+        assert_eq!(Dtc::try_from(&raw[..]), Ok(dtc));
+
+        let dtc = Dtc::try_new(190, 2, 1).unwrap();
+        assert_eq!(<[u8; 4]>::from(&dtc), [190, 0, 2, 1]);
+
+        assert_eq!(Dtc::try_new(1 << 19, 0, 0), Err(DiagnosticError::Spn));
+        assert_eq!(Dtc::try_new(0, 32, 0), Err(DiagnosticError::Fmi));
+        assert_eq!(
+            Dtc::try_new(0, 0, 127),
+            Err(DiagnosticError::OccurrenceCount)
+        );
+    }
+
+    #[test]
+    fn diagnostic_server() {
+        let mut storage = [DtcSlot::default(); 2];
+        let mut server = DiagnosticServer::new(&mut storage[..]);
+        let mut buf = [0; 16];
+
+        assert_eq!(server.encode_active(&mut buf), Some(8));
+        assert_eq!(buf[..8], [0x00, 0xFF, 0, 0, 0, 0, 0xFF, 0xFF]);
+        assert!(!server.take_changed());
+
+        let a = Dtc::try_new(190, 2, 1).unwrap();
+        let b = Dtc::try_new(100, 1, 1).unwrap();
+        assert_eq!(server.activate(a), Ok(()));
+        server.set_lamps(Lamps {
+            amber_warning: true,
+            ..Lamps::default()
+        });
+        assert!(server.take_changed());
+        assert_eq!(server.encode_active(&mut buf), Some(8));
+        assert_eq!(buf[..8], [0x04, 0xFF, 190, 0, 2, 1, 0xFF, 0xFF]);
+
+        assert_eq!(server.activate(b), Ok(()));
+        assert_eq!(server.encode_active(&mut buf), Some(10));
+        assert_eq!(server.encode_active(&mut buf[..8]), None);
+        assert!(server.activate(Dtc::try_new(1, 1, 1).unwrap()).is_err());
+
+        // reactivated with its occurrence count incremented
+        assert_eq!(server.deactivate(190, 2), Some(a));
+        assert_eq!(server.previously_active().count(), 1);
+        assert_eq!(server.encode_previously_active(&mut buf), Some(8));
+        assert_eq!(buf[..8], [0x00, 0xFF, 190, 0, 2, 1, 0xFF, 0xFF]);
+        assert_eq!(server.activate(a), Ok(()));
+        assert_eq!(server.active().next().unwrap().occurrence_count(), 2);
+
+        let mut cleared = 0;
+        server.clear_previously_active(|_| cleared += 1);
+        assert_eq!(cleared, 0);
+        server.clear_active(|_| cleared += 1);
+        assert_eq!(cleared, 2);
+        assert_eq!(server.active().count(), 0);
+    }
 }
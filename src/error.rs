@@ -0,0 +1,304 @@
+//! Errors
+//!
+//! Each module reports its own error type. [`Error`] collects them with
+//! `From` conversions, so callers mixing several modules can use `?`:
+//!
+//! ```
+//! use saelient::transport::RequestToSend;
+//! use saelient::{Error, Id, IdBuilder, Pgn};
+//!
+//! fn rts(data: &[u8]) -> Result<(Id, RequestToSend), Error> {
+//!     let id = IdBuilder::new()
+//!         .pgn(Pgn::TransportProtocolConnectionManagement)
+//!         .sa(0x80)
+//!         .da(0x00)
+//!         .try_build()?;
+//!     Ok((id, RequestToSend::try_from(data)?))
+//! }
+//! ```
+
+use crate::config::ConfigError;
+use crate::diagnostic::DiagnosticError;
+use crate::id::IdError;
+use crate::transport::{self, RequestToSendError, TransportError};
+use core::fmt;
+
+/// Any error reported by this crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Error {
+    /// Incomplete or invalid identifier.
+    Id(IdError),
+    /// Transport protocol failure.
+    Transport(TransportError),
+    /// Invalid diagnostic message.
+    Diagnostic(DiagnosticError),
+    /// Invalid node configuration.
+    Config(ConfigError),
+    /// Frame payload that does not hold the expected message.
+    Decode(DecodeError),
+}
+
+/// Reason a payload does not hold the expected message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DecodeError {
+    /// Payload of `actual` bytes where `expected` are required.
+    ///
+    /// For messages of variable length, `expected` is the bound that was
+    /// crossed.
+    Length { expected: usize, actual: usize },
+    /// Multiplexer or function byte selecting another message.
+    Mux(u8),
+    /// Byte at `offset` holding a value the message does not allow.
+    Value { offset: usize, value: u8 },
+}
+
+/// Payload of exactly `N` bytes.
+pub(crate) fn exact<const N: usize>(value: &[u8]) -> Result<&[u8; N], DecodeError> {
+    value.try_into().map_err(|_| DecodeError::Length {
+        expected: N,
+        actual: value.len(),
+    })
+}
+
+/// Payload of at least `len` bytes.
+pub(crate) fn at_least(value: &[u8], len: usize) -> Result<&[u8], DecodeError> {
+    if value.len() < len {
+        return Err(DecodeError::Length {
+            expected: len,
+            actual: value.len(),
+        });
+    }
+    Ok(value)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Id(e) => write!(f, "identifier: {e}"),
+            Error::Transport(e) => write!(f, "transport: {e}"),
+            Error::Diagnostic(e) => write!(f, "diagnostic: {e}"),
+            Error::Config(e) => write!(f, "configuration: {e}"),
+            Error::Decode(e) => write!(f, "malformed message: {e}"),
+        }
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Error::Id(e) => Some(e),
+            Error::Transport(e) => Some(e),
+            Error::Diagnostic(e) => Some(e),
+            Error::Config(e) => Some(e),
+            Error::Decode(e) => Some(e),
+        }
+    }
+}
+
+impl From<IdError> for Error {
+    fn from(value: IdError) -> Self {
+        Error::Id(value)
+    }
+}
+
+impl From<TransportError> for Error {
+    fn from(value: TransportError) -> Self {
+        Error::Transport(value)
+    }
+}
+
+impl From<transport::Error> for Error {
+    fn from(value: transport::Error) -> Self {
+        Error::Transport(value.into())
+    }
+}
+
+impl From<RequestToSendError> for Error {
+    fn from(value: RequestToSendError) -> Self {
+        Error::Transport(value.into())
+    }
+}
+
+impl From<DiagnosticError> for Error {
+    fn from(value: DiagnosticError) -> Self {
+        Error::Diagnostic(value)
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(value: ConfigError) -> Self {
+        Error::Config(value)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(value: DecodeError) -> Self {
+        Error::Decode(value)
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Length { expected, actual } => {
+                write!(f, "payload of {actual} bytes, expected {expected}")
+            }
+            DecodeError::Mux(mux) => write!(f, "unexpected multiplexer {mux:#04x}"),
+            DecodeError::Value { offset, value } => {
+                write!(f, "invalid value {value:#04x} at byte {offset}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+impl fmt::Display for IdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IdError::Priority => "priority above 7",
+            IdError::MissingPgn => "no parameter group number",
+            IdError::MissingSourceAddress => "no source address",
+            IdError::MissingDestinationAddress => "no destination address for PDU1 format",
+        })
+    }
+}
+
+impl core::error::Error for IdError {}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransportError::Session(e) => write!(f, "{e}"),
+            TransportError::RequestToSend(e) => write!(f, "request to send: {e}"),
+        }
+    }
+}
+
+impl core::error::Error for TransportError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            TransportError::Session(e) => Some(e),
+            TransportError::RequestToSend(e) => Some(e),
+        }
+    }
+}
+
+impl fmt::Display for transport::Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            transport::Error::StorageTooSmall => f.write_str("storage too small for the message"),
+            transport::Error::Sequence => f.write_str("unexpected sequence number"),
+            transport::Error::PreviousAbort => f.write_str("session was aborted"),
+            transport::Error::Storage => f.write_str("storage failed"),
+            transport::Error::Timeout => f.write_str("peer timed out"),
+            transport::Error::Aborted(reason) => {
+                write!(f, "aborted by peer with reason {}", u8::from(reason))
+            }
+        }
+    }
+}
+
+impl core::error::Error for transport::Error {}
+
+impl fmt::Display for RequestToSendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RequestToSendError::TooShort => "message shorter than 9 bytes",
+            RequestToSendError::TooLong => "message longer than 1785 bytes",
+            RequestToSendError::MaxPacketsPerResponse => "packets per response outside 1 to 254",
+        })
+    }
+}
+
+impl core::error::Error for RequestToSendError {}
+
+impl fmt::Display for DiagnosticError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DiagnosticError::Length => "length above 2047",
+            DiagnosticError::Command => "command above 7",
+            DiagnosticError::Status => "status above 7",
+            DiagnosticError::ErrorIndicator => "error indicator above 24 bits",
+            DiagnosticError::Spn => "SPN above 19 bits",
+            DiagnosticError::Fmi => "FMI above 31",
+            DiagnosticError::OccurrenceCount => "occurrence count above 126",
+        })
+    }
+}
+
+impl core::error::Error for DiagnosticError {}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::PreferredAddress => f.write_str("preferred address outside the range"),
+            ConfigError::AddressRange => f.write_str("invalid address range"),
+            ConfigError::Periodic(pgn) => {
+                write!(f, "invalid periodic message for PGN {}", u32::from(*pgn))
+            }
+            ConfigError::MaxPacketsPerCts => f.write_str("zero packets per CTS"),
+            ConfigError::MaxMessageSize => {
+                f.write_str("message size limit outside of 9 to 1785 bytes")
+            }
+        }
+    }
+}
+
+impl core::error::Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::RequestToSend;
+    use crate::{IdBuilder, Pgn};
+    use std::string::ToString;
+
+    fn rts(size: u16) -> Result<[u8; 8], Error> {
+        let rts = RequestToSend::try_new(size, None, Pgn::ProprietaryA)?;
+        Ok(rts.into())
+    }
+
+    #[test]
+    fn conversions() {
+        assert!(rts(9).is_ok());
+        assert_eq!(
+            rts(8),
+            Err(Error::Transport(TransportError::RequestToSend(
+                RequestToSendError::TooShort
+            )))
+        );
+        assert_eq!(
+            rts(8).unwrap_err().to_string(),
+            "transport: request to send: message shorter than 9 bytes"
+        );
+
+        let parse =
+            |data: &[u8]| -> Result<RequestToSend, Error> { Ok(RequestToSend::try_from(data)?) };
+        assert_eq!(
+            parse(&[0; 4]).unwrap_err(),
+            Error::Decode(DecodeError::Length {
+                expected: 8,
+                actual: 4
+            })
+        );
+        assert_eq!(
+            parse(&[0; 4]).unwrap_err().to_string(),
+            "malformed message: payload of 4 bytes, expected 8"
+        );
+        assert_eq!(
+            parse(&[17, 9, 0, 2, 0xFF, 0x00, 0xEF, 0x00]).unwrap_err(),
+            Error::Decode(DecodeError::Mux(17))
+        );
+        assert!(parse(&[16, 9, 0, 2, 0xFF, 0x00, 0xEF, 0x00]).is_ok());
+
+        let id = || -> Result<_, Error> { Ok(IdBuilder::new().sa(0).try_build()?) };
+        let e = id().unwrap_err();
+        assert_eq!(e.to_string(), "identifier: no parameter group number");
+        assert!(core::error::Error::source(&e).is_some());
+    }
+}
@@ -0,0 +1,74 @@
+//! Event callbacks
+//!
+//! Implement [`Events`] to be notified by [`Node::poll_with`] instead of
+//! inspecting the node after every poll.
+//!
+//! [`Node::poll_with`]: crate::node::Node::poll_with
+
+use crate::diagnostic::Dtc;
+use crate::id::Pgn;
+
+/// Notifications from the stack.
+///
+/// All methods default to doing nothing so only the relevant ones need to
+/// be implemented.
+pub trait Events {
+    /// The node claimed `address` and may use it.
+    ///
+    /// Not called for nodes keeping a fixed address.
+    fn on_address_claimed(&mut self, address: u8) {
+        let _ = address;
+    }
+
+    /// The node lost its address to another controller application.
+    ///
+    /// Followed by [`Events::on_address_claimed`] once another address of
+    /// the range is claimed.
+    fn on_address_lost(&mut self) {}
+
+    /// A complete message addressed to this node or broadcast was received,
+    /// either as a single frame or through a transport session.
+    fn on_message(&mut self, pgn: Pgn, data: &[u8], sa: u8) {
+        let _ = (pgn, data, sa);
+    }
+
+    /// A transport session from `sa` completed with `size` bytes.
+    ///
+    /// Called before [`Events::on_message`] for the reassembled message.
+    fn on_transfer_complete(&mut self, pgn: Pgn, sa: u8, size: usize) {
+        let _ = (pgn, sa, size);
+    }
+
+    /// The node changed the state of `dtc`, which is `active` now.
+    ///
+    /// Called for the DTCs cleared by DM3 and DM11 requests, reported as
+    /// inactive.
+    fn on_dtc_changed(&mut self, dtc: Dtc, active: bool) {
+        let _ = (dtc, active);
+    }
+
+    /// `sa` requested `pgn`, one of [`NodeConfig::requests`].
+    ///
+    /// Write the response into `buf` and return its length, or `None` if it
+    /// cannot be provided right now.
+    ///
+    /// [`NodeConfig::requests`]: crate::config::NodeConfig::requests
+    fn on_request(&mut self, pgn: Pgn, sa: u8, buf: &mut [u8]) -> Option<usize> {
+        let _ = (pgn, sa, buf);
+        None
+    }
+
+    /// `pgn`, one of [`NodeConfig::periodic`], is due.
+    ///
+    /// Write the data into `buf` and return its length, or `None` to skip
+    /// this transmission.
+    ///
+    /// [`NodeConfig::periodic`]: crate::config::NodeConfig::periodic
+    fn on_periodic(&mut self, pgn: Pgn, buf: &mut [u8]) -> Option<usize> {
+        let _ = (pgn, buf);
+        None
+    }
+}
+
+/// Ignores all events.
+impl Events for () {}
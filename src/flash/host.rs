@@ -0,0 +1,625 @@
+use super::MAX_REQUEST_LEN;
+use crate::diagnostic::{
+    BinaryDataTransfer, Command, ErrorIndicator, MemoryAccessRequest, MemoryAccessResponse,
+    Pointer, Status,
+};
+use crate::id::{Id, IdBuilder, Pgn};
+use crate::time::{Duration, Instant};
+use crate::transport::{self, ControlMessage, OutgoingTransfer};
+
+/// Largest DM16 payload carried by the transport protocol, including the
+/// length byte.
+const MAX_BLOCK_LEN: usize = 1785;
+
+/// Flashing failure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FlashError {
+    /// The target stayed busy or silent after all retries.
+    TooManyRetries,
+    /// The target reported a failed operation.
+    Target(ErrorIndicator),
+    /// Read back data differs from the image at the given address.
+    Verify(u32),
+    /// The transport session carrying a write failed.
+    Transport(transport::Error),
+    /// The image does not fit below the end of the address space.
+    AddressOverflow,
+}
+
+/// Stage of the flashing sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Phase {
+    Erase,
+    Write,
+    Verify,
+    Done,
+    Failed(FlashError),
+}
+
+/// Progress of the current phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Progress {
+    pub phase: Phase,
+    /// Bytes processed in this phase.
+    pub done: usize,
+    /// Bytes to process in this phase.
+    pub total: usize,
+}
+
+/// Step within a single memory access operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum Step {
+    /// Send the request once the deadline, if any, has passed.
+    Request,
+    /// Wait for the target to proceed.
+    Proceed,
+    /// Wait for the target to complete the operation.
+    Complete,
+    /// Wait for the data of a read.
+    Data,
+    /// Send the data of a write with the transport protocol.
+    Transport,
+}
+
+/// Host side of a bootloader session.
+///
+/// Sans-IO: feed every received frame and the current time to
+/// [`FlashHost::poll`] and send the returned frames. Reads are carried in
+/// single frame DM16 messages of up to 7 bytes. Writes are too, unless a
+/// buffer is given with [`FlashHost::buffer`] to send larger DM16 blocks with
+/// the transport protocol.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FlashHost<'a> {
+    source: u8,
+    target: u8,
+    address: u32,
+    image: &'a [u8],
+    key: u16,
+    timeout: Duration,
+    backoff: Duration,
+    retries: u8,
+    phase: Phase,
+    step: Step,
+    offset: usize,
+    attempt: u8,
+    deadline: Option<Instant>,
+    buf: &'a mut [u8],
+    /// Session sending the DM16 in `buf`, bound to `buf` while polled.
+    transfer: Option<OutgoingTransfer<'a>>,
+}
+
+impl<'a> FlashHost<'a> {
+    /// Flash `image` from `source` to `address` of the `target` node.
+    pub fn new(source: u8, target: u8, address: u32, image: &'a [u8]) -> Self {
+        Self {
+            source,
+            target,
+            address,
+            image,
+            key: 0xFFFF,
+            timeout: Duration::from_millis(1000),
+            backoff: Duration::from_millis(50),
+            retries: 3,
+            phase: Phase::Erase,
+            step: Step::Request,
+            offset: 0,
+            attempt: 0,
+            deadline: None,
+            buf: &mut [],
+            transfer: None,
+        }
+    }
+
+    /// Security key sent with every request.
+    ///
+    /// Default is `0xFFFF`, not available.
+    pub fn key(mut self, key: u16) -> Self {
+        self.key = key;
+        self
+    }
+
+    /// Buffer for DM16 messages sent with the transport protocol.
+    ///
+    /// Writes are split into blocks of up to `buf.len() - 1` bytes, capped at
+    /// 1784, instead of 7. Buffers of up to 8 bytes keep single frames, as
+    /// required by [`FlashTarget`](super::FlashTarget).
+    pub fn buffer(mut self, buf: &'a mut [u8]) -> Self {
+        self.buf = buf;
+        self
+    }
+
+    /// Time to wait for a response before retrying.
+    ///
+    /// Default is 1 second.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Retries of a busy or silent operation, each waiting twice as long as
+    /// the last starting from `backoff`.
+    ///
+    /// Default is 3 retries starting from 50 ms.
+    pub fn retries(mut self, retries: u8, backoff: Duration) -> Self {
+        self.retries = retries;
+        self.backoff = backoff;
+        self
+    }
+
+    /// Current progress.
+    pub fn progress(&self) -> Progress {
+        Progress {
+            phase: self.phase,
+            done: self.offset,
+            total: self.image.len(),
+        }
+    }
+
+    /// Check if flashing has finished, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        matches!(self.phase, Phase::Done | Phase::Failed(_))
+    }
+
+    /// Time at which [`FlashHost::poll`] has to be called again even without
+    /// an incoming frame, if any.
+    pub fn deadline(&self) -> Option<Instant> {
+        match &self.transfer {
+            Some(transfer) => transfer.deadline(),
+            None => self.deadline,
+        }
+    }
+
+    /// Advance the session.
+    ///
+    /// `incoming` is the frame received since the last call, if any. Returns
+    /// the next frame to send, so call again without an incoming frame until
+    /// it returns `None`.
+    pub fn poll(&mut self, incoming: Option<(Id, &[u8])>, now: Instant) -> Option<(Id, [u8; 8])> {
+        if self.is_finished() {
+            return None;
+        }
+
+        if let Some((id, data)) = incoming
+            && id.sa() == self.target
+            && id.da() == Some(self.source)
+        {
+            let frame = self.receive(id.pgn(), data, now);
+            if frame.is_some() || self.is_finished() {
+                return frame;
+            }
+        }
+
+        let due = self.deadline.is_none_or(|d| now >= d);
+        match self.step {
+            Step::Transport => self.transmit(now),
+            Step::Request if due => {
+                let Some(address) = self.memory_address() else {
+                    self.phase = Phase::Failed(FlashError::AddressOverflow);
+                    self.deadline = None;
+                    return None;
+                };
+                let (command, len) = self.operation();
+                let request = MemoryAccessRequest::try_new(
+                    command,
+                    Pointer::Direct(address),
+                    len as u16,
+                    self.key,
+                )
+                .ok()?;
+                self.step = Step::Proceed;
+                self.deadline = Some(now + self.timeout);
+                self.frame(Pgn::MemoryAccessRequest, (&request).into())
+            }
+            Step::Proceed | Step::Complete | Step::Data if due => {
+                self.retry(now);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn receive(&mut self, pgn: Pgn, data: &[u8], now: Instant) -> Option<(Id, [u8; 8])> {
+        match (pgn, self.step) {
+            (Pgn::MemoryAccessResponse, Step::Proceed | Step::Complete) => {
+                let response = MemoryAccessResponse::try_from(data).ok()?;
+                match response.status() {
+                    Status::Proceed if self.step == Step::Proceed => self.proceed(now),
+                    Status::OperationCompleted if self.step == Step::Complete => {
+                        let len = self.operation().1;
+                        self.close(len)
+                    }
+                    // the target keeps working on the operation
+                    Status::Busy if self.step == Step::Complete => {
+                        self.deadline = Some(now + self.timeout);
+                        None
+                    }
+                    Status::Busy => {
+                        self.retry(now);
+                        None
+                    }
+                    Status::OperationFailed => {
+                        self.phase = Phase::Failed(FlashError::Target(response.error_indicator()));
+                        None
+                    }
+                    _ => None,
+                }
+            }
+            (Pgn::BinaryDataTransfer, Step::Data) => {
+                let dm16 = BinaryDataTransfer::try_from(data).ok()?;
+                let (_, len) = self.operation();
+                let expected = self.image.get(self.offset..self.offset + len)?;
+                if dm16.data() != expected {
+                    let address = self.memory_address()?;
+                    self.phase = Phase::Failed(FlashError::Verify(address));
+                    return None;
+                }
+                self.close(len)
+            }
+            (Pgn::TransportProtocolConnectionManagement, Step::Transport) => {
+                let msg = ControlMessage::try_from(data).ok()?;
+                let len = self.operation().1;
+                let data = self.buf.get(..=len)?;
+                let mut transfer = self.transfer.take()?.rebind(data);
+                let result = transfer.handle(&msg).and_then(|_| transfer.poll(now));
+
+                if result.is_ok() && !transfer.is_finished() {
+                    self.transfer = Some(transfer.rebind(&[]));
+                    return None;
+                }
+                self.settle(result, now)
+            }
+            _ => None,
+        }
+    }
+
+    /// Next packet of the transport session carrying a write.
+    fn transmit(&mut self, now: Instant) -> Option<(Id, [u8; 8])> {
+        let len = self.operation().1;
+        let data = self.buf.get(..=len)?;
+        let mut transfer = self.transfer.take()?.rebind(data);
+        let packet = transfer.next_packet();
+        let result = transfer.poll(now);
+        self.transfer = Some(transfer.rebind(&[]));
+
+        match (result, packet) {
+            (Ok(()), Some(packet)) => {
+                self.frame(Pgn::TransportProtocolDataTransfer, packet.to_bytes())
+            }
+            (Ok(()), None) => None,
+            (Err(e), _) => self.settle(Err(e), now),
+        }
+    }
+
+    /// End the transport session carrying a write, waiting for the target to
+    /// complete the write once all data was acknowledged.
+    fn settle(
+        &mut self,
+        result: Result<(), (transport::Error, Option<transport::ConnectionAbort>)>,
+        now: Instant,
+    ) -> Option<(Id, [u8; 8])> {
+        self.transfer = None;
+        match result {
+            Ok(()) => {
+                self.step = Step::Complete;
+                self.deadline = Some(now + self.timeout);
+                None
+            }
+            Err((error, abort)) => {
+                self.phase = Phase::Failed(FlashError::Transport(error));
+                self.deadline = None;
+                self.frame(
+                    Pgn::TransportProtocolConnectionManagement,
+                    abort?.to_bytes(),
+                )
+            }
+        }
+    }
+
+    /// The target accepted the request.
+    fn proceed(&mut self, now: Instant) -> Option<(Id, [u8; 8])> {
+        self.deadline = Some(now + self.timeout);
+
+        match self.phase {
+            Phase::Write => {
+                let (_, len) = self.operation();
+                let chunk = self.image.get(self.offset..self.offset + len)?;
+                if let Some(dm16) = BinaryDataTransfer::new(chunk) {
+                    self.step = Step::Complete;
+                    return self.frame(Pgn::BinaryDataTransfer, (&dm16).into());
+                }
+
+                // lengths past 254 are only given by the request
+                let data = self.buf.get_mut(..=len)?;
+                data[0] = len.min(0xFF) as u8;
+                data[1..].copy_from_slice(chunk);
+                let mut transfer =
+                    OutgoingTransfer::new(data, None, Pgn::BinaryDataTransfer).ok()?;
+                let _ = transfer.poll(now);
+                let rts = transfer.rts().clone();
+                self.transfer = Some(transfer.rebind(&[]));
+                self.step = Step::Transport;
+                self.frame(Pgn::TransportProtocolConnectionManagement, rts.into())
+            }
+            Phase::Verify => {
+                self.step = Step::Data;
+                None
+            }
+            _ => {
+                self.step = Step::Complete;
+                None
+            }
+        }
+    }
+
+    /// Close the current operation and move on to the next.
+    fn close(&mut self, len: usize) -> Option<(Id, [u8; 8])> {
+        let request = MemoryAccessRequest::try_new(
+            Command::OperationCompleted,
+            Pointer::Direct(self.memory_address()?),
+            len as u16,
+            self.key,
+        )
+        .ok()?;
+
+        self.offset += len;
+        self.step = Step::Request;
+        self.attempt = 0;
+        self.deadline = None;
+
+        if self.offset >= self.image.len() {
+            self.offset = 0;
+            self.phase = match self.phase {
+                Phase::Erase => Phase::Write,
+                Phase::Write => Phase::Verify,
+                _ => Phase::Done,
+            };
+        }
+
+        self.frame(Pgn::MemoryAccessRequest, (&request).into())
+    }
+
+    /// Schedule the current request again after a backoff.
+    fn retry(&mut self, now: Instant) {
+        if self.attempt >= self.retries {
+            self.phase = Phase::Failed(FlashError::TooManyRetries);
+            self.deadline = None;
+            return;
+        }
+
+        let backoff = self.backoff.as_millis() << self.attempt.min(16);
+        self.attempt += 1;
+        self.step = Step::Request;
+        self.deadline = Some(now + Duration::from_millis(backoff));
+    }
+
+    /// Command and length of the current operation.
+    fn operation(&self) -> (Command, usize) {
+        let remaining = self.image.len().saturating_sub(self.offset);
+        match self.phase {
+            Phase::Erase => (Command::Erase, remaining.min(MAX_REQUEST_LEN)),
+            Phase::Write => (Command::Write, remaining.min(self.block_len())),
+            _ => (Command::Read, remaining.min(BinaryDataTransfer::MAX_LEN)),
+        }
+    }
+
+    /// Largest write of a single operation.
+    fn block_len(&self) -> usize {
+        match self.buf.len().min(MAX_BLOCK_LEN) {
+            len @ 9.. => (len - 1).min(MAX_REQUEST_LEN),
+            _ => BinaryDataTransfer::MAX_LEN,
+        }
+    }
+
+    /// Memory address of the current offset.
+    ///
+    /// Returns `None` if the image does not fit below the end of the address
+    /// space.
+    fn memory_address(&self) -> Option<u32> {
+        let last = u32::try_from(self.image.len().saturating_sub(1)).ok()?;
+        self.address.checked_add(last)?;
+        self.address.checked_add(u32::try_from(self.offset).ok()?)
+    }
+
+    fn frame(&self, pgn: Pgn, data: [u8; 8]) -> Option<(Id, [u8; 8])> {
+        let transport = matches!(
+            pgn,
+            Pgn::TransportProtocolConnectionManagement | Pgn::TransportProtocolDataTransfer
+        );
+        let id = IdBuilder::new()
+            .priority(if transport { 7 } else { 6 })
+            .pgn(pgn)
+            .sa(self.source)
+            .da(self.target)
+            .build()?;
+        Some((id, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{ClearToSend, DataTransfer, RequestToSend, Response, Transfer};
+
+    const HOST: u8 = 0xF9;
+    const TARGET: u8 = 0x00;
+
+    fn from_target(pgn: Pgn) -> Id {
+        IdBuilder::new()
+            .pgn(pgn)
+            .sa(TARGET)
+            .da(HOST)
+            .build()
+            .unwrap()
+    }
+
+    fn response(status: Status) -> (Id, [u8; 8]) {
+        let response =
+            MemoryAccessResponse::try_new(status, ErrorIndicator::None, 0, 0xFFFF).unwrap();
+        (from_target(Pgn::MemoryAccessResponse), (&response).into())
+    }
+
+    fn command(frame: (Id, [u8; 8])) -> Command {
+        assert_eq!(frame.0.pgn(), Pgn::MemoryAccessRequest);
+        assert_eq!(frame.0.da(), Some(TARGET));
+        MemoryAccessRequest::try_from(&frame.1[..])
+            .unwrap()
+            .command()
+    }
+
+    #[test]
+    fn erase_write_verify() {
+        let image = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let mut host = FlashHost::new(HOST, TARGET, 0x1000, &image);
+        let now = Instant::default();
+        let rx =
+            |host: &mut FlashHost, frame: (Id, [u8; 8])| host.poll(Some((frame.0, &frame.1)), now);
+
+        // erase all at once
+        assert_eq!(command(host.poll(None, now).unwrap()), Command::Erase);
+        assert!(rx(&mut host, response(Status::Proceed)).is_none());
+        assert!(rx(&mut host, response(Status::Busy)).is_none());
+        let close = rx(&mut host, response(Status::OperationCompleted)).unwrap();
+        assert_eq!(command(close), Command::OperationCompleted);
+        assert_eq!(host.progress().phase, Phase::Write);
+
+        // write in two chunks
+        for (offset, len) in [(0, 7), (7, 2)] {
+            let request = host.poll(None, now).unwrap();
+            let request = MemoryAccessRequest::try_from(&request.1[..]).unwrap();
+            assert_eq!(request.command(), Command::Write);
+            assert_eq!(request.pointer(), Pointer::Direct(0x1000 + offset));
+            assert_eq!(request.length(), len);
+
+            let (id, data) = rx(&mut host, response(Status::Proceed)).unwrap();
+            assert_eq!(id.pgn(), Pgn::BinaryDataTransfer);
+            assert_eq!(data[0], len as u8);
+            rx(&mut host, response(Status::OperationCompleted)).unwrap();
+        }
+        assert_eq!(host.progress().phase, Phase::Verify);
+
+        // read back
+        let dm16 = |data: &[u8]| {
+            let dm16 = BinaryDataTransfer::new(data).unwrap();
+            (from_target(Pgn::BinaryDataTransfer), <[u8; 8]>::from(&dm16))
+        };
+        assert_eq!(command(host.poll(None, now).unwrap()), Command::Read);
+        assert!(rx(&mut host, response(Status::Proceed)).is_none());
+        rx(&mut host, dm16(&image[..7])).unwrap();
+        assert_eq!(host.progress().done, 7);
+
+        assert_eq!(command(host.poll(None, now).unwrap()), Command::Read);
+        assert!(rx(&mut host, response(Status::Proceed)).is_none());
+        assert!(rx(&mut host, dm16(&[0, 0])).is_none());
+        assert_eq!(
+            host.progress().phase,
+            Phase::Failed(FlashError::Verify(0x1007))
+        );
+        assert!(host.is_finished());
+    }
+
+    #[test]
+    fn busy_backoff_and_timeout() {
+        let image = [0; 4];
+        let mut host =
+            FlashHost::new(HOST, TARGET, 0, &image).retries(2, Duration::from_millis(10));
+        let mut now = Instant::default();
+
+        assert!(host.poll(None, now).is_some());
+        let (id, data) = response(Status::Busy);
+        assert!(host.poll(Some((id, &data)), now).is_none());
+        assert_eq!(host.deadline(), Some(now + Duration::from_millis(10)));
+        assert!(host.poll(None, now).is_none());
+
+        now = now + Duration::from_millis(10);
+        assert!(host.poll(None, now).is_some());
+
+        // silent target
+        now = now + Duration::from_millis(1000);
+        assert!(host.poll(None, now).is_none());
+        assert_eq!(host.deadline(), Some(now + Duration::from_millis(20)));
+
+        now = now + Duration::from_millis(20);
+        assert!(host.poll(None, now).is_some());
+        now = now + Duration::from_millis(1000);
+        assert!(host.poll(None, now).is_none());
+        assert_eq!(
+            host.progress().phase,
+            Phase::Failed(FlashError::TooManyRetries)
+        );
+    }
+
+    #[test]
+    fn transport_write() {
+        let image: [u8; 40] = core::array::from_fn(|i| i as u8);
+        let mut buf = [0; 21];
+        let mut host = FlashHost::new(HOST, TARGET, 0x1000, &image).buffer(&mut buf);
+        let now = Instant::default();
+        let rx =
+            |host: &mut FlashHost, frame: (Id, [u8; 8])| host.poll(Some((frame.0, &frame.1)), now);
+
+        assert_eq!(command(host.poll(None, now).unwrap()), Command::Erase);
+        rx(&mut host, response(Status::Proceed));
+        rx(&mut host, response(Status::OperationCompleted)).unwrap();
+
+        // two blocks of 20 bytes
+        for offset in [0, 20] {
+            let request = host.poll(None, now).unwrap();
+            let request = MemoryAccessRequest::try_from(&request.1[..]).unwrap();
+            assert_eq!(request.command(), Command::Write);
+            assert_eq!(request.pointer(), Pointer::Direct(0x1000 + offset));
+            assert_eq!(request.length(), 20);
+
+            let (id, rts) = rx(&mut host, response(Status::Proceed)).unwrap();
+            assert_eq!(id.pgn(), Pgn::TransportProtocolConnectionManagement);
+            assert_eq!(id.da(), Some(TARGET));
+            let rts = RequestToSend::try_from(&rts[..]).unwrap();
+            assert_eq!(rts.pgn(), Pgn::BinaryDataTransfer);
+            assert!(host.poll(None, now).is_none());
+
+            let mut receiver = Transfer::new(rts);
+            let cts = ClearToSend::new(None, 1, Pgn::BinaryDataTransfer);
+            let cm = from_target(Pgn::TransportProtocolConnectionManagement);
+            let (id, packet) = rx(&mut host, (cm, cts.to_bytes())).unwrap();
+            assert_eq!(id.pgn(), Pgn::TransportProtocolDataTransfer);
+            let mut ack = receiver.next(DataTransfer::try_from(&packet[..]).unwrap());
+            while let Some((_, packet)) = host.poll(None, now) {
+                ack = receiver.next(DataTransfer::try_from(&packet[..]).unwrap());
+            }
+
+            let payload = receiver.finished().unwrap();
+            assert_eq!(payload[0], 20);
+            assert_eq!(&payload[1..], &image[offset as usize..][..20]);
+
+            let Ok(Some(Response::End(end))) = ack else {
+                panic!("Expected end of message acknowledge");
+            };
+            assert!(rx(&mut host, (cm, end.to_bytes())).is_none());
+            let close = rx(&mut host, response(Status::OperationCompleted)).unwrap();
+            assert_eq!(command(close), Command::OperationCompleted);
+        }
+        assert_eq!(host.progress().phase, Phase::Verify);
+    }
+
+    #[test]
+    fn address_overflow() {
+        let image = [0; 16];
+        let mut host = FlashHost::new(HOST, TARGET, u32::MAX - 8, &image);
+        assert!(host.poll(None, Instant::default()).is_none());
+        assert_eq!(
+            host.progress().phase,
+            Phase::Failed(FlashError::AddressOverflow)
+        );
+
+        // the last byte at the end of the address space
+        let mut host = FlashHost::new(HOST, TARGET, u32::MAX - 15, &image);
+        assert!(host.poll(None, Instant::default()).is_some());
+    }
+}
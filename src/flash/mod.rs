@@ -0,0 +1,16 @@
+//! Bootloader flashing over memory access (J1939-73 DM14/DM15/DM16)
+//!
+//! [`FlashHost`] drives a target through the erase, write and verify
+//! sequence of a memory image. With the `embedded-storage` feature,
+//! `FlashTarget` implements the device side on top of a NOR flash.
+
+mod host;
+#[cfg(feature = "embedded-storage")]
+mod target;
+
+pub use host::{FlashError, FlashHost, Phase, Progress};
+#[cfg(feature = "embedded-storage")]
+pub use target::{FlashTarget, Region};
+
+/// Largest length of a single memory access request.
+pub const MAX_REQUEST_LEN: usize = 0b111_1111_1111;
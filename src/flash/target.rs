@@ -0,0 +1,424 @@
+use crate::diagnostic::{
+    BinaryDataTransfer, Command, ErrorIndicator, MemoryAccessRequest, MemoryAccessResponse,
+    Pointer, Status,
+};
+use crate::id::{Id, IdBuilder, Pgn};
+use crate::node::Frames;
+use embedded_storage::nor_flash::NorFlash;
+
+/// Size of the buffer collecting writes into whole flash words.
+const STAGE_LEN: usize = 64;
+
+/// A range of memory addresses backed by flash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Region {
+    /// First memory address as seen by the host.
+    pub address: u32,
+    /// Flash offset of the first address.
+    pub offset: u32,
+    /// Length in bytes.
+    pub len: u32,
+}
+
+/// Operation in progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pending {
+    /// Erase accepted, to be run on the next poll.
+    Erase { peer: u8, from: u32, to: u32 },
+    /// Write accepted, waiting for the DM16 data.
+    Write { peer: u8, offset: u32, len: u16 },
+}
+
+/// Device side of a bootloader session.
+///
+/// Applies DM14 erase, write and read requests to a [`NorFlash`] after
+/// validating them against a region map. Erases run on the call to
+/// [`FlashTarget::poll`] after the request was accepted, and requests
+/// received until then are answered as busy.
+#[derive(Debug)]
+pub struct FlashTarget<'a, F> {
+    flash: F,
+    address: u8,
+    regions: &'a [Region],
+    pending: Option<Pending>,
+    stage: [u8; STAGE_LEN],
+    stage_offset: u32,
+    stage_len: usize,
+}
+
+impl<'a, F: NorFlash> FlashTarget<'a, F> {
+    /// Create a new target answering at `address`.
+    ///
+    /// Returns `None` if the write size of `flash` is larger than 64 bytes.
+    pub fn new(flash: F, address: u8, regions: &'a [Region]) -> Option<Self> {
+        if F::WRITE_SIZE > STAGE_LEN {
+            return None;
+        }
+
+        Some(Self {
+            flash,
+            address,
+            regions,
+            pending: None,
+            stage: [0xFF; STAGE_LEN],
+            stage_offset: 0,
+            stage_len: 0,
+        })
+    }
+
+    /// Check if an erase is waiting to run.
+    pub fn is_busy(&self) -> bool {
+        matches!(self.pending, Some(Pending::Erase { .. }))
+    }
+
+    /// Write any buffered data to flash, padding the last word with `0xFF`.
+    pub fn flush(&mut self) -> Result<(), F::Error> {
+        if self.stage_len == 0 {
+            return Ok(());
+        }
+
+        let len = self.stage_len.next_multiple_of(F::WRITE_SIZE);
+        self.stage[self.stage_len..len].fill(0xFF);
+        let result = self.flash.write(self.stage_offset, &self.stage[..len]);
+        self.stage_len = 0;
+        result
+    }
+
+    /// Release the flash.
+    pub fn into_inner(self) -> F {
+        self.flash
+    }
+
+    /// Advance the target.
+    ///
+    /// `incoming` is the frame received since the last call, if any. The
+    /// returned frames must be sent on the bus.
+    pub fn poll(&mut self, incoming: Option<(Id, &[u8])>) -> Frames {
+        let mut frames = Frames::default();
+
+        if let Some(Pending::Erase { peer, from, to }) = self.pending {
+            self.pending = None;
+            let result = self.flush().and_then(|_| self.flash.erase(from, to));
+            let response = match result {
+                Ok(()) => response(Status::OperationCompleted, ErrorIndicator::None, 0),
+                Err(_) => response(Status::OperationFailed, ErrorIndicator::InternalFailure, 0),
+            };
+            frames.push(self.frame(Pgn::MemoryAccessResponse, peer, response));
+        }
+
+        let Some((id, data)) = incoming else {
+            return frames;
+        };
+
+        if id.da() != Some(self.address) {
+            return frames;
+        }
+
+        match id.pgn() {
+            Pgn::MemoryAccessRequest => {
+                let Ok(request) = MemoryAccessRequest::try_from(data) else {
+                    return frames;
+                };
+                self.request(id.sa(), &request, &mut frames);
+            }
+            Pgn::BinaryDataTransfer => {
+                let Ok(dm16) = BinaryDataTransfer::try_from(data) else {
+                    return frames;
+                };
+                self.data(id.sa(), &dm16, &mut frames);
+            }
+            _ => {}
+        }
+
+        frames
+    }
+
+    fn request(&mut self, peer: u8, request: &MemoryAccessRequest, frames: &mut Frames) {
+        let len = request.length();
+        let fail = |indicator| response(Status::OperationFailed, indicator, len);
+
+        if request.command() == Command::OperationCompleted {
+            if matches!(self.pending, Some(Pending::Write { peer: p, .. }) if p == peer) {
+                self.pending = None;
+            }
+            return;
+        }
+
+        if self.pending.is_some() {
+            let indicator = match self.pending {
+                Some(Pending::Erase { .. }) => ErrorIndicator::BusyErase,
+                _ => ErrorIndicator::BusyWrite,
+            };
+            let busy = response(Status::Busy, indicator, len);
+            frames.push(self.frame(Pgn::MemoryAccessResponse, peer, busy));
+            return;
+        }
+
+        let Pointer::Direct(address) = request.pointer() else {
+            let response = fail(ErrorIndicator::AddressingGeneral);
+            frames.push(self.frame(Pgn::MemoryAccessResponse, peer, response));
+            return;
+        };
+
+        let Some(offset) = self.translate(address, len as u32) else {
+            let response = fail(ErrorIndicator::AddressingOutOfBounds);
+            frames.push(self.frame(Pgn::MemoryAccessResponse, peer, response));
+            return;
+        };
+
+        let proceed = response(Status::Proceed, ErrorIndicator::None, len);
+        match request.command() {
+            Command::Erase => {
+                let erase = F::ERASE_SIZE as u32;
+                let from = offset - offset % erase;
+                let to = (offset + len as u32).next_multiple_of(erase);
+                if self.translate_offset(from, to).is_none() {
+                    let response = fail(ErrorIndicator::AddressingBoundary);
+                    frames.push(self.frame(Pgn::MemoryAccessResponse, peer, response));
+                    return;
+                }
+                self.pending = Some(Pending::Erase { peer, from, to });
+                frames.push(self.frame(Pgn::MemoryAccessResponse, peer, proceed));
+            }
+            Command::Write if len as usize <= BinaryDataTransfer::MAX_LEN => {
+                self.pending = Some(Pending::Write { peer, offset, len });
+                frames.push(self.frame(Pgn::MemoryAccessResponse, peer, proceed));
+            }
+            Command::Read if len as usize <= BinaryDataTransfer::MAX_LEN => {
+                let mut buf = [0; BinaryDataTransfer::MAX_LEN];
+                let buf = &mut buf[..len as usize];
+                let read = self.flush().and_then(|_| self.flash.read(offset, buf));
+                let dm16 = match read {
+                    Ok(()) => BinaryDataTransfer::new(buf),
+                    Err(_) => None,
+                };
+                match dm16 {
+                    Some(dm16) => {
+                        frames.push(self.frame(Pgn::MemoryAccessResponse, peer, proceed));
+                        frames.push(self.frame(Pgn::BinaryDataTransfer, peer, (&dm16).into()));
+                    }
+                    None => {
+                        let response = fail(ErrorIndicator::InternalFailure);
+                        frames.push(self.frame(Pgn::MemoryAccessResponse, peer, response));
+                    }
+                }
+            }
+            Command::Write | Command::Read => {
+                let response = fail(ErrorIndicator::AddressingLength);
+                frames.push(self.frame(Pgn::MemoryAccessResponse, peer, response));
+            }
+            _ => {
+                let response = fail(ErrorIndicator::NotIdentified);
+                frames.push(self.frame(Pgn::MemoryAccessResponse, peer, response));
+            }
+        }
+    }
+
+    fn data(&mut self, peer: u8, dm16: &BinaryDataTransfer, frames: &mut Frames) {
+        let Some(Pending::Write {
+            peer: p,
+            offset,
+            len,
+        }) = self.pending
+        else {
+            return;
+        };
+
+        if p != peer {
+            return;
+        }
+
+        let response = if dm16.data().len() != len as usize {
+            response(
+                Status::OperationFailed,
+                ErrorIndicator::AddressingLength,
+                len,
+            )
+        } else if self.stage_write(offset, dm16.data()).is_err() {
+            response(
+                Status::OperationFailed,
+                ErrorIndicator::FlashVerifyOnWrite,
+                len,
+            )
+        } else {
+            response(Status::OperationCompleted, ErrorIndicator::None, len)
+        };
+
+        frames.push(self.frame(Pgn::MemoryAccessResponse, peer, response));
+    }
+
+    /// Buffer `data` at `offset`, writing out complete flash words.
+    fn stage_write(&mut self, offset: u32, data: &[u8]) -> Result<(), F::Error> {
+        let word = F::WRITE_SIZE as u32;
+
+        if self.stage_len > 0 && self.stage_offset + self.stage_len as u32 != offset {
+            self.flush()?;
+        }
+
+        if self.stage_len == 0 {
+            self.stage_offset = offset - offset % word;
+            self.stage_len = (offset % word) as usize;
+            self.stage[..self.stage_len].fill(0xFF);
+        }
+
+        for byte in data {
+            self.stage[self.stage_len] = *byte;
+            self.stage_len += 1;
+
+            if self.stage_len == STAGE_LEN {
+                self.flush()?;
+                self.stage_offset += STAGE_LEN as u32;
+            }
+        }
+
+        // write out complete words, keeping the tail for the next write
+        let complete = self.stage_len - self.stage_len % F::WRITE_SIZE;
+        if complete > 0 {
+            self.flash
+                .write(self.stage_offset, &self.stage[..complete])?;
+            self.stage.copy_within(complete..self.stage_len, 0);
+            self.stage_len -= complete;
+            self.stage_offset += complete as u32;
+        }
+
+        Ok(())
+    }
+
+    /// Flash offset of `len` bytes at memory `address`, if within a region.
+    fn translate(&self, address: u32, len: u32) -> Option<u32> {
+        let end = address.checked_add(len)?;
+        self.regions
+            .iter()
+            .find(|r| address >= r.address && end <= r.address + r.len)
+            .map(|r| r.offset + (address - r.address))
+    }
+
+    /// Check if the flash offsets `from..to` lie within a single region.
+    fn translate_offset(&self, from: u32, to: u32) -> Option<&Region> {
+        self.regions
+            .iter()
+            .find(|r| from >= r.offset && to <= r.offset + r.len)
+    }
+
+    fn frame(&self, pgn: Pgn, da: u8, data: [u8; 8]) -> Option<(Id, [u8; 8])> {
+        let id = IdBuilder::new().pgn(pgn).sa(self.address).da(da).build()?;
+        Some((id, data))
+    }
+}
+
+fn response(status: Status, indicator: ErrorIndicator, len: u16) -> [u8; 8] {
+    // lengths come from requests and indicators are never `Other`
+    MemoryAccessResponse::try_new(status, indicator, len, 0xFFFF)
+        .map_or([0xFF; 8], |response| (&response).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flash::{FlashHost, Phase};
+    use crate::time::Instant;
+    use embedded_storage::nor_flash::{ErrorType, NorFlashErrorKind, ReadNorFlash};
+
+    struct Ram([u8; 256]);
+
+    impl ErrorType for Ram {
+        type Error = NorFlashErrorKind;
+    }
+
+    impl ReadNorFlash for Ram {
+        const READ_SIZE: usize = 1;
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.0[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    impl NorFlash for Ram {
+        const WRITE_SIZE: usize = 4;
+        const ERASE_SIZE: usize = 32;
+
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+            self.0[from as usize..to as usize].fill(0xFF);
+            Ok(())
+        }
+
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+            assert_eq!(offset as usize % Self::WRITE_SIZE, 0);
+            assert_eq!(bytes.len() % Self::WRITE_SIZE, 0);
+            let offset = offset as usize;
+            for (cell, byte) in self.0[offset..offset + bytes.len()].iter_mut().zip(bytes) {
+                // programming only clears bits
+                *cell &= *byte;
+            }
+            Ok(())
+        }
+    }
+
+    const REGIONS: [Region; 1] = [Region {
+        address: 0x0800_0040,
+        offset: 0x40,
+        len: 0x80,
+    }];
+
+    #[test]
+    fn flash_with_host() {
+        let image: std::vec::Vec<u8> = (0..45).collect();
+        let mut target = FlashTarget::new(Ram([0; 256]), 0x00, &REGIONS).unwrap();
+        let mut host = FlashHost::new(0xF9, 0x00, 0x0800_0044, &image);
+        let now = Instant::default();
+
+        let mut to_target = host.poll(None, now);
+        for _ in 0..200 {
+            let frames: std::vec::Vec<_> = target
+                .poll(to_target.as_ref().map(|(id, data)| (*id, &data[..])))
+                .collect();
+            to_target = host.poll(None, now);
+            for (id, data) in frames {
+                if let Some(frame) = host.poll(Some((id, &data)), now) {
+                    to_target = Some(frame);
+                }
+            }
+            if host.is_finished() {
+                break;
+            }
+        }
+
+        assert_eq!(host.progress().phase, Phase::Done);
+        let flash = target.into_inner();
+        assert_eq!(flash.0[0x44..0x44 + 45], image[..]);
+        // erased up to the erase size boundaries
+        assert_eq!(flash.0[0x40..0x44], [0xFF; 4]);
+        assert_eq!(flash.0[0x71..0x80], [0xFF; 15]);
+        assert_eq!(flash.0[0x80], 0);
+    }
+
+    #[test]
+    fn reject_out_of_bounds() {
+        let mut target = FlashTarget::new(Ram([0; 256]), 0x00, &REGIONS).unwrap();
+        let request =
+            MemoryAccessRequest::try_new(Command::Erase, Pointer::Direct(0), 16, 0xFFFF).unwrap();
+        let id = IdBuilder::new()
+            .pgn(Pgn::MemoryAccessRequest)
+            .sa(0xF9)
+            .da(0x00)
+            .build()
+            .unwrap();
+        let data: [u8; 8] = (&request).into();
+
+        let (_, response) = target.poll(Some((id, &data))).next().unwrap();
+        let response = MemoryAccessResponse::try_from(&response[..]).unwrap();
+        assert_eq!(response.status(), Status::OperationFailed);
+        assert_eq!(
+            response.error_indicator(),
+            ErrorIndicator::AddressingOutOfBounds
+        );
+    }
+}
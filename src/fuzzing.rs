@@ -0,0 +1,145 @@
+//! [`Arbitrary`] implementations for frame-level types
+//!
+//! Values are generated through the same constructors and parsers used for
+//! bus traffic, so they always uphold the invariants of their type.
+
+use crate::can::Frame;
+use crate::diagnostic::{MemoryAccessRequest, MemoryAccessResponse};
+use crate::id::{Id, Pgn};
+use crate::payload::{MAX_LEN, Payload};
+use crate::transport::{
+    BroadcastAnnounce, ClearToSend, ConnectionAbort, ControlMessage, DataTransfer, EndOfMessageAck,
+    RequestToSend,
+};
+use arbitrary::{Arbitrary, Error, Result, Unstructured};
+
+/// Parse an arbitrary 8 byte frame, optionally forcing the multiplexer.
+fn parse<'a, T>(u: &mut Unstructured<'a>, mux: Option<u8>) -> Result<T>
+where
+    T: for<'b> TryFrom<&'b [u8]>,
+{
+    let mut raw: [u8; 8] = u.arbitrary()?;
+    if let Some(mux) = mux {
+        raw[0] = mux;
+    }
+    T::try_from(&raw[..]).map_err(|_| Error::IncorrectFormat)
+}
+
+impl<'a> Arbitrary<'a> for Id {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Id::new(u32::arbitrary(u)? & 0x1FFF_FFFF))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Pgn {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Pgn::from(u32::arbitrary(u)? & 0x3FFFF))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Payload {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let len = u.int_in_range(0..=MAX_LEN)?;
+        let data = u.bytes(len)?;
+        Payload::new(data).ok_or(Error::IncorrectFormat)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Frame {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let id = Id::arbitrary(u)?;
+        let len = u.int_in_range(0..=8)?;
+        let data = u.bytes(len)?;
+        <Frame as embedded_can::Frame>::new(embedded_can::Id::from(id), data)
+            .ok_or(Error::IncorrectFormat)
+    }
+}
+
+impl<'a> Arbitrary<'a> for RequestToSend {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        parse(u, Some(16))
+    }
+}
+
+impl<'a> Arbitrary<'a> for ClearToSend {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        parse(u, Some(17))
+    }
+}
+
+impl<'a> Arbitrary<'a> for EndOfMessageAck {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        parse(u, Some(19))
+    }
+}
+
+impl<'a> Arbitrary<'a> for BroadcastAnnounce {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        parse(u, Some(32))
+    }
+}
+
+impl<'a> Arbitrary<'a> for ConnectionAbort {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        parse(u, Some(255))
+    }
+}
+
+impl<'a> Arbitrary<'a> for ControlMessage {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mux = *u.choose(&[16, 17, 19, 32, 255])?;
+        parse(u, Some(mux))
+    }
+}
+
+impl<'a> Arbitrary<'a> for DataTransfer {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        parse(u, None)
+    }
+}
+
+impl<'a> Arbitrary<'a> for MemoryAccessRequest {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        parse(u, None)
+    }
+}
+
+impl<'a> Arbitrary<'a> for MemoryAccessResponse {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        parse(u, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate() {
+        let bytes: std::vec::Vec<u8> = (0..=255).cycle().take(1024).collect();
+        let mut u = Unstructured::new(&bytes);
+
+        let id = Id::arbitrary(&mut u).unwrap();
+        assert!(id.as_raw() <= 0x1FFF_FFFF);
+
+        let frame = Frame::arbitrary(&mut u).unwrap();
+        assert!(embedded_can::Frame::dlc(&frame) <= 8);
+
+        assert!(ControlMessage::arbitrary(&mut u).is_ok());
+        assert!(DataTransfer::arbitrary(&mut u).is_ok());
+        assert!(MemoryAccessRequest::arbitrary(&mut u).is_ok());
+    }
+
+    #[test]
+    fn transfer_zero_window() {
+        // input of the transfer target that divided by zero: a RTS for 3
+        // packets with a window of 0, followed by the first data transfer
+        let bytes = [16, 16, 0, 3, 0, 0, 0xEF, 0, 1, 1, 2, 3, 4, 5, 6, 7];
+        let mut u = Unstructured::new(&bytes);
+
+        let rts = RequestToSend::arbitrary(&mut u).unwrap();
+        let dt = DataTransfer::arbitrary(&mut u).unwrap();
+        let mut transfer = crate::transport::Transfer::new(rts);
+        assert_eq!(transfer.next(dt), Ok(None));
+    }
+}
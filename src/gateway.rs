@@ -0,0 +1,482 @@
+//! Bridging between two network segments
+//!
+//! [`Gateway`] decides which frames received on one side are forwarded to
+//! the other, optionally rewriting the source address and reassembling
+//! transport sessions addressed to the gateway so they can be re-originated
+//! as broadcasts on the other segment.
+
+use crate::id::{Id, IdBuilder, Pgn};
+use crate::node::{Frames, Node};
+use crate::router::Filter;
+use crate::time::Instant;
+use crate::transport::{BroadcastTransfer, ControlMessage, DataTransferRef};
+
+const GLOBAL: u8 = 0xFF;
+
+/// Check if `pgn` passes `rules` applied in `mode`.
+fn passes(mode: Mode, rules: &[Option<Filter>], pgn: Pgn) -> bool {
+    let matched = rules.iter().flatten().any(|r| r.matches(pgn));
+    match mode {
+        Mode::Allow => matched,
+        Mode::Deny => !matched,
+    }
+}
+
+/// Identifier `id` sent from `sa` instead.
+fn resource(id: Id, sa: u8) -> Option<Id> {
+    let builder = IdBuilder::new()
+        .priority(id.priority())
+        .pgn(id.pgn())
+        .edp(id.edp())
+        .sa(sa);
+    match id.da() {
+        Some(da) => builder.da(da).build(),
+        None => builder.build(),
+    }
+}
+
+/// One of the two bridged segments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Side {
+    A,
+    B,
+}
+
+impl Side {
+    /// The opposite segment.
+    pub fn other(&self) -> Side {
+        match self {
+            Side::A => Side::B,
+            Side::B => Side::A,
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Side::A => 0,
+            Side::B => 1,
+        }
+    }
+}
+
+/// How the filter rules are applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Mode {
+    /// Forward only PGNs matching a rule.
+    Allow,
+    /// Forward every PGN except those matching a rule.
+    Deny,
+}
+
+/// Result of handling a received frame.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Handled<'a> {
+    /// Frames to send back on the receiving side, such as transport
+    /// protocol responses.
+    pub responses: Frames,
+    /// Message to send on the other side.
+    pub forward: Option<Forward<'a>>,
+}
+
+/// Message forwarded to the other side.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Forward<'a> {
+    /// Received frame, with the source address rewritten if resourced.
+    Frame(Id, &'a [u8]),
+    /// Reassembled message, re-originated globally as a broadcast transfer.
+    ///
+    /// The identifier is the one of the connection management frames,
+    /// starting with [`BroadcastTransfer::announce`].
+    Broadcast(Id, BroadcastTransfer<'a>),
+}
+
+/// Transport session between two other nodes, forwarded frame by frame.
+#[derive(Debug, Clone, Copy)]
+struct Passing {
+    side: Side,
+    originator: u8,
+    /// Responder, or the global address for a broadcast.
+    responder: u8,
+    pgn: Pgn,
+    total_packets: u8,
+}
+
+/// Transport sessions seen passing through, to filter their data transfers
+/// by the PGN announced when they were opened.
+#[derive(Debug)]
+struct Sessions<const S: usize> {
+    entries: [Option<Passing>; S],
+    /// Entry replaced next once all are in use.
+    next: usize,
+}
+
+impl<const S: usize> Sessions<S> {
+    /// PGN carried by a transport protocol frame received on `side`.
+    ///
+    /// Connection management frames carry it themselves, data transfers
+    /// belong to the session opened before between the same addresses.
+    /// Returns `None` for malformed frames and data transfers of unknown
+    /// sessions.
+    fn pgn(&mut self, side: Side, id: Id, data: &[u8]) -> Option<Pgn> {
+        let (sa, da) = (id.sa(), id.da()?);
+
+        if id.pgn() == Pgn::TransportProtocolDataTransfer {
+            let sequence = DataTransferRef::try_from(data).ok()?.sequence();
+            let index = self.find(side, sa, da)?;
+            let session = self.entries[index]?;
+            // broadcasts are not acknowledged
+            if da == GLOBAL && sequence >= session.total_packets {
+                self.entries[index] = None;
+            }
+            return Some(session.pgn);
+        }
+
+        match ControlMessage::try_from(data).ok()? {
+            ControlMessage::RequestToSend(rts) => {
+                self.open(side, sa, da, rts.pgn(), rts.total_packets());
+                Some(rts.pgn())
+            }
+            ControlMessage::BroadcastAnnounce(bam) => {
+                self.open(side, sa, da, bam.pgn(), bam.total_packets());
+                Some(bam.pgn())
+            }
+            ControlMessage::ClearToSend(cts) => Some(cts.pgn()),
+            ControlMessage::EndOfMessageAck(end) => {
+                self.close(side, da, sa);
+                Some(end.pgn())
+            }
+            ControlMessage::ConnectionAbort(abort) => {
+                // sent by either side
+                self.close(side, sa, da);
+                self.close(side, da, sa);
+                Some(abort.pgn())
+            }
+        }
+    }
+
+    fn find(&self, side: Side, originator: u8, responder: u8) -> Option<usize> {
+        self.entries.iter().position(|entry| {
+            entry.is_some_and(|s| {
+                s.side == side && s.originator == originator && s.responder == responder
+            })
+        })
+    }
+
+    /// Record a session, replacing the one between the same addresses or,
+    /// when full, the oldest one.
+    fn open(&mut self, side: Side, originator: u8, responder: u8, pgn: Pgn, total_packets: u8) {
+        let index = match self.find(side, originator, responder) {
+            Some(index) => index,
+            None => match self.entries.iter().position(Option::is_none) {
+                Some(index) => index,
+                None => {
+                    let index = self.next % S;
+                    self.next = (index + 1) % S;
+                    index
+                }
+            },
+        };
+
+        if let Some(entry) = self.entries.get_mut(index) {
+            *entry = Some(Passing {
+                side,
+                originator,
+                responder,
+                pgn,
+                total_packets,
+            });
+        }
+    }
+
+    fn close(&mut self, side: Side, originator: u8, responder: u8) {
+        if let Some(index) = self.find(side, originator, responder) {
+            self.entries[index] = None;
+        }
+    }
+}
+
+/// Forwards frames between two segments.
+///
+/// Holds up to `N` filter rules without allocating. Transport sessions
+/// between other nodes are forwarded frame by frame and filtered by the PGN
+/// they carry, tracking up to `S` of them at once.
+#[derive(Debug)]
+pub struct Gateway<'a, const N: usize, const S: usize = 8> {
+    mode: Mode,
+    rules: [Option<Filter>; N],
+    source: [Option<u8>; 2],
+    nodes: Option<[Node<'a>; 2]>,
+    sessions: Sessions<S>,
+}
+
+impl<'a, const N: usize, const S: usize> Gateway<'a, N, S> {
+    /// Create a new gateway without rules.
+    pub fn new(mode: Mode) -> Self {
+        Self {
+            mode,
+            rules: [None; N],
+            source: [None; 2],
+            nodes: None,
+            sessions: Sessions {
+                entries: [None; S],
+                next: 0,
+            },
+        }
+    }
+
+    /// Add a filter rule.
+    ///
+    /// Returns the rule if the gateway is full.
+    pub fn rule(&mut self, filter: Filter) -> Result<(), Filter> {
+        match self.rules.iter_mut().find(|r| r.is_none()) {
+            Some(slot) => {
+                *slot = Some(filter);
+                Ok(())
+            }
+            None => Err(filter),
+        }
+    }
+
+    /// Rewrite the source address of frames forwarded onto `side` to `sa`.
+    pub fn resource(&mut self, side: Side, sa: u8) {
+        self.source[side.index()] = Some(sa);
+    }
+
+    /// Reassemble transport sessions instead of forwarding them frame by
+    /// frame.
+    ///
+    /// `a` and `b` receive the sessions addressed to them on the respective
+    /// side. Other transport frames, including broadcast transfers, are
+    /// forwarded frame by frame.
+    pub fn reassemble(&mut self, a: Node<'a>, b: Node<'a>) {
+        self.nodes = Some([a, b]);
+    }
+
+    /// Check if `pgn` passes the filter rules.
+    pub fn allows(&self, pgn: Pgn) -> bool {
+        passes(self.mode, &self.rules, pgn)
+    }
+
+    /// Handle a frame received on `from`.
+    pub fn handle<'s>(
+        &'s mut self,
+        from: Side,
+        id: Id,
+        data: &'s [u8],
+        now: Instant,
+    ) -> Handled<'s> {
+        let to = from.other();
+        let Self {
+            mode,
+            rules,
+            source,
+            nodes,
+            sessions,
+        } = self;
+
+        let transport = matches!(
+            id.pgn(),
+            Pgn::TransportProtocolConnectionManagement | Pgn::TransportProtocolDataTransfer
+        );
+
+        if let Some(nodes) = nodes
+            && transport
+            && id.da() == Some(nodes[from.index()].address())
+        {
+            let node = &mut nodes[from.index()];
+            let responses = node.poll(Some((id, data)), now);
+
+            let forward = node.received().and_then(|(pgn, sa, data)| {
+                if !passes(*mode, rules, pgn) {
+                    return None;
+                }
+
+                // re-originated globally, as the original destination was the gateway
+                let id = IdBuilder::new()
+                    .priority(id.priority())
+                    .pgn(Pgn::TransportProtocolConnectionManagement)
+                    .sa(source[to.index()].unwrap_or(sa))
+                    .da(GLOBAL)
+                    .build()?;
+                let transfer = BroadcastTransfer::new(data, pgn).ok()?;
+                Some(Forward::Broadcast(id, transfer))
+            });
+
+            return Handled { responses, forward };
+        }
+
+        let pgn = if transport {
+            sessions.pgn(from, id, data)
+        } else {
+            Some(id.pgn())
+        };
+
+        let forward = pgn
+            .filter(|pgn| passes(*mode, rules, *pgn))
+            .and_then(|_| match source[to.index()] {
+                Some(sa) => resource(id, sa),
+                None => Some(id),
+            })
+            .map(|id| Forward::Frame(id, data));
+
+        Handled {
+            responses: Frames::default(),
+            forward,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IdBuilder;
+    use crate::transport::RequestToSend;
+
+    #[test]
+    fn filter_and_resource() {
+        let mut gateway = Gateway::<2>::new(Mode::Allow);
+        assert!(gateway.rule(Filter::Pgn(Pgn::TimeDate)).is_ok());
+        gateway.resource(Side::B, 0x80);
+
+        let td = IdBuilder::new()
+            .pgn(Pgn::TimeDate)
+            .sa(0x10)
+            .build()
+            .unwrap();
+        let handled = gateway.handle(Side::A, td, &[0; 8], Instant::default());
+        let Some(Forward::Frame(id, data)) = handled.forward else {
+            panic!("Expected frame");
+        };
+        assert_eq!(id.pgn(), Pgn::TimeDate);
+        assert_eq!(id.sa(), 0x80);
+        assert_eq!(data, &[0; 8]);
+
+        // not resourced towards A
+        let handled = gateway.handle(Side::B, td, &[0; 8], Instant::default());
+        assert!(matches!(handled.forward, Some(Forward::Frame(id, _)) if id.sa() == 0x10));
+
+        let vd = IdBuilder::new()
+            .pgn(Pgn::VehicleDistance)
+            .sa(0x10)
+            .build()
+            .unwrap();
+        let handled = gateway.handle(Side::A, vd, &[0; 8], Instant::default());
+        assert!(handled.forward.is_none());
+    }
+
+    #[test]
+    fn reassemble() {
+        let mut a = [0; 16];
+        let mut b = [0; 16];
+        let mut gateway = Gateway::<1>::new(Mode::Deny);
+        gateway.reassemble(Node::new(0x80, &mut a[..]), Node::new(0x80, &mut b[..]));
+
+        let id = |pgn, da| {
+            IdBuilder::new()
+                .priority(5)
+                .pgn(pgn)
+                .sa(0x10)
+                .da(da)
+                .build()
+                .unwrap()
+        };
+        let cm = id(Pgn::TransportProtocolConnectionManagement, 0x80);
+        let dt = id(Pgn::TransportProtocolDataTransfer, 0x80);
+        let now = Instant::default();
+
+        let rts: [u8; 8] = RequestToSend::try_new(9, None, Pgn::ProprietaryA)
+            .unwrap()
+            .into();
+        let mut handled = gateway.handle(Side::A, cm, &rts, now);
+        assert!(handled.responses.next().is_some());
+        assert!(handled.forward.is_none());
+
+        let handled = gateway.handle(Side::A, dt, &[1, 1, 2, 3, 4, 5, 6, 7], now);
+        assert!(handled.forward.is_none());
+
+        let handled = gateway.handle(Side::A, dt, &[2, 8, 9, 0, 0, 0, 0, 0], now);
+        let Some(Forward::Broadcast(bam_id, mut transfer)) = handled.forward else {
+            panic!("Expected broadcast transfer");
+        };
+        assert_eq!(bam_id.pgn(), Pgn::TransportProtocolConnectionManagement);
+        assert_eq!(bam_id.priority(), 5);
+        assert_eq!(bam_id.sa(), 0x10);
+        assert_eq!(bam_id.da(), Some(0xFF));
+        let bam = transfer.announce(now);
+        assert_eq!(bam.pgn(), Pgn::ProprietaryA);
+        assert_eq!(bam.total_size(), 9);
+        let packet = transfer.next_packet(now + crate::time::Duration::from_millis(50));
+        assert_eq!(packet.unwrap().data(), [1, 2, 3, 4, 5, 6, 7]);
+
+        // sessions between other nodes pass through
+        let cm = id(Pgn::TransportProtocolConnectionManagement, 0x20);
+        let mut handled = gateway.handle(Side::A, cm, &rts, now);
+        assert!(handled.responses.next().is_none());
+        assert!(matches!(handled.forward, Some(Forward::Frame(id, _)) if id.da() == Some(0x20)));
+    }
+
+    #[test]
+    fn transport_filtered_by_carried_pgn() {
+        let id = |pgn, sa, da| {
+            IdBuilder::new()
+                .priority(7)
+                .pgn(pgn)
+                .sa(sa)
+                .da(da)
+                .build()
+                .unwrap()
+        };
+        let cm = id(Pgn::TransportProtocolConnectionManagement, 0x10, 0x20);
+        let dt = id(Pgn::TransportProtocolDataTransfer, 0x10, 0x20);
+        let now = Instant::default();
+        let rts = |pgn| -> [u8; 8] { RequestToSend::try_new(9, None, pgn).unwrap().into() };
+        let (proprietary, software) = (rts(Pgn::ProprietaryA), rts(Pgn::SoftwareIdentification));
+        let data = [1, 1, 2, 3, 4, 5, 6, 7];
+
+        let mut gateway = Gateway::<1>::new(Mode::Deny);
+        assert!(gateway.rule(Filter::Pgn(Pgn::ProprietaryA)).is_ok());
+        gateway.resource(Side::B, 0x80);
+
+        // denied PGN blocked, including its data transfers
+        let handled = gateway.handle(Side::A, cm, &proprietary, now);
+        assert!(handled.forward.is_none());
+        let handled = gateway.handle(Side::A, dt, &data, now);
+        assert!(handled.forward.is_none());
+
+        // other PGNs pass, resourced
+        let handled = gateway.handle(Side::A, cm, &software, now);
+        assert!(matches!(
+            handled.forward,
+            Some(Forward::Frame(id, _)) if id.sa() == 0x80 && id.da() == Some(0x20)
+        ));
+        let handled = gateway.handle(Side::A, dt, &data, now);
+        assert!(matches!(
+            handled.forward,
+            Some(Forward::Frame(id, _))
+                if id.pgn() == Pgn::TransportProtocolDataTransfer && id.priority() == 7
+        ));
+
+        // data transfers of unknown sessions dropped
+        let other = id(Pgn::TransportProtocolDataTransfer, 0x30, 0x20);
+        assert!(gateway.handle(Side::A, other, &data, now).forward.is_none());
+
+        let mut gateway = Gateway::<1>::new(Mode::Allow);
+        assert!(gateway.rule(Filter::Pgn(Pgn::ProprietaryA)).is_ok());
+
+        // allowed PGN passes, without allowing transport frames in general
+        let handled = gateway.handle(Side::A, cm, &proprietary, now);
+        assert!(handled.forward.is_some());
+        let handled = gateway.handle(Side::A, dt, &data, now);
+        assert!(handled.forward.is_some());
+        let handled = gateway.handle(Side::A, cm, &software, now);
+        assert!(handled.forward.is_none());
+        let handled = gateway.handle(Side::A, dt, &data, now);
+        assert!(handled.forward.is_none());
+    }
+}
@@ -1,8 +1,11 @@
+use core::hash::{Hash, Hasher};
+
 /// PDU format.
 ///
 /// See J1939™-21 section 5.3 for more details.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PduFormat {
     /// PS = DA (destination address)
     Pdu1(u8),
@@ -37,7 +40,9 @@ impl From<Pgn> for PduFormat {
 /// Equality comparisons exclude priority bits, making it easy to match frames
 /// by content regardless of priority.
 #[derive(Debug, Clone, Copy, Eq)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "u32", into = "u32"))]
 pub struct Id(u32);
 
 impl Id {
@@ -122,16 +127,35 @@ impl PartialEq for Id {
     }
 }
 
+/// Hashes exclude priority bits, like equality comparisons.
+impl Hash for Id {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.0 & 0x3FFFFFF).hash(state);
+    }
+}
+
+impl From<u32> for Id {
+    fn from(raw: u32) -> Self {
+        Self::new(raw)
+    }
+}
+
+impl From<Id> for u32 {
+    fn from(id: Id) -> Self {
+        id.0
+    }
+}
+
 impl From<embedded_can::ExtendedId> for Id {
     fn from(id: embedded_can::ExtendedId) -> Self {
         Self(id.as_raw())
     }
 }
 
-#[allow(clippy::unwrap_used)]
 impl From<Id> for embedded_can::ExtendedId {
     fn from(id: Id) -> Self {
-        embedded_can::ExtendedId::new(id.0).unwrap()
+        // identifiers are masked to 29 bits on construction
+        embedded_can::ExtendedId::new(id.0).unwrap_or(embedded_can::ExtendedId::ZERO)
     }
 }
 
@@ -141,8 +165,24 @@ impl From<Id> for embedded_can::Id {
     }
 }
 
+/// Incomplete or invalid identifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IdError {
+    /// Priority above 7.
+    Priority,
+    /// No parameter group number set.
+    MissingPgn,
+    /// No source address set.
+    MissingSourceAddress,
+    /// No destination address set for a PDU1 parameter group.
+    MissingDestinationAddress,
+}
+
 #[derive(Debug, Clone, Copy)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IdBuilder {
     priority: Option<u8>,
     pgn: Option<Pgn>,
@@ -170,16 +210,15 @@ impl IdBuilder {
 
     /// Priority.
     ///
-    /// Default is 6 if not set.
+    /// Default is 6 if not set. Priorities above 7 fail the build.
     pub fn priority(mut self, p: u8) -> Self {
-        assert!(p <= 7);
         self.priority = Some(p);
         self
     }
 
     /// Parameter group number.
     ///
-    /// Must be set or `.build()` will panic.
+    /// Must be set or `.build()` will fail.
     pub fn pgn(mut self, pgn: Pgn) -> Self {
         self.pgn = Some(pgn);
         self
@@ -193,7 +232,7 @@ impl IdBuilder {
 
     /// Destination address.
     ///
-    /// Required for PDU1 messages or `.build()` will panic.
+    /// Required for PDU1 messages or `.build()` will fail.
     pub fn da(mut self, da: u8) -> Self {
         self.da = Some(da);
         self
@@ -211,19 +250,31 @@ impl IdBuilder {
         self
     }
 
+    /// Build the identifier, `None` if it is incomplete or invalid.
     pub fn build(self) -> Option<Id> {
-        let mut id = ((self.priority.unwrap_or(6) as u32) << 26)
-            | (u32::from(self.pgn?) << 8)
-            | (self.sa? as u32);
+        self.try_build().ok()
+    }
+
+    /// Build the identifier.
+    pub fn try_build(self) -> Result<Id, IdError> {
+        let priority = self.priority.unwrap_or(6);
+        if priority > 7 {
+            return Err(IdError::Priority);
+        }
+        let pgn = self.pgn.ok_or(IdError::MissingPgn)?;
+        let sa = self.sa.ok_or(IdError::MissingSourceAddress)?;
+
+        let mut id = ((priority as u32) << 26) | (u32::from(pgn) << 8) | (sa as u32);
 
         if let PduFormat::Pdu1(_) = Id::new(id).pf() {
-            id |= (self.da? as u32) << 8;
+            let da = self.da.ok_or(IdError::MissingDestinationAddress)?;
+            id |= (da as u32) << 8;
         }
 
         id |= (self.dp as u32) << 24;
         id |= (self.edp as u32) << 25;
 
-        Some(Id(id))
+        Ok(Id(id))
     }
 }
 
@@ -234,8 +285,16 @@ impl Default for IdBuilder {
 }
 
 /// Parameter group number (PGN)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+///
+/// Equality comparisons and hashes use the number, so [`Pgn::Other`] holding
+/// the number of a named group equals that group.
+///
+/// Without the `descriptions` feature, `Debug` prints the number instead of
+/// the variant name.
+#[derive(Clone, Copy, Eq)]
+#[cfg_attr(feature = "descriptions", derive(Debug))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Pgn {
     /// RQST2 - Request 2
     Request2,
@@ -253,6 +312,24 @@ pub enum Pgn {
     Request,
     /// ACKM - Acknowledgement
     Acknowledgement,
+    /// ACL - Address Claimed
+    AddressClaimed,
+    /// DM1 - Active Diagnostic Trouble Codes
+    ActiveDiagnosticTroubleCodes,
+    /// DM2 - Previously Active Diagnostic Trouble Codes
+    PreviouslyActiveDiagnosticTroubleCodes,
+    /// DM3 - Diagnostic Data Clear/Reset of Previously Active DTCs
+    DiagnosticDataClearPreviouslyActive,
+    /// DM11 - Diagnostic Data Clear/Reset for Active DTCs
+    DiagnosticDataClearActive,
+    /// FD.TP.DT - CAN FD Transport Protocol - Data Transfer
+    FdTransportProtocolDataTransfer,
+    /// FD.TP.CM - CAN FD Transport Protocol - Connection Mgmt
+    FdTransportProtocolConnectionManagement,
+    /// ETP.DT - Extended Transport Protocol - Data Transfer
+    ExtendedTransportProtocolDataTransfer,
+    /// ETP.CM - Extended Transport Protocol - Connection Mgmt
+    ExtendedTransportProtocolConnectionManagement,
     /// TP.DT - Transport Protocol - Data Transfer
     TransportProtocolDataTransfer,
     /// TP.CM - Transport Protocol - Connection Mgmt
@@ -265,6 +342,62 @@ pub enum Pgn {
     ProprietaryB(u8),
     /// PropB2 - Proprietary B2
     ProprietaryB2(u8),
+    /// EEC2 - Electronic Engine Controller 2
+    ElectronicEngineController2,
+    /// EEC3 - Electronic Engine Controller 3
+    ElectronicEngineController3,
+    /// EFL/P1 - Engine Fluid Level/Pressure 1
+    EngineFluidLevelPressure1,
+    /// LFE - Fuel Economy (Liquid)
+    FuelEconomy,
+    /// LFC - Fuel Consumption (Liquid)
+    FuelConsumption,
+    /// VEP1 - Vehicle Electrical Power 1
+    VehicleElectricalPower1,
+    /// HOURS - Engine Hours, Revolutions
+    EngineHoursRevolutions,
+    /// VD - Vehicle Distance
+    VehicleDistance,
+    /// VDHR - High Resolution Vehicle Distance
+    HighResolutionVehicleDistance,
+    /// AMB - Ambient Conditions
+    AmbientConditions,
+    /// TSC1 - Torque/Speed Control 1
+    TorqueSpeedControl1,
+    /// EBC1 - Electronic Brake Controller 1
+    ElectronicBrakeController1,
+    /// EBC2 - Wheel Speed Information
+    WheelSpeedInformation,
+    /// ETC1 - Electronic Transmission Controller 1
+    ElectronicTransmissionController1,
+    /// ETC2 - Electronic Transmission Controller 2
+    ElectronicTransmissionController2,
+    /// TCO1 - Tachograph
+    Tachograph,
+    /// SOFT - Software Identification
+    SoftwareIdentification,
+    /// VI - Vehicle Identification
+    VehicleIdentification,
+    /// TD - Time/Date
+    TimeDate,
+    /// SHUTDN - Shutdown
+    Shutdown,
+    /// ERC1 - Electronic Retarder Controller 1
+    ElectronicRetarderController1,
+    /// AUXIO1 - Auxiliary Input/Output Status 1
+    AuxiliaryIoStatus1,
+    /// AUXIO2 - Auxiliary Input/Output Status 2
+    AuxiliaryIoStatus2,
+    /// Product Information (NMEA 2000)
+    ProductInformation,
+    /// Configuration Information (NMEA 2000)
+    ConfigurationInformation,
+    /// VT to ECU - Virtual Terminal to ECU (ISO 11783-6)
+    VtToEcu,
+    /// ECU to VT - ECU to Virtual Terminal (ISO 11783-6)
+    EcuToVt,
+    /// WSM - Working Set Master (ISO 11783-7)
+    WorkingSetMaster,
     /// Unknown PGN
     Other(u32),
 }
@@ -277,6 +410,13 @@ impl Pgn {
 
 impl From<u32> for Pgn {
     fn from(value: u32) -> Self {
+        Self::from_raw(value)
+    }
+}
+
+impl Pgn {
+    /// PGN from its number.
+    pub const fn from_raw(value: u32) -> Self {
         match value {
             51456 => Self::Request2,
             51712 => Self::Transfer,
@@ -286,10 +426,47 @@ impl From<u32> for Pgn {
             55552 => Self::MemoryAccessRequest,
             59904 => Self::Request,
             59392 => Self::Acknowledgement,
+            60928 => Self::AddressClaimed,
+            65226 => Self::ActiveDiagnosticTroubleCodes,
+            65227 => Self::PreviouslyActiveDiagnosticTroubleCodes,
+            65228 => Self::DiagnosticDataClearPreviouslyActive,
+            65235 => Self::DiagnosticDataClearActive,
+            19968 => Self::FdTransportProtocolDataTransfer,
+            19712 => Self::FdTransportProtocolConnectionManagement,
+            50944 => Self::ExtendedTransportProtocolDataTransfer,
+            51200 => Self::ExtendedTransportProtocolConnectionManagement,
             60160 => Self::TransportProtocolDataTransfer,
             60416 => Self::TransportProtocolConnectionManagement,
             61184 => Self::ProprietaryA,
             126720 => Self::ProprietaryA2,
+            61443 => Self::ElectronicEngineController2,
+            65247 => Self::ElectronicEngineController3,
+            65263 => Self::EngineFluidLevelPressure1,
+            65266 => Self::FuelEconomy,
+            65257 => Self::FuelConsumption,
+            65271 => Self::VehicleElectricalPower1,
+            65253 => Self::EngineHoursRevolutions,
+            65248 => Self::VehicleDistance,
+            65217 => Self::HighResolutionVehicleDistance,
+            65269 => Self::AmbientConditions,
+            0 => Self::TorqueSpeedControl1,
+            61441 => Self::ElectronicBrakeController1,
+            65215 => Self::WheelSpeedInformation,
+            61442 => Self::ElectronicTransmissionController1,
+            61445 => Self::ElectronicTransmissionController2,
+            65132 => Self::Tachograph,
+            65242 => Self::SoftwareIdentification,
+            65260 => Self::VehicleIdentification,
+            65254 => Self::TimeDate,
+            65252 => Self::Shutdown,
+            61440 => Self::ElectronicRetarderController1,
+            65241 => Self::AuxiliaryIoStatus1,
+            64829 => Self::AuxiliaryIoStatus2,
+            126996 => Self::ProductInformation,
+            126998 => Self::ConfigurationInformation,
+            58880 => Self::VtToEcu,
+            59136 => Self::EcuToVt,
+            65037 => Self::WorkingSetMaster,
             65280..=65535 => Self::ProprietaryB((value & 0xFF) as u8),
             130816..=131071 => Self::ProprietaryB2((value & 0xFF) as u8),
             _ => Self::Other(value),
@@ -299,7 +476,14 @@ impl From<u32> for Pgn {
 
 impl From<&Pgn> for u32 {
     fn from(value: &Pgn) -> Self {
-        match value {
+        value.as_raw()
+    }
+}
+
+impl Pgn {
+    /// Number of the PGN.
+    pub const fn as_raw(&self) -> u32 {
+        match self {
             Pgn::Request2 => 51456,
             Pgn::Transfer => 51712,
             Pgn::BootLoadData => 54784,
@@ -308,10 +492,47 @@ impl From<&Pgn> for u32 {
             Pgn::MemoryAccessRequest => 55552,
             Pgn::Request => 59904,
             Pgn::Acknowledgement => 59392,
+            Pgn::AddressClaimed => 60928,
+            Pgn::ActiveDiagnosticTroubleCodes => 65226,
+            Pgn::PreviouslyActiveDiagnosticTroubleCodes => 65227,
+            Pgn::DiagnosticDataClearPreviouslyActive => 65228,
+            Pgn::DiagnosticDataClearActive => 65235,
+            Pgn::FdTransportProtocolDataTransfer => 19968,
+            Pgn::FdTransportProtocolConnectionManagement => 19712,
+            Pgn::ExtendedTransportProtocolDataTransfer => 50944,
+            Pgn::ExtendedTransportProtocolConnectionManagement => 51200,
             Pgn::TransportProtocolDataTransfer => 60160,
             Pgn::TransportProtocolConnectionManagement => 60416,
             Pgn::ProprietaryA => 61184,
             Pgn::ProprietaryA2 => 126720,
+            Pgn::ElectronicEngineController2 => 61443,
+            Pgn::ElectronicEngineController3 => 65247,
+            Pgn::EngineFluidLevelPressure1 => 65263,
+            Pgn::FuelEconomy => 65266,
+            Pgn::FuelConsumption => 65257,
+            Pgn::VehicleElectricalPower1 => 65271,
+            Pgn::EngineHoursRevolutions => 65253,
+            Pgn::VehicleDistance => 65248,
+            Pgn::HighResolutionVehicleDistance => 65217,
+            Pgn::AmbientConditions => 65269,
+            Pgn::TorqueSpeedControl1 => 0,
+            Pgn::ElectronicBrakeController1 => 61441,
+            Pgn::WheelSpeedInformation => 65215,
+            Pgn::ElectronicTransmissionController1 => 61442,
+            Pgn::ElectronicTransmissionController2 => 61445,
+            Pgn::Tachograph => 65132,
+            Pgn::SoftwareIdentification => 65242,
+            Pgn::VehicleIdentification => 65260,
+            Pgn::TimeDate => 65254,
+            Pgn::Shutdown => 65252,
+            Pgn::ElectronicRetarderController1 => 61440,
+            Pgn::AuxiliaryIoStatus1 => 65241,
+            Pgn::AuxiliaryIoStatus2 => 64829,
+            Pgn::ProductInformation => 126996,
+            Pgn::ConfigurationInformation => 126998,
+            Pgn::VtToEcu => 58880,
+            Pgn::EcuToVt => 59136,
+            Pgn::WorkingSetMaster => 65037,
             Pgn::ProprietaryB(pgn) => (*pgn as u32) | 0xFF00,
             Pgn::ProprietaryB2(pgn) => (*pgn as u32) | 0x1FF00,
             Pgn::Other(pgn) => *pgn,
@@ -319,6 +540,25 @@ impl From<&Pgn> for u32 {
     }
 }
 
+impl PartialEq for Pgn {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_raw() == other.as_raw()
+    }
+}
+
+impl Hash for Pgn {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_raw().hash(state);
+    }
+}
+
+#[cfg(not(feature = "descriptions"))]
+impl core::fmt::Debug for Pgn {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Pgn({})", self.as_raw())
+    }
+}
+
 impl From<Pgn> for u32 {
     fn from(value: Pgn) -> Self {
         u32::from(&value)
@@ -326,6 +566,7 @@ impl From<Pgn> for u32 {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
@@ -377,6 +618,14 @@ mod tests {
 
         assert_eq!(id, Id::new(2565821696));
         assert_eq!(id.pf(), PduFormat::Pdu1(0xEF));
+
+        let builder = IdBuilder::new().sa(0x00).pgn(Pgn::ProprietaryA);
+        assert_eq!(builder.try_build(), Err(IdError::MissingDestinationAddress));
+        assert_eq!(
+            builder.da(0x55).priority(8).try_build(),
+            Err(IdError::Priority)
+        );
+        assert_eq!(IdBuilder::new().sa(0).try_build(), Err(IdError::MissingPgn));
     }
 
     #[test]
@@ -405,4 +654,24 @@ mod tests {
         assert_eq!(PduFormat::from(Pgn::ProprietaryA), PduFormat::Pdu1(239));
         assert_eq!(PduFormat::from(Pgn::ProprietaryB(0)), PduFormat::Pdu2(255));
     }
+
+    #[test]
+    fn hash_matches_eq() {
+        use std::collections::HashSet;
+
+        let ids: HashSet<Id> = [Id::new(0x18EF5500), Id::new(0x0CEF5500)].into();
+        assert_eq!(ids.len(), 1);
+
+        let pgns: HashSet<Pgn> = [Pgn::ProprietaryA, Pgn::Other(0xEF00)].into();
+        assert_eq!(Pgn::Other(0xEF00), Pgn::ProprietaryA);
+        assert_eq!(pgns.len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn deserialize_masked() {
+        let id: Id = serde_json::from_str("4294967295").unwrap();
+        assert_eq!(id.as_raw(), 0x1FFF_FFFF);
+        assert_eq!(serde_json::to_string(&id).unwrap(), "536870911");
+    }
 }
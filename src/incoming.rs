@@ -0,0 +1,117 @@
+//! Classification of received frames
+//!
+//! [`classify`] decodes a received frame into the message types of this
+//! crate, so a receive loop becomes a single match.
+
+use crate::diagnostic::{BootLoadData, MemoryAccessRequest, MemoryAccessResponse};
+use crate::id::{Id, Pgn};
+use crate::request::{Acknowledgement, Request, Request2, Transfer};
+use crate::transport::{ControlMessage, DataTransfer};
+
+/// Diagnostic memory access messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DmKind {
+    /// DM14 - Memory Access Request
+    MemoryAccessRequest(MemoryAccessRequest),
+    /// DM15 - Memory Access Response
+    MemoryAccessResponse(MemoryAccessResponse),
+    /// DM17 - Boot Load Data
+    BootLoadData(BootLoadData),
+}
+
+/// A classified received frame.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Incoming<'a> {
+    /// TP.CM - Transport Protocol - Connection Mgmt
+    TransportControl(ControlMessage),
+    /// TP.DT - Transport Protocol - Data Transfer
+    TransportData(DataTransfer),
+    /// Diagnostic memory access.
+    Diagnostic(DmKind),
+    /// RQST - Request
+    Request(Request),
+    /// RQST2 - Request 2
+    Request2(Request2),
+    /// XFER - Transfer
+    Transfer(Transfer<'a>),
+    /// ACKM - Acknowledgement
+    Acknowledgement(Acknowledgement),
+    /// Any other parameter group.
+    Application(Pgn, &'a [u8]),
+    /// A known parameter group that failed to decode.
+    Malformed(Pgn, &'a [u8]),
+}
+
+/// Classify a received frame by its PGN and decode it.
+pub fn classify<'a>(id: Id, data: &'a [u8]) -> Incoming<'a> {
+    let pgn = id.pgn();
+
+    let decoded = match pgn {
+        Pgn::TransportProtocolConnectionManagement => {
+            ControlMessage::try_from(data).map(Incoming::TransportControl)
+        }
+        Pgn::TransportProtocolDataTransfer => {
+            DataTransfer::try_from(data).map(Incoming::TransportData)
+        }
+        Pgn::MemoryAccessRequest => MemoryAccessRequest::try_from(data)
+            .map(|dm| Incoming::Diagnostic(DmKind::MemoryAccessRequest(dm))),
+        Pgn::MemoryAccessResponse => MemoryAccessResponse::try_from(data)
+            .map(|dm| Incoming::Diagnostic(DmKind::MemoryAccessResponse(dm))),
+        Pgn::BootLoadData => {
+            BootLoadData::try_from(data).map(|dm| Incoming::Diagnostic(DmKind::BootLoadData(dm)))
+        }
+        Pgn::Request => Request::try_from(data).map(Incoming::Request),
+        Pgn::Request2 => Request2::try_from(data).map(Incoming::Request2),
+        Pgn::Transfer => Transfer::try_from(data).map(Incoming::Transfer),
+        Pgn::Acknowledgement => Acknowledgement::try_from(data).map(Incoming::Acknowledgement),
+        _ => Ok(Incoming::Application(pgn, data)),
+    };
+
+    decoded.unwrap_or(Incoming::Malformed(pgn, data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IdBuilder;
+    use crate::transport::RequestToSend;
+
+    fn id(pgn: Pgn) -> Id {
+        IdBuilder::new().pgn(pgn).sa(0x10).da(0x20).build().unwrap()
+    }
+
+    #[test]
+    fn classify_frames() {
+        let rts: [u8; 8] = RequestToSend::try_new(9, None, Pgn::ProprietaryA)
+            .unwrap()
+            .into();
+        assert!(matches!(
+            classify(id(Pgn::TransportProtocolConnectionManagement), &rts),
+            Incoming::TransportControl(ControlMessage::RequestToSend(_))
+        ));
+
+        let request: [u8; 3] = (&Request::new(Pgn::TimeDate)).into();
+        assert!(matches!(
+            classify(id(Pgn::Request), &request),
+            Incoming::Request(r) if r.pgn() == Pgn::TimeDate
+        ));
+
+        assert!(matches!(
+            classify(id(Pgn::TransportProtocolConnectionManagement), &[0; 8]),
+            Incoming::Malformed(Pgn::TransportProtocolConnectionManagement, _)
+        ));
+
+        let vd = IdBuilder::new()
+            .pgn(Pgn::VehicleDistance)
+            .sa(0x10)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            classify(vd, &[0; 8]),
+            Incoming::Application(Pgn::VehicleDistance, _)
+        ));
+    }
+}
@@ -2,14 +2,74 @@
 #![cfg_attr(not(any(test, feature = "alloc", feature = "std")), no_std)]
 #![deny(clippy::unwrap_used, clippy::expect_used, clippy::panic)]
 
+pub mod address;
+#[cfg(feature = "std")]
+pub mod asc;
+#[cfg(feature = "std")]
+pub mod bus;
+pub mod can;
+#[cfg(feature = "std")]
+pub mod candump;
+pub mod channels;
+pub mod config;
+#[cfg(feature = "std")]
+pub mod dbc;
+#[cfg(feature = "alloc")]
+pub mod decode;
 pub mod diagnostic;
+pub mod error;
+pub mod events;
+pub mod flash;
+#[cfg(feature = "arbitrary")]
+mod fuzzing;
+pub mod gateway;
 mod id;
+pub mod incoming;
+mod logging;
+pub mod messages;
+pub mod name;
+pub mod nmea2000;
+pub mod node;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+pub mod payload;
+#[cfg(feature = "std")]
+pub mod pcapng;
 pub mod prelude;
+pub mod proprietary;
+pub mod request;
+pub mod router;
 pub mod signal;
 pub mod slot;
+#[cfg(feature = "socketcan")]
+pub mod socketcan;
+pub mod spn;
+pub mod telematics;
+pub mod time;
 pub mod transport;
+#[cfg(feature = "ufmt")]
+mod ufmt_impl;
+pub mod vt;
 
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "serde")]
+    pub use crate::slot::{deserialize_slot, serialize_slot};
+    #[cfg(feature = "ufmt")]
+    pub use crate::ufmt_impl::fmt_slot;
+    #[cfg(feature = "defmt")]
+    pub use defmt;
+    #[cfg(feature = "serde")]
+    pub use serde;
+    #[cfg(feature = "ufmt")]
+    pub use ufmt;
+}
+
+pub use can::Frame;
+pub use error::DecodeError;
+pub use error::Error;
 pub use id::Id;
 pub use id::IdBuilder;
+pub use id::IdError;
 pub use id::PduFormat;
 pub use id::Pgn;
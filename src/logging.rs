@@ -0,0 +1,28 @@
+//! Diagnostic events
+//!
+//! Forwards to the [`log`](https://crates.io/crates/log) facade with the `log`
+//! feature and compiles to nothing otherwise, so call sites need no `cfg`.
+
+macro_rules! trace {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        ::log::trace!($($arg)+);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = ::core::format_args!($($arg)+);
+        }
+    }};
+}
+
+macro_rules! debug {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        ::log::debug!($($arg)+);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = ::core::format_args!($($arg)+);
+        }
+    }};
+}
+
+pub(crate) use {debug, trace};
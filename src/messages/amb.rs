@@ -0,0 +1,114 @@
+use crate::error::{DecodeError, exact};
+use crate::signal::{Param8, Param16};
+use crate::slot::{SaePR05, SaeTP01, SaeTP02, Slot};
+
+/// AMB - Ambient Conditions
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AmbientConditions {
+    raw: [u8; 8],
+}
+
+impl AmbientConditions {
+    /// Create a new AMB message.
+    pub fn new(
+        barometric_pressure: SaePR05,
+        cab_interior_temperature: SaeTP02,
+        ambient_air_temperature: SaeTP02,
+        air_inlet_temperature: SaeTP01,
+        road_surface_temperature: SaeTP02,
+    ) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0] = barometric_pressure.parameter().into();
+        raw[1..3].copy_from_slice(&u16::from(cab_interior_temperature.parameter()).to_le_bytes());
+        raw[3..5].copy_from_slice(&u16::from(ambient_air_temperature.parameter()).to_le_bytes());
+        raw[5] = air_inlet_temperature.parameter().into();
+        raw[6..8].copy_from_slice(&u16::from(road_surface_temperature.parameter()).to_le_bytes());
+
+        Self { raw }
+    }
+
+    /// Barometric pressure (SPN 108).
+    pub fn barometric_pressure(&self) -> SaePR05 {
+        SaePR05::new(Param8::from(self.raw[0]))
+    }
+
+    /// Cab interior temperature (SPN 170).
+    pub fn cab_interior_temperature(&self) -> SaeTP02 {
+        SaeTP02::new(Param16::from(u16::from_le_bytes([
+            self.raw[1],
+            self.raw[2],
+        ])))
+    }
+
+    /// Ambient air temperature (SPN 171).
+    pub fn ambient_air_temperature(&self) -> SaeTP02 {
+        SaeTP02::new(Param16::from(u16::from_le_bytes([
+            self.raw[3],
+            self.raw[4],
+        ])))
+    }
+
+    /// Engine air inlet temperature (SPN 172).
+    pub fn air_inlet_temperature(&self) -> SaeTP01 {
+        SaeTP01::new(Param8::from(self.raw[5]))
+    }
+
+    /// Road surface temperature (SPN 79).
+    pub fn road_surface_temperature(&self) -> SaeTP02 {
+        SaeTP02::new(Param16::from(u16::from_le_bytes([
+            self.raw[6],
+            self.raw[7],
+        ])))
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&AmbientConditions> for [u8; 8] {
+    fn from(msg: &AmbientConditions) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for AmbientConditions {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amb() {
+        let raw: &[u8] = &[0xCA, 0xA0, 0x24, 0x60, 0x22, 0x3C, 0xFF, 0xFF];
+
+        let msg = AmbientConditions::try_from(raw).unwrap();
+        assert_eq!(msg.barometric_pressure().as_f32(), Some(101.0));
+        assert_eq!(msg.cab_interior_temperature().as_f32(), Some(20.0));
+        assert_eq!(msg.ambient_air_temperature().as_f32(), Some(2.0));
+        assert_eq!(msg.air_inlet_temperature().as_f32(), Some(20.0));
+        assert_eq!(msg.road_surface_temperature().as_f32(), None);
+
+        let msg = AmbientConditions::new(
+            SaePR05::from_f32(101.0).unwrap(),
+            SaeTP02::from_f32(20.0).unwrap(),
+            SaeTP02::from_f32(2.0).unwrap(),
+            SaeTP01::from_f32(20.0).unwrap(),
+            SaeTP02::new(Param16::from(0xFFFF)),
+        );
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, raw);
+    }
+}
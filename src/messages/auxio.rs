@@ -0,0 +1,208 @@
+use super::discrete;
+use crate::error::{DecodeError, exact};
+use crate::signal::{Discrete, Param16};
+use crate::slot::{SaeCN02, Slot};
+
+/// Read the `index`th 2-bit discrete from a packed run of bytes.
+fn get(bytes: &[u8], index: usize) -> Option<Discrete> {
+    let byte = *bytes.get(index / 4)?;
+    Some(discrete(byte, (index % 4) as u8 * 2))
+}
+
+/// Write the `index`th 2-bit discrete into a packed run of bytes.
+fn set(bytes: &mut [u8], index: usize, state: Discrete) -> Option<()> {
+    let byte = bytes.get_mut(index / 4)?;
+    let shift = (index % 4) * 2;
+    *byte = (*byte & !(0b11 << shift)) | (u8::from(state) << shift);
+    Some(())
+}
+
+/// AUXIO1 - Auxiliary Input/Output Status 1
+///
+/// Carries auxiliary I/O #01 to #16 and the two analog auxiliary channels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuxiliaryIoStatus1 {
+    raw: [u8; 8],
+}
+
+impl AuxiliaryIoStatus1 {
+    /// First auxiliary I/O number carried by this message.
+    pub const FIRST: u8 = 1;
+    /// Number of discrete auxiliary I/O carried by this message.
+    pub const COUNT: u8 = 16;
+
+    /// Create a new AUXIO1 message.
+    ///
+    /// `io` holds the states of auxiliary I/O #01 to #16 in order.
+    pub fn new(io: [Discrete; 16], channel1: SaeCN02, channel2: SaeCN02) -> Self {
+        let mut raw = [0xFF; 8];
+
+        for (index, state) in io.into_iter().enumerate() {
+            let _ = set(&mut raw[0..4], index, state);
+        }
+        raw[4..6].copy_from_slice(&u16::from(channel1.parameter()).to_le_bytes());
+        raw[6..8].copy_from_slice(&u16::from(channel2.parameter()).to_le_bytes());
+
+        Self { raw }
+    }
+
+    /// Auxiliary I/O `number` (SPN 701 to 716).
+    ///
+    /// Returns `None` if `number` is not between #01 and #16.
+    pub fn io(&self, number: u8) -> Option<Discrete> {
+        let index = number.checked_sub(Self::FIRST)?;
+        if index >= Self::COUNT {
+            return None;
+        }
+        get(&self.raw[0..4], index as usize)
+    }
+
+    /// Auxiliary I/O channel #1 (SPN 1083).
+    pub fn channel1(&self) -> SaeCN02 {
+        SaeCN02::new(Param16::from(u16::from_le_bytes([
+            self.raw[4],
+            self.raw[5],
+        ])))
+    }
+
+    /// Auxiliary I/O channel #2 (SPN 1084).
+    pub fn channel2(&self) -> SaeCN02 {
+        SaeCN02::new(Param16::from(u16::from_le_bytes([
+            self.raw[6],
+            self.raw[7],
+        ])))
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&AuxiliaryIoStatus1> for [u8; 8] {
+    fn from(msg: &AuxiliaryIoStatus1) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for AuxiliaryIoStatus1 {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+/// AUXIO2 - Auxiliary Input/Output Status 2
+///
+/// Carries auxiliary I/O #17 to #48.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuxiliaryIoStatus2 {
+    raw: [u8; 8],
+}
+
+impl AuxiliaryIoStatus2 {
+    /// First auxiliary I/O number carried by this message.
+    pub const FIRST: u8 = 17;
+    /// Number of discrete auxiliary I/O carried by this message.
+    pub const COUNT: u8 = 32;
+
+    /// Create a new AUXIO2 message.
+    ///
+    /// `io` holds the states of auxiliary I/O #17 to #48 in order.
+    pub fn new(io: [Discrete; 32]) -> Self {
+        let mut raw = [0xFF; 8];
+
+        for (index, state) in io.into_iter().enumerate() {
+            let _ = set(&mut raw, index, state);
+        }
+
+        Self { raw }
+    }
+
+    /// Auxiliary I/O `number`.
+    ///
+    /// Returns `None` if `number` is not between #17 and #48.
+    pub fn io(&self, number: u8) -> Option<Discrete> {
+        let index = number.checked_sub(Self::FIRST)?;
+        if index >= Self::COUNT {
+            return None;
+        }
+        get(&self.raw, index as usize)
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&AuxiliaryIoStatus2> for [u8; 8] {
+    fn from(msg: &AuxiliaryIoStatus2) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for AuxiliaryIoStatus2 {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auxio1() {
+        let raw: &[u8] = &[0xF9, 0xFF, 0xFF, 0x3F, 0xE8, 0x03, 0xFF, 0xFF];
+
+        let msg = AuxiliaryIoStatus1::try_from(raw).unwrap();
+        assert_eq!(msg.io(0), None);
+        assert_eq!(msg.io(1), Some(Discrete::Enabled));
+        assert_eq!(msg.io(2), Some(Discrete::ErrorIndicator));
+        assert_eq!(msg.io(3), Some(Discrete::NotAvailable));
+        assert_eq!(msg.io(16), Some(Discrete::Disabled));
+        assert_eq!(msg.io(17), None);
+        assert_eq!(msg.channel1().as_f32(), Some(1000.0));
+        assert_eq!(msg.channel2().as_f32(), None);
+
+        let mut io = [Discrete::NotAvailable; 16];
+        io[0] = Discrete::Enabled;
+        io[1] = Discrete::ErrorIndicator;
+        io[15] = Discrete::Disabled;
+        let msg = AuxiliaryIoStatus1::new(
+            io,
+            SaeCN02::from_f32(1000.0).unwrap(),
+            SaeCN02::new(Param16::from(0xFFFF)),
+        );
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, raw);
+    }
+
+    #[test]
+    fn auxio2() {
+        let mut io = [Discrete::NotAvailable; 32];
+        io[0] = Discrete::Disabled;
+        io[31] = Discrete::Enabled;
+        let msg = AuxiliaryIoStatus2::new(io);
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, [0xFC, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F]);
+
+        let msg = AuxiliaryIoStatus2::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(msg.io(16), None);
+        assert_eq!(msg.io(17), Some(Discrete::Disabled));
+        assert_eq!(msg.io(48), Some(Discrete::Enabled));
+        assert_eq!(msg.io(49), None);
+    }
+}
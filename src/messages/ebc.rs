@@ -0,0 +1,247 @@
+use super::discrete;
+use crate::error::{DecodeError, exact};
+use crate::signal::{Discrete, Param8, Param16};
+use crate::slot::{SaePC03, SaeVE01, SaeVE02, Slot};
+
+/// EBC1 - Electronic Brake Controller 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ElectronicBrakeController1 {
+    raw: [u8; 8],
+}
+
+impl ElectronicBrakeController1 {
+    /// Create a new EBC1 message.
+    ///
+    /// Parameters not covered by the arguments are set to not available.
+    pub fn new(
+        asr_engine_control: Discrete,
+        asr_brake_control: Discrete,
+        abs_active: Discrete,
+        brake_switch: Discrete,
+        brake_pedal_position: SaePC03,
+    ) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0] = u8::from(asr_engine_control)
+            | (u8::from(asr_brake_control) << 2)
+            | (u8::from(abs_active) << 4)
+            | (u8::from(brake_switch) << 6);
+        raw[1] = brake_pedal_position.parameter().into();
+
+        Self { raw }
+    }
+
+    /// ASR engine control active (SPN 561).
+    pub fn asr_engine_control(&self) -> Discrete {
+        discrete(self.raw[0], 0)
+    }
+
+    /// ASR brake control active (SPN 562).
+    pub fn asr_brake_control(&self) -> Discrete {
+        discrete(self.raw[0], 2)
+    }
+
+    /// Anti-lock braking (ABS) active (SPN 563).
+    pub fn abs_active(&self) -> Discrete {
+        discrete(self.raw[0], 4)
+    }
+
+    /// EBS brake switch (SPN 1121).
+    pub fn brake_switch(&self) -> Discrete {
+        discrete(self.raw[0], 6)
+    }
+
+    /// Brake pedal position (SPN 521).
+    pub fn brake_pedal_position(&self) -> SaePC03 {
+        SaePC03::new(Param8::from(self.raw[1]))
+    }
+
+    /// ABS off-road switch (SPN 575).
+    pub fn abs_off_road_switch(&self) -> Discrete {
+        discrete(self.raw[2], 0)
+    }
+
+    /// ASR off-road switch (SPN 576).
+    pub fn asr_off_road_switch(&self) -> Discrete {
+        discrete(self.raw[2], 2)
+    }
+
+    /// ASR "hill holder" switch (SPN 577).
+    pub fn asr_hill_holder_switch(&self) -> Discrete {
+        discrete(self.raw[2], 4)
+    }
+
+    /// Traction control override switch (SPN 1238).
+    pub fn traction_control_override_switch(&self) -> Discrete {
+        discrete(self.raw[2], 6)
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&ElectronicBrakeController1> for [u8; 8] {
+    fn from(msg: &ElectronicBrakeController1) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ElectronicBrakeController1 {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+/// EBC2 - Wheel Speed Information
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WheelSpeedInformation {
+    raw: [u8; 8],
+}
+
+impl WheelSpeedInformation {
+    /// Create a new EBC2 message.
+    ///
+    /// `relative_speeds` are the wheel speeds relative to the front axle speed
+    /// in the order front left, front right, rear #1 left, rear #1 right,
+    /// rear #2 left, rear #2 right.
+    pub fn new(front_axle_speed: SaeVE01, relative_speeds: [SaeVE02; 6]) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0..2].copy_from_slice(&u16::from(front_axle_speed.parameter()).to_le_bytes());
+        for (byte, speed) in raw[2..].iter_mut().zip(relative_speeds) {
+            *byte = speed.parameter().into();
+        }
+
+        Self { raw }
+    }
+
+    /// Front axle speed (SPN 904).
+    pub fn front_axle_speed(&self) -> SaeVE01 {
+        SaeVE01::new(Param16::from(u16::from_le_bytes([
+            self.raw[0],
+            self.raw[1],
+        ])))
+    }
+
+    /// Relative speed; front axle, left wheel (SPN 905).
+    pub fn front_left(&self) -> SaeVE02 {
+        SaeVE02::new(Param8::from(self.raw[2]))
+    }
+
+    /// Relative speed; front axle, right wheel (SPN 906).
+    pub fn front_right(&self) -> SaeVE02 {
+        SaeVE02::new(Param8::from(self.raw[3]))
+    }
+
+    /// Relative speed; rear axle #1, left wheel (SPN 907).
+    pub fn rear_1_left(&self) -> SaeVE02 {
+        SaeVE02::new(Param8::from(self.raw[4]))
+    }
+
+    /// Relative speed; rear axle #1, right wheel (SPN 908).
+    pub fn rear_1_right(&self) -> SaeVE02 {
+        SaeVE02::new(Param8::from(self.raw[5]))
+    }
+
+    /// Relative speed; rear axle #2, left wheel (SPN 909).
+    pub fn rear_2_left(&self) -> SaeVE02 {
+        SaeVE02::new(Param8::from(self.raw[6]))
+    }
+
+    /// Relative speed; rear axle #2, right wheel (SPN 910).
+    pub fn rear_2_right(&self) -> SaeVE02 {
+        SaeVE02::new(Param8::from(self.raw[7]))
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&WheelSpeedInformation> for [u8; 8] {
+    fn from(msg: &WheelSpeedInformation) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for WheelSpeedInformation {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ebc1() {
+        let raw: &[u8] = &[0xD0, 0x19, 0xFC, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+
+        let msg = ElectronicBrakeController1::try_from(raw).unwrap();
+        assert_eq!(msg.asr_engine_control(), Discrete::Disabled);
+        assert_eq!(msg.asr_brake_control(), Discrete::Disabled);
+        assert_eq!(msg.abs_active(), Discrete::Enabled);
+        assert_eq!(msg.brake_switch(), Discrete::NotAvailable);
+        assert_eq!(msg.brake_pedal_position().as_f32(), Some(10.0));
+        assert_eq!(msg.abs_off_road_switch(), Discrete::Disabled);
+        assert_eq!(
+            msg.traction_control_override_switch(),
+            Discrete::NotAvailable
+        );
+
+        let msg = ElectronicBrakeController1::new(
+            Discrete::Disabled,
+            Discrete::Disabled,
+            Discrete::Enabled,
+            Discrete::NotAvailable,
+            SaePC03::from_f32(10.0).unwrap(),
+        );
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, [0xD0, 0x19, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn ebc2() {
+        let raw: &[u8] = &[0x00, 0x50, 0x7D, 0x7E, 0x7C, 0x7D, 0xFF, 0xFF];
+
+        let msg = WheelSpeedInformation::try_from(raw).unwrap();
+        assert_eq!(msg.front_axle_speed().as_f32(), Some(80.0));
+        assert_eq!(msg.front_left().as_f32(), Some(0.0));
+        assert_eq!(msg.front_right().as_f32(), Some(0.0625));
+        assert_eq!(msg.rear_1_left().as_f32(), Some(-0.0625));
+        assert_eq!(msg.rear_1_right().as_f32(), Some(0.0));
+        assert_eq!(msg.rear_2_left().as_f32(), None);
+        assert_eq!(msg.rear_2_right().as_f32(), None);
+
+        let na = SaeVE02::new(Param8::from(0xFF));
+        let msg = WheelSpeedInformation::new(
+            SaeVE01::from_f32(80.0).unwrap(),
+            [
+                SaeVE02::from_f32(0.0).unwrap(),
+                SaeVE02::from_f32(0.0625).unwrap(),
+                SaeVE02::from_f32(-0.0625).unwrap(),
+                SaeVE02::from_f32(0.0).unwrap(),
+                na,
+                na,
+            ],
+        );
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, raw);
+    }
+}
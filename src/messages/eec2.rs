@@ -0,0 +1,130 @@
+use super::discrete;
+use crate::error::{DecodeError, exact};
+use crate::signal::{Discrete, Param8};
+use crate::slot::{SaePC01, SaePC03, Slot};
+
+/// EEC2 - Electronic Engine Controller 2
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ElectronicEngineController2 {
+    raw: [u8; 8],
+}
+
+impl ElectronicEngineController2 {
+    /// Create a new EEC2 message.
+    ///
+    /// Parameters not covered by the arguments are set to not available.
+    pub fn new(
+        accelerator_pedal_position_1: SaePC03,
+        accelerator_pedal_position_2: SaePC03,
+        percent_load: SaePC01,
+        road_speed_limit: Discrete,
+    ) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0] &= !(0b11 << 4);
+        raw[0] |= u8::from(road_speed_limit) << 4;
+        raw[1] = accelerator_pedal_position_1.parameter().into();
+        raw[2] = percent_load.parameter().into();
+        raw[4] = accelerator_pedal_position_2.parameter().into();
+
+        Self { raw }
+    }
+
+    /// Accelerator pedal 1 low idle switch (SPN 558).
+    pub fn low_idle_switch_1(&self) -> Discrete {
+        discrete(self.raw[0], 0)
+    }
+
+    /// Accelerator pedal kickdown switch (SPN 559).
+    pub fn kickdown_switch(&self) -> Discrete {
+        discrete(self.raw[0], 2)
+    }
+
+    /// Road speed limit status (SPN 1437).
+    ///
+    /// Enabled when the road speed limit is active.
+    pub fn road_speed_limit(&self) -> Discrete {
+        discrete(self.raw[0], 4)
+    }
+
+    /// Accelerator pedal 2 low idle switch (SPN 2970).
+    pub fn low_idle_switch_2(&self) -> Discrete {
+        discrete(self.raw[0], 6)
+    }
+
+    /// Accelerator pedal position 1 (SPN 91).
+    pub fn accelerator_pedal_position_1(&self) -> SaePC03 {
+        SaePC03::new(Param8::from(self.raw[1]))
+    }
+
+    /// Engine percent load at current speed (SPN 92).
+    pub fn percent_load(&self) -> SaePC01 {
+        SaePC01::new(Param8::from(self.raw[2]))
+    }
+
+    /// Remote accelerator pedal position (SPN 974).
+    pub fn remote_accelerator_pedal_position(&self) -> SaePC03 {
+        SaePC03::new(Param8::from(self.raw[3]))
+    }
+
+    /// Accelerator pedal position 2 (SPN 29).
+    pub fn accelerator_pedal_position_2(&self) -> SaePC03 {
+        SaePC03::new(Param8::from(self.raw[4]))
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&ElectronicEngineController2> for [u8; 8] {
+    fn from(msg: &ElectronicEngineController2) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ElectronicEngineController2 {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eec2() {
+        let raw: &[u8] = &[0xC4, 0xFA, 0x32, 0xFF, 0x64, 0xFF, 0xFF, 0xFF];
+
+        let msg = ElectronicEngineController2::try_from(raw).unwrap();
+        assert_eq!(msg.low_idle_switch_1(), Discrete::Disabled);
+        assert_eq!(msg.kickdown_switch(), Discrete::Enabled);
+        assert_eq!(msg.road_speed_limit(), Discrete::Disabled);
+        assert_eq!(msg.low_idle_switch_2(), Discrete::NotAvailable);
+        assert_eq!(msg.accelerator_pedal_position_1().as_f32(), Some(100.0));
+        assert_eq!(msg.percent_load().as_f32(), Some(50.0));
+        assert_eq!(msg.remote_accelerator_pedal_position().as_f32(), None);
+        assert_eq!(msg.accelerator_pedal_position_2().as_f32(), Some(40.0));
+    }
+
+    #[test]
+    fn eec2_new() {
+        let msg = ElectronicEngineController2::new(
+            SaePC03::from_f32(100.0).unwrap(),
+            SaePC03::from_f32(40.0).unwrap(),
+            SaePC01::from_f32(50.0).unwrap(),
+            Discrete::Enabled,
+        );
+
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, [0xDF, 0xFA, 0x32, 0xFF, 0x64, 0xFF, 0xFF, 0xFF]);
+    }
+}
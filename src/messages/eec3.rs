@@ -0,0 +1,101 @@
+use crate::error::{DecodeError, exact};
+use crate::signal::{Param8, Param16};
+use crate::slot::{SaeAV01, SaeCN01, SaePC05, Slot};
+
+/// EEC3 - Electronic Engine Controller 3
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ElectronicEngineController3 {
+    raw: [u8; 8],
+}
+
+impl ElectronicEngineController3 {
+    /// Create a new EEC3 message.
+    ///
+    /// Parameters not covered by the arguments are set to not available.
+    pub fn new(
+        nominal_friction: SaePC05,
+        desired_operating_speed: SaeAV01,
+        parasitic_losses: SaePC05,
+    ) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0] = nominal_friction.parameter().into();
+        raw[1..3].copy_from_slice(&u16::from(desired_operating_speed.parameter()).to_le_bytes());
+        raw[4] = parasitic_losses.parameter().into();
+
+        Self { raw }
+    }
+
+    /// Nominal friction - percent torque (SPN 514).
+    pub fn nominal_friction(&self) -> SaePC05 {
+        SaePC05::new(Param8::from(self.raw[0]))
+    }
+
+    /// Engine's desired operating speed (SPN 515).
+    pub fn desired_operating_speed(&self) -> SaeAV01 {
+        SaeAV01::new(Param16::from(u16::from_le_bytes([
+            self.raw[1],
+            self.raw[2],
+        ])))
+    }
+
+    /// Engine's desired operating speed asymmetry adjustment (SPN 519).
+    pub fn desired_operating_speed_asymmetry(&self) -> SaeCN01 {
+        SaeCN01::new(Param8::from(self.raw[3]))
+    }
+
+    /// Estimated engine parasitic losses - percent torque (SPN 2978).
+    pub fn parasitic_losses(&self) -> SaePC05 {
+        SaePC05::new(Param8::from(self.raw[4]))
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&ElectronicEngineController3> for [u8; 8] {
+    fn from(msg: &ElectronicEngineController3) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ElectronicEngineController3 {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eec3() {
+        let raw: &[u8] = &[0x8C, 0x40, 0x1F, 0x7D, 0x7A, 0xFF, 0xFF, 0xFF];
+
+        let msg = ElectronicEngineController3::try_from(raw).unwrap();
+        assert_eq!(msg.nominal_friction().as_f32(), Some(15.0));
+        assert_eq!(msg.desired_operating_speed().as_f32(), Some(1000.0));
+        assert_eq!(
+            msg.desired_operating_speed_asymmetry().as_f32(),
+            Some(125.0)
+        );
+        assert_eq!(msg.parasitic_losses().as_f32(), Some(-3.0));
+
+        let msg = ElectronicEngineController3::new(
+            SaePC05::from_f32(15.0).unwrap(),
+            SaeAV01::from_f32(1000.0).unwrap(),
+            SaePC05::from_f32(-3.0).unwrap(),
+        );
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, [0x8C, 0x40, 0x1F, 0xFF, 0x7A, 0xFF, 0xFF, 0xFF]);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::error::{DecodeError, exact};
+use crate::signal::{Param8, Param16};
+use crate::slot::{SaePC03, SaePR01, SaePR02, SaePR03, SaePR04, Slot};
+
+/// EFL/P1 - Engine Fluid Level/Pressure 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EngineFluidLevelPressure1 {
+    raw: [u8; 8],
+}
+
+impl EngineFluidLevelPressure1 {
+    /// Create a new EFL/P1 message.
+    ///
+    /// Parameters not covered by the arguments are set to not available.
+    pub fn new(
+        fuel_delivery_pressure: SaePR01,
+        oil_level: SaePC03,
+        oil_pressure: SaePR01,
+        crankcase_pressure: SaePR04,
+        coolant_pressure: SaePR02,
+        coolant_level: SaePC03,
+    ) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0] = fuel_delivery_pressure.parameter().into();
+        raw[2] = oil_level.parameter().into();
+        raw[3] = oil_pressure.parameter().into();
+        raw[4..6].copy_from_slice(&u16::from(crankcase_pressure.parameter()).to_le_bytes());
+        raw[6] = coolant_pressure.parameter().into();
+        raw[7] = coolant_level.parameter().into();
+
+        Self { raw }
+    }
+
+    /// Engine fuel delivery pressure (SPN 94).
+    pub fn fuel_delivery_pressure(&self) -> SaePR01 {
+        SaePR01::new(Param8::from(self.raw[0]))
+    }
+
+    /// Engine extended crankcase blow-by pressure (SPN 22).
+    pub fn crankcase_blow_by_pressure(&self) -> SaePR03 {
+        SaePR03::new(Param8::from(self.raw[1]))
+    }
+
+    /// Engine oil level (SPN 98).
+    pub fn oil_level(&self) -> SaePC03 {
+        SaePC03::new(Param8::from(self.raw[2]))
+    }
+
+    /// Engine oil pressure (SPN 100).
+    pub fn oil_pressure(&self) -> SaePR01 {
+        SaePR01::new(Param8::from(self.raw[3]))
+    }
+
+    /// Engine crankcase pressure (SPN 101).
+    pub fn crankcase_pressure(&self) -> SaePR04 {
+        SaePR04::new(Param16::from(u16::from_le_bytes([
+            self.raw[4],
+            self.raw[5],
+        ])))
+    }
+
+    /// Engine coolant pressure (SPN 109).
+    pub fn coolant_pressure(&self) -> SaePR02 {
+        SaePR02::new(Param8::from(self.raw[6]))
+    }
+
+    /// Engine coolant level (SPN 111).
+    pub fn coolant_level(&self) -> SaePC03 {
+        SaePC03::new(Param8::from(self.raw[7]))
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&EngineFluidLevelPressure1> for [u8; 8] {
+    fn from(msg: &EngineFluidLevelPressure1) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for EngineFluidLevelPressure1 {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eflp1() {
+        let raw: &[u8] = &[0x64, 0xFF, 0xC8, 0x4B, 0x00, 0x7D, 0x32, 0xFA];
+
+        let msg = EngineFluidLevelPressure1::try_from(raw).unwrap();
+        assert_eq!(msg.fuel_delivery_pressure().as_f32(), Some(400.0));
+        assert_eq!(msg.crankcase_blow_by_pressure().as_f32(), None);
+        assert_eq!(msg.oil_level().as_f32(), Some(80.0));
+        assert_eq!(msg.oil_pressure().as_f32(), Some(300.0));
+        assert_eq!(msg.crankcase_pressure().as_f32(), Some(0.0));
+        assert_eq!(msg.coolant_pressure().as_f32(), Some(100.0));
+        assert_eq!(msg.coolant_level().as_f32(), Some(100.0));
+
+        let msg = EngineFluidLevelPressure1::new(
+            SaePR01::from_f32(400.0).unwrap(),
+            SaePC03::from_f32(80.0).unwrap(),
+            SaePR01::from_f32(300.0).unwrap(),
+            SaePR04::from_f32(0.0).unwrap(),
+            SaePR02::from_f32(100.0).unwrap(),
+            SaePC03::from_f32(100.0).unwrap(),
+        );
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, raw);
+    }
+}
@@ -0,0 +1,217 @@
+use super::discrete;
+use crate::error::{DecodeError, exact};
+use crate::signal::{Discrete, Param8};
+use crate::slot::{SaePC05, Slot};
+use core::hash::{Hash, Hasher};
+
+/// ERC1 - Electronic Retarder Controller 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ElectronicRetarderController1 {
+    raw: [u8; 8],
+}
+
+impl ElectronicRetarderController1 {
+    /// Create a new ERC1 message.
+    ///
+    /// Parameters not covered by the arguments are set to not available.
+    pub fn new(
+        torque_mode: TorqueMode,
+        brake_assist_switch: Discrete,
+        shift_assist_switch: Discrete,
+        actual_torque: SaePC05,
+        intended_torque: SaePC05,
+    ) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0] = (u8::from(torque_mode) & 0b1111)
+            | (u8::from(brake_assist_switch) << 4)
+            | (u8::from(shift_assist_switch) << 6);
+        raw[1] = actual_torque.parameter().into();
+        raw[2] = intended_torque.parameter().into();
+
+        Self { raw }
+    }
+
+    /// Retarder torque mode (SPN 900).
+    pub fn torque_mode(&self) -> TorqueMode {
+        TorqueMode::from(self.raw[0] & 0b1111)
+    }
+
+    /// Retarder enable - brake assist switch (SPN 571).
+    pub fn brake_assist_switch(&self) -> Discrete {
+        discrete(self.raw[0], 4)
+    }
+
+    /// Retarder enable - shift assist switch (SPN 572).
+    pub fn shift_assist_switch(&self) -> Discrete {
+        discrete(self.raw[0], 6)
+    }
+
+    /// Actual retarder percent torque (SPN 520).
+    pub fn actual_torque(&self) -> SaePC05 {
+        SaePC05::new(Param8::from(self.raw[1]))
+    }
+
+    /// Intended retarder percent torque (SPN 1085).
+    pub fn intended_torque(&self) -> SaePC05 {
+        SaePC05::new(Param8::from(self.raw[2]))
+    }
+
+    /// Engine coolant load increase (SPN 1082).
+    pub fn coolant_load_increase(&self) -> Discrete {
+        discrete(self.raw[3], 0)
+    }
+
+    /// Retarder requesting brake light (SPN 1667).
+    pub fn requesting_brake_light(&self) -> Discrete {
+        discrete(self.raw[3], 2)
+    }
+
+    /// Source address of controlling device for retarder control (SPN 1480).
+    pub fn controlling_device(&self) -> u8 {
+        self.raw[4]
+    }
+
+    /// Drivers demand retarder percent torque (SPN 1715).
+    pub fn drivers_demand_torque(&self) -> SaePC05 {
+        SaePC05::new(Param8::from(self.raw[5]))
+    }
+
+    /// Actual maximum available retarder percent torque (SPN 1717).
+    pub fn max_available_torque(&self) -> SaePC05 {
+        SaePC05::new(Param8::from(self.raw[7]))
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&ElectronicRetarderController1> for [u8; 8] {
+    fn from(msg: &ElectronicRetarderController1) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ElectronicRetarderController1 {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+/// Engine or retarder torque mode.
+///
+/// The source of the active torque request.
+#[derive(Debug, Clone, Copy, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TorqueMode {
+    NoRequest,
+    AcceleratorPedal,
+    CruiseControl,
+    PtoGovernor,
+    RoadSpeedGovernor,
+    AsrControl,
+    TransmissionControl,
+    AbsControl,
+    TorqueLimiting,
+    HighSpeedGovernor,
+    BrakingSystem,
+    RemoteAccelerator,
+    Other,
+    NotAvailable,
+    Reserved(u8),
+}
+
+impl PartialEq for TorqueMode {
+    fn eq(&self, other: &Self) -> bool {
+        // Cast to underlying value to compare
+        u8::from(*self) == u8::from(*other)
+    }
+}
+
+impl Hash for TorqueMode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        u8::from(*self).hash(state);
+    }
+}
+
+impl From<TorqueMode> for u8 {
+    fn from(value: TorqueMode) -> Self {
+        match value {
+            TorqueMode::NoRequest => 0,
+            TorqueMode::AcceleratorPedal => 1,
+            TorqueMode::CruiseControl => 2,
+            TorqueMode::PtoGovernor => 3,
+            TorqueMode::RoadSpeedGovernor => 4,
+            TorqueMode::AsrControl => 5,
+            TorqueMode::TransmissionControl => 6,
+            TorqueMode::AbsControl => 7,
+            TorqueMode::TorqueLimiting => 8,
+            TorqueMode::HighSpeedGovernor => 9,
+            TorqueMode::BrakingSystem => 10,
+            TorqueMode::RemoteAccelerator => 11,
+            TorqueMode::Other => 14,
+            TorqueMode::NotAvailable => 15,
+            TorqueMode::Reserved(r) => r,
+        }
+    }
+}
+
+impl From<u8> for TorqueMode {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::NoRequest,
+            1 => Self::AcceleratorPedal,
+            2 => Self::CruiseControl,
+            3 => Self::PtoGovernor,
+            4 => Self::RoadSpeedGovernor,
+            5 => Self::AsrControl,
+            6 => Self::TransmissionControl,
+            7 => Self::AbsControl,
+            8 => Self::TorqueLimiting,
+            9 => Self::HighSpeedGovernor,
+            10 => Self::BrakingSystem,
+            11 => Self::RemoteAccelerator,
+            14 => Self::Other,
+            15 => Self::NotAvailable,
+            r => Self::Reserved(r),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn erc1() {
+        let raw: &[u8] = &[0xD6, 0x5F, 0x5A, 0xFF, 0x03, 0xFF, 0xFF, 0xFF];
+
+        let msg = ElectronicRetarderController1::try_from(raw).unwrap();
+        assert_eq!(msg.torque_mode(), TorqueMode::TransmissionControl);
+        assert_eq!(msg.brake_assist_switch(), Discrete::Enabled);
+        assert_eq!(msg.shift_assist_switch(), Discrete::NotAvailable);
+        assert_eq!(msg.actual_torque().as_f32(), Some(-30.0));
+        assert_eq!(msg.intended_torque().as_f32(), Some(-35.0));
+        assert_eq!(msg.controlling_device(), 0x03);
+        assert_eq!(msg.max_available_torque().as_f32(), None);
+
+        let msg = ElectronicRetarderController1::new(
+            TorqueMode::TransmissionControl,
+            Discrete::Enabled,
+            Discrete::NotAvailable,
+            SaePC05::from_f32(-30.0).unwrap(),
+            SaePC05::from_f32(-35.0).unwrap(),
+        );
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, [0xD6, 0x5F, 0x5A, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+    }
+}
@@ -0,0 +1,224 @@
+use super::discrete;
+use crate::error::{DecodeError, exact};
+use crate::signal::{Discrete, Param8, Param16};
+use crate::slot::{SaeAV01, SaeCN03, SaePC03, SaeRA01, Slot};
+
+/// ETC1 - Electronic Transmission Controller 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ElectronicTransmissionController1 {
+    raw: [u8; 8],
+}
+
+impl ElectronicTransmissionController1 {
+    /// Create a new ETC1 message.
+    ///
+    /// Parameters not covered by the arguments are set to not available.
+    pub fn new(
+        driveline_engaged: Discrete,
+        torque_converter_lockup: Discrete,
+        shift_in_progress: Discrete,
+        output_shaft_speed: SaeAV01,
+        input_shaft_speed: SaeAV01,
+    ) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0] = u8::from(driveline_engaged)
+            | (u8::from(torque_converter_lockup) << 2)
+            | (u8::from(shift_in_progress) << 4)
+            | 0b11000000;
+        raw[1..3].copy_from_slice(&u16::from(output_shaft_speed.parameter()).to_le_bytes());
+        raw[5..7].copy_from_slice(&u16::from(input_shaft_speed.parameter()).to_le_bytes());
+
+        Self { raw }
+    }
+
+    /// Transmission driveline engaged (SPN 560).
+    pub fn driveline_engaged(&self) -> Discrete {
+        discrete(self.raw[0], 0)
+    }
+
+    /// Transmission torque converter lockup engaged (SPN 573).
+    pub fn torque_converter_lockup(&self) -> Discrete {
+        discrete(self.raw[0], 2)
+    }
+
+    /// Transmission shift in process (SPN 574).
+    pub fn shift_in_progress(&self) -> Discrete {
+        discrete(self.raw[0], 4)
+    }
+
+    /// Transmission output shaft speed (SPN 191).
+    pub fn output_shaft_speed(&self) -> SaeAV01 {
+        SaeAV01::new(Param16::from(u16::from_le_bytes([
+            self.raw[1],
+            self.raw[2],
+        ])))
+    }
+
+    /// Percent clutch slip (SPN 522).
+    pub fn clutch_slip(&self) -> SaePC03 {
+        SaePC03::new(Param8::from(self.raw[3]))
+    }
+
+    /// Transmission input shaft speed (SPN 161).
+    pub fn input_shaft_speed(&self) -> SaeAV01 {
+        SaeAV01::new(Param16::from(u16::from_le_bytes([
+            self.raw[5],
+            self.raw[6],
+        ])))
+    }
+
+    /// Source address of controlling device for transmission control (SPN 1482).
+    pub fn controlling_device(&self) -> u8 {
+        self.raw[7]
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&ElectronicTransmissionController1> for [u8; 8] {
+    fn from(msg: &ElectronicTransmissionController1) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ElectronicTransmissionController1 {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+/// ETC2 - Electronic Transmission Controller 2
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ElectronicTransmissionController2 {
+    raw: [u8; 8],
+}
+
+impl ElectronicTransmissionController2 {
+    /// Create a new ETC2 message.
+    ///
+    /// Parameters not covered by the arguments are set to not available.
+    pub fn new(selected_gear: SaeCN03, actual_gear_ratio: SaeRA01, current_gear: SaeCN03) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0] = selected_gear.parameter().into();
+        raw[1..3].copy_from_slice(&u16::from(actual_gear_ratio.parameter()).to_le_bytes());
+        raw[3] = current_gear.parameter().into();
+
+        Self { raw }
+    }
+
+    /// Transmission selected gear (SPN 524).
+    ///
+    /// Negative values are reverse gears, zero is neutral.
+    pub fn selected_gear(&self) -> SaeCN03 {
+        SaeCN03::new(Param8::from(self.raw[0]))
+    }
+
+    /// Transmission actual gear ratio (SPN 526).
+    pub fn actual_gear_ratio(&self) -> SaeRA01 {
+        SaeRA01::new(Param16::from(u16::from_le_bytes([
+            self.raw[1],
+            self.raw[2],
+        ])))
+    }
+
+    /// Transmission current gear (SPN 523).
+    ///
+    /// Negative values are reverse gears, zero is neutral.
+    pub fn current_gear(&self) -> SaeCN03 {
+        SaeCN03::new(Param8::from(self.raw[3]))
+    }
+
+    /// Transmission requested range (SPN 162) as two ASCII characters.
+    pub fn requested_range(&self) -> [u8; 2] {
+        [self.raw[4], self.raw[5]]
+    }
+
+    /// Transmission current range (SPN 163) as two ASCII characters.
+    pub fn current_range(&self) -> [u8; 2] {
+        [self.raw[6], self.raw[7]]
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&ElectronicTransmissionController2> for [u8; 8] {
+    fn from(msg: &ElectronicTransmissionController2) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ElectronicTransmissionController2 {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal::Signal;
+
+    #[test]
+    fn etc1() {
+        let raw: &[u8] = &[0xC5, 0x40, 0x1F, 0x00, 0xFF, 0x80, 0x3E, 0x03];
+
+        let msg = ElectronicTransmissionController1::try_from(raw).unwrap();
+        assert_eq!(msg.driveline_engaged(), Discrete::Enabled);
+        assert_eq!(msg.torque_converter_lockup(), Discrete::Enabled);
+        assert_eq!(msg.shift_in_progress(), Discrete::Disabled);
+        assert_eq!(msg.output_shaft_speed().as_f32(), Some(1000.0));
+        assert_eq!(msg.clutch_slip().as_f32(), Some(0.0));
+        assert_eq!(msg.input_shaft_speed().as_f32(), Some(2000.0));
+        assert_eq!(msg.controlling_device(), 0x03);
+
+        let msg = ElectronicTransmissionController1::new(
+            Discrete::Enabled,
+            Discrete::Enabled,
+            Discrete::Disabled,
+            SaeAV01::from_f32(1000.0).unwrap(),
+            SaeAV01::from_f32(2000.0).unwrap(),
+        );
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, [0xC5, 0x40, 0x1F, 0xFF, 0xFF, 0x80, 0x3E, 0xFF]);
+    }
+
+    #[test]
+    fn etc2() {
+        let raw: &[u8] = &[0x82, 0xB8, 0x0B, 0x82, 0x44, 0x20, 0x44, 0x20];
+
+        let msg = ElectronicTransmissionController2::try_from(raw).unwrap();
+        assert_eq!(msg.selected_gear().as_f32(), Some(5.0));
+        assert_eq!(msg.actual_gear_ratio().parameter().value(), Some(3000));
+        assert_eq!(msg.current_gear().as_f32(), Some(5.0));
+        assert_eq!(&msg.requested_range(), b"D ");
+        assert_eq!(&msg.current_range(), b"D ");
+
+        let msg = ElectronicTransmissionController2::new(
+            SaeCN03::from_f32(5.0).unwrap(),
+            SaeRA01::new(Param16::from(3000)),
+            SaeCN03::from_f32(5.0).unwrap(),
+        );
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, [0x82, 0xB8, 0x0B, 0x82, 0xFF, 0xFF, 0xFF, 0xFF]);
+    }
+}
@@ -0,0 +1,85 @@
+use crate::error::{DecodeError, exact};
+use crate::signal::Param32;
+use crate::slot::{SaeCN05, SaeTM01, Slot};
+
+/// HOURS - Engine Hours, Revolutions
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EngineHoursRevolutions {
+    raw: [u8; 8],
+}
+
+impl EngineHoursRevolutions {
+    /// Create a new HOURS message.
+    pub fn new(total_hours: SaeTM01, total_revolutions: SaeCN05) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0..4].copy_from_slice(&u32::from(total_hours.parameter()).to_le_bytes());
+        raw[4..8].copy_from_slice(&u32::from(total_revolutions.parameter()).to_le_bytes());
+
+        Self { raw }
+    }
+
+    /// Engine total hours of operation (SPN 247).
+    pub fn total_hours(&self) -> SaeTM01 {
+        SaeTM01::new(Param32::from(u32::from_le_bytes([
+            self.raw[0],
+            self.raw[1],
+            self.raw[2],
+            self.raw[3],
+        ])))
+    }
+
+    /// Engine total revolutions (SPN 249).
+    pub fn total_revolutions(&self) -> SaeCN05 {
+        SaeCN05::new(Param32::from(u32::from_le_bytes([
+            self.raw[4],
+            self.raw[5],
+            self.raw[6],
+            self.raw[7],
+        ])))
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&EngineHoursRevolutions> for [u8; 8] {
+    fn from(msg: &EngineHoursRevolutions) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for EngineHoursRevolutions {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hours() {
+        let raw: &[u8] = &[0xD0, 0x07, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF];
+
+        let msg = EngineHoursRevolutions::try_from(raw).unwrap();
+        assert_eq!(msg.total_hours().as_f32(), Some(100.0));
+        assert_eq!(msg.total_revolutions().as_f32(), None);
+
+        let msg = EngineHoursRevolutions::new(
+            SaeTM01::new(Param32::from(2000)),
+            SaeCN05::new(Param32::from(0xFFFFFFFF)),
+        );
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, raw);
+    }
+}
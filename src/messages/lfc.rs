@@ -0,0 +1,85 @@
+use crate::error::{DecodeError, exact};
+use crate::signal::Param32;
+use crate::slot::{SaeVL01, Slot};
+
+/// LFC - Fuel Consumption (Liquid)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FuelConsumption {
+    raw: [u8; 8],
+}
+
+impl FuelConsumption {
+    /// Create a new LFC message.
+    pub fn new(trip_fuel: SaeVL01, total_fuel_used: SaeVL01) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0..4].copy_from_slice(&u32::from(trip_fuel.parameter()).to_le_bytes());
+        raw[4..8].copy_from_slice(&u32::from(total_fuel_used.parameter()).to_le_bytes());
+
+        Self { raw }
+    }
+
+    /// Engine trip fuel (SPN 182).
+    pub fn trip_fuel(&self) -> SaeVL01 {
+        SaeVL01::new(Param32::from(u32::from_le_bytes([
+            self.raw[0],
+            self.raw[1],
+            self.raw[2],
+            self.raw[3],
+        ])))
+    }
+
+    /// Engine total fuel used (SPN 250).
+    pub fn total_fuel_used(&self) -> SaeVL01 {
+        SaeVL01::new(Param32::from(u32::from_le_bytes([
+            self.raw[4],
+            self.raw[5],
+            self.raw[6],
+            self.raw[7],
+        ])))
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&FuelConsumption> for [u8; 8] {
+    fn from(msg: &FuelConsumption) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for FuelConsumption {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lfc() {
+        let raw: &[u8] = &[0xC8, 0x00, 0x00, 0x00, 0x40, 0x42, 0x0F, 0x00];
+
+        let msg = FuelConsumption::try_from(raw).unwrap();
+        assert_eq!(msg.trip_fuel().as_f32(), Some(100.0));
+        assert_eq!(msg.total_fuel_used().as_f32(), Some(500000.0));
+
+        let msg = FuelConsumption::new(
+            SaeVL01::from_f32(100.0).unwrap(),
+            SaeVL01::from_f32(500000.0).unwrap(),
+        );
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, raw);
+    }
+}
@@ -0,0 +1,113 @@
+use crate::error::{DecodeError, exact};
+use crate::signal::{Param8, Param16};
+use crate::slot::{SaeFE01, SaeFR01, SaePC03, Slot};
+
+/// LFE - Fuel Economy (Liquid)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FuelEconomy {
+    raw: [u8; 8],
+}
+
+impl FuelEconomy {
+    /// Create a new LFE message.
+    ///
+    /// Parameters not covered by the arguments are set to not available.
+    pub fn new(
+        fuel_rate: SaeFR01,
+        instantaneous_fuel_economy: SaeFE01,
+        average_fuel_economy: SaeFE01,
+        throttle_position: SaePC03,
+    ) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0..2].copy_from_slice(&u16::from(fuel_rate.parameter()).to_le_bytes());
+        raw[2..4].copy_from_slice(&u16::from(instantaneous_fuel_economy.parameter()).to_le_bytes());
+        raw[4..6].copy_from_slice(&u16::from(average_fuel_economy.parameter()).to_le_bytes());
+        raw[6] = throttle_position.parameter().into();
+
+        Self { raw }
+    }
+
+    /// Engine fuel rate (SPN 183).
+    pub fn fuel_rate(&self) -> SaeFR01 {
+        SaeFR01::new(Param16::from(u16::from_le_bytes([
+            self.raw[0],
+            self.raw[1],
+        ])))
+    }
+
+    /// Engine instantaneous fuel economy (SPN 184).
+    pub fn instantaneous_fuel_economy(&self) -> SaeFE01 {
+        SaeFE01::new(Param16::from(u16::from_le_bytes([
+            self.raw[2],
+            self.raw[3],
+        ])))
+    }
+
+    /// Engine average fuel economy (SPN 185).
+    pub fn average_fuel_economy(&self) -> SaeFE01 {
+        SaeFE01::new(Param16::from(u16::from_le_bytes([
+            self.raw[4],
+            self.raw[5],
+        ])))
+    }
+
+    /// Engine throttle valve 1 position (SPN 51).
+    pub fn throttle_position(&self) -> SaePC03 {
+        SaePC03::new(Param8::from(self.raw[6]))
+    }
+
+    /// Engine throttle valve 2 position (SPN 3673).
+    pub fn throttle_position_2(&self) -> SaePC03 {
+        SaePC03::new(Param8::from(self.raw[7]))
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&FuelEconomy> for [u8; 8] {
+    fn from(msg: &FuelEconomy) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for FuelEconomy {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lfe() {
+        let raw: &[u8] = &[0x90, 0x01, 0x00, 0x0A, 0x00, 0x06, 0x7D, 0xFF];
+
+        let msg = FuelEconomy::try_from(raw).unwrap();
+        assert_eq!(msg.fuel_rate().as_f32(), Some(20.0));
+        assert_eq!(msg.instantaneous_fuel_economy().as_f32(), Some(5.0));
+        assert_eq!(msg.average_fuel_economy().as_f32(), Some(3.0));
+        assert_eq!(msg.throttle_position().as_f32(), Some(50.0));
+        assert_eq!(msg.throttle_position_2().as_f32(), None);
+
+        let msg = FuelEconomy::new(
+            SaeFR01::from_f32(20.0).unwrap(),
+            SaeFE01::from_f32(5.0).unwrap(),
+            SaeFE01::from_f32(3.0).unwrap(),
+            SaePC03::from_f32(50.0).unwrap(),
+        );
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, raw);
+    }
+}
@@ -0,0 +1,48 @@
+//! Typed messages (J1939-71)
+
+mod amb;
+mod auxio;
+mod ebc;
+mod eec2;
+mod eec3;
+mod eflp1;
+mod erc1;
+mod etc;
+mod hours;
+mod lfc;
+mod lfe;
+mod shutdn;
+mod soft;
+mod tco1;
+mod td;
+mod tsc1;
+mod vd;
+mod vep1;
+mod vi;
+
+pub use amb::AmbientConditions;
+pub use auxio::{AuxiliaryIoStatus1, AuxiliaryIoStatus2};
+pub use ebc::{ElectronicBrakeController1, WheelSpeedInformation};
+pub use eec2::ElectronicEngineController2;
+pub use eec3::ElectronicEngineController3;
+pub use eflp1::EngineFluidLevelPressure1;
+pub use erc1::{ElectronicRetarderController1, TorqueMode};
+pub use etc::{ElectronicTransmissionController1, ElectronicTransmissionController2};
+pub use hours::EngineHoursRevolutions;
+pub use lfc::FuelConsumption;
+pub use lfe::FuelEconomy;
+pub use shutdn::Shutdown;
+pub use soft::SoftwareIdentification;
+pub use tco1::{Direction, DriverWorkingState, Tachograph};
+pub use td::{DateTime, TimeDate};
+pub use tsc1::{OverrideControlMode, OverridePriority, SpeedControlCondition, TorqueSpeedControl1};
+pub use vd::{HighResolutionVehicleDistance, VehicleDistance};
+pub use vep1::VehicleElectricalPower1;
+pub use vi::VehicleIdentification;
+
+use crate::signal::Discrete;
+
+/// Extract the 2-bit discrete parameter starting at bit `shift` of `byte`.
+fn discrete(byte: u8, shift: u8) -> Discrete {
+    Discrete::try_from((byte >> shift) & 0b11).unwrap_or(Discrete::NotAvailable)
+}
@@ -0,0 +1,146 @@
+use super::discrete;
+use crate::error::{DecodeError, exact};
+use crate::signal::Discrete;
+
+/// SHUTDN - Shutdown
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Shutdown {
+    raw: [u8; 8],
+}
+
+impl Shutdown {
+    /// Create a new SHUTDN message.
+    ///
+    /// Parameters not covered by the arguments are set to not available.
+    pub fn new(
+        idle_shutdown: Discrete,
+        ac_high_pressure_fan_switch: Discrete,
+        protection_shutdown: Discrete,
+        protection_approaching_shutdown: Discrete,
+    ) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0] = 0b11111100 | u8::from(idle_shutdown);
+        raw[1] = 0b11111100 | u8::from(ac_high_pressure_fan_switch);
+        raw[3] = 0b11110000
+            | u8::from(protection_shutdown)
+            | (u8::from(protection_approaching_shutdown) << 2);
+
+        Self { raw }
+    }
+
+    /// Engine idle shutdown has shutdown engine (SPN 590).
+    pub fn idle_shutdown(&self) -> Discrete {
+        discrete(self.raw[0], 0)
+    }
+
+    /// Engine idle shutdown driver alert mode (SPN 591).
+    pub fn idle_shutdown_driver_alert(&self) -> Discrete {
+        discrete(self.raw[0], 2)
+    }
+
+    /// Engine idle shutdown timer override (SPN 592).
+    pub fn idle_shutdown_timer_override(&self) -> Discrete {
+        discrete(self.raw[0], 4)
+    }
+
+    /// Engine idle shutdown timer state (SPN 593).
+    pub fn idle_shutdown_timer_state(&self) -> Discrete {
+        discrete(self.raw[0], 6)
+    }
+
+    /// A/C high pressure fan switch (SPN 985).
+    pub fn ac_high_pressure_fan_switch(&self) -> Discrete {
+        discrete(self.raw[1], 0)
+    }
+
+    /// Refrigerant low pressure switch (SPN 875).
+    pub fn refrigerant_low_pressure_switch(&self) -> Discrete {
+        discrete(self.raw[1], 2)
+    }
+
+    /// Refrigerant high pressure switch (SPN 605).
+    pub fn refrigerant_high_pressure_switch(&self) -> Discrete {
+        discrete(self.raw[1], 4)
+    }
+
+    /// Engine idle shutdown timer function (SPN 594).
+    pub fn idle_shutdown_timer_function(&self) -> Discrete {
+        discrete(self.raw[1], 6)
+    }
+
+    /// Engine wait to start lamp (SPN 1081).
+    pub fn wait_to_start_lamp(&self) -> Discrete {
+        discrete(self.raw[2], 0)
+    }
+
+    /// Engine protection system has shutdown engine (SPN 1110).
+    pub fn protection_shutdown(&self) -> Discrete {
+        discrete(self.raw[3], 0)
+    }
+
+    /// Engine protection system approaching shutdown (SPN 1109).
+    pub fn protection_approaching_shutdown(&self) -> Discrete {
+        discrete(self.raw[3], 2)
+    }
+
+    /// Engine protection system timer override (SPN 1108).
+    pub fn protection_timer_override(&self) -> Discrete {
+        discrete(self.raw[3], 4)
+    }
+
+    /// Engine protection system timer state (SPN 1107).
+    pub fn protection_timer_state(&self) -> Discrete {
+        discrete(self.raw[3], 6)
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&Shutdown> for [u8; 8] {
+    fn from(msg: &Shutdown) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for Shutdown {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shutdn() {
+        let raw: &[u8] = &[0xFD, 0xFC, 0xFF, 0xF4, 0xFF, 0xFF, 0xFF, 0xFF];
+
+        let msg = Shutdown::try_from(raw).unwrap();
+        assert_eq!(msg.idle_shutdown(), Discrete::Enabled);
+        assert_eq!(msg.idle_shutdown_timer_state(), Discrete::NotAvailable);
+        assert_eq!(msg.ac_high_pressure_fan_switch(), Discrete::Disabled);
+        assert_eq!(msg.wait_to_start_lamp(), Discrete::NotAvailable);
+        assert_eq!(msg.protection_shutdown(), Discrete::Disabled);
+        assert_eq!(msg.protection_approaching_shutdown(), Discrete::Enabled);
+
+        let msg = Shutdown::new(
+            Discrete::Enabled,
+            Discrete::Disabled,
+            Discrete::Disabled,
+            Discrete::Enabled,
+        );
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, raw);
+    }
+}
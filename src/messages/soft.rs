@@ -0,0 +1,107 @@
+use crate::error::{DecodeError, at_least};
+
+/// SOFT - Software Identification
+///
+/// Variable length message, usually received as a transport protocol payload.
+/// The first byte is the number of identification fields which follow, each
+/// terminated by an ASCII `*`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SoftwareIdentification<'a> {
+    raw: &'a [u8],
+}
+
+impl<'a> SoftwareIdentification<'a> {
+    const DELIMITER: u8 = b'*';
+
+    /// Encode software identification fields into `buf`.
+    ///
+    /// Returns `None` if `buf` is too small, there are more than 255 fields or
+    /// a field contains the `*` delimiter.
+    pub fn encode(fields: &[&[u8]], buf: &'a mut [u8]) -> Option<Self> {
+        let count = u8::try_from(fields.len()).ok()?;
+        *buf.first_mut()? = count;
+
+        let mut len = 1;
+        for field in fields {
+            if field.contains(&Self::DELIMITER) {
+                return None;
+            }
+            buf.get_mut(len..len + field.len())?.copy_from_slice(field);
+            len += field.len();
+            *buf.get_mut(len)? = Self::DELIMITER;
+            len += 1;
+        }
+
+        Some(Self { raw: &buf[..len] })
+    }
+
+    /// Number of software identification fields.
+    pub fn count(&self) -> u8 {
+        self.raw[0]
+    }
+
+    /// Iterate over the software identification fields, without delimiters.
+    pub fn fields(&self) -> impl Iterator<Item = &'a [u8]> + 'a {
+        let raw: &'a [u8] = self.raw;
+        raw[1..]
+            .split(|b| *b == Self::DELIMITER)
+            .take(raw[0] as usize)
+    }
+
+    /// Raw message bytes.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for SoftwareIdentification<'a> {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: at_least(value, 1)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soft() {
+        let raw: &[u8] = b"\x02APP 1.2.3*BOOT 0.1*";
+
+        let msg = SoftwareIdentification::try_from(raw).unwrap();
+        assert_eq!(msg.count(), 2);
+        let mut fields = msg.fields();
+        assert_eq!(fields.next(), Some(b"APP 1.2.3".as_ref()));
+        assert_eq!(fields.next(), Some(b"BOOT 0.1".as_ref()));
+        assert_eq!(fields.next(), None);
+
+        let mut buf = [0; 32];
+        let msg = SoftwareIdentification::encode(&[b"APP 1.2.3", b"BOOT 0.1"], &mut buf).unwrap();
+        assert_eq!(msg.as_bytes(), raw);
+    }
+
+    #[test]
+    fn soft_single_frame() {
+        // padded single frame message
+        let raw: &[u8] = &[0x01, b'1', b'.', b'0', b'*', 0xFF, 0xFF, 0xFF];
+
+        let msg = SoftwareIdentification::try_from(raw).unwrap();
+        let mut fields = msg.fields();
+        assert_eq!(fields.next(), Some(b"1.0".as_ref()));
+        assert_eq!(fields.next(), None);
+    }
+
+    #[test]
+    fn soft_encode_invalid() {
+        let mut buf = [0; 4];
+        assert!(SoftwareIdentification::encode(&[b"1.0.0"], &mut buf).is_none());
+
+        let mut buf = [0; 32];
+        assert!(SoftwareIdentification::encode(&[b"1*0"], &mut buf).is_none());
+    }
+}
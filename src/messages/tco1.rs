@@ -0,0 +1,252 @@
+use super::discrete;
+use crate::error::{DecodeError, exact};
+use crate::signal::{Discrete, Param16};
+use crate::slot::{SaeAV01, SaeVE01, Slot};
+use core::hash::{Hash, Hasher};
+
+/// TCO1 - Tachograph
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tachograph {
+    raw: [u8; 8],
+}
+
+impl Tachograph {
+    /// Create a new TCO1 message.
+    ///
+    /// Parameters not covered by the arguments are set to not available.
+    pub fn new(
+        driver_1: DriverWorkingState,
+        driver_2: DriverWorkingState,
+        vehicle_motion: Discrete,
+        overspeed: Discrete,
+        direction: Direction,
+        vehicle_speed: SaeVE01,
+    ) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0] = (u8::from(driver_1) & 0b111)
+            | ((u8::from(driver_2) & 0b111) << 3)
+            | (u8::from(vehicle_motion) << 6);
+        raw[1] &= !(0b11 << 6);
+        raw[1] |= u8::from(overspeed) << 6;
+        raw[3] &= !(0b11 << 6);
+        raw[3] |= u8::from(direction) << 6;
+        raw[6..8].copy_from_slice(&u16::from(vehicle_speed.parameter()).to_le_bytes());
+
+        Self { raw }
+    }
+
+    /// Driver 1 working state (SPN 1612).
+    pub fn driver_1(&self) -> DriverWorkingState {
+        DriverWorkingState::from(self.raw[0] & 0b111)
+    }
+
+    /// Driver 2 working state (SPN 1613).
+    pub fn driver_2(&self) -> DriverWorkingState {
+        DriverWorkingState::from((self.raw[0] >> 3) & 0b111)
+    }
+
+    /// Vehicle motion (SPN 1611).
+    pub fn vehicle_motion(&self) -> Discrete {
+        discrete(self.raw[0], 6)
+    }
+
+    /// Driver 1 time related states (SPN 1617).
+    pub fn driver_1_time_states(&self) -> u8 {
+        self.raw[1] & 0b1111
+    }
+
+    /// Driver card, driver 1 (SPN 1615).
+    pub fn driver_1_card(&self) -> Discrete {
+        discrete(self.raw[1], 4)
+    }
+
+    /// Vehicle overspeed (SPN 1614).
+    pub fn overspeed(&self) -> Discrete {
+        discrete(self.raw[1], 6)
+    }
+
+    /// Driver 2 time related states (SPN 1618).
+    pub fn driver_2_time_states(&self) -> u8 {
+        self.raw[2] & 0b1111
+    }
+
+    /// Driver card, driver 2 (SPN 1616).
+    pub fn driver_2_card(&self) -> Discrete {
+        discrete(self.raw[2], 4)
+    }
+
+    /// System event (SPN 1622).
+    pub fn system_event(&self) -> Discrete {
+        discrete(self.raw[3], 0)
+    }
+
+    /// Handling information (SPN 1621).
+    pub fn handling_information(&self) -> Discrete {
+        discrete(self.raw[3], 2)
+    }
+
+    /// Tachograph performance (SPN 1620).
+    ///
+    /// Enabled when a performance analysis is required.
+    pub fn performance(&self) -> Discrete {
+        discrete(self.raw[3], 4)
+    }
+
+    /// Direction indicator (SPN 1619).
+    pub fn direction(&self) -> Direction {
+        Direction::from(self.raw[3] >> 6)
+    }
+
+    /// Tachograph output shaft speed (SPN 1623).
+    pub fn output_shaft_speed(&self) -> SaeAV01 {
+        SaeAV01::new(Param16::from(u16::from_le_bytes([
+            self.raw[4],
+            self.raw[5],
+        ])))
+    }
+
+    /// Tachograph vehicle speed (SPN 1624).
+    pub fn vehicle_speed(&self) -> SaeVE01 {
+        SaeVE01::new(Param16::from(u16::from_le_bytes([
+            self.raw[6],
+            self.raw[7],
+        ])))
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&Tachograph> for [u8; 8] {
+    fn from(msg: &Tachograph) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for Tachograph {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+/// Tachograph driver working state.
+#[derive(Debug, Clone, Copy, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DriverWorkingState {
+    Rest,
+    DriverAvailable,
+    Work,
+    Drive,
+    Error,
+    NotAvailable,
+    Other(u8),
+}
+
+impl PartialEq for DriverWorkingState {
+    fn eq(&self, other: &Self) -> bool {
+        // Cast to underlying value to compare
+        u8::from(*self) == u8::from(*other)
+    }
+}
+
+impl Hash for DriverWorkingState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        u8::from(*self).hash(state);
+    }
+}
+
+impl From<DriverWorkingState> for u8 {
+    fn from(value: DriverWorkingState) -> Self {
+        match value {
+            DriverWorkingState::Rest => 0b000,
+            DriverWorkingState::DriverAvailable => 0b001,
+            DriverWorkingState::Work => 0b010,
+            DriverWorkingState::Drive => 0b011,
+            DriverWorkingState::Error => 0b110,
+            DriverWorkingState::NotAvailable => 0b111,
+            DriverWorkingState::Other(o) => o,
+        }
+    }
+}
+
+impl From<u8> for DriverWorkingState {
+    fn from(value: u8) -> Self {
+        match value {
+            0b000 => Self::Rest,
+            0b001 => Self::DriverAvailable,
+            0b010 => Self::Work,
+            0b011 => Self::Drive,
+            0b110 => Self::Error,
+            0b111 => Self::NotAvailable,
+            o => Self::Other(o),
+        }
+    }
+}
+
+/// Vehicle direction of travel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    Forward = 0b00,
+    Reverse = 0b01,
+    Error = 0b10,
+    NotAvailable = 0b11,
+}
+
+impl From<u8> for Direction {
+    fn from(value: u8) -> Self {
+        match value & 0b11 {
+            0b00 => Self::Forward,
+            0b01 => Self::Reverse,
+            0b10 => Self::Error,
+            _ => Self::NotAvailable,
+        }
+    }
+}
+
+impl From<Direction> for u8 {
+    fn from(value: Direction) -> Self {
+        value as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tco1() {
+        let raw: &[u8] = &[0x4B, 0x3F, 0xFF, 0x3F, 0xFF, 0xFF, 0x00, 0x50];
+
+        let msg = Tachograph::try_from(raw).unwrap();
+        assert_eq!(msg.driver_1(), DriverWorkingState::Drive);
+        assert_eq!(msg.driver_2(), DriverWorkingState::DriverAvailable);
+        assert_eq!(msg.vehicle_motion(), Discrete::Enabled);
+        assert_eq!(msg.overspeed(), Discrete::Disabled);
+        assert_eq!(msg.direction(), Direction::Forward);
+        assert_eq!(msg.output_shaft_speed().as_f32(), None);
+        assert_eq!(msg.vehicle_speed().as_f32(), Some(80.0));
+
+        let msg = Tachograph::new(
+            DriverWorkingState::Drive,
+            DriverWorkingState::DriverAvailable,
+            Discrete::Enabled,
+            Discrete::Disabled,
+            Direction::Forward,
+            SaeVE01::from_f32(80.0).unwrap(),
+        );
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, raw);
+    }
+}
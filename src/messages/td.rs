@@ -0,0 +1,155 @@
+use crate::error::{DecodeError, exact};
+use crate::signal::{Param8, Signal};
+
+/// TD - Time/Date
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeDate {
+    raw: [u8; 8],
+}
+
+impl TimeDate {
+    /// Create a new TD message.
+    ///
+    /// The local offset is set to not available.
+    ///
+    /// Returns `None` if any of the fields are outside of their transmittable
+    /// range.
+    pub fn new(date_time: DateTime) -> Option<Self> {
+        if date_time.second > 59 || date_time.millisecond > 999 {
+            return None;
+        }
+
+        let mut raw = [0xFF; 8];
+
+        raw[0] = date_time.second * 4 + (date_time.millisecond / 250) as u8;
+        raw[1] = Self::checked(date_time.minute, 59)?;
+        raw[2] = Self::checked(date_time.hour, 23)?;
+        raw[3] = Self::checked(date_time.month, 12)?;
+        raw[4] = Self::checked(date_time.day, 31)? * 4;
+        raw[5] = Self::checked(u8::try_from(date_time.year.checked_sub(1985)?).ok()?, 250)?;
+
+        Some(Self { raw })
+    }
+
+    /// Set the local hour and minute offsets from UTC.
+    ///
+    /// Returns `None` if the offsets are outside of -125 to 125.
+    pub fn with_local_offset(mut self, hours: i8, minutes: i8) -> Option<Self> {
+        self.raw[6] = minutes
+            .checked_add(125)
+            .and_then(|m| u8::try_from(m).ok())?;
+        self.raw[7] = hours.checked_add(125).and_then(|h| u8::try_from(h).ok())?;
+        Some(self)
+    }
+
+    /// Date and time in UTC.
+    ///
+    /// Returns `None` if any of the fields are not available.
+    pub fn date_time(&self) -> Option<DateTime> {
+        let seconds = Param8::from(self.raw[0]).value()?;
+
+        Some(DateTime {
+            year: Param8::from(self.raw[5]).value()? as u16 + 1985,
+            month: Param8::from(self.raw[3]).value()?,
+            day: Param8::from(self.raw[4]).value()? / 4,
+            hour: Param8::from(self.raw[2]).value()?,
+            minute: Param8::from(self.raw[1]).value()?,
+            second: seconds / 4,
+            millisecond: (seconds % 4) as u16 * 250,
+        })
+    }
+
+    /// Local hour and minute offset from UTC.
+    ///
+    /// Returns `None` if either offset is not available.
+    pub fn local_offset(&self) -> Option<(i8, i8)> {
+        let minutes = Param8::from(self.raw[6]).value()? as i16 - 125;
+        let hours = Param8::from(self.raw[7]).value()? as i16 - 125;
+        Some((hours as i8, minutes as i8))
+    }
+
+    fn checked(value: u8, max: u8) -> Option<u8> {
+        (value <= max).then_some(value)
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&TimeDate> for [u8; 8] {
+    fn from(msg: &TimeDate) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for TimeDate {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+/// Calendar date and time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DateTime {
+    /// Year, from 1985.
+    pub year: u16,
+    /// Month, 1 to 12.
+    pub month: u8,
+    /// Day of the month, 1 to 31.
+    pub day: u8,
+    /// Hour, 0 to 23.
+    pub hour: u8,
+    /// Minute, 0 to 59.
+    pub minute: u8,
+    /// Second, 0 to 59.
+    pub second: u8,
+    /// Millisecond, in 250 ms steps.
+    pub millisecond: u16,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn td() {
+        let raw: &[u8] = &[0x7A, 0x1E, 0x0E, 0x06, 0x3C, 0x27, 0x7D, 0x7F];
+
+        let msg = TimeDate::try_from(raw).unwrap();
+        let date_time = DateTime {
+            year: 2024,
+            month: 6,
+            day: 15,
+            hour: 14,
+            minute: 30,
+            second: 30,
+            millisecond: 500,
+        };
+        assert_eq!(msg.date_time(), Some(date_time));
+        assert_eq!(msg.local_offset(), Some((2, 0)));
+
+        let msg = TimeDate::new(date_time)
+            .unwrap()
+            .with_local_offset(2, 0)
+            .unwrap();
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, raw);
+    }
+
+    #[test]
+    fn td_not_available() {
+        let msg = TimeDate::try_from([0xFF; 8].as_ref()).unwrap();
+        assert_eq!(msg.date_time(), None);
+        assert_eq!(msg.local_offset(), None);
+    }
+}
@@ -0,0 +1,275 @@
+use crate::Id;
+use crate::error::{DecodeError, exact};
+use crate::signal::{Param8, Param16};
+use crate::slot::{SaeAV01, SaePC05, Slot};
+
+/// TSC1 - Torque/Speed Control 1
+///
+/// Destination specific engine or retarder control command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TorqueSpeedControl1 {
+    raw: [u8; 8],
+}
+
+impl TorqueSpeedControl1 {
+    /// Create a new TSC1 message.
+    ///
+    /// The message counter and checksum are left as not available, use
+    /// [`TorqueSpeedControl1::with_counter`] for receivers that require them.
+    pub fn new(
+        mode: OverrideControlMode,
+        condition: SpeedControlCondition,
+        priority: OverridePriority,
+        speed: SaeAV01,
+        torque: SaePC05,
+    ) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0] = 0b11000000;
+        raw[0] |= u8::from(mode);
+        raw[0] |= u8::from(condition) << 2;
+        raw[0] |= u8::from(priority) << 4;
+        raw[1..3].copy_from_slice(&u16::from(speed.parameter()).to_le_bytes());
+        raw[3] = torque.parameter().into();
+
+        Self { raw }
+    }
+
+    /// Set the message counter and compute the checksum for the given
+    /// identifier the message will be sent with.
+    ///
+    /// The counter is masked to 4 bits.
+    pub fn with_counter(mut self, counter: u8, id: Id) -> Self {
+        self.raw[7] = counter & 0x0F;
+        self.raw[7] |= self.compute_checksum(id) << 4;
+        self
+    }
+
+    /// Engine override control mode (SPN 695).
+    pub fn mode(&self) -> OverrideControlMode {
+        OverrideControlMode::from(self.raw[0] & 0b11)
+    }
+
+    /// Engine requested speed control conditions (SPN 696).
+    pub fn condition(&self) -> SpeedControlCondition {
+        SpeedControlCondition::from((self.raw[0] >> 2) & 0b11)
+    }
+
+    /// Override control mode priority (SPN 897).
+    pub fn priority(&self) -> OverridePriority {
+        OverridePriority::from((self.raw[0] >> 4) & 0b11)
+    }
+
+    /// Engine requested speed/speed limit (SPN 898).
+    pub fn speed(&self) -> SaeAV01 {
+        SaeAV01::new(Param16::from(u16::from_le_bytes([
+            self.raw[1],
+            self.raw[2],
+        ])))
+    }
+
+    /// Engine requested torque/torque limit (SPN 518).
+    pub fn torque(&self) -> SaePC05 {
+        SaePC05::new(Param8::from(self.raw[3]))
+    }
+
+    /// TSC1 transmission rate (SPN 3349).
+    ///
+    /// Raw 3-bit value, `0b111` is the standard 10 ms rate.
+    pub fn transmission_rate(&self) -> u8 {
+        self.raw[4] & 0b111
+    }
+
+    /// TSC1 control purpose (SPN 3350).
+    ///
+    /// Raw 5-bit value, `0b11111` is temporary powertrain control.
+    pub fn control_purpose(&self) -> u8 {
+        self.raw[4] >> 3
+    }
+
+    /// Message counter (SPN 4206).
+    pub fn counter(&self) -> u8 {
+        self.raw[7] & 0x0F
+    }
+
+    /// Message checksum (SPN 4207).
+    pub fn checksum(&self) -> u8 {
+        self.raw[7] >> 4
+    }
+
+    /// Check the message checksum against the identifier it was received with.
+    pub fn checksum_valid(&self, id: Id) -> bool {
+        self.checksum() == self.compute_checksum(id)
+    }
+
+    fn compute_checksum(&self, id: Id) -> u8 {
+        let sum: u32 = self.raw[..7]
+            .iter()
+            .chain(id.as_raw().to_le_bytes().iter())
+            .map(|b| *b as u32)
+            .sum::<u32>()
+            + self.counter() as u32;
+
+        (((sum >> 4) + sum) & 0x0F) as u8
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&TorqueSpeedControl1> for [u8; 8] {
+    fn from(msg: &TorqueSpeedControl1) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for TorqueSpeedControl1 {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+/// Engine override control mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OverrideControlMode {
+    /// Override disabled.
+    NoOverride = 0b00,
+    /// Govern speed to the requested speed.
+    SpeedControl = 0b01,
+    /// Control torque to the requested torque.
+    TorqueControl = 0b10,
+    /// Limit speed and torque to the requested values.
+    SpeedTorqueLimitControl = 0b11,
+}
+
+impl From<u8> for OverrideControlMode {
+    fn from(value: u8) -> Self {
+        match value & 0b11 {
+            0b00 => Self::NoOverride,
+            0b01 => Self::SpeedControl,
+            0b10 => Self::TorqueControl,
+            _ => Self::SpeedTorqueLimitControl,
+        }
+    }
+}
+
+impl From<OverrideControlMode> for u8 {
+    fn from(value: OverrideControlMode) -> Self {
+        value as u8
+    }
+}
+
+/// Engine requested speed control conditions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpeedControlCondition {
+    /// Transient optimized for driveline disengaged and non-lockup conditions.
+    TransientDisengaged = 0b00,
+    /// Stability optimized for driveline disengaged and non-lockup conditions.
+    StabilityDisengaged = 0b01,
+    /// Stability optimized for driveline engaged and/or in lockup condition 1.
+    StabilityEngaged1 = 0b10,
+    /// Stability optimized for driveline engaged and/or in lockup condition 2.
+    StabilityEngaged2 = 0b11,
+}
+
+impl From<u8> for SpeedControlCondition {
+    fn from(value: u8) -> Self {
+        match value & 0b11 {
+            0b00 => Self::TransientDisengaged,
+            0b01 => Self::StabilityDisengaged,
+            0b10 => Self::StabilityEngaged1,
+            _ => Self::StabilityEngaged2,
+        }
+    }
+}
+
+impl From<SpeedControlCondition> for u8 {
+    fn from(value: SpeedControlCondition) -> Self {
+        value as u8
+    }
+}
+
+/// Override control mode priority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OverridePriority {
+    /// Overrides all other requests.
+    Highest = 0b00,
+    /// Overrides requests of medium and low priority.
+    High = 0b01,
+    /// Overrides requests of low priority.
+    Medium = 0b10,
+    /// Overridden by all other requests.
+    Low = 0b11,
+}
+
+impl From<u8> for OverridePriority {
+    fn from(value: u8) -> Self {
+        match value & 0b11 {
+            0b00 => Self::Highest,
+            0b01 => Self::High,
+            0b10 => Self::Medium,
+            _ => Self::Low,
+        }
+    }
+}
+
+impl From<OverridePriority> for u8 {
+    fn from(value: OverridePriority) -> Self {
+        value as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tsc1() {
+        let id = Id::new(0x0C000003);
+        let raw: &[u8] = &[0xC1, 0xE0, 0x2E, 0xE1, 0xFF, 0xFF, 0xFF, 0xA3];
+
+        let msg = TorqueSpeedControl1::try_from(raw).unwrap();
+        assert_eq!(msg.mode(), OverrideControlMode::SpeedControl);
+        assert_eq!(msg.condition(), SpeedControlCondition::TransientDisengaged);
+        assert_eq!(msg.priority(), OverridePriority::Highest);
+        assert_eq!(msg.speed().as_f32(), Some(1500.0));
+        assert_eq!(msg.torque().as_f32(), Some(100.0));
+        assert_eq!(msg.transmission_rate(), 0b111);
+        assert_eq!(msg.control_purpose(), 0b11111);
+        assert_eq!(msg.counter(), 3);
+        assert_eq!(msg.checksum(), 0xA);
+        assert!(msg.checksum_valid(id));
+        assert!(!msg.checksum_valid(Id::new(0x0C000004)));
+
+        let msg = TorqueSpeedControl1::new(
+            OverrideControlMode::SpeedControl,
+            SpeedControlCondition::TransientDisengaged,
+            OverridePriority::Highest,
+            SaeAV01::from_f32(1500.0).unwrap(),
+            SaePC05::from_f32(100.0).unwrap(),
+        )
+        .with_counter(3, id);
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, raw);
+
+        // cruise control at 250 ms
+        let raw: &[u8] = &[0xC1, 0xE0, 0x2E, 0xE1, 0x0B, 0xFF, 0xFF, 0xFF];
+        let msg = TorqueSpeedControl1::try_from(raw).unwrap();
+        assert_eq!(msg.transmission_rate(), 0b011);
+        assert_eq!(msg.control_purpose(), 0b00001);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::error::{DecodeError, exact};
+use crate::signal::Param32;
+use crate::slot::{SaeDS01, SaeDS02, Slot};
+
+/// VD - Vehicle Distance
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VehicleDistance {
+    raw: [u8; 8],
+}
+
+impl VehicleDistance {
+    /// Create a new VD message.
+    pub fn new(trip_distance: SaeDS01, total_distance: SaeDS01) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0..4].copy_from_slice(&u32::from(trip_distance.parameter()).to_le_bytes());
+        raw[4..8].copy_from_slice(&u32::from(total_distance.parameter()).to_le_bytes());
+
+        Self { raw }
+    }
+
+    /// Trip distance (SPN 244).
+    pub fn trip_distance(&self) -> SaeDS01 {
+        SaeDS01::new(Param32::from(u32::from_le_bytes([
+            self.raw[0],
+            self.raw[1],
+            self.raw[2],
+            self.raw[3],
+        ])))
+    }
+
+    /// Total vehicle distance (SPN 245).
+    pub fn total_distance(&self) -> SaeDS01 {
+        SaeDS01::new(Param32::from(u32::from_le_bytes([
+            self.raw[4],
+            self.raw[5],
+            self.raw[6],
+            self.raw[7],
+        ])))
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&VehicleDistance> for [u8; 8] {
+    fn from(msg: &VehicleDistance) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for VehicleDistance {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+/// VDHR - High Resolution Vehicle Distance
+///
+/// Note the total distance comes before the trip distance, unlike [`VehicleDistance`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HighResolutionVehicleDistance {
+    raw: [u8; 8],
+}
+
+impl HighResolutionVehicleDistance {
+    /// Create a new VDHR message.
+    pub fn new(total_distance: SaeDS02, trip_distance: SaeDS02) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0..4].copy_from_slice(&u32::from(total_distance.parameter()).to_le_bytes());
+        raw[4..8].copy_from_slice(&u32::from(trip_distance.parameter()).to_le_bytes());
+
+        Self { raw }
+    }
+
+    /// High resolution total vehicle distance (SPN 917).
+    pub fn total_distance(&self) -> SaeDS02 {
+        SaeDS02::new(Param32::from(u32::from_le_bytes([
+            self.raw[0],
+            self.raw[1],
+            self.raw[2],
+            self.raw[3],
+        ])))
+    }
+
+    /// High resolution trip distance (SPN 918).
+    pub fn trip_distance(&self) -> SaeDS02 {
+        SaeDS02::new(Param32::from(u32::from_le_bytes([
+            self.raw[4],
+            self.raw[5],
+            self.raw[6],
+            self.raw[7],
+        ])))
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&HighResolutionVehicleDistance> for [u8; 8] {
+    fn from(msg: &HighResolutionVehicleDistance) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for HighResolutionVehicleDistance {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vd() {
+        let raw: &[u8] = &[0x50, 0x00, 0x00, 0x00, 0x00, 0x35, 0x0C, 0x00];
+
+        let msg = VehicleDistance::try_from(raw).unwrap();
+        assert_eq!(msg.trip_distance().as_f32(), Some(10.0));
+        assert_eq!(msg.total_distance().as_f32(), Some(100000.0));
+
+        let msg = VehicleDistance::new(
+            SaeDS01::from_f32(10.0).unwrap(),
+            SaeDS01::from_f32(100000.0).unwrap(),
+        );
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, raw);
+    }
+
+    #[test]
+    fn vdhr() {
+        let raw: &[u8] = &[0x80, 0x96, 0x98, 0x00, 0xD0, 0x07, 0x00, 0x00];
+
+        let msg = HighResolutionVehicleDistance::try_from(raw).unwrap();
+        assert_eq!(msg.total_distance().as_f32(), Some(50_000_000.0));
+        assert_eq!(msg.trip_distance().as_f32(), Some(10_000.0));
+
+        let msg = HighResolutionVehicleDistance::new(
+            SaeDS02::from_f32(50_000_000.0).unwrap(),
+            SaeDS02::from_f32(10_000.0).unwrap(),
+        );
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, raw);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::error::{DecodeError, exact};
+use crate::signal::{Param8, Param16};
+use crate::slot::{SaeEC01, SaeEC02, SaeEV01, Slot};
+
+/// VEP1 - Vehicle Electrical Power 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VehicleElectricalPower1 {
+    raw: [u8; 8],
+}
+
+impl VehicleElectricalPower1 {
+    /// Create a new VEP1 message.
+    pub fn new(
+        net_battery_current: SaeEC01,
+        alternator_current: SaeEC02,
+        charging_system_potential: SaeEV01,
+        battery_potential: SaeEV01,
+        keyswitch_potential: SaeEV01,
+    ) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0] = net_battery_current.parameter().into();
+        raw[1] = alternator_current.parameter().into();
+        raw[2..4].copy_from_slice(&u16::from(charging_system_potential.parameter()).to_le_bytes());
+        raw[4..6].copy_from_slice(&u16::from(battery_potential.parameter()).to_le_bytes());
+        raw[6..8].copy_from_slice(&u16::from(keyswitch_potential.parameter()).to_le_bytes());
+
+        Self { raw }
+    }
+
+    /// Net battery current (SPN 114).
+    pub fn net_battery_current(&self) -> SaeEC01 {
+        SaeEC01::new(Param8::from(self.raw[0]))
+    }
+
+    /// Alternator current (SPN 115).
+    pub fn alternator_current(&self) -> SaeEC02 {
+        SaeEC02::new(Param8::from(self.raw[1]))
+    }
+
+    /// Charging system potential (SPN 167).
+    pub fn charging_system_potential(&self) -> SaeEV01 {
+        SaeEV01::new(Param16::from(u16::from_le_bytes([
+            self.raw[2],
+            self.raw[3],
+        ])))
+    }
+
+    /// Battery potential / power input 1 (SPN 168).
+    pub fn battery_potential(&self) -> SaeEV01 {
+        SaeEV01::new(Param16::from(u16::from_le_bytes([
+            self.raw[4],
+            self.raw[5],
+        ])))
+    }
+
+    /// Keyswitch battery potential (SPN 158).
+    pub fn keyswitch_potential(&self) -> SaeEV01 {
+        SaeEV01::new(Param16::from(u16::from_le_bytes([
+            self.raw[6],
+            self.raw[7],
+        ])))
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&VehicleElectricalPower1> for [u8; 8] {
+    fn from(msg: &VehicleElectricalPower1) -> Self {
+        msg.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for VehicleElectricalPower1 {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vep1() {
+        let raw: &[u8] = &[0x73, 0x28, 0x18, 0x01, 0x10, 0x01, 0x08, 0x01];
+
+        let msg = VehicleElectricalPower1::try_from(raw).unwrap();
+        assert_eq!(msg.net_battery_current().as_f32(), Some(-10.0));
+        assert_eq!(msg.alternator_current().as_f32(), Some(40.0));
+        assert_eq!(msg.charging_system_potential().as_f32(), Some(14.0));
+        assert_eq!(msg.battery_potential().as_f32(), Some(13.6));
+        assert_eq!(msg.keyswitch_potential().as_f32(), Some(13.2));
+
+        let msg = VehicleElectricalPower1::new(
+            SaeEC01::from_f32(-10.0).unwrap(),
+            SaeEC02::from_f32(40.0).unwrap(),
+            SaeEV01::new(Param16::from(280)),
+            SaeEV01::new(Param16::from(272)),
+            SaeEV01::new(Param16::from(264)),
+        );
+        let bytes: [u8; 8] = (&msg).into();
+        assert_eq!(bytes, raw);
+    }
+}
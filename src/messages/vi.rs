@@ -0,0 +1,107 @@
+use crate::error::DecodeError;
+use crate::request::Request;
+use crate::{Id, Pgn};
+
+/// VI - Vehicle Identification
+///
+/// The vehicle identification number (VIN) terminated by an ASCII `*`. Usually
+/// received as a transport protocol payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct VehicleIdentification<'a> {
+    raw: &'a [u8],
+}
+
+impl<'a> VehicleIdentification<'a> {
+    const DELIMITER: u8 = b'*';
+
+    /// Encode a VIN into `buf`.
+    ///
+    /// Returns `None` if `buf` is too small or the VIN contains the `*`
+    /// delimiter.
+    pub fn encode(vin: &[u8], buf: &'a mut [u8]) -> Option<Self> {
+        if vin.contains(&Self::DELIMITER) {
+            return None;
+        }
+
+        buf.get_mut(..vin.len())?.copy_from_slice(vin);
+        *buf.get_mut(vin.len())? = Self::DELIMITER;
+
+        Some(Self {
+            raw: &buf[..vin.len() + 1],
+        })
+    }
+
+    /// Identifier and data of a request for this PGN.
+    ///
+    /// A `da` of `None` issues a global request.
+    pub fn request(sa: u8, da: Option<u8>) -> Option<(Id, [u8; 3])> {
+        Request::new(Pgn::VehicleIdentification).to_frame(sa, da)
+    }
+
+    /// Vehicle identification number, without the delimiter.
+    pub fn vin(&self) -> &'a [u8] {
+        // always terminated by the delimiter
+        &self.raw[..self.raw.len() - 1]
+    }
+
+    /// Raw message bytes.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for VehicleIdentification<'a> {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let Some(end) = value.iter().position(|b| *b == Self::DELIMITER) else {
+            // the delimiter would take one more byte
+            return Err(DecodeError::Length {
+                expected: value.len() + 1,
+                actual: value.len(),
+            });
+        };
+
+        Ok(Self {
+            raw: &value[..=end],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vi() {
+        let raw: &[u8] = b"1M8GDM9AXKP042788*";
+
+        let msg = VehicleIdentification::try_from(raw).unwrap();
+        assert_eq!(msg.vin(), b"1M8GDM9AXKP042788");
+
+        let mut buf = [0; 32];
+        let msg = VehicleIdentification::encode(b"1M8GDM9AXKP042788", &mut buf).unwrap();
+        assert_eq!(msg.as_bytes(), raw);
+    }
+
+    #[test]
+    fn vi_single_frame() {
+        let raw: &[u8] = &[b'A', b'B', b'C', b'*', 0xFF, 0xFF, 0xFF, 0xFF];
+
+        let msg = VehicleIdentification::try_from(raw).unwrap();
+        assert_eq!(msg.vin(), b"ABC");
+        assert_eq!(msg.as_bytes(), b"ABC*");
+
+        assert!(VehicleIdentification::try_from(b"ABC".as_ref()).is_err());
+    }
+
+    #[test]
+    fn vi_request() {
+        let (id, data) = VehicleIdentification::request(0xF9, None).unwrap();
+        assert_eq!(id.pgn(), Pgn::Request);
+        assert_eq!(id.sa(), 0xF9);
+        assert_eq!(id.da(), Some(0xFF));
+        assert_eq!(data, [0xEC, 0xFE, 0x00]);
+    }
+}
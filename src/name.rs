@@ -0,0 +1,100 @@
+//! Device NAME (J1939-81)
+
+/// 64-bit NAME identifying a controller application on the network.
+///
+/// Also used as the address claim priority, a lower value wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Name(u64);
+
+impl Name {
+    /// Create a NAME from its raw value.
+    pub const fn new(raw: u64) -> Self {
+        Self(raw)
+    }
+
+    /// Raw value.
+    pub const fn as_raw(&self) -> u64 {
+        self.0
+    }
+
+    /// Identity number.
+    pub fn identity_number(&self) -> u32 {
+        (self.0 & 0x1F_FFFF) as u32
+    }
+
+    /// Manufacturer code.
+    pub fn manufacturer_code(&self) -> u16 {
+        ((self.0 >> 21) & 0x7FF) as u16
+    }
+
+    /// ECU instance.
+    pub fn ecu_instance(&self) -> u8 {
+        ((self.0 >> 32) & 0x07) as u8
+    }
+
+    /// Function instance.
+    pub fn function_instance(&self) -> u8 {
+        ((self.0 >> 35) & 0x1F) as u8
+    }
+
+    /// Function.
+    pub fn function(&self) -> u8 {
+        (self.0 >> 40) as u8
+    }
+
+    /// Vehicle system.
+    pub fn vehicle_system(&self) -> u8 {
+        ((self.0 >> 49) & 0x7F) as u8
+    }
+
+    /// Vehicle system instance.
+    pub fn vehicle_system_instance(&self) -> u8 {
+        ((self.0 >> 56) & 0x0F) as u8
+    }
+
+    /// Industry group.
+    pub fn industry_group(&self) -> u8 {
+        ((self.0 >> 60) & 0x07) as u8
+    }
+
+    /// Check if the controller application can claim an address other than
+    /// its preferred one.
+    pub fn arbitrary_address_capable(&self) -> bool {
+        self.0 >> 63 == 1
+    }
+}
+
+impl From<u64> for Name {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Name> for u64 {
+    fn from(value: Name) -> Self {
+        value.0
+    }
+}
+
+impl From<Name> for [u8; 8] {
+    fn from(value: Name) -> Self {
+        value.0.to_le_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields() {
+        let name = Name::new(0x8000_1E00_0000_0000 | (0x123 << 21) | 0x1234);
+        assert_eq!(name.identity_number(), 0x1234);
+        assert_eq!(name.manufacturer_code(), 0x123);
+        assert_eq!(name.function(), 0x1E);
+        assert_eq!(name.industry_group(), 0);
+        assert!(name.arbitrary_address_capable());
+    }
+}
@@ -0,0 +1,183 @@
+//! Fast packet protocol (NMEA 2000)
+//!
+//! Carries up to 223 bytes in a sequence of up to 32 frames without
+//! connection management.
+
+use crate::transport::assembly::Segments;
+
+/// Largest payload that fits into a fast packet sequence.
+pub const MAX_SIZE: usize = 223;
+
+/// Six payload bytes follow the size in the first frame, seven in the others.
+const SEGMENTS: Segments = Segments::new(6, 7);
+
+/// Fast packet reassembly error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Error {
+    /// Frame is not 8 bytes long.
+    Length,
+    /// Frame received out of order or from another sequence.
+    Sequence,
+    /// Announced size is larger than [`MAX_SIZE`].
+    TooLarge,
+}
+
+/// Reassembles a fast packet sequence from a single source.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Assembler {
+    buf: [u8; MAX_SIZE],
+    size: usize,
+    sequence: u8,
+    next_frame: u8,
+}
+
+impl Assembler {
+    /// Create a new idle assembler.
+    pub fn new() -> Self {
+        Self {
+            buf: [0; MAX_SIZE],
+            size: 0,
+            sequence: 0,
+            next_frame: 0,
+        }
+    }
+
+    /// Feed the next frame.
+    ///
+    /// Returns the payload once the last frame of the sequence was received.
+    /// A first frame always restarts reassembly.
+    pub fn push(&mut self, data: &[u8]) -> Result<Option<&[u8]>, Error> {
+        let data: &[u8; 8] = data.try_into().map_err(|_| Error::Length)?;
+
+        let sequence = data[0] >> 5;
+        let frame = data[0] & 0b11111;
+
+        let chunk = if frame == 0 {
+            let size = data[1] as usize;
+            if size > MAX_SIZE {
+                self.next_frame = 0;
+                return Err(Error::TooLarge);
+            }
+            self.size = size;
+            self.sequence = sequence;
+            &data[2..]
+        } else {
+            if self.next_frame == 0 || sequence != self.sequence || frame != self.next_frame {
+                self.next_frame = 0;
+                return Err(Error::Sequence);
+            }
+            &data[1..]
+        };
+
+        // frames are 8 bytes, so every segment is whole
+        let done = SEGMENTS
+            .store(self.buf.as_mut_slice(), frame.into(), self.size, chunk)
+            .map_err(|_| Error::Length)?;
+        if done {
+            self.next_frame = 0;
+            return Ok(Some(&self.buf[..self.size]));
+        }
+
+        self.next_frame = frame + 1;
+        Ok(None)
+    }
+}
+
+impl Default for Assembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Frames of a fast packet sequence, padded with `0xFF`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Frames<'a> {
+    data: &'a [u8],
+    sequence: u8,
+    frame: u8,
+}
+
+impl<'a> Frames<'a> {
+    /// Split `data` into frames using the 3-bit `sequence` counter.
+    ///
+    /// Returns `None` if `data` is larger than [`MAX_SIZE`].
+    pub fn new(data: &'a [u8], sequence: u8) -> Option<Self> {
+        if data.len() > MAX_SIZE {
+            return None;
+        }
+
+        Some(Self {
+            data,
+            sequence: sequence & 0b111,
+            frame: 0,
+        })
+    }
+}
+
+impl Iterator for Frames<'_> {
+    type Item = [u8; 8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let total = self.data.len();
+        if usize::from(self.frame) >= SEGMENTS.count(total) {
+            return None;
+        }
+
+        let mut raw = [0xFF; 8];
+        raw[0] = (self.sequence << 5) | self.frame;
+
+        let start = if self.frame == 0 {
+            raw[1] = self.data.len() as u8;
+            2
+        } else {
+            1
+        };
+
+        let range = SEGMENTS.range(self.frame.into(), total);
+        raw[start..start + range.len()].copy_from_slice(&self.data[range]);
+
+        self.frame += 1;
+
+        Some(raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let data: [u8; 20] = core::array::from_fn(|i| i as u8);
+        let mut assembler = Assembler::new();
+
+        let mut frames = Frames::new(&data, 5).unwrap();
+        let first = frames.next().unwrap();
+        assert_eq!(first[..3], [0xA0, 20, 0]);
+
+        assert_eq!(assembler.push(&first), Ok(None));
+        assert_eq!(assembler.push(&frames.next().unwrap()), Ok(None));
+        let last = frames.next().unwrap();
+        assert_eq!(last, [0xA2, 13, 14, 15, 16, 17, 18, 19]);
+        assert_eq!(assembler.push(&last), Ok(Some(data.as_ref())));
+        assert!(frames.next().is_none());
+    }
+
+    #[test]
+    fn out_of_order() {
+        let data = [0; 20];
+        let mut assembler = Assembler::new();
+        let frames: [[u8; 8]; 3] = {
+            let mut f = Frames::new(&data, 0).unwrap();
+            [f.next().unwrap(), f.next().unwrap(), f.next().unwrap()]
+        };
+
+        assert_eq!(assembler.push(&frames[1]), Err(Error::Sequence));
+        assert_eq!(assembler.push(&frames[0]), Ok(None));
+        assert_eq!(assembler.push(&frames[2]), Err(Error::Sequence));
+    }
+}
@@ -0,0 +1,310 @@
+//! NMEA 2000 product and configuration information
+//!
+//! Both are requested with an ISO request ([`Request`]) and answered using
+//! the [`fast_packet`] protocol.
+
+pub mod fast_packet;
+
+use crate::error::{DecodeError, at_least};
+use crate::request::Request;
+use crate::{Id, Pgn};
+
+/// Length of the fixed string fields of [`ProductInformation`].
+const STRING_LEN: usize = 32;
+
+/// Remove the padding from a fixed length string field.
+fn trim(field: &[u8]) -> &[u8] {
+    let end = field
+        .iter()
+        .rposition(|b| !matches!(b, 0x00 | 0xFF | b'@' | b' '))
+        .map_or(0, |i| i + 1);
+    &field[..end]
+}
+
+/// Identifier and data of an ISO request for `pgn` sent from `sa`.
+///
+/// A `da` of `None` requests the information from all nodes.
+pub fn request(pgn: Pgn, sa: u8, da: Option<u8>) -> Option<(Id, [u8; 3])> {
+    Request::new(pgn).to_frame(sa, da)
+}
+
+/// PGN 126996 - Product Information
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ProductInformation<'a> {
+    raw: &'a [u8],
+}
+
+impl<'a> ProductInformation<'a> {
+    /// Size of the message in bytes.
+    pub const SIZE: usize = 134;
+
+    /// Encode a product information message into `buf`.
+    ///
+    /// String fields longer than 32 bytes are truncated. Returns `None` if
+    /// `buf` is too small.
+    #[allow(clippy::too_many_arguments)]
+    pub fn encode(
+        nmea2000_version: u16,
+        product_code: u16,
+        model_id: &[u8],
+        software_version: &[u8],
+        model_version: &[u8],
+        serial_code: &[u8],
+        certification_level: u8,
+        load_equivalency: u8,
+        buf: &'a mut [u8],
+    ) -> Option<Self> {
+        let raw = buf.get_mut(..Self::SIZE)?;
+        raw.fill(0xFF);
+
+        raw[0..2].copy_from_slice(&nmea2000_version.to_le_bytes());
+        raw[2..4].copy_from_slice(&product_code.to_le_bytes());
+        for (index, field) in [model_id, software_version, model_version, serial_code]
+            .into_iter()
+            .enumerate()
+        {
+            let start = 4 + index * STRING_LEN;
+            let len = field.len().min(STRING_LEN);
+            raw[start..start + len].copy_from_slice(&field[..len]);
+        }
+        raw[132] = certification_level;
+        raw[133] = load_equivalency;
+
+        Some(Self { raw })
+    }
+
+    /// NMEA 2000 database version, in units of 0.001.
+    pub fn nmea2000_version(&self) -> u16 {
+        u16::from_le_bytes([self.raw[0], self.raw[1]])
+    }
+
+    /// Manufacturer's product code.
+    pub fn product_code(&self) -> u16 {
+        u16::from_le_bytes([self.raw[2], self.raw[3]])
+    }
+
+    /// Model ID without padding.
+    pub fn model_id(&self) -> &'a [u8] {
+        self.string(0)
+    }
+
+    /// Software version code without padding.
+    pub fn software_version(&self) -> &'a [u8] {
+        self.string(1)
+    }
+
+    /// Model version without padding.
+    pub fn model_version(&self) -> &'a [u8] {
+        self.string(2)
+    }
+
+    /// Model serial code without padding.
+    pub fn serial_code(&self) -> &'a [u8] {
+        self.string(3)
+    }
+
+    /// Certification level.
+    pub fn certification_level(&self) -> u8 {
+        self.raw[132]
+    }
+
+    /// Load equivalency number, in units of 50 mA.
+    pub fn load_equivalency(&self) -> u8 {
+        self.raw[133]
+    }
+
+    /// Raw message bytes.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    fn string(&self, index: usize) -> &'a [u8] {
+        let start = 4 + index * STRING_LEN;
+        trim(&self.raw[start..start + STRING_LEN])
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ProductInformation<'a> {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: &at_least(value, Self::SIZE)?[..Self::SIZE],
+        })
+    }
+}
+
+/// PGN 126998 - Configuration Information
+///
+/// Three variable length strings, each prefixed by its length and an
+/// encoding byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ConfigurationInformation<'a> {
+    raw: &'a [u8],
+}
+
+impl<'a> ConfigurationInformation<'a> {
+    /// Encoding byte for ASCII strings.
+    const ASCII: u8 = 1;
+
+    /// Encode a configuration information message into `buf`.
+    ///
+    /// Returns `None` if `buf` is too small or a string is longer than 253
+    /// bytes.
+    pub fn encode(
+        installation1: &[u8],
+        installation2: &[u8],
+        manufacturer: &[u8],
+        buf: &'a mut [u8],
+    ) -> Option<Self> {
+        let mut len = 0;
+
+        for field in [installation1, installation2, manufacturer] {
+            let header = buf.get_mut(len..len + 2)?;
+            header[0] = u8::try_from(field.len() + 2).ok()?;
+            header[1] = Self::ASCII;
+            buf.get_mut(len + 2..len + 2 + field.len())?
+                .copy_from_slice(field);
+            len += field.len() + 2;
+        }
+
+        Some(Self { raw: &buf[..len] })
+    }
+
+    /// Installation description 1.
+    pub fn installation1(&self) -> &'a [u8] {
+        self.string(0).unwrap_or_default()
+    }
+
+    /// Installation description 2.
+    pub fn installation2(&self) -> &'a [u8] {
+        self.string(1).unwrap_or_default()
+    }
+
+    /// Manufacturer information.
+    pub fn manufacturer(&self) -> &'a [u8] {
+        self.string(2).unwrap_or_default()
+    }
+
+    /// Raw message bytes.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    fn string(&self, index: usize) -> Option<&'a [u8]> {
+        let mut rest = self.raw;
+        for _ in 0..index {
+            rest = rest.get(Self::field_len(rest)?..)?;
+        }
+        rest.get(2..Self::field_len(rest)?)
+    }
+
+    fn field_len(raw: &[u8]) -> Option<usize> {
+        let len = *raw.first()? as usize;
+        (len >= 2).then_some(len)
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ConfigurationInformation<'a> {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        // all three strings must be present
+        let mut offset = 0;
+        for _ in 0..3 {
+            let len = at_least(value, offset + 2)?[offset];
+            if len < 2 {
+                return Err(DecodeError::Value { offset, value: len });
+            }
+            offset += usize::from(len);
+            at_least(value, offset)?;
+        }
+
+        Ok(Self { raw: value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fast_packet::{Assembler, Frames};
+    use super::*;
+
+    #[test]
+    fn product_information() {
+        let mut buf = [0; 140];
+        let msg = ProductInformation::encode(
+            2100,
+            1234,
+            b"Saelient",
+            b"1.0.0",
+            b"A",
+            b"SN0001",
+            1,
+            2,
+            &mut buf,
+        )
+        .unwrap();
+        let raw: std::vec::Vec<u8> = msg.as_bytes().to_vec();
+
+        // reassemble from fast packet frames
+        let mut assembler = Assembler::new();
+        let mut payload = None;
+        for frame in Frames::new(&raw, 1).unwrap() {
+            if let Some(data) = assembler.push(&frame).unwrap() {
+                payload = Some(data.to_vec());
+            }
+        }
+        let payload = payload.unwrap();
+
+        let msg = ProductInformation::try_from(payload.as_ref()).unwrap();
+        assert_eq!(msg.nmea2000_version(), 2100);
+        assert_eq!(msg.product_code(), 1234);
+        assert_eq!(msg.model_id(), b"Saelient");
+        assert_eq!(msg.software_version(), b"1.0.0");
+        assert_eq!(msg.model_version(), b"A");
+        assert_eq!(msg.serial_code(), b"SN0001");
+        assert_eq!(msg.certification_level(), 1);
+        assert_eq!(msg.load_equivalency(), 2);
+
+        assert!(ProductInformation::try_from(&payload[..100]).is_err());
+    }
+
+    #[test]
+    fn configuration_information() {
+        let mut buf = [0; 32];
+        let msg = ConfigurationInformation::encode(b"Helm", b"", b"umi", &mut buf).unwrap();
+        assert_eq!(
+            msg.as_bytes(),
+            &[6, 1, b'H', b'e', b'l', b'm', 2, 1, 5, 1, b'u', b'm', b'i']
+        );
+
+        let msg = ConfigurationInformation::try_from(msg.as_bytes()).unwrap();
+        assert_eq!(msg.installation1(), b"Helm");
+        assert_eq!(msg.installation2(), b"");
+        assert_eq!(msg.manufacturer(), b"umi");
+
+        assert_eq!(
+            ConfigurationInformation::try_from([6, 1, b'H'].as_ref()),
+            Err(DecodeError::Length {
+                expected: 6,
+                actual: 3
+            })
+        );
+        assert_eq!(
+            ConfigurationInformation::try_from([1, 1, b'H'].as_ref()),
+            Err(DecodeError::Value {
+                offset: 0,
+                value: 1
+            })
+        );
+    }
+
+    #[test]
+    fn iso_request() {
+        let (id, data) = request(Pgn::ProductInformation, 0x10, None).unwrap();
+        assert_eq!(id.pgn(), Pgn::Request);
+        assert_eq!(data, [0x14, 0xF0, 0x01]);
+    }
+}
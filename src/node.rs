@@ -0,0 +1,1248 @@
+//! Node orchestration (J1939-21)
+//!
+//! [`Node`] ties address claiming, transport sessions, requests and
+//! diagnostics together into a single entry point that is fed every received
+//! frame and yields the frames that have to be sent in response.
+
+use crate::address::AddressManager;
+use crate::config::{ConfigError, NodeConfig, Periodic};
+use crate::diagnostic::{DiagnosticServer, DtcSlot};
+use crate::events::Events;
+use crate::id::{Id, IdBuilder, Pgn};
+use crate::logging::{debug, trace};
+use crate::name::Name;
+use crate::request::{Acknowledgement, Control, Request};
+use crate::time::{Duration, Instant};
+use crate::transport::{
+    BroadcastTransfer, ControlMessage, DataTransfer, OutgoingTransfer, TransportManager,
+};
+use managed::ManagedSlice;
+
+const GLOBAL: u8 = 0xFF;
+
+/// Interval of the DM1 message.
+const DM1_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// Largest window of outgoing transport sessions, so the packets granted by a
+/// CTS fit in the [`Frames`] of one poll.
+const MAX_WINDOW: u8 = 4;
+
+/// Message longer than a frame being sent.
+#[derive(Debug)]
+enum Outgoing<'a> {
+    /// To a single node, paced by its CTS.
+    Connection {
+        peer: u8,
+        transfer: OutgoingTransfer<'a>,
+    },
+    Broadcast(BroadcastTransfer<'a>),
+}
+
+/// Sends messages longer than a frame from a buffer, one at a time.
+#[derive(Debug)]
+struct Outbox<'a> {
+    buffer: ManagedSlice<'a, u8>,
+    /// Length of the message in the buffer.
+    len: usize,
+    /// Transfer of the message, bound to an empty payload between polls.
+    transfer: Option<Outgoing<'a>>,
+}
+
+impl<'a> Outbox<'a> {
+    /// Send the message written by `write` as `pgn` from `sa` to `da`.
+    ///
+    /// Messages longer than a frame need the buffer to be idle. Returns
+    /// `false` if the message could not be sent.
+    #[allow(clippy::too_many_arguments)]
+    fn send(
+        &mut self,
+        sa: u8,
+        da: u8,
+        pgn: Pgn,
+        priority: u8,
+        now: Instant,
+        frames: &mut Frames,
+        write: impl FnOnce(&mut [u8]) -> Option<usize>,
+    ) -> bool {
+        if frames.is_full() {
+            return false;
+        }
+
+        let mut frame = [0xFF; 8];
+        let buf: &mut [u8] = if self.transfer.is_none() && self.buffer.len() > 8 {
+            &mut self.buffer[..]
+        } else {
+            &mut frame
+        };
+        let Some(len) = write(buf).filter(|&len| len <= buf.len()) else {
+            return false;
+        };
+
+        if len <= 8 {
+            let mut data = [0xFF; 8];
+            data[..len].copy_from_slice(&buf[..len]);
+            let id = IdBuilder::new()
+                .priority(priority)
+                .pgn(pgn)
+                .sa(sa)
+                .da(da)
+                .build();
+            frames.push(id.map(|id| (id, data)));
+            return id.is_some();
+        }
+
+        // only the buffer holds more than a frame
+        let data = &self.buffer[..len];
+        let Some(id) = tp_cm(sa, da) else {
+            return false;
+        };
+        if da == GLOBAL {
+            let Ok(mut transfer) = BroadcastTransfer::new(data, pgn) else {
+                return false;
+            };
+            frames.push(Some((id, transfer.announce(now).to_bytes())));
+            self.transfer = Some(Outgoing::Broadcast(transfer.rebind(&[])));
+        } else {
+            let Ok(mut transfer) = OutgoingTransfer::new(data, Some(MAX_WINDOW), pgn) else {
+                return false;
+            };
+            frames.push(Some((id, transfer.rts().to_bytes())));
+            let _ = transfer.poll(now);
+            self.transfer = Some(Outgoing::Connection {
+                peer: da,
+                transfer: transfer.rebind(&[]),
+            });
+        }
+
+        debug!("sending {len} bytes of PGN {} to {da:#04x}", pgn.as_raw());
+        self.len = len;
+        true
+    }
+
+    /// Handle a connection management message `msg` from `peer` to `sa`.
+    fn handle(
+        &mut self,
+        sa: u8,
+        peer: u8,
+        msg: &ControlMessage,
+        now: Instant,
+        frames: &mut Frames,
+    ) {
+        let (transfer, data) = match self.transfer.take() {
+            Some(Outgoing::Connection { peer: p, transfer }) if p == peer => {
+                (transfer, &self.buffer[..self.len])
+            }
+            other => {
+                self.transfer = other;
+                return;
+            }
+        };
+
+        let mut transfer = transfer.rebind(data);
+        if let Err((_, abort)) = transfer.handle(msg) {
+            debug!("transport session with {peer:#04x} aborted");
+            if let Some(abort) = abort {
+                frames.push(tp_cm(sa, peer).map(|id| (id, abort.to_bytes())));
+            }
+            return;
+        }
+
+        trace!("sending {} packets to {peer:#04x}", transfer.granted());
+        for packet in transfer.packets() {
+            frames.push(tp_dt(sa, peer, &packet));
+        }
+        let _ = transfer.poll(now);
+
+        if transfer.is_finished() {
+            debug!("transport session with {peer:#04x} complete");
+            return;
+        }
+        self.transfer = Some(Outgoing::Connection {
+            peer,
+            transfer: transfer.rebind(&[]),
+        });
+    }
+
+    /// Send the broadcast packets due and abort sessions whose receiver went
+    /// silent.
+    fn poll(&mut self, sa: u8, now: Instant, frames: &mut Frames) {
+        match self.transfer.take() {
+            Some(Outgoing::Broadcast(transfer)) => {
+                let mut transfer = transfer.rebind(&self.buffer[..self.len]);
+                if !frames.is_full()
+                    && let Some(packet) = transfer.next_packet(now)
+                {
+                    frames.push(tp_dt(sa, GLOBAL, &packet));
+                }
+                if !transfer.is_finished() {
+                    self.transfer = Some(Outgoing::Broadcast(transfer.rebind(&[])));
+                }
+            }
+            Some(Outgoing::Connection { peer, mut transfer }) => match transfer.poll(now) {
+                Ok(()) => self.transfer = Some(Outgoing::Connection { peer, transfer }),
+                Err((_, abort)) => {
+                    debug!("transport session with {peer:#04x} timed out");
+                    if let Some(abort) = abort {
+                        frames.push(tp_cm(sa, peer).map(|id| (id, abort.to_bytes())));
+                    }
+                }
+            },
+            None => {}
+        }
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        match self.transfer.as_ref()? {
+            Outgoing::Connection { transfer, .. } => transfer.deadline(),
+            Outgoing::Broadcast(transfer) => transfer.deadline(),
+        }
+    }
+}
+
+/// Sends the periodic table of a configuration.
+///
+/// Entries are due at multiples of their interval since the scheduler
+/// started, so no state is kept per entry.
+#[derive(Debug, Default)]
+struct Scheduler {
+    /// Time of the first poll.
+    start: Option<Instant>,
+    /// Milliseconds since the start up to which all entries were sent, `None`
+    /// before the first scan.
+    done: Option<u64>,
+    /// Milliseconds since the start of the scan in progress.
+    target: u64,
+    /// Next entry of the scan in progress.
+    cursor: usize,
+}
+
+impl Scheduler {
+    /// Send the entries of `table` due at `now` with `send`.
+    ///
+    /// `send` returns `false` when there is no room for the entry, which is
+    /// retried on the next poll.
+    fn poll(&mut self, table: &[Periodic], now: Instant, mut send: impl FnMut(&Periodic) -> bool) {
+        let start = *self.start.get_or_insert(now);
+        if self.cursor == 0 {
+            self.target = now.saturating_duration_since(start).as_millis();
+        }
+
+        while let Some(entry) = table.get(self.cursor) {
+            let interval = entry.interval.as_millis().max(1);
+            let due = self
+                .done
+                .is_none_or(|done| self.target / interval > done / interval);
+            if due && !send(entry) {
+                return;
+            }
+            self.cursor += 1;
+        }
+
+        self.cursor = 0;
+        self.done = Some(self.target);
+    }
+
+    /// Time the next entry of `table` is due, once started.
+    fn deadline(&self, table: &[Periodic]) -> Option<Instant> {
+        let start = self.start?;
+        let Some(done) = self.done.filter(|_| self.cursor == 0) else {
+            return Some(start + Duration::from_millis(self.target));
+        };
+
+        table
+            .iter()
+            .map(|entry| {
+                let interval = entry.interval.as_millis().max(1);
+                start + Duration::from_millis((done / interval + 1) * interval)
+            })
+            .min()
+    }
+}
+
+/// A J1939 node.
+///
+/// Claims its address, receives up to `N` transport sessions and broadcast
+/// transfers at once, answers requests and reports its DTCs, all driven from
+/// [`Node::poll`]. Until an address is claimed, the node only takes part in
+/// address claiming.
+///
+/// Requests for the address claimed, DM1 and DM2 messages are answered, and
+/// DM3 and DM11 requests clear the DTCs. Requests for the PGNs of
+/// [`NodeConfig::requests`] are answered from [`Events::on_request`], and
+/// the entries of [`NodeConfig::periodic`] are sent with the data of
+/// [`Events::on_periodic`]. Requests for other PGNs addressed to the node are
+/// negatively acknowledged.
+#[derive(Debug)]
+pub struct Node<'a, const N: usize = 1> {
+    config: NodeConfig<'a>,
+    address: AddressManager,
+    transport: TransportManager<'a, ManagedSlice<'a, u8>, N>,
+    outbox: Outbox<'a>,
+    diagnostics: DiagnosticServer<'a>,
+    /// Time the next DM1 is due, once DTC storage is given.
+    dm1: Option<Instant>,
+    periodic: Scheduler,
+}
+
+impl<'a> Node<'a> {
+    /// Create a new node at `address`.
+    ///
+    /// The node keeps `address` without claiming it. `storage` is used to
+    /// reassemble transport sessions and bounds the largest message that can
+    /// be received.
+    pub fn new(address: u8, storage: impl Into<ManagedSlice<'a, u8>>) -> Self {
+        Self::build(
+            NodeConfig::new(Name::default(), address),
+            AddressManager::fixed(address),
+            [storage.into()],
+        )
+    }
+
+    /// Create a new node from a validated configuration.
+    ///
+    /// The node claims the preferred address of `config` on the first poll,
+    /// and other addresses of its range when contested.
+    pub fn with_config(
+        config: NodeConfig<'a>,
+        storage: impl Into<ManagedSlice<'a, u8>>,
+    ) -> Result<Self, ConfigError> {
+        Node::with_sessions(config, [storage.into()])
+    }
+}
+
+impl<'a, const N: usize> Node<'a, N> {
+    /// Create a new node from a validated configuration, receiving up to `N`
+    /// transport sessions at once, each into one of `storage`.
+    pub fn with_sessions(
+        config: NodeConfig<'a>,
+        storage: [ManagedSlice<'a, u8>; N],
+    ) -> Result<Self, ConfigError> {
+        config.validate()?;
+
+        let address = AddressManager::new(
+            config.name(),
+            config.preferred_address(),
+            config.addresses(),
+        );
+        Ok(Self::build(config, address, storage))
+    }
+
+    fn build(
+        config: NodeConfig<'a>,
+        address: AddressManager,
+        storage: [ManagedSlice<'a, u8>; N],
+    ) -> Self {
+        let transport = TransportManager::new(address.source_address(), storage)
+            .with_max_packets_per_cts(config.packets_per_cts(255))
+            .with_max_message_size(config.message_size_limit());
+
+        Self {
+            config,
+            address,
+            transport,
+            outbox: Outbox {
+                buffer: ManagedSlice::Borrowed(&mut []),
+                len: 0,
+                transfer: None,
+            },
+            diagnostics: DiagnosticServer::new(ManagedSlice::Borrowed(&mut [])),
+            dm1: None,
+            periodic: Scheduler::default(),
+        }
+    }
+
+    /// Send messages longer than a frame from `buffer`, one at a time.
+    ///
+    /// Messages for a single node are sent through a transport session,
+    /// others through a broadcast transfer. Without a buffer, only messages
+    /// fitting in a frame are sent.
+    pub fn with_tx_buffer(mut self, buffer: impl Into<ManagedSlice<'a, u8>>) -> Self {
+        self.outbox.buffer = buffer.into();
+        self
+    }
+
+    /// Keep DTCs in `storage`, sending the active ones with DM1 every second
+    /// and on change.
+    pub fn with_dtc_storage(mut self, storage: impl Into<ManagedSlice<'a, DtcSlot>>) -> Self {
+        self.diagnostics = DiagnosticServer::new(storage);
+        self.dm1 = Some(Instant::from_millis(0));
+        self
+    }
+
+    /// Configuration of this node.
+    pub fn config(&self) -> &NodeConfig<'a> {
+        &self.config
+    }
+
+    /// Source address of this node.
+    ///
+    /// See [`AddressManager::source_address`].
+    pub fn address(&self) -> u8 {
+        self.address.source_address()
+    }
+
+    /// Address claiming state of this node.
+    pub fn address_manager(&self) -> &AddressManager {
+        &self.address
+    }
+
+    /// DTCs and lamps reported by this node.
+    pub fn diagnostics(&self) -> &DiagnosticServer<'a> {
+        &self.diagnostics
+    }
+
+    /// DTCs and lamps reported by this node, to update them.
+    pub fn diagnostics_mut(&mut self) -> &mut DiagnosticServer<'a> {
+        &mut self.diagnostics
+    }
+
+    /// Message completed by the last call to [`Node::poll`].
+    ///
+    /// Returns the PGN, the source address of the sender and the payload.
+    pub fn received(&self) -> Option<(Pgn, u8, &[u8])> {
+        let message = self.transport.completed()?;
+        Some((message.pgn, message.source, message.data))
+    }
+
+    /// Time at which [`Node::poll`] has to be called again even without an
+    /// incoming frame, if any.
+    pub fn deadline(&self) -> Option<Instant> {
+        let claimed = self.address.address().is_some();
+        [
+            self.address.deadline(),
+            self.transport.deadline(),
+            self.outbox.deadline(),
+            self.dm1.filter(|_| claimed),
+            self.periodic.deadline(self.config.periodic_table()),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+
+    /// Advance the node.
+    ///
+    /// `incoming` is the frame received since the last call, if any, and
+    /// `now` is the current time. The returned frames
+    /// must be sent on the bus.
+    pub fn poll(&mut self, incoming: Option<(Id, &[u8])>, now: Instant) -> Frames {
+        self.poll_with(incoming, now, &mut ())
+    }
+
+    /// Advance the node like [`Node::poll`], notifying `events`.
+    pub fn poll_with<E: Events>(
+        &mut self,
+        incoming: Option<(Id, &[u8])>,
+        now: Instant,
+        events: &mut E,
+    ) -> Frames {
+        let mut frames = Frames::default();
+        let claimed = self.address.address();
+        self.transport.release_completed();
+
+        frames.push(self.address.poll(now));
+        self.transport.set_address(self.address.source_address());
+
+        if let Some((id, data)) = incoming {
+            self.receive(id, data, now, events, &mut frames);
+        }
+
+        if let Some(address) = self.address.address() {
+            while !frames.is_full()
+                && let Some(frame) = self.transport.poll(now)
+            {
+                frames.push(Some(frame));
+            }
+            self.outbox.poll(address, now, &mut frames);
+            self.send_dm1(address, now, &mut frames);
+            self.send_periodic(address, now, events, &mut frames);
+        }
+
+        match (claimed, self.address.address()) {
+            (claimed, Some(address)) if claimed != Some(address) => {
+                events.on_address_claimed(address)
+            }
+            (Some(_), None) => events.on_address_lost(),
+            _ => {}
+        }
+
+        if let Some((pgn, sa, data)) = self.received() {
+            events.on_transfer_complete(pgn, sa, data.len());
+            events.on_message(pgn, data, sa);
+        }
+
+        if let Some((id, data)) = incoming {
+            let pgn = id.pgn();
+            let transport = matches!(
+                pgn,
+                Pgn::TransportProtocolConnectionManagement | Pgn::TransportProtocolDataTransfer
+            );
+            let address = self.address();
+            let addressed = id.da().is_none_or(|da| da == address || da == GLOBAL);
+
+            if !transport && addressed {
+                events.on_message(pgn, data, id.sa());
+            }
+        }
+
+        frames
+    }
+
+    fn receive<E: Events>(
+        &mut self,
+        id: Id,
+        data: &[u8],
+        now: Instant,
+        events: &mut E,
+        frames: &mut Frames,
+    ) {
+        let address = self.address.source_address();
+        if id.da().is_some_and(|da| da != address && da != GLOBAL) {
+            return;
+        }
+
+        frames.push(self.address.handle(id, data, now));
+        self.transport.set_address(self.address.source_address());
+        let Some(address) = self.address.address() else {
+            return;
+        };
+
+        match id.pgn() {
+            Pgn::TransportProtocolConnectionManagement => {
+                if id.da() == Some(address)
+                    && let Ok(msg) = ControlMessage::try_from(data)
+                {
+                    self.outbox.handle(address, id.sa(), &msg, now, frames);
+                }
+                frames.push(self.transport.handle(id, data, now));
+            }
+            Pgn::TransportProtocolDataTransfer => {
+                frames.push(self.transport.handle(id, data, now));
+            }
+            Pgn::Request => self.request(address, id, data, now, events, frames),
+            _ => {}
+        }
+    }
+
+    fn request<E: Events>(
+        &mut self,
+        address: u8,
+        id: Id,
+        data: &[u8],
+        now: Instant,
+        events: &mut E,
+        frames: &mut Frames,
+    ) {
+        let Ok(request) = Request::try_from(data) else {
+            return;
+        };
+        let (requester, pgn) = (id.sa(), request.pgn());
+        let specific = id.da() == Some(address);
+        let da = if specific { requester } else { GLOBAL };
+        let diagnostics = &mut self.diagnostics;
+
+        let control = match pgn {
+            // answered by the address manager
+            Pgn::AddressClaimed => return,
+            Pgn::ActiveDiagnosticTroubleCodes => {
+                let sent = self.outbox.send(address, da, pgn, 6, now, frames, |buf| {
+                    diagnostics.encode_active(buf)
+                });
+                if sent {
+                    return;
+                }
+                Control::CannotRespond
+            }
+            Pgn::PreviouslyActiveDiagnosticTroubleCodes => {
+                let sent = self.outbox.send(address, da, pgn, 6, now, frames, |buf| {
+                    diagnostics.encode_previously_active(buf)
+                });
+                if sent {
+                    return;
+                }
+                Control::CannotRespond
+            }
+            Pgn::DiagnosticDataClearPreviouslyActive => {
+                diagnostics.clear_previously_active(|dtc| events.on_dtc_changed(dtc, false));
+                Control::Ack
+            }
+            Pgn::DiagnosticDataClearActive => {
+                diagnostics.clear_active(|dtc| events.on_dtc_changed(dtc, false));
+                Control::Ack
+            }
+            pgn if self.config.supports_request(pgn) => {
+                let sent = self.outbox.send(address, da, pgn, 6, now, frames, |buf| {
+                    events.on_request(pgn, requester, buf)
+                });
+                if sent {
+                    return;
+                }
+                Control::CannotRespond
+            }
+            _ => Control::Nack,
+        };
+
+        // global requests are never acknowledged
+        if specific {
+            frames.push(Acknowledgement::new(control, 0xFF, requester, pgn).to_frame(address));
+        }
+    }
+
+    /// Send the DM1 message if due or changed.
+    fn send_dm1(&mut self, address: u8, now: Instant, frames: &mut Frames) {
+        let Some(due) = self.dm1 else {
+            return;
+        };
+        let changed = self.diagnostics.take_changed();
+        if !changed && now < due {
+            return;
+        }
+
+        let diagnostics = &self.diagnostics;
+        let pgn = Pgn::ActiveDiagnosticTroubleCodes;
+        let sent = self
+            .outbox
+            .send(address, GLOBAL, pgn, 6, now, frames, |buf| {
+                diagnostics.encode_active(buf)
+            });
+
+        // retried once the message in progress is sent
+        self.dm1 = Some(match self.outbox.deadline() {
+            Some(deadline) if !sent => deadline,
+            _ => now + DM1_INTERVAL,
+        });
+    }
+
+    /// Send the periodic entries that are due.
+    fn send_periodic<E: Events>(
+        &mut self,
+        address: u8,
+        now: Instant,
+        events: &mut E,
+        frames: &mut Frames,
+    ) {
+        let outbox = &mut self.outbox;
+        self.periodic
+            .poll(self.config.periodic_table(), now, |entry| {
+                if frames.is_full() {
+                    return false;
+                }
+                let pgn = entry.pgn;
+                outbox.send(address, GLOBAL, pgn, entry.priority, now, frames, |buf| {
+                    events.on_periodic(pgn, buf)
+                });
+                true
+            });
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<const N: usize> defmt::Format for Node<'_, N> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(
+            f,
+            "Node {{ address: {}, config: {}, sessions: {} }}",
+            self.address,
+            self.config,
+            self.transport.sessions()
+        )
+    }
+}
+
+/// Connection management frame from `sa` to `da`.
+fn tp_cm(sa: u8, da: u8) -> Option<Id> {
+    IdBuilder::new()
+        .priority(7)
+        .pgn(Pgn::TransportProtocolConnectionManagement)
+        .sa(sa)
+        .da(da)
+        .build()
+}
+
+/// Data transfer frame carrying `packet` from `sa` to `da`.
+fn tp_dt(sa: u8, da: u8, packet: &DataTransfer) -> Option<(Id, [u8; 8])> {
+    let id = IdBuilder::new()
+        .priority(7)
+        .pgn(Pgn::TransportProtocolDataTransfer)
+        .sa(sa)
+        .da(da)
+        .build()?;
+    Some((id, packet.into()))
+}
+
+/// Frames produced by a single [`Node::poll`].
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Frames {
+    frames: [Option<(Id, [u8; 8])>; 8],
+    index: usize,
+}
+
+impl Frames {
+    pub(crate) fn push(&mut self, frame: Option<(Id, [u8; 8])>) {
+        if let Some(slot) = self.frames.iter_mut().find(|f| f.is_none()) {
+            *slot = frame;
+        }
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
+        self.frames.iter().all(Option::is_some)
+    }
+}
+
+impl Iterator for Frames {
+    type Item = (Id, [u8; 8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame = self.frames.get_mut(self.index)?.take();
+        self.index += 1;
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::CLAIM_TIMEOUT;
+    use crate::diagnostic::Dtc;
+    use crate::transport::{
+        AbortReason, AbortSenderRole, BroadcastAnnounce, ClearToSend, ConnectionAbort,
+        EndOfMessageAck, RequestToSend, T2,
+    };
+
+    fn frame(pgn: Pgn, sa: u8, da: u8) -> Id {
+        IdBuilder::new()
+            .priority(7)
+            .pgn(pgn)
+            .sa(sa)
+            .da(da)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn receive_transport() {
+        let mut storage = [0; 32];
+        let mut node = Node::new(0x20, &mut storage[..]);
+
+        let cm = frame(Pgn::TransportProtocolConnectionManagement, 0x10, 0x20);
+        let dt = frame(Pgn::TransportProtocolDataTransfer, 0x10, 0x20);
+
+        let rts: [u8; 8] = RequestToSend::try_new(10, None, Pgn::ProprietaryA)
+            .unwrap()
+            .into();
+        let mut out = node.poll(Some((cm, &rts)), Instant::from_millis(0));
+        let (id, cts) = out.next().unwrap();
+        assert_eq!(id.da(), Some(0x10));
+        assert_eq!(id.sa(), 0x20);
+        assert_eq!(cts[..3], [17, 2, 1]);
+        assert!(out.next().is_none());
+
+        // frames for other nodes are ignored
+        let other = frame(Pgn::TransportProtocolDataTransfer, 0x10, 0x30);
+        assert!(
+            node.poll(Some((other, &[1; 8])), Instant::from_millis(10))
+                .next()
+                .is_none()
+        );
+
+        assert!(
+            node.poll(
+                Some((dt, &[1, 1, 2, 3, 4, 5, 6, 7])),
+                Instant::from_millis(20)
+            )
+            .next()
+            .is_none()
+        );
+        let mut out = node.poll(
+            Some((dt, &[2, 8, 9, 10, 0xFF, 0xFF, 0xFF, 0xFF])),
+            Instant::from_millis(30),
+        );
+        let (_, end) = out.next().unwrap();
+        assert_eq!(end[0], 19);
+
+        let (pgn, sa, data) = node.received().unwrap();
+        assert_eq!(pgn, Pgn::ProprietaryA);
+        assert_eq!(sa, 0x10);
+        assert_eq!(data, &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+
+        // storage is reused for the next session
+        assert!(node.poll(None, Instant::from_millis(40)).next().is_none());
+        assert!(node.received().is_none());
+        let mut out = node.poll(Some((cm, &rts)), Instant::from_millis(50));
+        assert_eq!(out.next().unwrap().1[0], 17);
+    }
+
+    #[test]
+    fn events() {
+        #[derive(Default)]
+        struct Recorder {
+            messages: usize,
+            transfers: usize,
+        }
+
+        impl Events for Recorder {
+            fn on_message(&mut self, _: Pgn, _: &[u8], _: u8) {
+                self.messages += 1;
+            }
+
+            fn on_transfer_complete(&mut self, pgn: Pgn, sa: u8, size: usize) {
+                assert_eq!((pgn, sa, size), (Pgn::ProprietaryA, 0x10, 9));
+                self.transfers += 1;
+            }
+        }
+
+        let mut events = Recorder::default();
+        let mut node = Node::new(0x20, std::vec![]);
+        let now = Instant::from_millis(0);
+
+        let cm = frame(Pgn::TransportProtocolConnectionManagement, 0x10, 0x20);
+        let dt = frame(Pgn::TransportProtocolDataTransfer, 0x10, 0x20);
+        let rts: [u8; 8] = RequestToSend::try_new(9, None, Pgn::ProprietaryA)
+            .unwrap()
+            .into();
+        node.poll_with(Some((cm, &rts)), now, &mut events);
+        node.poll_with(Some((dt, &[1; 8])), now, &mut events);
+        node.poll_with(Some((dt, &[2; 8])), now, &mut events);
+        assert_eq!((events.messages, events.transfers), (1, 1));
+
+        // addressed to another node
+        let other = frame(Pgn::ProprietaryA, 0x10, 0x30);
+        node.poll_with(Some((other, &[0; 8])), now, &mut events);
+        assert_eq!(events.messages, 1);
+
+        let own = frame(Pgn::ProprietaryA, 0x10, 0x20);
+        node.poll_with(Some((own, &[0; 8])), now, &mut events);
+        assert_eq!(events.messages, 2);
+    }
+
+    #[test]
+    fn config_limits() {
+        let mut storage = [0; 32];
+        let config = NodeConfig::new(Name::default(), 0x20)
+            .max_packets_per_cts(1)
+            .max_message_size(14);
+        let mut node = Node::with_config(config, &mut storage[..]).unwrap();
+        assert_eq!(node.address(), 0x20);
+        let (claim, _) = node.poll(None, Instant::from_millis(0)).next().unwrap();
+        assert_eq!(claim.pgn(), Pgn::AddressClaimed);
+
+        let cm = frame(Pgn::TransportProtocolConnectionManagement, 0x10, 0x20);
+        let dt = frame(Pgn::TransportProtocolDataTransfer, 0x10, 0x20);
+
+        let rts: [u8; 8] = RequestToSend::try_new(10, None, Pgn::ProprietaryA)
+            .unwrap()
+            .into();
+        // ignored until the address is claimed
+        assert!(
+            node.poll(Some((cm, &rts)), Instant::from_millis(0))
+                .next()
+                .is_none()
+        );
+
+        let start = Instant::from_millis(0) + CLAIM_TIMEOUT;
+        let (_, cts) = node.poll(Some((cm, &rts)), start).next().unwrap();
+        assert_eq!(cts[..3], [17, 1, 1]);
+
+        let (_, cts) = node
+            .poll(Some((dt, &[1; 8])), start + Duration::from_millis(10))
+            .next()
+            .unwrap();
+        assert_eq!(cts[..3], [17, 1, 2]);
+
+        let rts: [u8; 8] = RequestToSend::try_new(15, None, Pgn::ProprietaryA)
+            .unwrap()
+            .into();
+        let cm = frame(Pgn::TransportProtocolConnectionManagement, 0x10, 0x20);
+        let (_, abort) = node
+            .poll(Some((cm, &rts)), start + Duration::from_millis(20))
+            .next()
+            .unwrap();
+        assert_eq!(abort[..2], [255, 9]);
+
+        assert!(Node::with_config(NodeConfig::new(Name::default(), 0xFF), std::vec![]).is_err());
+    }
+
+    #[test]
+    fn reject_and_timeout() {
+        let mut storage = [0; 16];
+        let mut node = Node::new(0x20, &mut storage[..]);
+
+        let cm = frame(Pgn::TransportProtocolConnectionManagement, 0x10, 0x20);
+
+        // too large for storage
+        let rts: [u8; 8] = RequestToSend::try_new(20, None, Pgn::ProprietaryA)
+            .unwrap()
+            .into();
+        let (_, abort) = node
+            .poll(Some((cm, &rts)), Instant::from_millis(0))
+            .next()
+            .unwrap();
+        assert_eq!(abort[..2], [255, 2]);
+
+        // second peer while busy
+        let rts: [u8; 8] = RequestToSend::try_new(10, None, Pgn::ProprietaryA)
+            .unwrap()
+            .into();
+        assert_eq!(
+            node.poll(Some((cm, &rts)), Instant::from_millis(0))
+                .next()
+                .unwrap()
+                .1[0],
+            17
+        );
+        let cm2 = frame(Pgn::TransportProtocolConnectionManagement, 0x11, 0x20);
+        let (id, abort) = node
+            .poll(Some((cm2, &rts)), Instant::from_millis(10))
+            .next()
+            .unwrap();
+        assert_eq!(id.da(), Some(0x11));
+        assert_eq!(abort[..2], [255, 1]);
+
+        // peer goes silent
+        assert!(
+            node.poll(
+                None,
+                Instant::from_millis(0) + T2 - Duration::from_millis(1)
+            )
+            .next()
+            .is_none()
+        );
+        let (id, abort) = node
+            .poll(None, Instant::from_millis(0) + T2)
+            .next()
+            .unwrap();
+        assert_eq!(id.da(), Some(0x10));
+        assert_eq!(abort[..2], [255, 3]);
+        assert!(node.received().is_none());
+    }
+
+    #[test]
+    fn abort_by_peer() {
+        let mut storage = [0; 16];
+        let mut node = Node::new(0x20, &mut storage[..]);
+
+        let cm = frame(Pgn::TransportProtocolConnectionManagement, 0x10, 0x20);
+        let rts: [u8; 8] = RequestToSend::try_new(10, None, Pgn::ProprietaryA)
+            .unwrap()
+            .into();
+        assert_eq!(
+            node.poll(Some((cm, &rts)), Instant::from_millis(0)).count(),
+            1
+        );
+        assert!(node.deadline().is_some());
+
+        // an abort for another PGN leaves the session alone
+        let abort = |pgn| -> [u8; 8] {
+            (&ConnectionAbort::new(AbortReason::Timeout, AbortSenderRole::Sender, pgn)).into()
+        };
+        node.poll(
+            Some((cm, &abort(Pgn::ProprietaryA2))),
+            Instant::from_millis(10),
+        );
+        assert!(node.deadline().is_some());
+
+        node.poll(
+            Some((cm, &abort(Pgn::ProprietaryA))),
+            Instant::from_millis(20),
+        );
+        assert!(node.deadline().is_none());
+    }
+
+    #[test]
+    fn address_claim() {
+        let name = Name::new((1 << 63) | 0x100);
+        let config = NodeConfig::new(name, 0x80).address_range(0x80, 0x81);
+        let mut node = Node::with_config(config, std::vec![]).unwrap();
+        let now = Instant::from_millis(0);
+        assert_eq!(node.deadline(), Some(now));
+
+        let (id, data) = node.poll(None, now).next().unwrap();
+        assert_eq!((id.pgn(), id.sa()), (Pgn::AddressClaimed, 0x80));
+        assert_eq!(data, name.as_raw().to_le_bytes());
+        assert_eq!(node.deadline(), Some(now + CLAIM_TIMEOUT));
+        assert!(node.poll(None, now + CLAIM_TIMEOUT).next().is_none());
+        assert_eq!(node.address_manager().address(), Some(0x80));
+
+        // a lower NAME takes the address, transport sessions follow
+        let claim = frame(Pgn::AddressClaimed, 0x80, GLOBAL);
+        let (id, _) = node
+            .poll(Some((claim, &0x10u64.to_le_bytes())), now)
+            .next()
+            .unwrap();
+        assert_eq!(id.sa(), 0x81);
+        assert_eq!(node.address(), 0x81);
+
+        let later = now + CLAIM_TIMEOUT + CLAIM_TIMEOUT;
+        node.poll(None, later);
+        let cm = frame(Pgn::TransportProtocolConnectionManagement, 0x10, 0x81);
+        let rts = RequestToSend::try_new(9, None, Pgn::ProprietaryA).unwrap();
+        let (id, _) = node
+            .poll(Some((cm, &rts.to_bytes())), later)
+            .next()
+            .unwrap();
+        assert_eq!((id.sa(), id.da()), (0x81, Some(0x10)));
+    }
+
+    #[test]
+    fn address_and_dtc_events() {
+        #[derive(Default)]
+        struct Recorder {
+            claimed: std::vec::Vec<u8>,
+            lost: usize,
+            cleared: std::vec::Vec<Dtc>,
+        }
+
+        impl Events for Recorder {
+            fn on_address_claimed(&mut self, address: u8) {
+                self.claimed.push(address);
+            }
+
+            fn on_address_lost(&mut self) {
+                self.lost += 1;
+            }
+
+            fn on_dtc_changed(&mut self, dtc: Dtc, active: bool) {
+                assert!(!active);
+                self.cleared.push(dtc);
+            }
+        }
+
+        let name = Name::new((1 << 63) | 0x100);
+        let config = NodeConfig::new(name, 0x80).address_range(0x80, 0x81);
+        let mut node = Node::with_config(config, std::vec![])
+            .unwrap()
+            .with_dtc_storage(std::vec![DtcSlot::default(); 2]);
+        let mut events = Recorder::default();
+        let now = Instant::from_millis(0);
+
+        node.poll_with(None, now, &mut events);
+        node.poll_with(None, now + CLAIM_TIMEOUT, &mut events);
+        assert_eq!(events.claimed, [0x80]);
+
+        // losing the address, then claiming the next one
+        let claim = frame(Pgn::AddressClaimed, 0x80, GLOBAL);
+        node.poll_with(Some((claim, &0x10u64.to_le_bytes())), now, &mut events);
+        assert_eq!(events.lost, 1);
+        let later = now + CLAIM_TIMEOUT + CLAIM_TIMEOUT;
+        node.poll_with(None, later, &mut events);
+        assert_eq!(events.claimed, [0x80, 0x81]);
+
+        // DTCs cleared by DM3 are reported
+        let dtc = Dtc::try_new(190, 2, 1).unwrap();
+        node.diagnostics_mut().activate(dtc).unwrap();
+        node.diagnostics_mut().deactivate(190, 2).unwrap();
+        let (id, data) = Request::new(Pgn::DiagnosticDataClearPreviouslyActive)
+            .to_frame(0x10, Some(0x81))
+            .unwrap();
+        node.poll_with(Some((id, &data)), later, &mut events);
+        assert_eq!(events.cleared.len(), 1);
+        assert!(events.cleared[0].matches(&dtc));
+    }
+
+    #[test]
+    fn broadcast() {
+        let mut node = Node::new(0x20, std::vec![]);
+        let now = Instant::from_millis(0);
+
+        let cm = frame(Pgn::TransportProtocolConnectionManagement, 0x10, GLOBAL);
+        let dt = frame(Pgn::TransportProtocolDataTransfer, 0x10, GLOBAL);
+        let bam = BroadcastAnnounce::try_new(9, Pgn::ProprietaryB(1)).unwrap();
+        assert!(node.poll(Some((cm, &bam.to_bytes())), now).next().is_none());
+        node.poll(Some((dt, &[1, 1, 2, 3, 4, 5, 6, 7])), now);
+        assert!(node.received().is_none());
+        node.poll(Some((dt, &[2, 8, 9, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF])), now);
+
+        let (pgn, sa, data) = node.received().unwrap();
+        assert_eq!((pgn, sa), (Pgn::ProprietaryB(1), 0x10));
+        assert_eq!(data, &[1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn requests() {
+        let mut node =
+            Node::new(0x20, std::vec![]).with_dtc_storage(std::vec![DtcSlot::default(); 2]);
+        let now = Instant::from_millis(0);
+        let request = |pgn, da| Request::new(pgn).to_frame(0x10, Some(da)).unwrap();
+
+        // DM1 is due right away
+        let (id, data) = node.poll(None, now).next().unwrap();
+        assert_eq!(id.pgn(), Pgn::ActiveDiagnosticTroubleCodes);
+        assert_eq!(data, [0x00, 0xFF, 0, 0, 0, 0, 0xFF, 0xFF]);
+        assert_eq!(node.deadline(), Some(now + DM1_INTERVAL));
+
+        // and again on change
+        let dtc = Dtc::try_new(190, 2, 1).unwrap();
+        node.diagnostics_mut().activate(dtc).unwrap();
+        let (_, data) = node.poll(None, now).next().unwrap();
+        assert_eq!(data[2..6], [190, 0, 2, 1]);
+
+        let (id, data) = request(Pgn::PreviouslyActiveDiagnosticTroubleCodes, 0x20);
+        let (id, _) = node.poll(Some((id, &data)), now).next().unwrap();
+        assert_eq!(id.pgn(), Pgn::PreviouslyActiveDiagnosticTroubleCodes);
+
+        // DM11 clears the active DTCs
+        let (id, data) = request(Pgn::DiagnosticDataClearActive, 0x20);
+        let mut out = node.poll(Some((id, &data)), now);
+        let (id, data) = out.next().unwrap();
+        assert_eq!(id.pgn(), Pgn::Acknowledgement);
+        assert_eq!(
+            Acknowledgement::try_from(&data[..]).unwrap().control(),
+            Control::Ack
+        );
+        let (_, data) = out.next().unwrap();
+        assert_eq!(data[2..6], [0, 0, 0, 0]);
+
+        // unsupported
+        let (id, data) = request(Pgn::VehicleDistance, 0x20);
+        let (_, data) = node.poll(Some((id, &data)), now).next().unwrap();
+        assert_eq!(
+            Acknowledgement::try_from(&data[..]).unwrap().control(),
+            Control::Nack
+        );
+        let (id, data) = request(Pgn::VehicleDistance, GLOBAL);
+        assert!(node.poll(Some((id, &data)), now).next().is_none());
+        let (id, data) = request(Pgn::VehicleDistance, 0x30);
+        assert!(node.poll(Some((id, &data)), now).next().is_none());
+    }
+
+    #[test]
+    fn send_transport() {
+        let mut node = Node::new(0x20, std::vec![])
+            .with_dtc_storage(std::vec![DtcSlot::default(); 3])
+            .with_tx_buffer(std::vec![0; 32]);
+        let now = Instant::from_millis(0);
+        for spn in 1..=3 {
+            node.diagnostics_mut()
+                .activate(Dtc::try_new(spn, 1, 1).unwrap())
+                .unwrap();
+        }
+
+        // DM1 of 14 bytes broadcast
+        let (id, data) = node.poll(None, now).next().unwrap();
+        assert_eq!(
+            (id.pgn(), id.da()),
+            (Pgn::TransportProtocolConnectionManagement, Some(GLOBAL))
+        );
+        let bam = BroadcastAnnounce::try_from(&data[..]).unwrap();
+        assert_eq!(
+            (bam.pgn(), bam.total_size()),
+            (Pgn::ActiveDiagnosticTroubleCodes, 14)
+        );
+        let due = now + Duration::from_millis(50);
+        assert_eq!(node.deadline(), Some(due));
+        let (id, data) = node.poll(None, due).next().unwrap();
+        assert_eq!(id.pgn(), Pgn::TransportProtocolDataTransfer);
+        assert_eq!(data[..3], [1, 0x00, 0xFF]);
+
+        // requested DM1 waits for the broadcast, answered as busy
+        let (id, data) = Request::new(Pgn::ActiveDiagnosticTroubleCodes)
+            .to_frame(0x10, Some(0x20))
+            .unwrap();
+        let (_, ack) = node.poll(Some((id, &data)), due).next().unwrap();
+        assert_eq!(
+            Acknowledgement::try_from(&ack[..]).unwrap().control(),
+            Control::CannotRespond
+        );
+        let done = due + Duration::from_millis(50);
+        node.poll(None, done).next().unwrap();
+
+        // then sent through a session with the requester
+        let (rts_id, rts) = node.poll(Some((id, &data)), done).next().unwrap();
+        assert_eq!(rts_id.da(), Some(0x10));
+        assert_eq!(rts[..5], [16, 14, 0, 2, MAX_WINDOW]);
+
+        let cm = frame(Pgn::TransportProtocolConnectionManagement, 0x10, 0x20);
+        let cts = ClearToSend::new(Some(2), 1, Pgn::ActiveDiagnosticTroubleCodes);
+        let mut out = node.poll(Some((cm, &cts.to_bytes())), done);
+        assert_eq!(out.next().unwrap().1[0], 1);
+        assert_eq!(out.next().unwrap().1[0], 2);
+        assert!(out.next().is_none());
+
+        let end = EndOfMessageAck::new(14, 2, Pgn::ActiveDiagnosticTroubleCodes);
+        node.poll(Some((cm, &end.to_bytes())), done);
+        assert_eq!(node.deadline(), Some(now + DM1_INTERVAL));
+    }
+
+    #[test]
+    fn config_requests_and_periodic() {
+        struct App;
+
+        impl Events for App {
+            fn on_request(&mut self, pgn: Pgn, sa: u8, buf: &mut [u8]) -> Option<usize> {
+                assert_eq!((pgn, sa), (Pgn::SoftwareIdentification, 0x10));
+                buf.get_mut(..3)?.copy_from_slice(&[1, b'A', b'*']);
+                Some(3)
+            }
+
+            fn on_periodic(&mut self, pgn: Pgn, buf: &mut [u8]) -> Option<usize> {
+                (pgn == Pgn::VehicleDistance).then(|| {
+                    buf[..8].fill(0);
+                    8
+                })
+            }
+        }
+
+        let periodic = [
+            Periodic {
+                pgn: Pgn::VehicleDistance,
+                interval: Duration::from_millis(100),
+                priority: 6,
+            },
+            Periodic {
+                pgn: Pgn::TimeDate,
+                interval: Duration::from_millis(1000),
+                priority: 6,
+            },
+        ];
+        let requests = [Pgn::SoftwareIdentification];
+        let config = NodeConfig::new(Name::new(0x100), 0x20)
+            .periodic(&periodic)
+            .requests(&requests);
+        let mut node = Node::with_config(config, std::vec![]).unwrap();
+        let mut app = App;
+
+        // nothing periodic before the address is claimed
+        let start = Instant::from_millis(0);
+        assert_eq!(node.poll_with(None, start, &mut app).count(), 1);
+        let claimed = start + CLAIM_TIMEOUT;
+        let mut out = node.poll_with(None, claimed, &mut app);
+        let (id, _) = out.next().unwrap();
+        assert_eq!((id.pgn(), id.sa()), (Pgn::VehicleDistance, 0x20));
+        // skipped without data
+        assert!(out.next().is_none());
+
+        assert_eq!(node.deadline(), Some(claimed + Duration::from_millis(100)));
+        assert!(
+            node.poll_with(None, claimed + Duration::from_millis(99), &mut app)
+                .next()
+                .is_none()
+        );
+        assert_eq!(
+            node.poll_with(None, claimed + Duration::from_millis(150), &mut app)
+                .count(),
+            1
+        );
+
+        let (id, data) = Request::new(Pgn::SoftwareIdentification)
+            .to_frame(0x10, Some(0x20))
+            .unwrap();
+        let now = claimed + Duration::from_millis(160);
+        let (id, data) = node
+            .poll_with(Some((id, &data)), now, &mut app)
+            .next()
+            .unwrap();
+        assert_eq!(id.pgn(), Pgn::SoftwareIdentification);
+        assert_eq!(data, [1, b'A', b'*', 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+
+        // supported but not provided
+        let (id, data) = Request::new(Pgn::SoftwareIdentification)
+            .to_frame(0x10, Some(0x20))
+            .unwrap();
+        let (_, ack) = node.poll(Some((id, &data)), now).next().unwrap();
+        assert_eq!(
+            Acknowledgement::try_from(&ack[..]).unwrap().control(),
+            Control::CannotRespond
+        );
+    }
+}
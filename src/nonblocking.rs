@@ -0,0 +1,261 @@
+//! Async integration
+//!
+//! Drives a [`Node`] and [`Router`] from an async CAN driver and timer, for
+//! use with executors such as embassy.
+
+use crate::events::Events;
+use crate::id::Id;
+use crate::node::Node;
+use crate::payload::Payload;
+use crate::router::Router;
+use crate::time::{Clock, Instant};
+use core::future::{Future, poll_fn};
+use core::pin::pin;
+use core::task::Poll;
+
+/// Async CAN driver.
+///
+/// `embedded-can` only provides blocking and `nb` traits, so drivers
+/// implement this one on top of their own async API.
+pub trait Can {
+    type Error;
+
+    /// Transmit a frame.
+    fn transmit(&mut self, id: Id, data: &[u8]) -> impl Future<Output = Result<(), Self::Error>>;
+
+    /// Wait for the next received J1939 frame, classic or FD.
+    ///
+    /// The future must be cancel safe. [`run`] drops it when a node deadline
+    /// passes first, and a frame it already took from the hardware must then
+    /// be returned by the next call instead of being lost.
+    fn receive(&mut self) -> impl Future<Output = Result<(Id, Payload), Self::Error>>;
+}
+
+/// Async timer.
+pub trait Timer: Clock {
+    /// Wait until `deadline` has passed.
+    fn wait_until(&mut self, deadline: Instant) -> impl Future<Output = ()>;
+}
+
+/// Run the receive loop.
+///
+/// Received messages are delivered through `router` and `events`, and frames
+/// required by `node` are transmitted. `node` is polled on its deadline even
+/// when the bus is silent, dropping the pending [`Can::receive`], so that it
+/// claims its address, sends DM1 and the periodic messages provided by
+/// `events`, and aborts stalled sessions. Only returns on a driver error.
+pub async fn run<C: Can, T: Timer, E: Events, const N: usize>(
+    node: &mut Node<'_>,
+    router: &mut Router<'_, N>,
+    events: &mut E,
+    can: &mut C,
+    timer: &mut T,
+) -> C::Error {
+    loop {
+        let incoming = match node.deadline() {
+            Some(deadline) => {
+                let mut receive = pin!(can.receive());
+                let mut wait = pin!(timer.wait_until(deadline));
+                poll_fn(|cx| match receive.as_mut().poll(cx) {
+                    Poll::Ready(frame) => Poll::Ready(Some(frame)),
+                    Poll::Pending => wait.as_mut().poll(cx).map(|_| None),
+                })
+                .await
+            }
+            None => Some(can.receive().await),
+        };
+
+        let frame = match incoming.transpose() {
+            Ok(frame) => frame,
+            Err(e) => return e,
+        };
+
+        let frames = router.route_with(
+            node,
+            frame.as_ref().map(|(id, data)| (*id, data.as_slice())),
+            timer.now(),
+            events,
+        );
+        for (id, data) in frames {
+            if let Err(e) = can.transmit(id, &data).await {
+                return e;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::CLAIM_TIMEOUT;
+    use crate::config::{NodeConfig, Periodic};
+    use crate::id::{IdBuilder, Pgn};
+    use crate::name::Name;
+    use crate::router::Filter;
+    use crate::time::Duration;
+    use crate::transport::RequestToSend;
+    use core::task::{Context, Waker};
+    use std::collections::VecDeque;
+    use std::vec::Vec;
+
+    struct Bus {
+        rx: VecDeque<(Id, Payload)>,
+        tx: Vec<(Id, [u8; 8])>,
+        /// Frames to transmit before failing once `rx` is empty.
+        expected: usize,
+    }
+
+    impl Can for Bus {
+        type Error = ();
+
+        async fn transmit(&mut self, id: Id, data: &[u8]) -> Result<(), ()> {
+            self.tx.push((id, data.try_into().map_err(|_| ())?));
+            Ok(())
+        }
+
+        async fn receive(&mut self) -> Result<(Id, Payload), ()> {
+            match self.rx.pop_front() {
+                Some(frame) => Ok(frame),
+                None if self.tx.len() < self.expected => core::future::pending().await,
+                None => Err(()),
+            }
+        }
+    }
+
+    struct TestTimer(Instant);
+
+    impl Clock for TestTimer {
+        fn now(&self) -> Instant {
+            self.0
+        }
+    }
+
+    impl Timer for TestTimer {
+        async fn wait_until(&mut self, deadline: Instant) {
+            self.0 = self.0.max(deadline);
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn receive_loop() {
+        let mut received = 0;
+        let mut on_message = |_: u8, _: Pgn, data: &[u8]| received += data.len();
+        let mut router = Router::<1>::new();
+        assert!(
+            router
+                .register(Filter::Pgn(Pgn::ProprietaryA), &mut on_message)
+                .is_ok()
+        );
+
+        let mut storage = [0; 16];
+        let mut node = Node::new(0x20, &mut storage[..]);
+
+        let id = |pgn| {
+            IdBuilder::new()
+                .priority(7)
+                .pgn(pgn)
+                .sa(0x10)
+                .da(0x20)
+                .build()
+                .unwrap()
+        };
+        let mut bus = Bus {
+            rx: VecDeque::from([
+                (
+                    id(Pgn::TransportProtocolConnectionManagement),
+                    RequestToSend::try_new(9, None, Pgn::ProprietaryA)
+                        .unwrap()
+                        .to_bytes()
+                        .into(),
+                ),
+                (
+                    id(Pgn::TransportProtocolDataTransfer),
+                    [1, 1, 2, 3, 4, 5, 6, 7].into(),
+                ),
+                (
+                    id(Pgn::TransportProtocolDataTransfer),
+                    [2, 8, 9, 0, 0, 0, 0, 0].into(),
+                ),
+            ]),
+            tx: Vec::new(),
+            expected: 0,
+        };
+
+        block_on(run(
+            &mut node,
+            &mut router,
+            &mut (),
+            &mut bus,
+            &mut TestTimer(Instant::default()),
+        ));
+
+        // CTS and end of message acknowledgement
+        assert_eq!(bus.tx.len(), 2);
+        assert_eq!(bus.tx[0].1[0], 17);
+        assert_eq!(bus.tx[1].1[0], 19);
+        assert_eq!(received, 9);
+    }
+
+    #[test]
+    fn claim_and_periodic() {
+        struct App;
+
+        impl Events for App {
+            fn on_periodic(&mut self, _: Pgn, buf: &mut [u8]) -> Option<usize> {
+                buf.get_mut(..8)?.fill(0);
+                Some(8)
+            }
+        }
+
+        let mut received = 0;
+        let mut on_message = |_: u8, _: Pgn, data: &[u8]| received += data.len();
+        let mut router = Router::<1>::new();
+        assert!(
+            router
+                .register(Filter::Pgn(Pgn::ProprietaryB(1)), &mut on_message)
+                .is_ok()
+        );
+
+        let periodic = [Periodic {
+            pgn: Pgn::VehicleDistance,
+            interval: Duration::from_millis(100),
+            priority: 6,
+        }];
+        let config = NodeConfig::new(Name::new(0x100), 0x20).periodic(&periodic);
+        let mut node = Node::with_config(config, std::vec![]).unwrap();
+
+        let fd = IdBuilder::new()
+            .pgn(Pgn::ProprietaryB(1))
+            .sa(0x10)
+            .build()
+            .unwrap();
+        let mut bus = Bus {
+            rx: VecDeque::from([(fd, Payload::new(&[0; 64]).unwrap())]),
+            tx: Vec::new(),
+            expected: 3,
+        };
+        let mut timer = TestTimer(Instant::default());
+
+        block_on(run(&mut node, &mut router, &mut App, &mut bus, &mut timer));
+
+        assert_eq!(received, 64);
+        let (claim, first, second) = (bus.tx[0].0, bus.tx[1].0, bus.tx[2].0);
+        assert_eq!((claim.pgn(), claim.sa()), (Pgn::AddressClaimed, 0x20));
+        assert_eq!(first.pgn(), Pgn::VehicleDistance);
+        assert_eq!(second.pgn(), Pgn::VehicleDistance);
+        assert_eq!(
+            timer.now(),
+            Instant::default() + CLAIM_TIMEOUT + Duration::from_millis(100)
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! Classic and FD frame payloads
+//!
+//! [`Payload`] holds the data of either a classic CAN frame (up to 8 bytes)
+//! or a CAN FD frame (up to 64 bytes) so both can share one API.
+
+use crate::error::DecodeError;
+use core::ops::Deref;
+
+/// Largest CAN FD payload.
+pub const MAX_LEN: usize = 64;
+
+/// Payload lengths selectable by the data length code.
+const LENGTHS: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 12, 16, 20, 24, 32, 48, 64];
+
+/// Payload length of a data length code.
+///
+/// Returns `None` for codes above 15.
+pub fn dlc_to_len(dlc: u8) -> Option<usize> {
+    LENGTHS.get(dlc as usize).map(|&len| len as usize)
+}
+
+/// Smallest data length code that fits `len` bytes.
+///
+/// Returns `None` if `len` is larger than [`MAX_LEN`].
+pub fn len_to_dlc(len: usize) -> Option<u8> {
+    LENGTHS
+        .iter()
+        .position(|&l| l as usize >= len)
+        .map(|dlc| dlc as u8)
+}
+
+/// Payload of a classic or FD frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Payload {
+    data: [u8; MAX_LEN],
+    len: u8,
+}
+
+impl Payload {
+    /// Create a payload from `data`.
+    ///
+    /// `data` longer than 8 bytes is padded with `0xFF` up to the next length
+    /// a data length code can express. Returns `None` if `data` is larger
+    /// than [`MAX_LEN`].
+    pub fn new(data: &[u8]) -> Option<Self> {
+        let dlc = len_to_dlc(data.len())?;
+        let len = dlc_to_len(dlc)?;
+
+        let mut raw = [0xFF; MAX_LEN];
+        raw[..data.len()].copy_from_slice(data);
+
+        Some(Self {
+            data: raw,
+            len: len as u8,
+        })
+    }
+
+    /// Payload bytes, including padding.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+
+    /// Data length code.
+    pub fn dlc(&self) -> u8 {
+        len_to_dlc(self.len as usize).unwrap_or(15)
+    }
+
+    /// Check if this payload requires a CAN FD frame.
+    pub fn is_fd(&self) -> bool {
+        self.len > 8
+    }
+
+    /// Classic 8 byte payload, padded with `0xFF`.
+    ///
+    /// Returns `None` for FD payloads.
+    pub fn classic(&self) -> Option<[u8; 8]> {
+        if self.is_fd() {
+            return None;
+        }
+
+        let mut raw = [0xFF; 8];
+        raw[..self.len as usize].copy_from_slice(self.as_slice());
+        Some(raw)
+    }
+}
+
+impl Deref for Payload {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl AsRef<[u8]> for Payload {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl From<[u8; 8]> for Payload {
+    fn from(value: [u8; 8]) -> Self {
+        let mut data = [0xFF; MAX_LEN];
+        data[..8].copy_from_slice(&value);
+        Self { data, len: 8 }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for Payload {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Self::new(value).ok_or(DecodeError::Length {
+            expected: MAX_LEN,
+            actual: value.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dlc() {
+        assert_eq!(dlc_to_len(8), Some(8));
+        assert_eq!(dlc_to_len(9), Some(12));
+        assert_eq!(dlc_to_len(15), Some(64));
+        assert_eq!(dlc_to_len(16), None);
+        assert_eq!(len_to_dlc(9), Some(9));
+        assert_eq!(len_to_dlc(64), Some(15));
+        assert_eq!(len_to_dlc(65), None);
+    }
+
+    #[test]
+    fn payload() {
+        let classic = Payload::from([1; 8]);
+        assert!(!classic.is_fd());
+        assert_eq!(classic.classic(), Some([1; 8]));
+
+        let short = Payload::new(&[1, 2, 3]).unwrap();
+        assert_eq!(short.len(), 3);
+        assert_eq!(
+            short.classic(),
+            Some([1, 2, 3, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF])
+        );
+
+        let fd = Payload::new(&[0; 13]).unwrap();
+        assert!(fd.is_fd());
+        assert_eq!(fd.len(), 16);
+        assert_eq!(fd.dlc(), 10);
+        assert_eq!(fd[13..], [0xFF; 3]);
+        assert!(fd.classic().is_none());
+
+        assert!(Payload::try_from([0; 65].as_ref()).is_err());
+    }
+}
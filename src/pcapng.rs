@@ -0,0 +1,179 @@
+//! pcapng capture files
+//!
+//! Writes frames with the SocketCAN link type, so captures can be opened in
+//! Wireshark and cross-checked with its J1939 dissector.
+
+use crate::id::Id;
+use std::io::{self, Write};
+use std::time::Duration;
+use std::vec::Vec;
+
+/// `LINKTYPE_CAN_SOCKETCAN`
+const LINKTYPE: u16 = 227;
+
+const SECTION_HEADER: u32 = 0x0A0D_0D0A;
+const INTERFACE_DESCRIPTION: u32 = 0x0000_0001;
+const ENHANCED_PACKET: u32 = 0x0000_0006;
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+
+/// `if_name` interface option.
+const OPTION_NAME: u16 = 2;
+
+/// Extended frame format flag of SocketCAN identifiers.
+const CAN_EFF_FLAG: u32 = 0x8000_0000;
+/// CAN FD frame flag of SocketCAN frames.
+const CANFD_FDF: u8 = 0x04;
+
+/// Writes frames as a pcapng capture.
+#[derive(Debug)]
+pub struct Writer<W> {
+    inner: W,
+}
+
+impl<W: Write> Writer<W> {
+    /// Create a new writer recording frames as received on `interface`.
+    ///
+    /// Writes the section header and interface description.
+    pub fn new(mut inner: W, interface: &str) -> io::Result<Self> {
+        let mut shb = Vec::new();
+        shb.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+        shb.extend_from_slice(&1u16.to_le_bytes());
+        shb.extend_from_slice(&0u16.to_le_bytes());
+        // section length not specified
+        shb.extend_from_slice(&(-1i64).to_le_bytes());
+        block(&mut inner, SECTION_HEADER, &shb)?;
+
+        let mut idb = Vec::new();
+        idb.extend_from_slice(&LINKTYPE.to_le_bytes());
+        idb.extend_from_slice(&0u16.to_le_bytes());
+        // no snapshot length limit
+        idb.extend_from_slice(&0u32.to_le_bytes());
+        option(&mut idb, OPTION_NAME, interface.as_bytes())?;
+        // end of options
+        idb.extend_from_slice(&[0; 4]);
+        block(&mut inner, INTERFACE_DESCRIPTION, &idb)?;
+
+        Ok(Self { inner })
+    }
+
+    /// Write a frame.
+    ///
+    /// Payloads longer than 8 bytes are written as CAN FD frames. Timestamps
+    /// are recorded in microseconds.
+    pub fn write(&mut self, timestamp: Duration, id: Id, data: &[u8]) -> io::Result<()> {
+        if data.len() > crate::payload::MAX_LEN {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+
+        let fd = data.len() > 8;
+        let mut frame = Vec::with_capacity(72);
+        frame.extend_from_slice(&(id.as_raw() | CAN_EFF_FLAG).to_be_bytes());
+        frame.push(data.len() as u8);
+        frame.push(if fd { CANFD_FDF } else { 0 });
+        frame.extend_from_slice(&[0; 2]);
+        frame.extend_from_slice(data);
+        frame.resize(if fd { 72 } else { 16 }, 0);
+
+        let micros = timestamp.as_micros() as u64;
+        let mut epb = Vec::with_capacity(20 + frame.len());
+        // interface id
+        epb.extend_from_slice(&0u32.to_le_bytes());
+        epb.extend_from_slice(&((micros >> 32) as u32).to_le_bytes());
+        epb.extend_from_slice(&(micros as u32).to_le_bytes());
+        epb.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        epb.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        epb.extend_from_slice(&frame);
+
+        block(&mut self.inner, ENHANCED_PACKET, &epb)
+    }
+
+    /// Unwrap the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Append an option, padded to 32 bits.
+fn option(out: &mut Vec<u8>, code: u16, value: &[u8]) -> io::Result<()> {
+    let len =
+        u16::try_from(value.len()).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+    out.extend_from_slice(&code.to_le_bytes());
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(value);
+    out.resize(out.len().next_multiple_of(4), 0);
+    Ok(())
+}
+
+/// Write a block with `body` padded to 32 bits.
+fn block<W: Write>(out: &mut W, kind: u32, body: &[u8]) -> io::Result<()> {
+    let padded = body.len().next_multiple_of(4);
+    let len = (padded + 12) as u32;
+
+    out.write_all(&kind.to_le_bytes())?;
+    out.write_all(&len.to_le_bytes())?;
+    out.write_all(body)?;
+    out.write_all(&[0; 3][..padded - body.len()])?;
+    out.write_all(&len.to_le_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u32_at(buf: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(buf[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn capture() {
+        let mut writer = Writer::new(Vec::new(), "can0").unwrap();
+        writer
+            .write(
+                Duration::from_micros(0x1_0000_0002),
+                Id::new(0x18EF5500),
+                &[1, 2, 3],
+            )
+            .unwrap();
+        let buf = writer.into_inner();
+
+        // section header
+        assert_eq!(u32_at(&buf, 0), SECTION_HEADER);
+        assert_eq!(u32_at(&buf, 4), 28);
+        assert_eq!(u32_at(&buf, 8), BYTE_ORDER_MAGIC);
+        assert_eq!(u32_at(&buf, 24), 28);
+
+        // interface description with name option
+        assert_eq!(u32_at(&buf, 28), INTERFACE_DESCRIPTION);
+        assert_eq!(u32_at(&buf, 32), 32);
+        assert_eq!(buf[36..38], LINKTYPE.to_le_bytes());
+        assert_eq!(&buf[48..52], b"can0");
+
+        // enhanced packet
+        let epb = 60;
+        assert_eq!(u32_at(&buf, epb), ENHANCED_PACKET);
+        assert_eq!(u32_at(&buf, epb + 4), 48);
+        assert_eq!(u32_at(&buf, epb + 12), 1);
+        assert_eq!(u32_at(&buf, epb + 16), 2);
+        assert_eq!(u32_at(&buf, epb + 20), 16);
+        assert_eq!(buf[epb + 28..epb + 32], [0x98, 0xEF, 0x55, 0x00]);
+        assert_eq!(buf[epb + 32], 3);
+        assert_eq!(buf[epb + 36..epb + 44], [1, 2, 3, 0, 0, 0, 0, 0]);
+        assert_eq!(u32_at(&buf, epb + 44), 48);
+        assert_eq!(buf.len(), epb + 48);
+    }
+
+    #[test]
+    fn fd() {
+        let mut writer = Writer::new(Vec::new(), "can0").unwrap();
+        writer
+            .write(Duration::ZERO, Id::new(0x18EF5500), &[0; 12])
+            .unwrap();
+        let buf = writer.into_inner();
+
+        assert_eq!(u32_at(&buf, 60 + 20), 72);
+        assert_eq!(buf[60 + 28 + 5], CANFD_FDF);
+
+        let mut writer = Writer::new(Vec::new(), "can0").unwrap();
+        assert!(writer.write(Duration::ZERO, Id::new(0), &[0; 65]).is_err());
+    }
+}
@@ -1,2 +1,3 @@
 pub use crate::signal::Signal as _j1939_signal_Signal;
 pub use crate::slot::Slot as _j1939_slot_Slot;
+pub use crate::time::Clock as _j1939_time_Clock;
@@ -0,0 +1,341 @@
+//! Proprietary messages (J1939-21)
+
+use crate::transport::RequestToSend;
+use crate::{Id, IdBuilder, Pgn};
+
+/// Global destination address.
+const GLOBAL: u8 = 0xFF;
+
+/// Largest payload that can be sent using the transport protocol.
+const MAX_SIZE: usize = 1785;
+
+/// PropA - Proprietary A
+///
+/// Destination specific proprietary message with a manufacturer defined
+/// payload of 1 to 1785 bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ProprietaryA<'a> {
+    da: u8,
+    data: &'a [u8],
+}
+
+impl<'a> ProprietaryA<'a> {
+    /// Create a new proprietary A message for `da`.
+    ///
+    /// Returns `None` if `data` is empty or longer than 1785 bytes.
+    pub fn new(da: u8, data: &'a [u8]) -> Option<Self> {
+        if data.is_empty() || data.len() > MAX_SIZE {
+            return None;
+        }
+
+        Some(Self { da, data })
+    }
+
+    /// Validate a received message addressed to `address`.
+    ///
+    /// `id` is the identifier of the single frame, or of the transport protocol
+    /// frames when `data` is a reassembled payload. Returns `None` if the PGN
+    /// is not proprietary A or the message is addressed to another node.
+    pub fn receive(id: Id, data: &'a [u8], address: u8) -> Option<Self> {
+        if id.pgn() != Pgn::ProprietaryA {
+            return None;
+        }
+
+        let da = id.da()?;
+        if da != address && da != GLOBAL {
+            return None;
+        }
+
+        Self::new(da, data)
+    }
+
+    /// Destination address.
+    pub fn da(&self) -> u8 {
+        self.da
+    }
+
+    /// Payload.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Frame or transport session required to send this message from `sa`.
+    ///
+    /// Returns `None` for global messages longer than 8 bytes which require a
+    /// broadcast transfer.
+    pub fn outgoing(&self, sa: u8) -> Option<Outgoing<'a>> {
+        if self.data.len() <= 8 {
+            let id = IdBuilder::new()
+                .pgn(Pgn::ProprietaryA)
+                .sa(sa)
+                .da(self.da)
+                .build()?;
+            return Some(Outgoing::Single(id, self.data));
+        }
+
+        if self.da == GLOBAL {
+            return None;
+        }
+
+        let id = IdBuilder::new()
+            .priority(7)
+            .pgn(Pgn::TransportProtocolConnectionManagement)
+            .sa(sa)
+            .da(self.da)
+            .build()?;
+        let len = u16::try_from(self.data.len()).ok()?;
+        let rts = RequestToSend::try_new(len, None, Pgn::ProprietaryA).ok()?;
+
+        Some(Outgoing::Transport(id, rts))
+    }
+}
+
+/// PropB - Proprietary B
+///
+/// Broadcast proprietary message keyed by the group extension (GE) of the PGN.
+/// Covers both [`Pgn::ProprietaryB`] and [`Pgn::ProprietaryB2`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ProprietaryB<'a> {
+    pgn: Pgn,
+    data: &'a [u8],
+}
+
+impl<'a> ProprietaryB<'a> {
+    /// Create a new proprietary B message.
+    ///
+    /// Returns `None` if `pgn` is not a proprietary B PGN or `data` is empty
+    /// or longer than 1785 bytes.
+    pub fn new(pgn: Pgn, data: &'a [u8]) -> Option<Self> {
+        if !matches!(pgn, Pgn::ProprietaryB(_) | Pgn::ProprietaryB2(_)) {
+            return None;
+        }
+
+        if data.is_empty() || data.len() > MAX_SIZE {
+            return None;
+        }
+
+        Some(Self { pgn, data })
+    }
+
+    /// Parse a received message.
+    ///
+    /// `id` is the identifier of the single frame, or of the transport protocol
+    /// frames when `data` is a reassembled payload.
+    pub fn receive(id: Id, data: &'a [u8]) -> Option<Self> {
+        Self::new(id.pgn(), data)
+    }
+
+    /// PGN, either [`Pgn::ProprietaryB`] or [`Pgn::ProprietaryB2`].
+    pub fn pgn(&self) -> Pgn {
+        self.pgn
+    }
+
+    /// Group extension.
+    pub fn ge(&self) -> u8 {
+        match self.pgn {
+            Pgn::ProprietaryB(ge) | Pgn::ProprietaryB2(ge) => ge,
+            // checked on construction
+            _ => 0,
+        }
+    }
+
+    /// Payload.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Frame required to send this message from `sa`.
+    ///
+    /// Returns `None` for messages longer than 8 bytes which require a
+    /// broadcast transfer.
+    pub fn outgoing(&self, sa: u8) -> Option<Outgoing<'a>> {
+        if self.data.len() > 8 {
+            return None;
+        }
+
+        let id = IdBuilder::new().pgn(self.pgn).sa(sa).build()?;
+        Some(Outgoing::Single(id, self.data))
+    }
+}
+
+/// Proprietary B message handler.
+///
+/// Called with the source address and the received message.
+pub type Handler<'a> = &'a mut dyn FnMut(u8, &ProprietaryB<'_>);
+
+/// Routes received proprietary B messages to handlers by group extension.
+///
+/// Holds up to `N` handlers without allocating.
+pub struct Dispatcher<'a, const N: usize> {
+    handlers: [Option<(Pgn, Handler<'a>)>; N],
+}
+
+impl<'a, const N: usize> Dispatcher<'a, N> {
+    /// Create a new empty dispatcher.
+    pub fn new() -> Self {
+        Self {
+            handlers: [const { None }; N],
+        }
+    }
+
+    /// Register a handler for a proprietary B PGN.
+    ///
+    /// Replaces any existing handler for the same PGN. Returns the handler if
+    /// `pgn` is not a proprietary B PGN or the dispatcher is full.
+    pub fn register(&mut self, pgn: Pgn, handler: Handler<'a>) -> Result<(), Handler<'a>> {
+        if !matches!(pgn, Pgn::ProprietaryB(_) | Pgn::ProprietaryB2(_)) {
+            return Err(handler);
+        }
+
+        let slot = match self
+            .handlers
+            .iter()
+            .position(|h| matches!(h, Some((p, _)) if *p == pgn))
+        {
+            Some(index) => &mut self.handlers[index],
+            None => match self.handlers.iter_mut().find(|h| h.is_none()) {
+                Some(slot) => slot,
+                None => return Err(handler),
+            },
+        };
+
+        *slot = Some((pgn, handler));
+        Ok(())
+    }
+
+    /// Dispatch a received message to its handler.
+    ///
+    /// Returns `true` if a handler was found for the message.
+    pub fn dispatch(&mut self, id: Id, data: &[u8]) -> bool {
+        let Some(msg) = ProprietaryB::receive(id, data) else {
+            return false;
+        };
+
+        for (pgn, handler) in self.handlers.iter_mut().flatten() {
+            if *pgn == msg.pgn() {
+                handler(id.sa(), &msg);
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+impl<const N: usize> Default for Dispatcher<'_, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How an outgoing message is to be sent.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Outgoing<'a> {
+    /// Fits into a single frame.
+    Single(Id, &'a [u8]),
+    /// Requires the transport protocol, started by sending the request to send.
+    Transport(Id, RequestToSend),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proprietary_a_single() {
+        let msg = ProprietaryA::new(0x55, &[1, 2, 3]).unwrap();
+
+        let Some(Outgoing::Single(id, data)) = msg.outgoing(0x00) else {
+            panic!("Expected single frame");
+        };
+        assert_eq!(id, Id::new(2565821696));
+        assert_eq!(data, &[1, 2, 3]);
+    }
+
+    #[test]
+    fn proprietary_a_transport() {
+        let data = [0xAA; 64];
+        let msg = ProprietaryA::new(0x55, &data).unwrap();
+
+        let Some(Outgoing::Transport(id, rts)) = msg.outgoing(0x00) else {
+            panic!("Expected transport");
+        };
+        assert_eq!(id.pgn(), Pgn::TransportProtocolConnectionManagement);
+        assert_eq!(id.da(), Some(0x55));
+        assert_eq!(rts.total_size(), 64);
+        assert_eq!(rts.pgn(), Pgn::ProprietaryA);
+
+        // global multi-packet messages require broadcast
+        let msg = ProprietaryA::new(GLOBAL, &data).unwrap();
+        assert!(msg.outgoing(0x00).is_none());
+    }
+
+    #[test]
+    fn proprietary_a_receive() {
+        let id = Id::new(2565821696);
+
+        let msg = ProprietaryA::receive(id, &[1, 2, 3], 0x55).unwrap();
+        assert_eq!(msg.da(), 0x55);
+        assert_eq!(msg.data(), &[1, 2, 3]);
+
+        assert!(ProprietaryA::receive(id, &[1, 2, 3], 0x56).is_none());
+        assert!(ProprietaryA::receive(id, &[], 0x55).is_none());
+        assert!(ProprietaryA::receive(Id::new(0x18EA55F9), &[1, 2, 3], 0x55).is_none());
+    }
+
+    #[test]
+    fn proprietary_a_size() {
+        assert!(ProprietaryA::new(0x55, &[]).is_none());
+        assert!(ProprietaryA::new(0x55, &[0; MAX_SIZE]).is_some());
+        assert!(ProprietaryA::new(0x55, &[0; MAX_SIZE + 1]).is_none());
+    }
+
+    #[test]
+    fn proprietary_b() {
+        let msg = ProprietaryB::new(Pgn::ProprietaryB(0x10), &[1, 2, 3]).unwrap();
+        assert_eq!(msg.ge(), 0x10);
+
+        let Some(Outgoing::Single(id, _)) = msg.outgoing(0x80) else {
+            panic!("Expected single frame");
+        };
+        assert_eq!(id, Id::new(0x18FF1080));
+        assert_eq!(id.ge(), Some(0x10));
+
+        let msg = ProprietaryB::new(Pgn::ProprietaryB2(0x10), &[1, 2, 3]).unwrap();
+        let Some(Outgoing::Single(id, _)) = msg.outgoing(0x80) else {
+            panic!("Expected single frame");
+        };
+        assert_eq!(id, Id::new(0x19FF1080));
+        assert!(id.dp());
+
+        assert!(ProprietaryB::new(Pgn::ProprietaryA, &[1, 2, 3]).is_none());
+    }
+
+    #[test]
+    fn proprietary_b_dispatch() {
+        let mut received = None;
+        let mut handler = |sa: u8, msg: &ProprietaryB<'_>| received = Some((sa, msg.ge()));
+        let mut ignored = |_: u8, _: &ProprietaryB<'_>| {};
+
+        let mut dispatcher = Dispatcher::<1>::new();
+        assert!(
+            dispatcher
+                .register(Pgn::ProprietaryB(0x10), &mut handler)
+                .is_ok()
+        );
+        assert!(
+            dispatcher
+                .register(Pgn::ProprietaryB(0x20), &mut ignored)
+                .is_err()
+        );
+
+        assert!(!dispatcher.dispatch(Id::new(0x18FF2080), &[1, 2, 3]));
+        assert!(!dispatcher.dispatch(Id::new(0x19FF1080), &[1, 2, 3]));
+        assert!(dispatcher.dispatch(Id::new(0x18FF1080), &[1, 2, 3]));
+
+        assert_eq!(received, Some((0x80, 0x10)));
+    }
+}
@@ -0,0 +1,679 @@
+//! Requests (J1939-21)
+
+use crate::error::{DecodeError, at_least, exact};
+use crate::signal::Discrete;
+use crate::transport::{BroadcastTransfer, OutgoingTransfer};
+use crate::{Id, IdBuilder, Pgn};
+use core::hash::{Hash, Hasher};
+
+/// Global destination address.
+const GLOBAL: u8 = 0xFF;
+
+/// RQST - Request
+///
+/// Requests the transmission of a PGN from a specific or all nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Request {
+    pgn: Pgn,
+}
+
+impl Request {
+    /// Create a new request for a PGN.
+    pub fn new(pgn: Pgn) -> Self {
+        Self { pgn }
+    }
+
+    /// Requested PGN.
+    pub fn pgn(&self) -> Pgn {
+        self.pgn
+    }
+
+    /// Identifier and data for sending this request from `sa`.
+    ///
+    /// A `da` of `None` issues a global request.
+    pub fn to_frame(&self, sa: u8, da: Option<u8>) -> Option<(Id, [u8; 3])> {
+        let id = IdBuilder::new()
+            .pgn(Pgn::Request)
+            .sa(sa)
+            .da(da.unwrap_or(GLOBAL))
+            .build()?;
+
+        Some((id, self.into()))
+    }
+}
+
+impl From<&Request> for [u8; 3] {
+    fn from(value: &Request) -> Self {
+        let pgn = u32::from(value.pgn).to_le_bytes();
+        [pgn[0], pgn[1], pgn[2]]
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for Request {
+    type Error = DecodeError;
+
+    /// Parse a request.
+    ///
+    /// Requests are 3 bytes long but padded requests up to 8 bytes are
+    /// accepted.
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        if !(3..=8).contains(&value.len()) {
+            return Err(DecodeError::Length {
+                expected: value.len().clamp(3, 8),
+                actual: value.len(),
+            });
+        }
+
+        Ok(Self {
+            pgn: Pgn::from(u32::from_le_bytes([value[0], value[1], value[2], 0x00])),
+        })
+    }
+}
+
+/// RQST2 - Request 2
+///
+/// Like [`Request`] but allows the requester to ask for the response to be
+/// sent using the [`Transfer`] PGN.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Request2 {
+    raw: [u8; 8],
+}
+
+impl Request2 {
+    /// Create a new request 2 for a PGN.
+    pub fn new(pgn: Pgn, use_transfer: bool) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0..3].copy_from_slice(&u32::from(pgn).to_le_bytes()[..3]);
+        raw[3] = 0b11111100 | use_transfer as u8;
+
+        Self { raw }
+    }
+
+    /// Requested PGN.
+    pub fn pgn(&self) -> Pgn {
+        Pgn::from(u32::from_le_bytes([
+            self.raw[0],
+            self.raw[1],
+            self.raw[2],
+            0x00,
+        ]))
+    }
+
+    /// Use transfer mode.
+    ///
+    /// Enabled when the response should be sent using the [`Transfer`] PGN.
+    pub fn use_transfer(&self) -> Discrete {
+        Discrete::try_from(self.raw[3] & 0b11).unwrap_or(Discrete::NotAvailable)
+    }
+
+    /// Identifier and data for sending this request from `sa`.
+    ///
+    /// A `da` of `None` issues a global request.
+    pub fn to_frame(&self, sa: u8, da: Option<u8>) -> Option<(Id, [u8; 8])> {
+        let id = IdBuilder::new()
+            .pgn(Pgn::Request2)
+            .sa(sa)
+            .da(da.unwrap_or(GLOBAL))
+            .build()?;
+
+        Some((id, self.raw))
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&Request2> for [u8; 8] {
+    fn from(value: &Request2) -> Self {
+        value.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for Request2 {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+/// XFER - Transfer
+///
+/// Carries the data of another PGN in response to a [`Request2`]. Usually sent
+/// as a transport protocol payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Transfer<'a> {
+    raw: &'a [u8],
+}
+
+impl<'a> Transfer<'a> {
+    /// Encode the data of `pgn` into `buf`.
+    ///
+    /// Returns `None` if `buf` is too small.
+    pub fn encode(pgn: Pgn, data: &[u8], buf: &'a mut [u8]) -> Option<Self> {
+        let len = data.len() + 3;
+
+        buf.get_mut(..3)?
+            .copy_from_slice(&u32::from(pgn).to_le_bytes()[..3]);
+        buf.get_mut(3..len)?.copy_from_slice(data);
+
+        Some(Self { raw: &buf[..len] })
+    }
+
+    /// Transferred PGN.
+    pub fn pgn(&self) -> Pgn {
+        Pgn::from(u32::from_le_bytes([
+            self.raw[0],
+            self.raw[1],
+            self.raw[2],
+            0x00,
+        ]))
+    }
+
+    /// Data of the transferred PGN.
+    pub fn data(&self) -> &'a [u8] {
+        &self.raw[3..]
+    }
+
+    /// Raw message bytes.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for Transfer<'a> {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: at_least(value, 3)?,
+        })
+    }
+}
+
+/// ACKM - Acknowledgement
+///
+/// Positive or negative acknowledgement of a request or command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Acknowledgement {
+    raw: [u8; 8],
+}
+
+impl Acknowledgement {
+    /// Create a new acknowledgement of `pgn` on behalf of the node at `address`.
+    pub fn new(control: Control, group_function: u8, address: u8, pgn: Pgn) -> Self {
+        let mut raw = [0xFF; 8];
+
+        raw[0] = control.into();
+        raw[1] = group_function;
+        raw[4] = address;
+        raw[5..8].copy_from_slice(&u32::from(pgn).to_le_bytes()[..3]);
+
+        Self { raw }
+    }
+
+    /// Control byte.
+    pub fn control(&self) -> Control {
+        Control::from(self.raw[0])
+    }
+
+    /// Group function value, if the acknowledged PGN has one.
+    pub fn group_function(&self) -> u8 {
+        self.raw[1]
+    }
+
+    /// Address of the node being acknowledged.
+    pub fn address(&self) -> u8 {
+        self.raw[4]
+    }
+
+    /// Acknowledged PGN.
+    pub fn pgn(&self) -> Pgn {
+        Pgn::from(u32::from_le_bytes([
+            self.raw[5],
+            self.raw[6],
+            self.raw[7],
+            0x00,
+        ]))
+    }
+
+    /// Identifier and data for sending this acknowledgement from `sa`.
+    ///
+    /// Acknowledgements are always sent to the global address.
+    pub fn to_frame(&self, sa: u8) -> Option<(Id, [u8; 8])> {
+        let id = IdBuilder::new()
+            .pgn(Pgn::Acknowledgement)
+            .sa(sa)
+            .da(GLOBAL)
+            .build()?;
+
+        Some((id, self.raw))
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&Acknowledgement> for [u8; 8] {
+    fn from(value: &Acknowledgement) -> Self {
+        value.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for Acknowledgement {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+/// Acknowledgement control byte.
+#[derive(Debug, Clone, Copy, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Control {
+    /// Positive acknowledgement.
+    Ack,
+    /// Negative acknowledgement.
+    Nack,
+    /// Access denied.
+    AccessDenied,
+    /// Cannot respond.
+    CannotRespond,
+    Other(u8),
+}
+
+impl PartialEq for Control {
+    fn eq(&self, other: &Self) -> bool {
+        // Cast to underlying value to compare
+        u8::from(*self) == u8::from(*other)
+    }
+}
+
+impl Hash for Control {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        u8::from(*self).hash(state);
+    }
+}
+
+impl From<Control> for u8 {
+    fn from(value: Control) -> Self {
+        match value {
+            Control::Ack => 0,
+            Control::Nack => 1,
+            Control::AccessDenied => 2,
+            Control::CannotRespond => 3,
+            Control::Other(o) => o,
+        }
+    }
+}
+
+impl From<u8> for Control {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Ack,
+            1 => Self::Nack,
+            2 => Self::AccessDenied,
+            3 => Self::CannotRespond,
+            o => Self::Other(o),
+        }
+    }
+}
+
+/// Request provider called with the address of the requester and a buffer
+/// for the response.
+///
+/// Returns the length of the response, or `None` if it cannot be provided
+/// right now.
+pub type Provider<'a> = &'a mut dyn FnMut(u8, &mut [u8]) -> Option<usize>;
+
+/// Answers received requests from registered providers.
+///
+/// Follows the J1939-21 rules: requests for unsupported PGNs are negatively
+/// acknowledged when sent to this node specifically and ignored when sent to
+/// the global address. Holds up to `N` providers without allocating.
+pub struct Responder<'a, const N: usize> {
+    providers: [Option<(Pgn, Provider<'a>)>; N],
+}
+
+impl<'a, const N: usize> Responder<'a, N> {
+    /// Create a new responder without any providers.
+    pub fn new() -> Self {
+        Self {
+            providers: [const { None }; N],
+        }
+    }
+
+    /// Register a provider for `pgn`.
+    ///
+    /// Replaces any existing provider for the same PGN. Returns the provider
+    /// if the responder is full.
+    pub fn register(&mut self, pgn: Pgn, provider: Provider<'a>) -> Result<(), Provider<'a>> {
+        let slot = match self
+            .providers
+            .iter()
+            .position(|p| matches!(p, Some((p, _)) if *p == pgn))
+        {
+            Some(index) => &mut self.providers[index],
+            None => match self.providers.iter_mut().find(|p| p.is_none()) {
+                Some(slot) => slot,
+                None => return Err(provider),
+            },
+        };
+
+        *slot = Some((pgn, provider));
+        Ok(())
+    }
+
+    /// Check if a provider is registered for `pgn`.
+    pub fn supports(&self, pgn: Pgn) -> bool {
+        self.providers.iter().flatten().any(|(p, _)| *p == pgn)
+    }
+
+    /// Handle a received frame on behalf of the node at `address`.
+    ///
+    /// Returns `None` if the frame is not a request for this node or the
+    /// request should be ignored. Responses are written to `buf`, those longer
+    /// than 8 bytes are returned as a transport session to drive.
+    pub fn handle<'b>(
+        &mut self,
+        address: u8,
+        id: Id,
+        data: &[u8],
+        buf: &'b mut [u8],
+    ) -> Option<Reply<'b>> {
+        if id.pgn() != Pgn::Request {
+            return None;
+        }
+
+        let da = id.da()?;
+        if da != address && da != GLOBAL {
+            return None;
+        }
+
+        let requester = id.sa();
+        let pgn = Request::try_from(data).ok()?.pgn();
+
+        let len = self
+            .providers
+            .iter_mut()
+            .flatten()
+            .find(|(p, _)| *p == pgn)
+            .map(|(_, provider)| provider(requester, buf));
+
+        let control = match len {
+            Some(Some(len)) if len <= buf.len() => {
+                let da = if da == GLOBAL { GLOBAL } else { requester };
+                match respond(address, da, pgn, &buf[..len]) {
+                    Some(reply) => return Some(reply),
+                    None => Control::CannotRespond,
+                }
+            }
+            Some(_) => Control::CannotRespond,
+            None => Control::Nack,
+        };
+
+        if da == GLOBAL {
+            return None;
+        }
+
+        Some(Reply::Acknowledgement(Acknowledgement::new(
+            control, 0xFF, requester, pgn,
+        )))
+    }
+}
+
+impl<const N: usize> Default for Responder<'_, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Response from `address` to `da` carrying `data` as `pgn`.
+///
+/// Returns `None` if the data is too long for the transport protocol.
+fn respond(address: u8, da: u8, pgn: Pgn, data: &[u8]) -> Option<Reply<'_>> {
+    if data.len() <= 8 {
+        let id = IdBuilder::new().pgn(pgn).sa(address).da(da).build()?;
+        return Some(Reply::Response(id, data));
+    }
+
+    let id = IdBuilder::new()
+        .priority(7)
+        .pgn(Pgn::TransportProtocolConnectionManagement)
+        .sa(address)
+        .da(da)
+        .build()?;
+
+    if da == GLOBAL {
+        let transfer = BroadcastTransfer::new(data, pgn).ok()?;
+        Some(Reply::Broadcast(id, transfer))
+    } else {
+        let transfer = OutgoingTransfer::new(data, None, pgn).ok()?;
+        Some(Reply::Transport(id, transfer))
+    }
+}
+
+/// Outcome of handling a request.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Reply<'a> {
+    /// Requested data of up to 8 bytes with its identifier.
+    Response(Id, &'a [u8]),
+    /// Requested data for the requester, sent with the transport protocol.
+    ///
+    /// The identifier is the one of the connection management frames, starting
+    /// with [`OutgoingTransfer::rts`].
+    Transport(Id, OutgoingTransfer<'a>),
+    /// Requested data for a global request, sent with a broadcast transfer.
+    ///
+    /// The identifier is the one of the connection management frames, starting
+    /// with [`BroadcastTransfer::announce`].
+    Broadcast(Id, BroadcastTransfer<'a>),
+    /// Acknowledgement to send using [`Acknowledgement::to_frame`].
+    Acknowledgement(Acknowledgement),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request() {
+        let raw: &[u8] = &[0xEC, 0xFE, 0x00];
+
+        let rq = Request::try_from(raw).unwrap();
+        assert_eq!(rq.pgn(), Pgn::VehicleIdentification);
+
+        let bytes: [u8; 3] = (&rq).into();
+        assert_eq!(raw, bytes);
+
+        // padded
+        let raw: &[u8] = &[0xEC, 0xFE, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        assert!(Request::try_from(raw).is_ok());
+        assert!(Request::try_from(&raw[..2]).is_err());
+    }
+
+    #[test]
+    fn request_frame() {
+        let rq = Request::new(Pgn::VehicleIdentification);
+
+        let (id, _) = rq.to_frame(0xF9, None).unwrap();
+        assert_eq!(id, Id::new(0x18EAFFF9));
+        assert_eq!(id.pgn(), Pgn::Request);
+        assert_eq!(id.da(), Some(0xFF));
+
+        let (id, data) = rq.to_frame(0xF9, Some(0x00)).unwrap();
+        assert_eq!(id, Id::new(0x18EA00F9));
+        assert_eq!(data, [0xEC, 0xFE, 0x00]);
+    }
+
+    #[test]
+    fn request2() {
+        let raw: &[u8] = &[0xEC, 0xFE, 0x00, 0xFD, 0xFF, 0xFF, 0xFF, 0xFF];
+
+        let rq = Request2::try_from(raw).unwrap();
+        assert_eq!(rq.pgn(), Pgn::VehicleIdentification);
+        assert_eq!(rq.use_transfer(), Discrete::Enabled);
+
+        let (id, data) = Request2::new(Pgn::VehicleIdentification, true)
+            .to_frame(0xF9, Some(0x00))
+            .unwrap();
+        assert_eq!(id, Id::new(0x18C900F9));
+        assert_eq!(data, raw);
+    }
+
+    #[test]
+    fn transfer() {
+        let raw: &[u8] = &[0xEC, 0xFE, 0x00, b'A', b'B', b'C', b'*'];
+
+        let xfer = Transfer::try_from(raw).unwrap();
+        assert_eq!(xfer.pgn(), Pgn::VehicleIdentification);
+        assert_eq!(xfer.data(), b"ABC*");
+
+        let mut buf = [0; 16];
+        let xfer = Transfer::encode(Pgn::VehicleIdentification, b"ABC*", &mut buf).unwrap();
+        assert_eq!(xfer.as_bytes(), raw);
+
+        let mut buf = [0; 4];
+        assert!(Transfer::encode(Pgn::VehicleIdentification, b"ABC*", &mut buf).is_none());
+    }
+
+    #[test]
+    fn acknowledgement() {
+        let raw: &[u8] = &[0x01, 0xFF, 0xFF, 0xFF, 0xF9, 0xEC, 0xFE, 0x00];
+
+        let ack = Acknowledgement::try_from(raw).unwrap();
+        assert_eq!(ack.control(), Control::Nack);
+        assert_eq!(ack.address(), 0xF9);
+        assert_eq!(ack.pgn(), Pgn::VehicleIdentification);
+
+        let (id, data) =
+            Acknowledgement::new(Control::Nack, 0xFF, 0xF9, Pgn::VehicleIdentification)
+                .to_frame(0x00)
+                .unwrap();
+        assert_eq!(id, Id::new(0x18E8FF00));
+        assert_eq!(data, raw);
+    }
+
+    #[test]
+    fn responder() {
+        let mut provide_vi = |_: u8, buf: &mut [u8]| {
+            buf.get_mut(..4)?.copy_from_slice(b"ABC*");
+            Some(4)
+        };
+        let mut busy = |_: u8, _: &mut [u8]| None;
+
+        let mut responder = Responder::<2>::new();
+        assert!(
+            responder
+                .register(Pgn::VehicleIdentification, &mut provide_vi)
+                .is_ok()
+        );
+        assert!(responder.register(Pgn::TimeDate, &mut busy).is_ok());
+        assert!(responder.supports(Pgn::TimeDate));
+
+        let mut buf = [0; 8];
+        let request = |pgn, da| Request::new(pgn).to_frame(0xF9, da).unwrap();
+
+        // specific request is answered
+        let (id, data) = request(Pgn::VehicleIdentification, Some(0x00));
+        let reply = responder.handle(0x00, id, &data, &mut buf);
+        let Some(Reply::Response(id, data)) = reply else {
+            panic!("Expected response");
+        };
+        assert_eq!(id.pgn(), Pgn::VehicleIdentification);
+        assert_eq!(id.sa(), 0x00);
+        assert_eq!(data, b"ABC*");
+
+        // requests for other nodes are ignored
+        let (id, data) = request(Pgn::VehicleIdentification, Some(0x01));
+        assert!(responder.handle(0x00, id, &data, &mut buf).is_none());
+
+        // unsupported specific request is negatively acknowledged
+        let (id, data) = request(Pgn::VehicleDistance, Some(0x00));
+        let reply = responder.handle(0x00, id, &data, &mut buf);
+        let Some(Reply::Acknowledgement(ack)) = reply else {
+            panic!("Expected acknowledgement");
+        };
+        assert_eq!(ack.control(), Control::Nack);
+        assert_eq!(ack.address(), 0xF9);
+        assert_eq!(ack.pgn(), Pgn::VehicleDistance);
+
+        // provider unable to respond
+        let (id, data) = request(Pgn::TimeDate, Some(0x00));
+        let reply = responder.handle(0x00, id, &data, &mut buf);
+        assert!(
+            matches!(reply, Some(Reply::Acknowledgement(ack)) if ack.control() == Control::CannotRespond)
+        );
+
+        // unsupported global request is ignored
+        let (id, data) = request(Pgn::VehicleDistance, None);
+        assert!(responder.handle(0x00, id, &data, &mut buf).is_none());
+    }
+
+    #[test]
+    fn responder_transport() {
+        let mut provide = |_: u8, buf: &mut [u8]| {
+            buf.get_mut(..20)?.fill(0xAA);
+            Some(20)
+        };
+        let mut responder = Responder::<1>::new();
+        assert!(
+            responder
+                .register(Pgn::VehicleIdentification, &mut provide)
+                .is_ok()
+        );
+
+        let mut buf = [0; 32];
+        let (id, data) = Request::new(Pgn::VehicleIdentification)
+            .to_frame(0xF9, Some(0x00))
+            .unwrap();
+        let Some(Reply::Transport(id, transfer)) = responder.handle(0x00, id, &data, &mut buf)
+        else {
+            panic!("Expected transport session");
+        };
+        assert_eq!(id.pgn(), Pgn::TransportProtocolConnectionManagement);
+        assert_eq!((id.sa(), id.da()), (0x00, Some(0xF9)));
+        assert_eq!(transfer.rts().total_size(), 20);
+        assert_eq!(transfer.rts().pgn(), Pgn::VehicleIdentification);
+
+        let (id, data) = Request::new(Pgn::VehicleIdentification)
+            .to_frame(0xF9, None)
+            .unwrap();
+        let Some(Reply::Broadcast(id, mut transfer)) = responder.handle(0x00, id, &data, &mut buf)
+        else {
+            panic!("Expected broadcast transfer");
+        };
+        assert_eq!(id.da(), Some(0xFF));
+        let bam = transfer.announce(crate::time::Instant::from_millis(0));
+        assert_eq!(bam.total_size(), 20);
+
+        // too small a buffer cannot respond
+        let mut buf = [0; 8];
+        let (id, data) = Request::new(Pgn::VehicleIdentification)
+            .to_frame(0xF9, Some(0x00))
+            .unwrap();
+        let reply = responder.handle(0x00, id, &data, &mut buf);
+        assert!(
+            matches!(reply, Some(Reply::Acknowledgement(ack)) if ack.control() == Control::CannotRespond)
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! PGN based message routing
+//!
+//! [`Router`] delivers received messages to handlers registered per PGN or
+//! PGN range, diverting transport protocol traffic through a [`Node`] so that
+//! handlers only ever see complete messages.
+
+use crate::events::Events;
+use crate::id::{Id, Pgn};
+use crate::node::{Frames, Node};
+use crate::time::Instant;
+
+const GLOBAL: u8 = 0xFF;
+
+/// Message handler called with the source address, PGN and payload.
+pub type Handler<'a> = &'a mut dyn FnMut(u8, Pgn, &[u8]);
+
+/// Selects the messages delivered to a handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Filter {
+    /// A single PGN.
+    Pgn(Pgn),
+    /// An inclusive range of raw PGN values.
+    Range(u32, u32),
+}
+
+impl Filter {
+    /// Check if `pgn` is selected by this filter.
+    pub fn matches(&self, pgn: Pgn) -> bool {
+        match self {
+            Filter::Pgn(p) => *p == pgn,
+            Filter::Range(start, end) => (*start..=*end).contains(&u32::from(pgn)),
+        }
+    }
+}
+
+/// Routes received messages to handlers by PGN.
+///
+/// Holds up to `N` handlers without allocating. A message is delivered to
+/// every handler whose filter matches, in registration order.
+pub struct Router<'a, const N: usize> {
+    handlers: [Option<(Filter, Handler<'a>)>; N],
+}
+
+impl<'a, const N: usize> Router<'a, N> {
+    /// Create a new empty router.
+    pub fn new() -> Self {
+        Self {
+            handlers: [const { None }; N],
+        }
+    }
+
+    /// Register a handler.
+    ///
+    /// Returns the handler if the router is full.
+    pub fn register(&mut self, filter: Filter, handler: Handler<'a>) -> Result<(), Handler<'a>> {
+        match self.handlers.iter_mut().find(|h| h.is_none()) {
+            Some(slot) => {
+                *slot = Some((filter, handler));
+                Ok(())
+            }
+            None => Err(handler),
+        }
+    }
+
+    /// Deliver a complete message to the matching handlers.
+    ///
+    /// Returns `true` if at least one handler was called.
+    pub fn dispatch(&mut self, sa: u8, pgn: Pgn, data: &[u8]) -> bool {
+        let mut handled = false;
+
+        for (filter, handler) in self.handlers.iter_mut().flatten() {
+            if filter.matches(pgn) {
+                handler(sa, pgn, data);
+                handled = true;
+            }
+        }
+
+        handled
+    }
+
+    /// Route a received frame.
+    ///
+    /// Transport protocol frames are passed to `node` and the reassembled
+    /// message is dispatched once complete. Other frames are dispatched
+    /// directly unless addressed to another node. Returns the frames `node`
+    /// needs to send.
+    pub fn route(
+        &mut self,
+        node: &mut Node<'_>,
+        incoming: Option<(Id, &[u8])>,
+        now: Instant,
+    ) -> Frames {
+        self.route_with(node, incoming, now, &mut ())
+    }
+
+    /// Route a received frame, polling `node` with `events`.
+    ///
+    /// Like [`Router::route`], with `events` notified by the node and
+    /// providing its requested and periodic messages.
+    pub fn route_with<E: Events>(
+        &mut self,
+        node: &mut Node<'_>,
+        incoming: Option<(Id, &[u8])>,
+        now: Instant,
+        events: &mut E,
+    ) -> Frames {
+        let frames = node.poll_with(incoming, now, events);
+
+        if let Some((pgn, sa, data)) = node.received() {
+            self.dispatch(sa, pgn, data);
+        }
+
+        if let Some((id, data)) = incoming {
+            let pgn = id.pgn();
+            let transport = matches!(
+                pgn,
+                Pgn::TransportProtocolConnectionManagement | Pgn::TransportProtocolDataTransfer
+            );
+            let addressed = id
+                .da()
+                .is_none_or(|da| da == node.address() || da == GLOBAL);
+
+            if !transport && addressed {
+                self.dispatch(id.sa(), pgn, data);
+            }
+        }
+
+        frames
+    }
+}
+
+impl<const N: usize> Default for Router<'_, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::IdBuilder;
+    use crate::transport::RequestToSend;
+
+    #[test]
+    fn filter() {
+        assert!(Filter::Pgn(Pgn::TimeDate).matches(Pgn::TimeDate));
+        assert!(!Filter::Pgn(Pgn::TimeDate).matches(Pgn::VehicleDistance));
+        assert!(Filter::Range(0xFF00, 0xFFFF).matches(Pgn::ProprietaryB(0x10)));
+        assert!(!Filter::Range(0xFF00, 0xFFFF).matches(Pgn::ProprietaryA));
+    }
+
+    #[test]
+    fn route() {
+        let mut single = 0;
+        let mut multi = [0; 16];
+        let mut on_td = |_: u8, _: Pgn, _: &[u8]| single += 1;
+        let mut on_prop_a = |_: u8, _: Pgn, data: &[u8]| multi[..data.len()].copy_from_slice(data);
+
+        let mut router = Router::<2>::new();
+        assert!(
+            router
+                .register(Filter::Pgn(Pgn::TimeDate), &mut on_td)
+                .is_ok()
+        );
+        assert!(
+            router
+                .register(Filter::Pgn(Pgn::ProprietaryA), &mut on_prop_a)
+                .is_ok()
+        );
+
+        let mut storage = [0; 16];
+        let mut node = Node::new(0x20, &mut storage[..]);
+
+        let td = IdBuilder::new()
+            .pgn(Pgn::TimeDate)
+            .sa(0x10)
+            .build()
+            .unwrap();
+        assert!(
+            router
+                .route(&mut node, Some((td, &[0; 8])), Instant::from_millis(0))
+                .next()
+                .is_none()
+        );
+
+        let id = |pgn| {
+            IdBuilder::new()
+                .priority(7)
+                .pgn(pgn)
+                .sa(0x10)
+                .da(0x20)
+                .build()
+                .unwrap()
+        };
+        let cm = id(Pgn::TransportProtocolConnectionManagement);
+        let dt = id(Pgn::TransportProtocolDataTransfer);
+
+        let rts: [u8; 8] = RequestToSend::try_new(9, None, Pgn::ProprietaryA)
+            .unwrap()
+            .into();
+        assert!(
+            router
+                .route(&mut node, Some((cm, &rts)), Instant::from_millis(0))
+                .next()
+                .is_some()
+        );
+        router.route(
+            &mut node,
+            Some((dt, &[1, 1, 2, 3, 4, 5, 6, 7])),
+            Instant::from_millis(10),
+        );
+        router.route(
+            &mut node,
+            Some((dt, &[2, 8, 9, 0, 0, 0, 0, 0])),
+            Instant::from_millis(20),
+        );
+
+        assert_eq!(single, 1);
+        assert_eq!(multi[..10], [1, 2, 3, 4, 5, 6, 7, 8, 9, 0]);
+    }
+}
@@ -43,7 +43,8 @@ macro_rules! signal_impl {
     ($type:ident, $base:ty, $valid:pat, $indicator:pat, $error:pat, $not_present:pat) => {
         /// Parameter signal.
         #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-        #[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
         pub struct $type($base);
 
         impl Signal for $type {
@@ -181,7 +182,8 @@ signal_impl!(
 
 /// Discrete parameter
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Discrete {
     Disabled = 0b00,
     Enabled = 0b01,
@@ -189,29 +191,42 @@ pub enum Discrete {
     NotAvailable = 0b11,
 }
 
+impl Discrete {
+    /// Decode the two bit value, `None` if it is out of range.
+    pub const fn from_raw(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Disabled),
+            1 => Some(Self::Enabled),
+            2 => Some(Self::ErrorIndicator),
+            3 => Some(Self::NotAvailable),
+            _ => None,
+        }
+    }
+
+    /// Two bit value.
+    pub const fn to_raw(self) -> u8 {
+        self as u8
+    }
+}
+
 impl TryFrom<u8> for Discrete {
     type Error = u8;
 
     fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            x if x == Self::Disabled as u8 => Ok(Self::Disabled),
-            x if x == Self::Enabled as u8 => Ok(Self::Enabled),
-            x if x == Self::ErrorIndicator as u8 => Ok(Self::ErrorIndicator),
-            x if x == Self::NotAvailable as u8 => Ok(Self::NotAvailable),
-            _ => Err(value),
-        }
+        Self::from_raw(value).ok_or(value)
     }
 }
 
 impl From<Discrete> for u8 {
     fn from(value: Discrete) -> Self {
-        value as u8
+        value.to_raw()
     }
 }
 
 /// Control command
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Command {
     Disable = 0b00,
     Enable = 0b01,
@@ -219,23 +234,35 @@ pub enum Command {
     NoAction = 0b11,
 }
 
+impl Command {
+    /// Decode the two bit value, `None` if it is out of range.
+    pub const fn from_raw(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Disable),
+            1 => Some(Self::Enable),
+            2 => Some(Self::Reserved),
+            3 => Some(Self::NoAction),
+            _ => None,
+        }
+    }
+
+    /// Two bit value.
+    pub const fn to_raw(self) -> u8 {
+        self as u8
+    }
+}
+
 impl TryFrom<u8> for Command {
     type Error = u8;
 
     fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            x if x == Self::Disable as u8 => Ok(Self::Disable),
-            x if x == Self::Enable as u8 => Ok(Self::Enable),
-            x if x == Self::Reserved as u8 => Ok(Self::Reserved),
-            x if x == Self::NoAction as u8 => Ok(Self::NoAction),
-            _ => Err(value),
-        }
+        Self::from_raw(value).ok_or(value)
     }
 }
 
 impl From<Command> for u8 {
     fn from(value: Command) -> Self {
-        value as u8
+        value.to_raw()
     }
 }
 
@@ -271,4 +298,12 @@ mod tests {
         assert_eq!(Param4::from_raw(0xA).unwrap().value(), Some(0xA));
         assert_eq!(Param4::from_raw(0xF).unwrap().value(), None);
     }
+
+    #[test]
+    fn const_discrete() {
+        const ENABLED: u8 = Discrete::Enabled.to_raw();
+        assert_eq!(Discrete::from_raw(ENABLED), Some(Discrete::Enabled));
+        assert_eq!(Command::try_from(3), Ok(Command::NoAction));
+        assert_eq!(Command::try_from(4), Err(4));
+    }
 }
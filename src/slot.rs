@@ -1,6 +1,6 @@
 //! Scaling, limit, offset, and transfer functions (J1939-73)
 
-use crate::signal::{Param8, Param16, Signal};
+use crate::signal::{Param8, Param16, Param32, Signal};
 use num::{FromPrimitive, cast::AsPrimitive};
 
 pub trait Slot<T: Signal>: Sized {
@@ -34,6 +34,156 @@ pub trait Slot<T: Signal>: Sized {
     }
 }
 
+/// Serialize `slot` as the newtype struct `name` around its parameter.
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub fn serialize_slot<S, T, P>(
+    name: &'static str,
+    slot: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    T: Slot<P>,
+    P: Signal + serde::Serialize,
+{
+    serializer.serialize_newtype_struct(name, &slot.parameter())
+}
+
+/// Deserialize a slot serialized by [`serialize_slot`].
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub fn deserialize_slot<'de, D, T, P>(name: &'static str, deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Slot<P>,
+    P: Signal + serde::Deserialize<'de>,
+{
+    use core::marker::PhantomData;
+    use serde::de::{self, SeqAccess, Visitor};
+
+    struct SlotVisitor<T, P>(PhantomData<(T, P)>);
+
+    impl<'de, T: Slot<P>, P: Signal + serde::Deserialize<'de>> Visitor<'de> for SlotVisitor<T, P> {
+        type Value = T;
+
+        fn expecting(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.write_str("a slot parameter")
+        }
+
+        fn visit_newtype_struct<D: serde::Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<T, D::Error> {
+            P::deserialize(deserializer).map(T::new)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<T, A::Error> {
+            let parameter = seq
+                .next_element::<P>()?
+                .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+            Ok(T::new(parameter))
+        }
+    }
+
+    deserializer.deserialize_newtype_struct(name, SlotVisitor(PhantomData))
+}
+
+/// Implement `defmt::Format` for a slot if this crate is built with the
+/// `defmt` feature, independent of the features of the expanding crate.
+#[cfg(feature = "defmt")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __slot_format {
+    ($type:ident) => {
+        impl $crate::__private::defmt::Format for $type {
+            fn format(&self, f: $crate::__private::defmt::Formatter<'_>) {
+                $crate::__private::defmt::Format::format(&self.0, f)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "defmt"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __slot_format {
+    ($type:ident) => {};
+}
+
+/// Implement `ufmt::uDebug` and `ufmt::uDisplay` for a slot if this crate
+/// is built with the `ufmt` feature.
+#[cfg(feature = "ufmt")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __slot_ufmt {
+    ($type:ident, $param:ident) => {
+        impl $crate::__private::ufmt::uDebug for $type {
+            fn fmt<W: $crate::__private::ufmt::uWrite + ?Sized>(
+                &self,
+                f: &mut $crate::__private::ufmt::Formatter<'_, W>,
+            ) -> Result<(), W::Error> {
+                f.write_str(stringify!($type))?;
+                f.write_str("(")?;
+                $crate::__private::ufmt::uDebug::fmt(&self.0, f)?;
+                f.write_str(")")
+            }
+        }
+
+        impl $crate::__private::ufmt::uDisplay for $type {
+            fn fmt<W: $crate::__private::ufmt::uWrite + ?Sized>(
+                &self,
+                f: &mut $crate::__private::ufmt::Formatter<'_, W>,
+            ) -> Result<(), W::Error> {
+                $crate::__private::fmt_slot::<$param, _, _>(self, f)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "ufmt"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __slot_ufmt {
+    ($type:ident, $param:ident) => {};
+}
+
+/// Implement `serde::Serialize` and `serde::Deserialize` for a slot if this
+/// crate is built with the `serde` feature, as a newtype like the derives.
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __slot_serde {
+    ($type:ident, $param:ident) => {
+        impl $crate::__private::serde::Serialize for $type {
+            fn serialize<S: $crate::__private::serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                $crate::__private::serialize_slot(stringify!($type), self, serializer)
+            }
+        }
+
+        impl<'de> $crate::__private::serde::Deserialize<'de> for $type {
+            fn deserialize<D: $crate::__private::serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<Self, D::Error> {
+                $crate::__private::deserialize_slot::<D, Self, $param>(
+                    stringify!($type),
+                    deserializer,
+                )
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "serde"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __slot_serde {
+    ($type:ident, $param:ident) => {};
+}
+
 #[macro_export]
 macro_rules! slot_impl {
     ($type:ident, $param:ident, $offset:expr, $scale:expr, $unit:expr, $comment:expr) => {
@@ -41,6 +191,10 @@ macro_rules! slot_impl {
         #[derive(Debug, Clone, Copy, PartialEq, Eq)]
         pub struct $type($param);
 
+        $crate::__slot_format!($type);
+        $crate::__slot_ufmt!($type, $param);
+        $crate::__slot_serde!($type, $param);
+
         impl Slot<$param> for $type {
             const UNIT: &str = $unit;
             const OFFSET: f32 = $offset;
@@ -82,6 +236,183 @@ slot_impl!(
     "V",
     "Voltage - 0.001 V per bit"
 );
+slot_impl!(
+    SaeCN01,
+    Param8,
+    0.0,
+    1.0,
+    "count",
+    "Count - 1 count per bit"
+);
+slot_impl!(
+    SaeCN02,
+    Param16,
+    0.0,
+    1.0,
+    "count",
+    "Count - 1 count per bit"
+);
+slot_impl!(
+    SaeCN04,
+    Param32,
+    0.0,
+    1.0,
+    "count",
+    "Count - 1 count per bit"
+);
+slot_impl!(SaeRA01, Param16, 0.0, 0.001, "", "Ratio - 0.001 per bit");
+slot_impl!(SaePC01, Param8, 0.0, 1.0, "%", "Percent - 1 % per bit");
+slot_impl!(SaePC03, Param8, 0.0, 0.4, "%", "Percent - 0.4 % per bit");
+slot_impl!(
+    SaePC05,
+    Param8,
+    -125.0,
+    1.0,
+    "%",
+    "Percent - 1 % per bit, -125 % offset"
+);
+slot_impl!(
+    SaeAV01,
+    Param16,
+    0.0,
+    0.125,
+    "rpm",
+    "Angular velocity - 0.125 rpm per bit"
+);
+slot_impl!(SaePR01, Param8, 0.0, 4.0, "kPa", "Pressure - 4 kPa per bit");
+slot_impl!(SaePR02, Param8, 0.0, 2.0, "kPa", "Pressure - 2 kPa per bit");
+slot_impl!(
+    SaePR03,
+    Param8,
+    0.0,
+    0.05,
+    "kPa",
+    "Pressure - 0.05 kPa per bit"
+);
+slot_impl!(
+    SaePR04,
+    Param16,
+    -250.0,
+    0.0078125,
+    "kPa",
+    "Pressure - 1/128 kPa per bit, -250 kPa offset"
+);
+slot_impl!(
+    SaeFR01,
+    Param16,
+    0.0,
+    0.05,
+    "L/h",
+    "Flow rate - 0.05 L/h per bit"
+);
+slot_impl!(
+    SaeFE01,
+    Param16,
+    0.0,
+    0.001953125,
+    "km/L",
+    "Fuel economy - 1/512 km/L per bit"
+);
+slot_impl!(SaeVL01, Param32, 0.0, 0.5, "L", "Volume - 0.5 L per bit");
+slot_impl!(
+    SaeEC01,
+    Param8,
+    -125.0,
+    1.0,
+    "A",
+    "Current - 1 A per bit, -125 A offset"
+);
+slot_impl!(SaeEC02, Param8, 0.0, 1.0, "A", "Current - 1 A per bit");
+slot_impl!(SaeEV01, Param16, 0.0, 0.05, "V", "Voltage - 0.05 V per bit");
+slot_impl!(SaeTM01, Param32, 0.0, 0.05, "h", "Time - 0.05 h per bit");
+slot_impl!(
+    SaeCN05,
+    Param32,
+    0.0,
+    1000.0,
+    "r",
+    "Count - 1000 revolutions per bit"
+);
+slot_impl!(
+    SaeDS01,
+    Param32,
+    0.0,
+    0.125,
+    "km",
+    "Distance - 0.125 km per bit"
+);
+slot_impl!(SaeDS02, Param32, 0.0, 5.0, "m", "Distance - 5 m per bit");
+slot_impl!(
+    SaePR05,
+    Param8,
+    0.0,
+    0.5,
+    "kPa",
+    "Pressure - 0.5 kPa per bit"
+);
+slot_impl!(
+    SaeTP02,
+    Param16,
+    -273.0,
+    0.03125,
+    "°C",
+    "Temperature - 0.03125 °C per bit, -273 °C offset"
+);
+slot_impl!(
+    SaeVE01,
+    Param16,
+    0.0,
+    0.00390625,
+    "km/h",
+    "Velocity - 1/256 km/h per bit"
+);
+slot_impl!(
+    SaeVE02,
+    Param8,
+    -7.8125,
+    0.0625,
+    "km/h",
+    "Velocity - 1/16 km/h per bit, -7.8125 km/h offset"
+);
+slot_impl!(
+    SaeCN03,
+    Param8,
+    -125.0,
+    1.0,
+    "",
+    "Count - 1 gear per bit, -125 offset"
+);
+
+/// Bit-mapped - no scaling, each bit carries its own meaning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SaeBM<T: Signal>(T);
+
+impl<T: Signal + Copy> Slot<T> for SaeBM<T> {
+    const UNIT: &str = "";
+    const SCALE: f32 = 1.0;
+
+    fn new(parameter: T) -> Self {
+        Self(parameter)
+    }
+
+    fn parameter(&self) -> T {
+        self.0
+    }
+}
+
+impl<T: Signal + Copy> SaeBM<T> {
+    /// State of bit `n` of the raw value.
+    ///
+    /// Returns `None` if `n` is outside of the underlying base type.
+    pub fn bit(&self, n: u32) -> Option<bool> {
+        if n >= core::mem::size_of::<T::Base>() as u32 * 8 {
+            return None;
+        }
+        let raw: u32 = self.0.to_raw().as_();
+        Some((raw >> n) & 1 != 0)
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -150,4 +481,59 @@ mod tests {
         assert_eq!(slot.parameter().value().unwrap(), 64225);
         assert_eq!(slot.as_f32(), Some(64.225006));
     }
+
+    #[test]
+    fn slot_sae_cn01() {
+        let slot = SaeCN01::from_f32(250.0).unwrap();
+        assert_eq!(slot.parameter().value().unwrap(), 250);
+        assert_eq!(slot.as_f32(), Some(250.0));
+
+        // outside of the valid range
+        assert!(SaeCN01::from_f32(251.0).unwrap().as_f32().is_none());
+    }
+
+    #[test]
+    fn slot_sae_ra01() {
+        let slot = SaeRA01::from_f32(0.0).unwrap();
+        assert_eq!(slot.parameter().value().unwrap(), 0);
+
+        let slot = SaeRA01::new(Param16::from(3250));
+        assert!((slot.as_f32().unwrap() - 3.25).abs() < f32::EPSILON * 4.0);
+    }
+
+    #[test]
+    fn slot_sae_pc03() {
+        let slot = SaePC03::from_f32(100.0).unwrap();
+        assert_eq!(slot.parameter().value().unwrap(), 250);
+        assert_eq!(slot.as_f32(), Some(100.0));
+
+        let slot = SaePC03::from_f32(0.0).unwrap();
+        assert_eq!(slot.as_f32(), Some(0.0));
+    }
+
+    #[test]
+    fn slot_sae_bm() {
+        let slot = SaeBM::new(Param16::from(0b1000_0000_0000_0101));
+        assert_eq!(slot.bit(0), Some(true));
+        assert_eq!(slot.bit(1), Some(false));
+        assert_eq!(slot.bit(2), Some(true));
+        assert_eq!(slot.bit(15), Some(true));
+        assert_eq!(slot.bit(16), None);
+        assert_eq!(slot.bit(32), None);
+
+        let slot = SaeBM::new(Param8::from(0b1000_0001));
+        assert_eq!(slot.bit(7), Some(true));
+        assert_eq!(slot.bit(8), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let slot = SaeTP01::new(Param8::from(250));
+        assert_eq!(serde_json::to_string(&slot).unwrap(), "250");
+        assert_eq!(serde_json::from_str::<SaeTP01>("250").unwrap(), slot);
+
+        let bytes = postcard::to_allocvec(&slot).unwrap();
+        assert_eq!(postcard::from_bytes::<SaeTP01>(&bytes).unwrap(), slot);
+    }
 }
@@ -0,0 +1,95 @@
+//! Linux SocketCAN backend
+//!
+//! Binds a [`Node`] to a SocketCAN interface. Frames are converted with
+//! [`crate::can::to_frame`] and [`crate::can::from_frame`], which also work
+//! with [`CanFrame`] directly.
+
+use crate::Id;
+use crate::can::{from_frame, to_frame};
+use crate::node::Node;
+use crate::router::Router;
+use crate::time::{Clock, StdClock};
+use ::socketcan::{CanFrame, CanSocket, Socket};
+use std::io;
+use std::time::Duration;
+
+/// Poll interval used while the node has no pending deadline.
+const IDLE: Duration = Duration::from_millis(100);
+
+/// A node attached to a SocketCAN interface.
+pub struct Interface<'a> {
+    socket: CanSocket,
+    node: Node<'a>,
+    clock: StdClock,
+}
+
+impl<'a> Interface<'a> {
+    /// Open the interface named `ifname`, e.g. `can0`.
+    pub fn open(ifname: &str, node: Node<'a>) -> io::Result<Self> {
+        Ok(Self {
+            socket: CanSocket::open(ifname)?,
+            node,
+            clock: StdClock::new(),
+        })
+    }
+
+    /// The attached node.
+    pub fn node(&self) -> &Node<'a> {
+        &self.node
+    }
+
+    /// Transmit a single frame.
+    pub fn transmit(&self, id: Id, data: &[u8]) -> io::Result<()> {
+        let frame: CanFrame = to_frame(id, data).ok_or(io::ErrorKind::InvalidInput)?;
+        self.socket.write_frame(&frame)
+    }
+
+    /// Wait for the next frame, or the node deadline, and route it.
+    ///
+    /// Received messages are delivered through `router` and the frames
+    /// required by the node are transmitted.
+    pub fn poll<const N: usize>(&mut self, router: &mut Router<'_, N>) -> io::Result<()> {
+        let timeout = match self.node.deadline() {
+            Some(deadline) => deadline.saturating_duration_since(self.clock.now()).into(),
+            None => IDLE,
+        };
+
+        let incoming = match self.socket.read_frame_timeout(timeout) {
+            Ok(frame) => from_frame(&frame),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+                ) =>
+            {
+                None
+            }
+            Err(e) => return Err(e),
+        };
+
+        let now = self.clock.now();
+        let frames = router.route(
+            &mut self.node,
+            incoming.as_ref().map(|(id, data)| (*id, data.as_ref())),
+            now,
+        );
+        for (id, data) in frames {
+            self.transmit(id, &data)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_conversion() {
+        let id = Id::new(0x18FEF100);
+
+        let frame: CanFrame = to_frame(id, &[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        assert_eq!(from_frame(&frame), Some((id, [1, 2, 3, 4, 5, 6, 7, 8])));
+    }
+}
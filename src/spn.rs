@@ -0,0 +1,338 @@
+//! Suspect parameter numbers (J1939-71)
+//!
+//! Describes where a parameter lives within its parameter group and how its
+//! raw value is scaled, so values can be extracted generically from any
+//! received PGN.
+//!
+//! Besides the builtin definitions, [`load_csv`] reads definitions at run
+//! time from a CSV database with the columns
+//!
+//! ```text
+//! spn,name,pgn,start,len,scale,offset,unit
+//! 190,Engine Speed,61444,24,16,0.125,0,rpm
+//! ```
+//!
+//! With the `serde` feature, the same fields can be deserialized from JSON
+//! or any other format, with the PGN as a number.
+
+use crate::Pgn;
+use crate::signal::{Param8, Param16, Param32, Signal};
+
+/// Definition of a suspect parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpnDef<'a> {
+    /// Suspect parameter number.
+    pub spn: u32,
+    /// Parameter name.
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub name: &'a str,
+    /// Parameter group carrying the parameter.
+    #[cfg_attr(feature = "serde", serde(with = "pgn_number"))]
+    pub pgn: Pgn,
+    /// Position of the least significant bit within the data.
+    pub start: u16,
+    /// Length in bits.
+    pub len: u8,
+    /// Value scale factor.
+    pub scale: f32,
+    /// Value offset.
+    pub offset: f32,
+    /// Unit of measurement.
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub unit: &'a str,
+}
+
+/// Serialize a PGN as its number.
+#[cfg(feature = "serde")]
+mod pgn_number {
+    use crate::Pgn;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(pgn: &Pgn, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(u32::from(*pgn))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pgn, D::Error> {
+        u32::deserialize(deserializer).map(Pgn::from)
+    }
+}
+
+impl SpnDef<'_> {
+    /// Raw little-endian value of the parameter within `data`.
+    ///
+    /// Returns `None` if `data` is too short or the parameter is longer than
+    /// 32 bits.
+    pub fn raw(&self, data: &[u8]) -> Option<u32> {
+        if self.len == 0 || self.len > 32 {
+            return None;
+        }
+
+        let start = self.start as usize;
+        let end = start + self.len as usize;
+        let bytes = data.get(start / 8..end.div_ceil(8))?;
+
+        let mut value = 0u64;
+        for (i, byte) in bytes.iter().enumerate() {
+            value |= (*byte as u64) << (i * 8);
+        }
+        value >>= start % 8;
+        value &= (1u64 << self.len) - 1;
+
+        Some(value as u32)
+    }
+
+    /// Scaled value of the parameter within `data`.
+    ///
+    /// Returns `None` if the raw value is an error indicator or not
+    /// available.
+    pub fn value(&self, data: &[u8]) -> Option<f32> {
+        let raw = self.raw(data)?;
+
+        if !valid(raw, self.len) {
+            return None;
+        }
+
+        Some(raw as f32 * self.scale + self.offset)
+    }
+}
+
+/// Check if `raw` is a valid value for a parameter of `len` bits, rather than
+/// an error indicator or not available.
+pub(crate) fn valid(raw: u32, len: u8) -> bool {
+    match len {
+        8 => Param8::from(raw as u8).is_valid(),
+        16 => Param16::from(raw as u16).is_valid(),
+        32 => Param32::from(raw).is_valid(),
+        0 | 1 => true,
+        // short parameters reserve the top two values
+        len => raw < (1u32 << len.min(31)).saturating_sub(2),
+    }
+}
+
+/// Lookup over a set of parameter definitions.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Registry<'a> {
+    defs: &'a [SpnDef<'a>],
+}
+
+impl<'a> Registry<'a> {
+    /// Create a registry over `defs`.
+    pub const fn new(defs: &'a [SpnDef<'a>]) -> Self {
+        Self { defs }
+    }
+
+    /// Registry of the parameters carried by the typed messages of this
+    /// crate.
+    pub const fn builtin() -> Registry<'static> {
+        Registry { defs: BUILTIN }
+    }
+
+    /// Look up a parameter by number.
+    pub fn get(&self, spn: u32) -> Option<&'a SpnDef<'a>> {
+        self.defs.iter().find(|d| d.spn == spn)
+    }
+
+    /// Parameters carried by `pgn`.
+    pub fn for_pgn(&self, pgn: Pgn) -> impl Iterator<Item = &'a SpnDef<'a>> {
+        self.defs.iter().filter(move |d| d.pgn == pgn)
+    }
+
+    /// All parameters.
+    pub fn iter(&self) -> impl Iterator<Item = &'a SpnDef<'a>> {
+        self.defs.iter()
+    }
+}
+
+/// Load parameter definitions from a CSV database.
+///
+/// Names and units borrow from `text`, so the definitions can be used with
+/// [`Registry::new`] without further copies. A header line starting with
+/// `spn` and empty lines are skipped. Fields may be quoted to contain
+/// commas, but not quotes.
+///
+/// Returns the number of the first malformed line, counted from 1.
+#[cfg(feature = "alloc")]
+pub fn load_csv(text: &str) -> Result<std::vec::Vec<SpnDef<'_>>, usize> {
+    let mut defs = std::vec::Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("spn") {
+            continue;
+        }
+
+        let def = parse_csv_line(line).ok_or(index + 1)?;
+        defs.push(def);
+    }
+
+    Ok(defs)
+}
+
+#[cfg(feature = "alloc")]
+fn parse_csv_line(line: &str) -> Option<SpnDef<'_>> {
+    let mut fields = [""; 8];
+    let mut rest = Some(line);
+
+    for field in fields.iter_mut() {
+        // only the unit may be left out
+        let Some(line) = rest else {
+            break;
+        };
+        let line = line.trim_start();
+        let (value, next) = match line.strip_prefix('"') {
+            Some(quoted) => {
+                let (value, next) = quoted.split_once('"')?;
+                match next.trim_start() {
+                    "" => (value, None),
+                    next => (value, Some(next.strip_prefix(',')?)),
+                }
+            }
+            None => match line.split_once(',') {
+                Some((value, next)) => (value, Some(next)),
+                None => (line, None),
+            },
+        };
+        *field = value.trim();
+        rest = next;
+    }
+
+    if rest.is_some() {
+        return None;
+    }
+
+    let [spn, name, pgn, start, len, scale, offset, unit] = fields;
+    let def = SpnDef {
+        spn: spn.parse().ok()?,
+        name,
+        pgn: Pgn::from(pgn.parse::<u32>().ok()?),
+        start: start.parse().ok()?,
+        len: len.parse().ok()?,
+        scale: scale.parse().ok()?,
+        offset: offset.parse().ok()?,
+        unit,
+    };
+
+    (1..=32).contains(&def.len).then_some(def)
+}
+
+macro_rules! spn {
+    ($spn:expr, $name:expr, $pgn:ident, $start:expr, $len:expr, $scale:expr, $offset:expr, $unit:expr) => {
+        SpnDef {
+            spn: $spn,
+            name: $name,
+            pgn: Pgn::$pgn,
+            start: $start,
+            len: $len,
+            scale: $scale,
+            offset: $offset,
+            unit: $unit,
+        }
+    };
+}
+
+#[rustfmt::skip]
+static BUILTIN: &[SpnDef<'static>] = &[
+    spn!(91, "Accelerator Pedal Position 1", ElectronicEngineController2, 8, 8, 0.4, 0.0, "%"),
+    spn!(92, "Engine Percent Load At Current Speed", ElectronicEngineController2, 16, 8, 1.0, 0.0, "%"),
+    spn!(108, "Barometric Pressure", AmbientConditions, 0, 8, 0.5, 0.0, "kPa"),
+    spn!(170, "Cab Interior Temperature", AmbientConditions, 8, 16, 0.03125, -273.0, "°C"),
+    spn!(171, "Ambient Air Temperature", AmbientConditions, 24, 16, 0.03125, -273.0, "°C"),
+    spn!(172, "Engine Air Inlet Temperature", AmbientConditions, 40, 8, 1.0, -40.0, "°C"),
+    spn!(244, "Trip Distance", VehicleDistance, 0, 32, 0.125, 0.0, "km"),
+    spn!(245, "Total Vehicle Distance", VehicleDistance, 32, 32, 0.125, 0.0, "km"),
+    spn!(247, "Engine Total Hours of Operation", EngineHoursRevolutions, 0, 32, 0.05, 0.0, "h"),
+    spn!(249, "Engine Total Revolutions", EngineHoursRevolutions, 32, 32, 1000.0, 0.0, "r"),
+    spn!(182, "Engine Trip Fuel", FuelConsumption, 0, 32, 0.5, 0.0, "L"),
+    spn!(250, "Engine Total Fuel Used", FuelConsumption, 32, 32, 0.5, 0.0, "L"),
+    spn!(183, "Engine Fuel Rate", FuelEconomy, 0, 16, 0.05, 0.0, "L/h"),
+    spn!(184, "Engine Instantaneous Fuel Economy", FuelEconomy, 16, 16, 0.001953125, 0.0, "km/L"),
+    spn!(185, "Engine Average Fuel Economy", FuelEconomy, 32, 16, 0.001953125, 0.0, "km/L"),
+    spn!(114, "Net Battery Current", VehicleElectricalPower1, 0, 8, 1.0, -125.0, "A"),
+    spn!(115, "Alternator Current", VehicleElectricalPower1, 8, 8, 1.0, 0.0, "A"),
+    spn!(167, "Charging System Potential", VehicleElectricalPower1, 16, 16, 0.05, 0.0, "V"),
+    spn!(168, "Battery Potential / Power Input 1", VehicleElectricalPower1, 32, 16, 0.05, 0.0, "V"),
+    spn!(158, "Keyswitch Battery Potential", VehicleElectricalPower1, 48, 16, 0.05, 0.0, "V"),
+    spn!(94, "Engine Fuel Delivery Pressure", EngineFluidLevelPressure1, 0, 8, 4.0, 0.0, "kPa"),
+    spn!(98, "Engine Oil Level", EngineFluidLevelPressure1, 16, 8, 0.4, 0.0, "%"),
+    spn!(100, "Engine Oil Pressure", EngineFluidLevelPressure1, 24, 8, 4.0, 0.0, "kPa"),
+    spn!(109, "Engine Coolant Pressure", EngineFluidLevelPressure1, 48, 8, 2.0, 0.0, "kPa"),
+    spn!(111, "Engine Coolant Level", EngineFluidLevelPressure1, 56, 8, 0.4, 0.0, "%"),
+    spn!(191, "Transmission Output Shaft Speed", ElectronicTransmissionController1, 8, 16, 0.125, 0.0, "rpm"),
+    spn!(161, "Transmission Input Shaft Speed", ElectronicTransmissionController1, 40, 16, 0.125, 0.0, "rpm"),
+    spn!(523, "Transmission Current Gear", ElectronicTransmissionController2, 24, 8, 1.0, -125.0, ""),
+    spn!(917, "High Resolution Total Vehicle Distance", HighResolutionVehicleDistance, 0, 32, 5.0, 0.0, "m"),
+    spn!(918, "High Resolution Trip Distance", HighResolutionVehicleDistance, 32, 32, 5.0, 0.0, "m"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract() {
+        let registry = Registry::builtin();
+
+        // VEP1 with battery potential of 13.2 V
+        let data = [0xFF, 0xFF, 0xFF, 0xFF, 0x08, 0x01, 0xFF, 0xFF];
+        let spn = registry.get(168).unwrap();
+        assert_eq!(spn.raw(&data), Some(264));
+        assert!((spn.value(&data).unwrap() - 13.2).abs() < 0.001);
+        assert_eq!(registry.get(167).unwrap().value(&data), None);
+
+        assert_eq!(registry.for_pgn(Pgn::VehicleElectricalPower1).count(), 5);
+    }
+
+    #[test]
+    fn short_parameter() {
+        let def = SpnDef {
+            spn: 0,
+            name: "",
+            pgn: Pgn::Other(0),
+            start: 4,
+            len: 2,
+            scale: 1.0,
+            offset: 0.0,
+            unit: "",
+        };
+        assert_eq!(def.value(&[0b0001_0000]), Some(1.0));
+        assert_eq!(def.value(&[0b0011_0000]), None);
+        assert_eq!(def.value(&[]), None);
+    }
+
+    #[test]
+    fn csv() {
+        let text = "\
+spn,name,pgn,start,len,scale,offset,unit
+190,Engine Speed,61444,24,16,0.125,0,rpm
+
+\"5000\", \"Speed, Wheel\" ,65265,8,16,0.00390625,0,km/h
+5001,Counter,65280,0,4,1,0
+";
+        let defs = load_csv(text).unwrap();
+        assert_eq!(defs.len(), 3);
+        assert_eq!(defs[0].name, "Engine Speed");
+        assert_eq!(defs[0].pgn, Pgn::from(61444));
+        assert_eq!(defs[1].name, "Speed, Wheel");
+        assert_eq!(defs[2].unit, "");
+
+        let registry = Registry::new(&defs);
+        assert_eq!(registry.get(5000).unwrap().start, 8);
+
+        assert_eq!(load_csv("1,a,0,0,16,1,0,b\n2,a,0,0,x,1,0,b"), Err(2));
+        assert_eq!(load_csv("1,a,0,0,0,1,0,b"), Err(1));
+        assert_eq!(load_csv("1,a,0,0,8,1,0,b,c"), Err(1));
+        assert_eq!(load_csv("1,\"a,0,0,8,1,0,b"), Err(1));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn json() {
+        let text = r#"[{"spn":190,"name":"Engine Speed","pgn":61444,"start":24,"len":16,"scale":0.125,"offset":0.0,"unit":"rpm"}]"#;
+        let defs: std::vec::Vec<SpnDef<'_>> = serde_json::from_str(text).unwrap();
+        assert_eq!(defs[0].pgn, Pgn::from(61444));
+        assert_eq!(serde_json::to_string(&defs).unwrap(), text);
+    }
+}
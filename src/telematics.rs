@@ -0,0 +1,304 @@
+//! Telematics snapshots
+//!
+//! [`Snapshot`] latches the latest valid value of a set of parameters from
+//! the received traffic, ready to be sent over an uplink.
+//!
+//! With the `serde` feature, [`Snapshot::compact`] serializes the readings
+//! as nested sequences without field names or strings, for compact formats
+//! such as postcard or CBOR:
+//!
+//! ```text
+//! [time, [[spn, value, age, stale], ...]]
+//! ```
+//!
+//! Receivers decode it into a [`CompactSnapshot`] with the `alloc` feature.
+
+use crate::Pgn;
+use crate::spn::SpnDef;
+use crate::time::{Duration, Instant};
+
+/// Latest value of a subscribed parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Reading {
+    /// Suspect parameter number.
+    pub spn: u32,
+    /// Latest valid value, if any was received.
+    pub value: Option<f32>,
+    /// Time the value was received.
+    pub timestamp: Option<Instant>,
+    /// Set if no valid value was received within the maximum age.
+    pub stale: bool,
+}
+
+impl Reading {
+    /// Compact form of this reading at `now`.
+    pub fn compact(&self, now: Instant) -> CompactReading {
+        CompactReading {
+            spn: self.spn,
+            value: self.value,
+            age: self.timestamp.map(|at| {
+                let age = now.saturating_duration_since(at).as_millis();
+                u32::try_from(age).unwrap_or(u32::MAX)
+            }),
+            stale: self.stale,
+        }
+    }
+}
+
+/// Reading encoded as `[spn, value, age, stale]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        into = "(u32, Option<f32>, Option<u32>, bool)",
+        from = "(u32, Option<f32>, Option<u32>, bool)"
+    )
+)]
+pub struct CompactReading {
+    /// Suspect parameter number.
+    pub spn: u32,
+    /// Latest valid value, if any was received.
+    pub value: Option<f32>,
+    /// Milliseconds since the value was received, saturating.
+    pub age: Option<u32>,
+    /// Set if no valid value was received within the maximum age.
+    pub stale: bool,
+}
+
+impl From<CompactReading> for (u32, Option<f32>, Option<u32>, bool) {
+    fn from(value: CompactReading) -> Self {
+        (value.spn, value.value, value.age, value.stale)
+    }
+}
+
+impl From<(u32, Option<f32>, Option<u32>, bool)> for CompactReading {
+    fn from((spn, value, age, stale): (u32, Option<f32>, Option<u32>, bool)) -> Self {
+        Self {
+            spn,
+            value,
+            age,
+            stale,
+        }
+    }
+}
+
+/// Compact encoding of all readings of a [`Snapshot`], see
+/// [`Snapshot::compact`].
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Copy)]
+pub struct Compact<'s, 'a, const N: usize> {
+    snapshot: &'s Snapshot<'a, N>,
+    now: Instant,
+}
+
+#[cfg(feature = "serde")]
+impl<const N: usize> serde::Serialize for Compact<'_, '_, N> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeTuple;
+
+        struct Readings<'c, 's, 'a, const N: usize>(&'c Compact<'s, 'a, N>);
+
+        impl<const N: usize> serde::Serialize for Readings<'_, '_, '_, N> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeSeq;
+
+                let Compact { snapshot, now } = self.0;
+                let len = snapshot.entries.iter().flatten().count();
+                let mut seq = serializer.serialize_seq(Some(len))?;
+                for reading in snapshot.snapshot(*now) {
+                    seq.serialize_element(&reading.compact(*now))?;
+                }
+                seq.end()
+            }
+        }
+
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&self.now)?;
+        tuple.serialize_element(&Readings(self))?;
+        tuple.end()
+    }
+}
+
+/// Decoded [`Compact`] snapshot.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        into = "(Instant, std::vec::Vec<CompactReading>)",
+        from = "(Instant, std::vec::Vec<CompactReading>)"
+    )
+)]
+pub struct CompactSnapshot {
+    /// Time the snapshot was taken.
+    pub time: Instant,
+    pub readings: std::vec::Vec<CompactReading>,
+}
+
+#[cfg(feature = "alloc")]
+impl From<CompactSnapshot> for (Instant, std::vec::Vec<CompactReading>) {
+    fn from(value: CompactSnapshot) -> Self {
+        (value.time, value.readings)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<(Instant, std::vec::Vec<CompactReading>)> for CompactSnapshot {
+    fn from((time, readings): (Instant, std::vec::Vec<CompactReading>)) -> Self {
+        Self { time, readings }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct Entry<'a> {
+    def: &'a SpnDef<'a>,
+    latest: Option<(f32, Instant)>,
+}
+
+/// Latches the latest values of up to `N` parameters.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Snapshot<'a, const N: usize> {
+    entries: [Option<Entry<'a>>; N],
+    max_age: Duration,
+}
+
+impl<'a, const N: usize> Snapshot<'a, N> {
+    /// Create a new snapshot considering values older than `max_age` stale.
+    pub fn new(max_age: Duration) -> Self {
+        Self {
+            entries: [None; N],
+            max_age,
+        }
+    }
+
+    /// Subscribe to a parameter.
+    ///
+    /// Returns the definition if the snapshot is full.
+    pub fn subscribe(&mut self, def: &'a SpnDef<'a>) -> Result<(), &'a SpnDef<'a>> {
+        match self.entries.iter_mut().find(|e| e.is_none()) {
+            Some(slot) => {
+                *slot = Some(Entry { def, latest: None });
+                Ok(())
+            }
+            None => Err(def),
+        }
+    }
+
+    /// Latch the subscribed parameters carried by a received message.
+    ///
+    /// Not available and error values keep the previous value.
+    pub fn update(&mut self, pgn: Pgn, data: &[u8], now: Instant) {
+        for entry in self.entries.iter_mut().flatten() {
+            if entry.def.pgn != pgn {
+                continue;
+            }
+
+            if let Some(value) = entry.def.value(data) {
+                entry.latest = Some((value, now));
+            }
+        }
+    }
+
+    /// Readings of all subscribed parameters at `now`.
+    pub fn snapshot(&self, now: Instant) -> impl Iterator<Item = Reading> + '_ {
+        self.entries.iter().flatten().map(move |entry| {
+            let stale = entry
+                .latest
+                .is_none_or(|(_, at)| now.saturating_duration_since(at) > self.max_age);
+
+            Reading {
+                spn: entry.def.spn,
+                value: entry.latest.map(|(value, _)| value),
+                timestamp: entry.latest.map(|(_, at)| at),
+                stale,
+            }
+        })
+    }
+
+    /// Compact encoding of the readings at `now`.
+    #[cfg(feature = "serde")]
+    pub fn compact(&self, now: Instant) -> Compact<'_, 'a, N> {
+        Compact {
+            snapshot: self,
+            now,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spn::Registry;
+
+    #[test]
+    fn latch() {
+        let registry = Registry::builtin();
+        let mut snapshot = Snapshot::<2>::new(Duration::from_millis(1000));
+        assert!(snapshot.subscribe(registry.get(108).unwrap()).is_ok());
+        assert!(snapshot.subscribe(registry.get(245).unwrap()).is_ok());
+        assert!(snapshot.subscribe(registry.get(244).unwrap()).is_err());
+
+        let readings: std::vec::Vec<_> = snapshot.snapshot(Instant::default()).collect();
+        assert_eq!(readings.len(), 2);
+        assert!(readings.iter().all(|r| r.stale && r.value.is_none()));
+
+        // AMB with barometric pressure of 100 kPa
+        let amb = [200, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        snapshot.update(Pgn::AmbientConditions, &amb, Instant::from_millis(100));
+
+        // not available keeps the latched value
+        let amb = [0xFF; 8];
+        snapshot.update(Pgn::AmbientConditions, &amb, Instant::from_millis(200));
+
+        let reading = snapshot
+            .snapshot(Instant::from_millis(500))
+            .find(|r| r.spn == 108)
+            .unwrap();
+        assert_eq!(reading.value, Some(100.0));
+        assert_eq!(reading.timestamp, Some(Instant::from_millis(100)));
+        assert!(!reading.stale);
+
+        let reading = snapshot
+            .snapshot(Instant::from_millis(1200))
+            .find(|r| r.spn == 108)
+            .unwrap();
+        assert!(reading.stale);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn compact() {
+        let registry = Registry::builtin();
+        let mut snapshot = Snapshot::<3>::new(Duration::from_millis(1000));
+        assert!(snapshot.subscribe(registry.get(108).unwrap()).is_ok());
+        assert!(snapshot.subscribe(registry.get(245).unwrap()).is_ok());
+
+        let amb = [200, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        snapshot.update(Pgn::AmbientConditions, &amb, Instant::from_millis(100));
+
+        let now = Instant::from_millis(500);
+        let json = serde_json::to_string(&snapshot.compact(now)).unwrap();
+        assert_eq!(json, "[500,[[108,100.0,400,false],[245,null,null,true]]]");
+
+        let bytes = postcard::to_allocvec(&snapshot.compact(now)).unwrap();
+        let decoded: CompactSnapshot = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.time, now);
+        assert_eq!(decoded.readings.len(), 2);
+        assert_eq!(
+            decoded.readings[0],
+            CompactReading {
+                spn: 108,
+                value: Some(100.0),
+                age: Some(400),
+                stale: false,
+            }
+        );
+    }
+}
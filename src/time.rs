@@ -0,0 +1,144 @@
+//! Time keeping
+//!
+//! A single millisecond time base shared by every subsystem with timers, such
+//! as transport timeouts. Implement [`Clock`] once for the platform timer.
+
+use core::ops::{Add, Sub};
+
+/// A point in time, in milliseconds since an arbitrary epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Instant(u64);
+
+impl Instant {
+    /// Create an instant from milliseconds since the epoch.
+    pub const fn from_millis(millis: u64) -> Self {
+        Self(millis)
+    }
+
+    /// Milliseconds since the epoch.
+    pub const fn as_millis(&self) -> u64 {
+        self.0
+    }
+
+    /// Time elapsed from `earlier` to this instant.
+    ///
+    /// Saturates to zero if `earlier` is later than this instant.
+    pub fn saturating_duration_since(&self, earlier: Instant) -> Duration {
+        Duration(self.0.saturating_sub(earlier.0))
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, rhs: Duration) -> Self::Output {
+        Instant(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    fn sub(self, rhs: Duration) -> Self::Output {
+        Instant(self.0.saturating_sub(rhs.0))
+    }
+}
+
+/// A span of time, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Duration(u64);
+
+impl Duration {
+    /// Create a duration from milliseconds.
+    pub const fn from_millis(millis: u64) -> Self {
+        Self(millis)
+    }
+
+    /// Length in milliseconds.
+    pub const fn as_millis(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Add for Duration {
+    type Output = Duration;
+
+    fn add(self, rhs: Duration) -> Self::Output {
+        Duration(self.0.saturating_add(rhs.0))
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Duration> for std::time::Duration {
+    fn from(value: Duration) -> Self {
+        std::time::Duration::from_millis(value.0)
+    }
+}
+
+/// Source of the current time.
+pub trait Clock {
+    /// Current monotonic time.
+    fn now(&self) -> Instant;
+}
+
+impl<F: Fn() -> Instant> Clock for F {
+    fn now(&self) -> Instant {
+        self()
+    }
+}
+
+/// Clock based on [`std::time::Instant`], counting from its creation.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct StdClock {
+    epoch: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl StdClock {
+    /// Create a new clock starting at zero.
+    pub fn new() -> Self {
+        Self {
+            epoch: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for StdClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for StdClock {
+    fn now(&self) -> Instant {
+        Instant(self.epoch.elapsed().as_millis() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let start = Instant::from_millis(1000);
+        let later = start + Duration::from_millis(250);
+        assert_eq!(later.as_millis(), 1250);
+        assert_eq!(later.saturating_duration_since(start).as_millis(), 250);
+        assert_eq!(start.saturating_duration_since(later).as_millis(), 0);
+        assert_eq!((start - Duration::from_millis(2000)).as_millis(), 0);
+    }
+
+    #[test]
+    fn closure_clock() {
+        let clock = || Instant::from_millis(42);
+        assert_eq!(clock.now(), Instant::from_millis(42));
+    }
+}
@@ -0,0 +1,106 @@
+//! Reassembly shared by the multi-packet protocols
+//!
+//! Each protocol carries its payload in numbered segments of a fixed size,
+//! with a protocol-specific header in front of each one. The protocol layers
+//! check their headers and sequence numbers, then hand the segment to
+//! [`Segments::store`], which places it in the payload and drops the padding
+//! of the last one.
+
+use super::{Error, TransferStorage};
+use core::ops::Range;
+
+/// Payload bytes carried by each segment of a protocol.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Segments {
+    /// Payload bytes of the first segment.
+    first: usize,
+    /// Payload bytes of every following segment.
+    rest: usize,
+}
+
+impl Segments {
+    /// J1939-21 transport protocol data transfers.
+    pub(crate) const TP: Self = Self::uniform(7);
+
+    /// Segments of `len` bytes each.
+    pub(crate) const fn uniform(len: usize) -> Self {
+        Self {
+            first: len,
+            rest: len,
+        }
+    }
+
+    /// Segments of `rest` bytes after a first one of `first` bytes.
+    pub(crate) const fn new(first: usize, rest: usize) -> Self {
+        Self { first, rest }
+    }
+
+    /// Payload range of segment `index`, counted from 0, within `total`
+    /// bytes.
+    ///
+    /// The range is empty for segments past the end of the payload.
+    pub(crate) fn range(&self, index: usize, total: usize) -> Range<usize> {
+        let (start, len) = match index {
+            0 => (0, self.first),
+            _ => (self.first + (index - 1) * self.rest, self.rest),
+        };
+        let start = start.min(total);
+        start..(start + len).min(total)
+    }
+
+    /// Number of segments needed for `total` bytes.
+    pub(crate) fn count(&self, total: usize) -> usize {
+        match total.checked_sub(self.first) {
+            None | Some(0) => 1,
+            Some(rest) => 1 + rest.div_ceil(self.rest),
+        }
+    }
+
+    /// Store the payload bytes of segment `index` of a `total`-byte payload.
+    ///
+    /// `data` is the segment without its header. Bytes past the end of the
+    /// payload are padding and are not written. Returns whether the segment
+    /// completes the payload, or [`Error::Sequence`] if `data` is cut short.
+    pub(crate) fn store<S: TransferStorage + ?Sized>(
+        &self,
+        storage: &mut S,
+        index: usize,
+        total: usize,
+        data: &[u8],
+    ) -> Result<bool, Error> {
+        let range = self.range(index, total);
+        let data = data.get(..range.len()).ok_or(Error::Sequence)?;
+        storage.write(range.start, data)?;
+        Ok(range.end == total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segments() {
+        assert_eq!(Segments::TP.range(0, 16), 0..7);
+        assert_eq!(Segments::TP.range(2, 16), 14..16);
+        assert_eq!(Segments::TP.range(3, 16), 16..16);
+        assert_eq!(Segments::TP.count(16), 3);
+        assert_eq!(Segments::TP.count(7), 1);
+
+        let fast = Segments::new(6, 7);
+        assert_eq!(fast.range(1, 20), 6..13);
+        assert_eq!(fast.count(20), 3);
+        assert_eq!(fast.count(0), 1);
+
+        let mut buf = [0u8; 9];
+        assert_eq!(
+            Segments::TP.store(buf.as_mut_slice(), 1, 9, &[8, 9, 0xFF, 0xFF]),
+            Ok(true)
+        );
+        assert_eq!(buf[7..], [8, 9]);
+        assert_eq!(
+            Segments::TP.store(buf.as_mut_slice(), 0, 9, &[1, 2]),
+            Err(Error::Sequence)
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Checksums computed while a payload is reassembled
+//!
+//! Wrapping the storage of a [`Transfer`](super::Transfer) in [`Checksummed`]
+//! updates a CRC with the payload bytes of each packet as it arrives, so
+//! firmware images can be verified without reading the payload back, even
+//! from storage that can not be read in place.
+
+use super::{Error, TransferStorage};
+
+/// Checksum updated with consecutive chunks of a payload.
+pub trait Checksum: Default {
+    type Output: Copy;
+
+    /// Add `data` to the checksum.
+    fn update(&mut self, data: &[u8]);
+
+    /// Checksum of the bytes added so far.
+    fn value(&self) -> Self::Output;
+}
+
+/// CRC-32 (ISO-HDLC) as used by zlib and Ethernet.
+///
+/// Computed bitwise rather than with a lookup table to keep it small.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Crc32(u32);
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self(0xFFFF_FFFF)
+    }
+}
+
+impl Checksum for Crc32 {
+    type Output = u32;
+
+    fn update(&mut self, data: &[u8]) {
+        for byte in data {
+            self.0 ^= u32::from(*byte);
+            for _ in 0..8 {
+                let mask = (self.0 & 1).wrapping_neg();
+                self.0 = (self.0 >> 1) ^ (0xEDB8_8320 & mask);
+            }
+        }
+    }
+
+    fn value(&self) -> u32 {
+        !self.0
+    }
+}
+
+/// CRC-16 (IBM-3740), also known as CRC-16/CCITT-FALSE.
+///
+/// Computed bitwise rather than with a lookup table to keep it small.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Crc16(u16);
+
+impl Default for Crc16 {
+    fn default() -> Self {
+        Self(0xFFFF)
+    }
+}
+
+impl Checksum for Crc16 {
+    type Output = u16;
+
+    fn update(&mut self, data: &[u8]) {
+        for byte in data {
+            self.0 ^= u16::from(*byte) << 8;
+            for _ in 0..8 {
+                let mask = (self.0 >> 15).wrapping_neg();
+                self.0 = (self.0 << 1) ^ (0x1021 & mask);
+            }
+        }
+    }
+
+    fn value(&self) -> u16 {
+        self.0
+    }
+}
+
+/// Storage computing a checksum of the payload written to `S`.
+///
+/// Bytes are added to the checksum once `S` has stored them. Packets written
+/// again, as when a sender repeats part of a window, are not counted twice,
+/// so the checksum keeps the bytes first written when a [`Transfer`] is
+/// rewound to replace corrupted ones.
+///
+/// [`Transfer`]: super::Transfer
+#[derive(Debug)]
+pub struct Checksummed<S, C> {
+    storage: S,
+    checksum: C,
+    len: usize,
+}
+
+impl<S: TransferStorage, C: Checksum> Checksummed<S, C> {
+    /// Compute a checksum of the payload written to `storage`.
+    pub fn new(storage: S) -> Self {
+        Self {
+            storage,
+            checksum: C::default(),
+            len: 0,
+        }
+    }
+
+    /// Checksum of the payload bytes written so far.
+    pub fn checksum(&self) -> C::Output {
+        self.checksum.value()
+    }
+
+    /// Unwrap the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+}
+
+impl<S: TransferStorage, C: Checksum> TransferStorage for Checksummed<S, C> {
+    fn capacity(&self) -> usize {
+        self.storage.capacity()
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), Error> {
+        // a gap would leave bytes out of the checksum
+        if offset > self.len {
+            return Err(Error::Storage);
+        }
+        self.storage.write(offset, data)?;
+
+        let new = data.get(self.len - offset..).unwrap_or_default();
+        self.checksum.update(new);
+        self.len += new.len();
+        Ok(())
+    }
+
+    fn read(&self, len: usize) -> Option<&[u8]> {
+        self.storage.read(len)
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.storage.finish()
+    }
+
+    fn reset(&mut self) {
+        self.storage.reset();
+        self.checksum = C::default();
+        self.len = 0;
+    }
+
+    fn rewritable(&self) -> bool {
+        self.storage.rewritable()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::Pgn;
+    use crate::transport::{DataTransfer, RequestToSend, Transfer};
+
+    #[test]
+    fn check_values() {
+        let mut crc32 = Crc32::default();
+        crc32.update(b"1234");
+        crc32.update(b"56789");
+        assert_eq!(crc32.value(), 0xCBF4_3926);
+
+        let mut crc16 = Crc16::default();
+        crc16.update(b"123456789");
+        assert_eq!(crc16.value(), 0x29B1);
+    }
+
+    #[test]
+    fn transfer() {
+        let rts = RequestToSend::try_new(9, None, Pgn::ProprietaryA).unwrap();
+        let mut buf = [0u8; 16];
+        let storage = Checksummed::<_, Crc32>::new(buf.as_mut_slice());
+        let mut transfer = Transfer::with_storage(rts, storage);
+
+        transfer.next(DataTransfer::new(1, *b"1234567")).unwrap();
+        assert_eq!(transfer.checksum(), None);
+
+        // padding of the last packet is left out
+        transfer
+            .next(DataTransfer::new(
+                2,
+                [b'8', b'9', 0xFF, 0xFF, 0xFF, 0xFF, 0xFF],
+            ))
+            .unwrap();
+        assert_eq!(transfer.checksum(), Some(0xCBF4_3926));
+        assert_eq!(transfer.finished(), Some(b"123456789".as_slice()));
+
+        // repeated bytes are not counted twice
+        let mut storage = Checksummed::<_, Crc16>::new(std::vec::Vec::new());
+        storage.write(0, b"1234567").unwrap();
+        storage.write(0, b"1234567").unwrap();
+        storage.write(7, b"89").unwrap();
+        assert_eq!(storage.checksum(), 0x29B1);
+        assert_eq!(storage.write(10, b"0"), Err(Error::Storage));
+    }
+}
@@ -0,0 +1,906 @@
+//! Extended transport protocol (ISO 11783-3)
+//!
+//! Moves payloads of [`MIN_SIZE`] up to [`MAX_SIZE`] bytes between two
+//! addresses, as ISOBUS devices do with object pools. Sessions open like
+//! transport protocol ones, but each CTS is followed by a data packet offset
+//! (DPO) message, to which the sequence numbers of the following data
+//! transfers are relative. Data transfers and connection aborts share their
+//! format with the transport protocol.
+//!
+//! Unlike transport protocol sessions, these do not track timeouts.
+
+use super::assembly::Segments;
+use super::message::{pgn, raw};
+use super::sender::packet;
+use super::{
+    AbortReason, AbortSenderRole, CompletedTransfer, ConnectionAbort, DataTransfer, Error,
+    TransferStorage,
+};
+use crate::error::{DecodeError, exact};
+use crate::id::Pgn;
+
+/// Smallest payload sent with the extended transport protocol, one byte more
+/// than the transport protocol can carry.
+pub const MIN_SIZE: u32 = 1786;
+
+/// Largest payload, carried in 16 777 215 packets.
+pub const MAX_SIZE: u32 = 0xFF_FFFF * 7;
+
+/// Packet number carried in bytes 2 to 4 of a connection management message.
+fn packet_number(value: &[u8; 8]) -> u32 {
+    u32::from_le_bytes([value[2], value[3], value[4], 0x00])
+}
+
+/// Encode a message of `mux` with five bytes of fields and the PGN.
+const fn encode(mux: u8, fields: [u8; 4], pgn: Pgn) -> [u8; 8] {
+    let pgn = pgn.as_raw().to_le_bytes();
+    [
+        mux, fields[0], fields[1], fields[2], fields[3], pgn[0], pgn[1], pgn[2],
+    ]
+}
+
+/// Extended request to send (ETP.CM_RTS) message.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RequestToSend {
+    total_size: u32,
+    pgn: Pgn,
+}
+
+impl RequestToSend {
+    const MUX: u8 = 20;
+
+    /// Create a new request to send message.
+    ///
+    /// Returns `None` unless `total_size` is between [`MIN_SIZE`] and
+    /// [`MAX_SIZE`].
+    pub const fn new(total_size: u32, pgn: Pgn) -> Option<Self> {
+        if total_size < MIN_SIZE || total_size > MAX_SIZE {
+            return None;
+        }
+        Some(Self { total_size, pgn })
+    }
+
+    /// Encode the message.
+    pub const fn to_bytes(&self) -> [u8; 8] {
+        encode(Self::MUX, self.total_size.to_le_bytes(), self.pgn)
+    }
+
+    /// Total message size in bytes.
+    pub const fn total_size(&self) -> u32 {
+        self.total_size
+    }
+
+    /// Number of packets needed for the message.
+    pub const fn total_packets(&self) -> u32 {
+        self.total_size.div_ceil(7)
+    }
+
+    /// Tranfer contents PGN.
+    pub const fn pgn(&self) -> Pgn {
+        self.pgn
+    }
+
+    fn from_raw(value: &[u8; 8]) -> Self {
+        Self {
+            total_size: u32::from_le_bytes([value[1], value[2], value[3], value[4]]),
+            pgn: pgn(value),
+        }
+    }
+}
+
+impl From<&RequestToSend> for [u8; 8] {
+    fn from(value: &RequestToSend) -> Self {
+        value.to_bytes()
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for RequestToSend {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        raw(value, Self::MUX).map(Self::from_raw)
+    }
+}
+
+/// Extended clear to send (ETP.CM_CTS) message.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClearToSend {
+    packets: u8,
+    next_packet: u32,
+    pgn: Pgn,
+}
+
+impl ClearToSend {
+    const MUX: u8 = 21;
+
+    /// Create a new CTS message granting `packets` packets from packet number
+    /// `next_packet`, counted from 1 over the whole payload.
+    ///
+    /// `next_packet` is limited to 24 bits.
+    pub const fn new(packets: u8, next_packet: u32, pgn: Pgn) -> Self {
+        Self {
+            packets,
+            next_packet: next_packet & 0xFF_FFFF,
+            pgn,
+        }
+    }
+
+    /// Create a CTS message holding the connection open without granting
+    /// any packets.
+    pub const fn hold(next_packet: u32, pgn: Pgn) -> Self {
+        Self::new(0, next_packet, pgn)
+    }
+
+    /// Encode the message.
+    pub const fn to_bytes(&self) -> [u8; 8] {
+        let next = self.next_packet.to_le_bytes();
+        encode(
+            Self::MUX,
+            [self.packets, next[0], next[1], next[2]],
+            self.pgn,
+        )
+    }
+
+    /// Number of packets that can be sent.
+    pub const fn packets(&self) -> u8 {
+        self.packets
+    }
+
+    /// Number of the next packet, counted from 1 over the whole payload.
+    pub const fn next_packet(&self) -> u32 {
+        self.next_packet
+    }
+
+    /// Check if the message holds the connection open without granting any
+    /// packets.
+    pub const fn is_hold(&self) -> bool {
+        self.packets == 0
+    }
+
+    /// Tranfer contents PGN.
+    pub const fn pgn(&self) -> Pgn {
+        self.pgn
+    }
+
+    fn from_raw(value: &[u8; 8]) -> Self {
+        Self {
+            packets: value[1],
+            next_packet: packet_number(value),
+            pgn: pgn(value),
+        }
+    }
+}
+
+impl From<&ClearToSend> for [u8; 8] {
+    fn from(value: &ClearToSend) -> Self {
+        value.to_bytes()
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ClearToSend {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        raw(value, Self::MUX).map(Self::from_raw)
+    }
+}
+
+/// Data packet offset (ETP.CM_DPO) message.
+///
+/// Sent by the originator before the packets granted by each CTS. The
+/// sequence numbers of these packets start at 1 after `offset` packets.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DataPacketOffset {
+    packets: u8,
+    offset: u32,
+    pgn: Pgn,
+}
+
+impl DataPacketOffset {
+    const MUX: u8 = 22;
+
+    /// Create a new data packet offset message for `packets` packets
+    /// following the first `offset` packets of the payload.
+    ///
+    /// `offset` is limited to 24 bits.
+    pub const fn new(packets: u8, offset: u32, pgn: Pgn) -> Self {
+        Self {
+            packets,
+            offset: offset & 0xFF_FFFF,
+            pgn,
+        }
+    }
+
+    /// Encode the message.
+    pub const fn to_bytes(&self) -> [u8; 8] {
+        let offset = self.offset.to_le_bytes();
+        encode(
+            Self::MUX,
+            [self.packets, offset[0], offset[1], offset[2]],
+            self.pgn,
+        )
+    }
+
+    /// Number of packets the offset applies to.
+    pub const fn packets(&self) -> u8 {
+        self.packets
+    }
+
+    /// Packets of the payload sent before the first one of this window.
+    pub const fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Tranfer contents PGN.
+    pub const fn pgn(&self) -> Pgn {
+        self.pgn
+    }
+
+    fn from_raw(value: &[u8; 8]) -> Self {
+        Self {
+            packets: value[1],
+            offset: packet_number(value),
+            pgn: pgn(value),
+        }
+    }
+}
+
+impl From<&DataPacketOffset> for [u8; 8] {
+    fn from(value: &DataPacketOffset) -> Self {
+        value.to_bytes()
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for DataPacketOffset {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        raw(value, Self::MUX).map(Self::from_raw)
+    }
+}
+
+/// Extended end of message acknowledge (ETP.CM_EOMA) message.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EndOfMessageAck {
+    total_size: u32,
+    pgn: Pgn,
+}
+
+impl EndOfMessageAck {
+    const MUX: u8 = 23;
+
+    /// Creates a new end of message acknowledge message.
+    pub const fn new(total_size: u32, pgn: Pgn) -> Self {
+        Self { total_size, pgn }
+    }
+
+    /// Encode the message.
+    pub const fn to_bytes(&self) -> [u8; 8] {
+        encode(Self::MUX, self.total_size.to_le_bytes(), self.pgn)
+    }
+
+    /// Total message size in bytes.
+    pub const fn total_size(&self) -> u32 {
+        self.total_size
+    }
+
+    /// Tranfer contents PGN.
+    pub const fn pgn(&self) -> Pgn {
+        self.pgn
+    }
+
+    fn from_raw(value: &[u8; 8]) -> Self {
+        Self {
+            total_size: u32::from_le_bytes([value[1], value[2], value[3], value[4]]),
+            pgn: pgn(value),
+        }
+    }
+}
+
+impl From<&EndOfMessageAck> for [u8; 8] {
+    fn from(value: &EndOfMessageAck) -> Self {
+        value.to_bytes()
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for EndOfMessageAck {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        raw(value, Self::MUX).map(Self::from_raw)
+    }
+}
+
+/// Extended connection management message.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ControlMessage {
+    RequestToSend(RequestToSend),
+    ClearToSend(ClearToSend),
+    DataPacketOffset(DataPacketOffset),
+    EndOfMessageAck(EndOfMessageAck),
+    ConnectionAbort(ConnectionAbort),
+}
+
+impl ControlMessage {
+    /// Tranfer contents PGN.
+    pub fn pgn(&self) -> Pgn {
+        match self {
+            Self::RequestToSend(rts) => rts.pgn(),
+            Self::ClearToSend(cts) => cts.pgn(),
+            Self::DataPacketOffset(dpo) => dpo.pgn(),
+            Self::EndOfMessageAck(end) => end.pgn(),
+            Self::ConnectionAbort(abort) => abort.pgn(),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ControlMessage {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        const RTS: u8 = RequestToSend::MUX;
+        const CTS: u8 = ClearToSend::MUX;
+        const DPO: u8 = DataPacketOffset::MUX;
+        const END: u8 = EndOfMessageAck::MUX;
+
+        match exact::<8>(value)? {
+            raw @ [RTS, ..] => Ok(Self::RequestToSend(RequestToSend::from_raw(raw))),
+            raw @ [CTS, ..] => Ok(Self::ClearToSend(ClearToSend::from_raw(raw))),
+            raw @ [DPO, ..] => Ok(Self::DataPacketOffset(DataPacketOffset::from_raw(raw))),
+            raw @ [END, ..] => Ok(Self::EndOfMessageAck(EndOfMessageAck::from_raw(raw))),
+            raw => ConnectionAbort::try_from(&raw[..]).map(Self::ConnectionAbort),
+        }
+    }
+}
+
+/// Extended connection management message sent in response to data
+/// transfers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Response {
+    Cts(ClearToSend),
+    End(EndOfMessageAck),
+}
+
+impl From<&Response> for [u8; 8] {
+    fn from(value: &Response) -> Self {
+        match value {
+            Response::Cts(cts) => cts.into(),
+            Response::End(end) => end.into(),
+        }
+    }
+}
+
+/// An ongoing extended transport protocol transfer, received into `S`.
+///
+/// Send [`Transfer::cts`] once the transfer was created from the RTS, then
+/// feed it with the DPO messages and data transfers of the originator.
+#[derive(Debug)]
+pub struct Transfer<S> {
+    pgn: Pgn,
+    total_size: u32,
+    /// Packets received so far.
+    rx_packets: u32,
+    /// Packets before the current window, announced by its DPO.
+    offset: u32,
+    /// Packets of the current window, 0 until its DPO arrives.
+    window: u8,
+    /// Packets granted by each CTS.
+    packets_per_cts: u8,
+    aborted: bool,
+    source: u8,
+    storage: S,
+}
+
+impl<S: TransferStorage> Transfer<S> {
+    /// Create a new transfer from a RTS message received from the sender,
+    /// checking that `storage` can hold the payload.
+    ///
+    /// Returns the storage if it is too small.
+    pub fn try_with_storage(rts: RequestToSend, storage: S) -> Result<Self, S> {
+        let fits = usize::try_from(rts.total_size()).is_ok_and(|size| storage.capacity() >= size);
+        if !fits {
+            return Err(storage);
+        }
+        Ok(Self::with_storage(rts, storage))
+    }
+
+    /// Create a new transfer from a RTS message received from the sender,
+    /// reassembling the payload into `storage`.
+    ///
+    /// Each CTS grants 255 packets unless limited with
+    /// [`Transfer::with_packets_per_cts`].
+    pub fn with_storage(rts: RequestToSend, storage: S) -> Self {
+        Self {
+            pgn: rts.pgn(),
+            total_size: rts.total_size(),
+            rx_packets: 0,
+            offset: 0,
+            window: 0,
+            packets_per_cts: 255,
+            aborted: false,
+            source: 0xFE,
+            storage,
+        }
+    }
+
+    /// Grant at most `packets` packets with each CTS, at least 1.
+    pub fn with_packets_per_cts(mut self, packets: u8) -> Self {
+        self.packets_per_cts = packets.max(1);
+        self
+    }
+
+    /// Record `source` as the address of the sender.
+    pub fn with_source(mut self, source: u8) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Address of the sender, `0xFE` until one is recorded.
+    pub fn source(&self) -> u8 {
+        self.source
+    }
+
+    /// The CTS granting the next window.
+    ///
+    /// Send it once the transfer was created. The following ones are
+    /// returned by [`Transfer::next`]. `None` once the transfer ended.
+    pub fn cts(&self) -> Option<ClearToSend> {
+        if self.aborted || self.is_finished() {
+            return None;
+        }
+
+        let remaining = self.total_packets() - self.rx_packets;
+        let packets = u8::try_from(remaining).map_or(self.packets_per_cts, |remaining| {
+            remaining.min(self.packets_per_cts)
+        });
+        Some(ClearToSend::new(packets, self.rx_packets + 1, self.pgn))
+    }
+
+    /// Feed the transfer with a connection management message from the
+    /// sender.
+    ///
+    /// Messages for other PGNs are ignored. A DPO while packets of the last
+    /// one are still expected, that does not continue the payload where it
+    /// stopped, or that announces more packets than were granted aborts the
+    /// transfer with the matching DPO abort reason. On failure, the
+    /// connection abort to send is returned alongside the error, unless the
+    /// sender aborted the session itself.
+    pub fn handle(&mut self, msg: &ControlMessage) -> Result<(), (Error, Option<ConnectionAbort>)> {
+        if msg.pgn() != self.pgn || self.aborted || self.is_finished() {
+            return Ok(());
+        }
+
+        match msg {
+            ControlMessage::DataPacketOffset(dpo) => {
+                let granted = self.cts().map_or(0, |cts| cts.packets());
+                let reason = if self.window != 0 {
+                    Some(AbortReason::UnexpectedDataPacketOffset)
+                } else if dpo.offset() != self.rx_packets || dpo.packets() == 0 {
+                    Some(AbortReason::BadDataPacketOffset)
+                } else if dpo.packets() > granted {
+                    Some(AbortReason::DataPacketOffsetExceedsCts)
+                } else {
+                    None
+                };
+                if let Some(reason) = reason {
+                    let abort = self.abort(reason);
+                    return Err((Error::Sequence, Some(abort)));
+                }
+                self.offset = dpo.offset();
+                self.window = dpo.packets();
+            }
+            ControlMessage::ConnectionAbort(abort) => {
+                self.aborted = true;
+                return Err((Error::Aborted(abort.reason()), None));
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Feed the transfer with the next data transfer.
+    ///
+    /// Its sequence number counts from 1 after the offset of the last DPO.
+    pub fn next(
+        &mut self,
+        msg: DataTransfer,
+    ) -> Result<Option<Response>, (Error, ConnectionAbort)> {
+        if self.aborted || self.is_finished() {
+            return Err((
+                Error::PreviousAbort,
+                self.abort(AbortReason::UnexpectedDataTransfer),
+            ));
+        }
+        if self.window == 0 {
+            return Err((
+                Error::Sequence,
+                self.abort(AbortReason::UnexpectedDataTransfer),
+            ));
+        }
+        if self.rx_packets - self.offset + 1 != u32::from(msg.sequence()) {
+            return Err((Error::Sequence, self.abort(AbortReason::BadSequenceNumber)));
+        }
+
+        let mut result = Segments::TP
+            .store(
+                &mut self.storage,
+                self.rx_packets as usize,
+                self.total_size as usize,
+                &msg.data(),
+            )
+            .map(|_| ());
+        self.rx_packets += 1;
+
+        let response = if self.rx_packets == self.total_packets() {
+            result = result.and_then(|_| self.storage.finish());
+            Some(Response::End(EndOfMessageAck::new(
+                self.total_size,
+                self.pgn,
+            )))
+        } else if self.rx_packets == self.offset + u32::from(self.window) {
+            self.window = 0;
+            self.cts().map(Response::Cts)
+        } else {
+            None
+        };
+
+        if let Err(e) = result {
+            return Err((e, self.abort(AbortReason::Custom)));
+        }
+        Ok(response)
+    }
+
+    /// Abort the transfer, returning the connection abort to send.
+    pub fn abort(&mut self, reason: AbortReason) -> ConnectionAbort {
+        self.aborted = true;
+        ConnectionAbort::new(reason, AbortSenderRole::Receiver, self.pgn)
+    }
+
+    /// Check if the whole payload has been stored.
+    pub fn is_finished(&self) -> bool {
+        self.rx_packets >= self.total_packets() && !self.aborted
+    }
+
+    /// Return read-only access to the payload once the transfer is
+    /// complete. Storage that can not be read in place returns `None`.
+    pub fn finished(&self) -> Option<&[u8]> {
+        if self.is_finished() {
+            self.storage.read(self.total_size as usize)
+        } else {
+            None
+        }
+    }
+
+    /// The payload with its PGN and sender, once the transfer is complete.
+    pub fn completed(&self) -> Option<CompletedTransfer<&[u8]>> {
+        Some(CompletedTransfer {
+            pgn: self.pgn,
+            source: self.source,
+            data: self.finished()?,
+        })
+    }
+
+    /// Consume the transfer and hand back its storage for reuse.
+    pub fn into_storage(mut self) -> S {
+        self.storage.reset();
+        self.storage
+    }
+
+    fn total_packets(&self) -> u32 {
+        self.total_size.div_ceil(7)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<S> defmt::Format for Transfer<S> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(
+            f,
+            "etp::Transfer {{ pgn: {}, source: {=u8:#x}, total_size: {}, rx_packets: {}, aborted: {} }}",
+            self.pgn,
+            self.source,
+            self.total_size,
+            self.rx_packets,
+            self.aborted
+        )
+    }
+}
+
+/// Progress of an outgoing transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum State {
+    ClearToSend,
+    Sending,
+    EndOfMessageAck,
+    Finished,
+    Aborted,
+}
+
+/// An outgoing extended transport protocol transfer.
+///
+/// Starts by sending [`OutgoingTransfer::rts`]. Each CTS of the receiver is
+/// answered with the DPO returned by [`OutgoingTransfer::handle`], followed
+/// by the packets from [`OutgoingTransfer::next_packet`].
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OutgoingTransfer<'a> {
+    data: &'a [u8],
+    rts: RequestToSend,
+    /// Number of the next packet, counted from 1 over the whole payload.
+    next_packet: u32,
+    /// Packets before the current window.
+    offset: u32,
+    /// Packets left of the current window.
+    window: u8,
+    state: State,
+}
+
+impl<'a> OutgoingTransfer<'a> {
+    /// Create a new transfer sending `data` as `pgn`.
+    ///
+    /// Returns `None` unless `data` is between [`MIN_SIZE`] and
+    /// [`MAX_SIZE`] bytes long.
+    pub fn new(data: &'a [u8], pgn: Pgn) -> Option<Self> {
+        let rts = RequestToSend::new(u32::try_from(data.len()).ok()?, pgn)?;
+        Some(Self {
+            data,
+            rts,
+            next_packet: 1,
+            offset: 0,
+            window: 0,
+            state: State::ClearToSend,
+        })
+    }
+
+    /// The RTS message to send to open the session.
+    pub fn rts(&self) -> &RequestToSend {
+        &self.rts
+    }
+
+    /// Check if the receiver acknowledged the whole payload.
+    pub fn is_finished(&self) -> bool {
+        self.state == State::Finished
+    }
+
+    /// Check if the session was aborted by either side.
+    pub fn is_aborted(&self) -> bool {
+        self.state == State::Aborted
+    }
+
+    /// Feed the transfer with a connection management message from the
+    /// receiver.
+    ///
+    /// Returns the DPO to send in answer to a CTS granting packets. Messages
+    /// for other PGNs are ignored. On failure, the connection abort to send
+    /// is returned alongside the error, unless the receiver aborted the
+    /// session itself.
+    pub fn handle(
+        &mut self,
+        msg: &ControlMessage,
+    ) -> Result<Option<DataPacketOffset>, (Error, Option<ConnectionAbort>)> {
+        if msg.pgn() != self.rts.pgn() {
+            return Ok(None);
+        }
+        if matches!(self.state, State::Finished | State::Aborted) {
+            return Err((Error::PreviousAbort, None));
+        }
+
+        match msg {
+            ControlMessage::ClearToSend(cts) => {
+                if self.state == State::Sending {
+                    return Err(self.abort(Error::Sequence, AbortReason::CtsWhileDataTransfer));
+                }
+                if cts.is_hold() {
+                    return Ok(None);
+                }
+
+                let total_packets = self.rts.total_packets();
+                let next = cts.next_packet();
+                if next == 0 || next > total_packets {
+                    return Err(self.abort(Error::Sequence, AbortReason::BadSequenceNumber));
+                }
+
+                let remaining = total_packets - next + 1;
+                self.next_packet = next;
+                self.offset = next - 1;
+                self.window = u8::try_from(remaining)
+                    .map_or(cts.packets(), |remaining| remaining.min(cts.packets()));
+                self.state = State::Sending;
+                return Ok(Some(DataPacketOffset::new(
+                    self.window,
+                    self.offset,
+                    self.rts.pgn(),
+                )));
+            }
+            ControlMessage::EndOfMessageAck(_) if self.state == State::EndOfMessageAck => {
+                self.state = State::Finished;
+            }
+            ControlMessage::ConnectionAbort(abort) => {
+                self.state = State::Aborted;
+                return Err((Error::Aborted(abort.reason()), None));
+            }
+            _ => {}
+        }
+
+        Ok(None)
+    }
+
+    /// Next packet granted by the receiver, if any.
+    ///
+    /// The last packet is padded with `0xFF`.
+    pub fn next_packet(&mut self) -> Option<DataTransfer> {
+        if self.state != State::Sending {
+            return None;
+        }
+
+        // at most 255 packets per window
+        let sequence = (self.next_packet - self.offset) as u8;
+        let packet = packet(self.data, (self.next_packet - 1) as usize, sequence);
+
+        self.window -= 1;
+        if self.next_packet == self.rts.total_packets() {
+            self.state = State::EndOfMessageAck;
+        } else {
+            self.next_packet += 1;
+            if self.window == 0 {
+                self.state = State::ClearToSend;
+            }
+        }
+
+        Some(packet)
+    }
+
+    fn abort(&mut self, error: Error, reason: AbortReason) -> (Error, Option<ConnectionAbort>) {
+        self.state = State::Aborted;
+        let abort = ConnectionAbort::new(reason, AbortSenderRole::Sender, self.rts.pgn());
+        (error, Some(abort))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec;
+
+    #[test]
+    fn messages() {
+        let pgn = Pgn::Other(0xE700);
+        assert_eq!(RequestToSend::new(1785, pgn), None);
+        assert_eq!(RequestToSend::new(MAX_SIZE + 1, pgn), None);
+
+        let rts = RequestToSend::new(100_000, pgn).unwrap();
+        assert_eq!(
+            rts.to_bytes(),
+            [20, 0xA0, 0x86, 0x01, 0x00, 0x00, 0xE7, 0x00]
+        );
+        assert_eq!(rts.total_packets(), 14286);
+
+        let dpo = DataPacketOffset::new(255, 0x01_0203, pgn);
+        assert_eq!(
+            dpo.to_bytes(),
+            [22, 255, 0x03, 0x02, 0x01, 0x00, 0xE7, 0x00]
+        );
+
+        for msg in [
+            ControlMessage::RequestToSend(rts.clone()),
+            ControlMessage::ClearToSend(ClearToSend::new(16, 300, pgn)),
+            ControlMessage::DataPacketOffset(dpo),
+            ControlMessage::EndOfMessageAck(EndOfMessageAck::new(100_000, pgn)),
+        ] {
+            let bytes = match &msg {
+                ControlMessage::RequestToSend(m) => m.to_bytes(),
+                ControlMessage::ClearToSend(m) => m.to_bytes(),
+                ControlMessage::DataPacketOffset(m) => m.to_bytes(),
+                ControlMessage::EndOfMessageAck(m) => m.to_bytes(),
+                ControlMessage::ConnectionAbort(m) => m.to_bytes(),
+            };
+            assert_eq!(ControlMessage::try_from(&bytes[..]), Ok(msg));
+        }
+
+        // TP.CM messages are not ETP.CM messages
+        assert!(ControlMessage::try_from(&[16, 9, 0, 2, 0xFF, 0x00, 0xE7, 0x00][..]).is_err());
+    }
+
+    #[test]
+    fn session() {
+        let pgn = Pgn::Other(0xE700);
+        let payload: vec::Vec<u8> = (0..2000).map(|i| i as u8).collect();
+        let mut sender = OutgoingTransfer::new(&payload, pgn).unwrap();
+        let mut receiver =
+            Transfer::with_storage(sender.rts().clone(), vec::Vec::new()).with_packets_per_cts(200);
+
+        let mut cts = receiver.cts().unwrap();
+        let mut windows = 0;
+        let end = loop {
+            let dpo = sender
+                .handle(&ControlMessage::ClearToSend(cts.clone()))
+                .unwrap()
+                .unwrap();
+            receiver
+                .handle(&ControlMessage::DataPacketOffset(dpo))
+                .unwrap();
+            windows += 1;
+
+            let mut response = None;
+            while let Some(packet) = sender.next_packet() {
+                response = receiver.next(packet).unwrap();
+            }
+            match response.unwrap() {
+                Response::Cts(next) => cts = next,
+                Response::End(end) => break end,
+            }
+        };
+
+        // 286 packets in windows of 200
+        assert_eq!(windows, 2);
+        assert_eq!(end.total_size(), 2000);
+        sender
+            .handle(&ControlMessage::EndOfMessageAck(end))
+            .unwrap();
+        assert!(sender.is_finished());
+        assert_eq!(receiver.finished(), Some(payload.as_slice()));
+    }
+
+    #[test]
+    fn errors() {
+        let pgn = Pgn::Other(0xE700);
+        let rts = RequestToSend::new(2000, pgn).unwrap();
+        let mut receiver = Transfer::with_storage(rts.clone(), vec::Vec::new());
+
+        // data before the DPO
+        let (error, abort) = receiver.next(DataTransfer::new(1, [0; 7])).unwrap_err();
+        assert_eq!(error, Error::Sequence);
+        assert_eq!(abort.reason(), AbortReason::UnexpectedDataTransfer);
+
+        // DPO beyond the packets received so far
+        let mut receiver = Transfer::with_storage(rts.clone(), vec::Vec::new());
+        let dpo = ControlMessage::DataPacketOffset(DataPacketOffset::new(10, 5, pgn));
+        let (_, abort) = receiver.handle(&dpo).unwrap_err();
+        assert_eq!(abort.unwrap().reason(), AbortReason::BadDataPacketOffset);
+
+        // more packets than granted
+        let mut receiver =
+            Transfer::with_storage(rts.clone(), vec::Vec::new()).with_packets_per_cts(2);
+        let dpo = ControlMessage::DataPacketOffset(DataPacketOffset::new(3, 0, pgn));
+        let (_, abort) = receiver.handle(&dpo).unwrap_err();
+        assert_eq!(
+            abort.unwrap().reason(),
+            AbortReason::DataPacketOffsetExceedsCts
+        );
+
+        // sequence numbers are relative to the offset
+        let mut receiver = Transfer::with_storage(rts, vec::Vec::new()).with_packets_per_cts(2);
+        let dpo = ControlMessage::DataPacketOffset(DataPacketOffset::new(2, 0, pgn));
+        receiver.handle(&dpo).unwrap();
+        receiver.next(DataTransfer::new(1, [0; 7])).unwrap();
+        let response = receiver.next(DataTransfer::new(2, [0; 7])).unwrap();
+        assert!(matches!(response, Some(Response::Cts(cts)) if cts.next_packet() == 3));
+        let dpo = ControlMessage::DataPacketOffset(DataPacketOffset::new(2, 2, pgn));
+        receiver.handle(&dpo).unwrap();
+        assert!(receiver.next(DataTransfer::new(1, [0; 7])).is_ok());
+        assert!(receiver.next(DataTransfer::new(3, [0; 7])).is_err());
+
+        let abort = ConnectionAbort::new(AbortReason::Timeout, AbortSenderRole::Receiver, pgn);
+        let mut sender = OutgoingTransfer::new(&[0; 2000], pgn).unwrap();
+        assert_eq!(
+            sender.handle(&ControlMessage::ConnectionAbort(abort)),
+            Err((Error::Aborted(AbortReason::Timeout), None))
+        );
+        assert!(sender.is_aborted());
+        assert!(OutgoingTransfer::new(&[0; 1785], pgn).is_none());
+    }
+}
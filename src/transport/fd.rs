@@ -0,0 +1,1164 @@
+//! CAN FD transport protocol (J1939-22)
+//!
+//! Moves payloads of up to [`MAX_SIZE`] bytes in segments of [`SEGMENT`]
+//! bytes, each carried by a 64-byte FD.TP.DT frame. Connection management
+//! messages are 12 bytes long. Every message carries a session number, so
+//! one pair of addresses can run up to 16 sessions at once.
+//!
+//! The destination specific session ends with an end of message status
+//! (EOMS) from the originator, answered with an end of message acknowledge
+//! (EOMA). Unlike J1939-21 transport sessions, these do not track timeouts.
+
+use super::assembly::Segments;
+use super::{AbortReason, AbortSenderRole, CompletedTransfer, Error, TransferStorage};
+use crate::error::{DecodeError, exact};
+use crate::id::Pgn;
+
+/// Payload bytes carried by each data transfer.
+pub const SEGMENT: usize = 60;
+
+/// Largest payload, whose size must fit in 24 bits.
+pub const MAX_SIZE: u32 = 0xFF_FFFF;
+
+/// Segments of all FD.TP sessions.
+const SEGMENTS: Segments = Segments::uniform(SEGMENT);
+
+/// Number of segments needed for `total_size` bytes.
+const fn total_segments(total_size: u32) -> u32 {
+    total_size.div_ceil(SEGMENT as u32)
+}
+
+/// 24-bit number starting at byte `at`.
+fn u24(value: &[u8; 12], at: usize) -> u32 {
+    u32::from_le_bytes([value[at], value[at + 1], value[at + 2], 0x00])
+}
+
+/// PGN carried in bytes 9 to 11 of a connection management message.
+fn pgn(value: &[u8; 12]) -> Pgn {
+    Pgn::from(u24(value, 8))
+}
+
+/// Encode a connection management message.
+///
+/// The first byte holds the session number in its upper and the control
+/// code in its lower four bits.
+const fn encode(session: u8, control: u8, fields: [u8; 7], pgn: Pgn) -> [u8; 12] {
+    let pgn = pgn.as_raw().to_le_bytes();
+    [
+        (session << 4) | control,
+        fields[0],
+        fields[1],
+        fields[2],
+        fields[3],
+        fields[4],
+        fields[5],
+        fields[6],
+        pgn[0],
+        pgn[1],
+        pgn[2],
+        0xFF, // reserved
+    ]
+}
+
+/// Size and segment count fields shared by RTS, BAM, EOMS and EOMA.
+const fn size_fields(total_size: u32, byte8: u8) -> [u8; 7] {
+    let size = total_size.to_le_bytes();
+    let segments = total_segments(total_size).to_le_bytes();
+    [
+        size[0],
+        size[1],
+        size[2],
+        segments[0],
+        segments[1],
+        segments[2],
+        byte8,
+    ]
+}
+
+/// Session numbers only have four bits.
+const fn session_number(session: u8) -> u8 {
+    session & 0x0F
+}
+
+/// Request to send (FD.TP.CM_RTS) message.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RequestToSend {
+    session: u8,
+    total_size: u32,
+    max_segments_per_cts: Option<u8>,
+    pgn: Pgn,
+}
+
+impl RequestToSend {
+    const CONTROL: u8 = 0;
+
+    /// Create a new request to send message for `session`, counted modulo
+    /// 16.
+    ///
+    /// Returns `None` if `total_size` is 0 or above [`MAX_SIZE`], or
+    /// `max_segments_per_cts` is 0 or 255. No limit is designated with
+    /// `None`.
+    pub const fn new(
+        session: u8,
+        total_size: u32,
+        max_segments_per_cts: Option<u8>,
+        pgn: Pgn,
+    ) -> Option<Self> {
+        if total_size == 0 || total_size > MAX_SIZE {
+            return None;
+        }
+        if matches!(max_segments_per_cts, Some(0 | 255)) {
+            return None;
+        }
+        Some(Self {
+            session: session_number(session),
+            total_size,
+            max_segments_per_cts,
+            pgn,
+        })
+    }
+
+    /// Encode the message.
+    pub const fn to_bytes(&self) -> [u8; 12] {
+        let max = match self.max_segments_per_cts {
+            Some(max) => max,
+            None => 0xFF,
+        };
+        encode(
+            self.session,
+            Self::CONTROL,
+            size_fields(self.total_size, max),
+            self.pgn,
+        )
+    }
+
+    /// Session number.
+    pub const fn session(&self) -> u8 {
+        self.session
+    }
+
+    /// Total message size in bytes.
+    pub const fn total_size(&self) -> u32 {
+        self.total_size
+    }
+
+    /// Number of segments needed for the message.
+    pub const fn total_segments(&self) -> u32 {
+        total_segments(self.total_size)
+    }
+
+    /// The maximum number of segments the sender is allowed to respond with
+    /// for each CTS. `None` designates no limit.
+    pub const fn max_segments_per_cts(&self) -> Option<u8> {
+        self.max_segments_per_cts
+    }
+
+    /// Tranfer contents PGN.
+    pub const fn pgn(&self) -> Pgn {
+        self.pgn
+    }
+
+    fn from_raw(value: &[u8; 12]) -> Self {
+        Self {
+            session: value[0] >> 4,
+            total_size: u24(value, 1),
+            max_segments_per_cts: match value[7] {
+                0xFF => None,
+                max => Some(max),
+            },
+            pgn: pgn(value),
+        }
+    }
+}
+
+/// Broadcast announce (FD.TP.CM_BAM) message.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BroadcastAnnounce {
+    session: u8,
+    total_size: u32,
+    pgn: Pgn,
+}
+
+impl BroadcastAnnounce {
+    const CONTROL: u8 = 4;
+
+    /// Create a new broadcast announce message for `session`, counted modulo
+    /// 16.
+    ///
+    /// Returns `None` if `total_size` is 0 or above [`MAX_SIZE`].
+    pub const fn new(session: u8, total_size: u32, pgn: Pgn) -> Option<Self> {
+        if total_size == 0 || total_size > MAX_SIZE {
+            return None;
+        }
+        Some(Self {
+            session: session_number(session),
+            total_size,
+            pgn,
+        })
+    }
+
+    /// Encode the message.
+    pub const fn to_bytes(&self) -> [u8; 12] {
+        encode(
+            self.session,
+            Self::CONTROL,
+            size_fields(self.total_size, 0xFF),
+            self.pgn,
+        )
+    }
+
+    /// Session number.
+    pub const fn session(&self) -> u8 {
+        self.session
+    }
+
+    /// Total message size in bytes.
+    pub const fn total_size(&self) -> u32 {
+        self.total_size
+    }
+
+    /// Number of segments needed for the message.
+    pub const fn total_segments(&self) -> u32 {
+        total_segments(self.total_size)
+    }
+
+    /// Tranfer contents PGN.
+    pub const fn pgn(&self) -> Pgn {
+        self.pgn
+    }
+
+    fn from_raw(value: &[u8; 12]) -> Self {
+        Self {
+            session: value[0] >> 4,
+            total_size: u24(value, 1),
+            pgn: pgn(value),
+        }
+    }
+}
+
+/// Clear to send (FD.TP.CM_CTS) message.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClearToSend {
+    session: u8,
+    segments: u8,
+    next_segment: u32,
+    pgn: Pgn,
+}
+
+impl ClearToSend {
+    const CONTROL: u8 = 1;
+
+    /// Create a new CTS message granting `segments` segments from segment
+    /// number `next_segment`, counted from 1.
+    ///
+    /// A CTS granting no segments holds the connection open.
+    pub const fn new(session: u8, segments: u8, next_segment: u32, pgn: Pgn) -> Self {
+        Self {
+            session: session_number(session),
+            segments,
+            next_segment: next_segment & 0xFF_FFFF,
+            pgn,
+        }
+    }
+
+    /// Encode the message.
+    pub const fn to_bytes(&self) -> [u8; 12] {
+        let next = self.next_segment.to_le_bytes();
+        encode(
+            self.session,
+            Self::CONTROL,
+            [self.segments, next[0], next[1], next[2], 0xFF, 0xFF, 0xFF],
+            self.pgn,
+        )
+    }
+
+    /// Session number.
+    pub const fn session(&self) -> u8 {
+        self.session
+    }
+
+    /// Number of segments that can be sent.
+    pub const fn segments(&self) -> u8 {
+        self.segments
+    }
+
+    /// Number of the next segment, counted from 1.
+    pub const fn next_segment(&self) -> u32 {
+        self.next_segment
+    }
+
+    /// Tranfer contents PGN.
+    pub const fn pgn(&self) -> Pgn {
+        self.pgn
+    }
+
+    fn from_raw(value: &[u8; 12]) -> Self {
+        Self {
+            session: value[0] >> 4,
+            segments: value[1],
+            next_segment: u24(value, 2),
+            pgn: pgn(value),
+        }
+    }
+}
+
+/// End of message status (FD.TP.CM_EOMS) message, sent by the originator
+/// after the last segment.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EndOfMessageStatus {
+    session: u8,
+    total_size: u32,
+    pgn: Pgn,
+}
+
+impl EndOfMessageStatus {
+    const CONTROL: u8 = 2;
+
+    /// Create a new end of message status message.
+    pub const fn new(session: u8, total_size: u32, pgn: Pgn) -> Self {
+        Self {
+            session: session_number(session),
+            total_size: total_size & MAX_SIZE,
+            pgn,
+        }
+    }
+
+    /// Encode the message.
+    pub const fn to_bytes(&self) -> [u8; 12] {
+        encode(
+            self.session,
+            Self::CONTROL,
+            size_fields(self.total_size, 0xFF),
+            self.pgn,
+        )
+    }
+
+    /// Session number.
+    pub const fn session(&self) -> u8 {
+        self.session
+    }
+
+    /// Total message size in bytes.
+    pub const fn total_size(&self) -> u32 {
+        self.total_size
+    }
+
+    /// Tranfer contents PGN.
+    pub const fn pgn(&self) -> Pgn {
+        self.pgn
+    }
+
+    fn from_raw(value: &[u8; 12]) -> Self {
+        Self {
+            session: value[0] >> 4,
+            total_size: u24(value, 1),
+            pgn: pgn(value),
+        }
+    }
+}
+
+/// End of message acknowledge (FD.TP.CM_EOMA) message.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EndOfMessageAck {
+    session: u8,
+    total_size: u32,
+    pgn: Pgn,
+}
+
+impl EndOfMessageAck {
+    const CONTROL: u8 = 3;
+
+    /// Create a new end of message acknowledge message.
+    pub const fn new(session: u8, total_size: u32, pgn: Pgn) -> Self {
+        Self {
+            session: session_number(session),
+            total_size: total_size & MAX_SIZE,
+            pgn,
+        }
+    }
+
+    /// Encode the message.
+    pub const fn to_bytes(&self) -> [u8; 12] {
+        encode(
+            self.session,
+            Self::CONTROL,
+            size_fields(self.total_size, 0xFF),
+            self.pgn,
+        )
+    }
+
+    /// Session number.
+    pub const fn session(&self) -> u8 {
+        self.session
+    }
+
+    /// Total message size in bytes.
+    pub const fn total_size(&self) -> u32 {
+        self.total_size
+    }
+
+    /// Tranfer contents PGN.
+    pub const fn pgn(&self) -> Pgn {
+        self.pgn
+    }
+
+    fn from_raw(value: &[u8; 12]) -> Self {
+        Self {
+            session: value[0] >> 4,
+            total_size: u24(value, 1),
+            pgn: pgn(value),
+        }
+    }
+}
+
+/// Connection abort (FD.TP.CM_Abort) message.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConnectionAbort {
+    session: u8,
+    reason: AbortReason,
+    sender_role: AbortSenderRole,
+    pgn: Pgn,
+}
+
+impl ConnectionAbort {
+    const CONTROL: u8 = 15;
+
+    /// Create a new connection abort message.
+    pub const fn new(
+        session: u8,
+        reason: AbortReason,
+        sender_role: AbortSenderRole,
+        pgn: Pgn,
+    ) -> Self {
+        Self {
+            session: session_number(session),
+            reason,
+            sender_role,
+            pgn,
+        }
+    }
+
+    /// Encode the message.
+    pub const fn to_bytes(&self) -> [u8; 12] {
+        encode(
+            self.session,
+            Self::CONTROL,
+            [
+                self.reason.as_raw(),
+                0xFC | self.sender_role as u8,
+                0xFF,
+                0xFF,
+                0xFF,
+                0xFF,
+                0xFF,
+            ],
+            self.pgn,
+        )
+    }
+
+    /// Session number.
+    pub const fn session(&self) -> u8 {
+        self.session
+    }
+
+    /// Abort reason.
+    pub const fn reason(&self) -> AbortReason {
+        self.reason
+    }
+
+    /// Abort sender role.
+    pub const fn sender_role(&self) -> AbortSenderRole {
+        self.sender_role
+    }
+
+    /// Tranfer contents PGN.
+    pub const fn pgn(&self) -> Pgn {
+        self.pgn
+    }
+
+    fn from_raw(value: &[u8; 12]) -> Self {
+        Self {
+            session: value[0] >> 4,
+            reason: AbortReason::from_raw(value[1]),
+            sender_role: AbortSenderRole::try_from(value[2] & 0b0000_0011)
+                .unwrap_or(AbortSenderRole::NotSpecified),
+            pgn: pgn(value),
+        }
+    }
+}
+
+/// FD.TP connection management message.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ControlMessage {
+    RequestToSend(RequestToSend),
+    ClearToSend(ClearToSend),
+    EndOfMessageStatus(EndOfMessageStatus),
+    EndOfMessageAck(EndOfMessageAck),
+    BroadcastAnnounce(BroadcastAnnounce),
+    ConnectionAbort(ConnectionAbort),
+}
+
+impl ControlMessage {
+    /// Session number.
+    pub fn session(&self) -> u8 {
+        match self {
+            Self::RequestToSend(m) => m.session(),
+            Self::ClearToSend(m) => m.session(),
+            Self::EndOfMessageStatus(m) => m.session(),
+            Self::EndOfMessageAck(m) => m.session(),
+            Self::BroadcastAnnounce(m) => m.session(),
+            Self::ConnectionAbort(m) => m.session(),
+        }
+    }
+
+    /// Tranfer contents PGN.
+    pub fn pgn(&self) -> Pgn {
+        match self {
+            Self::RequestToSend(m) => m.pgn(),
+            Self::ClearToSend(m) => m.pgn(),
+            Self::EndOfMessageStatus(m) => m.pgn(),
+            Self::EndOfMessageAck(m) => m.pgn(),
+            Self::BroadcastAnnounce(m) => m.pgn(),
+            Self::ConnectionAbort(m) => m.pgn(),
+        }
+    }
+
+    /// Encode the message.
+    pub fn to_bytes(&self) -> [u8; 12] {
+        match self {
+            Self::RequestToSend(m) => m.to_bytes(),
+            Self::ClearToSend(m) => m.to_bytes(),
+            Self::EndOfMessageStatus(m) => m.to_bytes(),
+            Self::EndOfMessageAck(m) => m.to_bytes(),
+            Self::BroadcastAnnounce(m) => m.to_bytes(),
+            Self::ConnectionAbort(m) => m.to_bytes(),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ControlMessage {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let raw = exact::<12>(value)?;
+        match raw[0] & 0x0F {
+            RequestToSend::CONTROL => Ok(Self::RequestToSend(RequestToSend::from_raw(raw))),
+            ClearToSend::CONTROL => Ok(Self::ClearToSend(ClearToSend::from_raw(raw))),
+            EndOfMessageStatus::CONTROL => {
+                Ok(Self::EndOfMessageStatus(EndOfMessageStatus::from_raw(raw)))
+            }
+            EndOfMessageAck::CONTROL => Ok(Self::EndOfMessageAck(EndOfMessageAck::from_raw(raw))),
+            BroadcastAnnounce::CONTROL => {
+                Ok(Self::BroadcastAnnounce(BroadcastAnnounce::from_raw(raw)))
+            }
+            ConnectionAbort::CONTROL => Ok(Self::ConnectionAbort(ConnectionAbort::from_raw(raw))),
+            control => Err(DecodeError::Mux(control)),
+        }
+    }
+}
+
+/// Data transfer (FD.TP.DT) message.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DataTransfer {
+    session: u8,
+    segment: u32,
+    data: [u8; SEGMENT],
+}
+
+impl DataTransfer {
+    /// Create a new data transfer message carrying segment number `segment`
+    /// of `session`, counted from 1.
+    ///
+    /// The last segment should be padded with `0xFF`.
+    pub const fn new(session: u8, segment: u32, data: [u8; SEGMENT]) -> Self {
+        Self {
+            session: session_number(session),
+            segment: segment & 0xFF_FFFF,
+            data,
+        }
+    }
+
+    /// Encode the message.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0xFF; 64];
+        bytes[0] = (self.session << 4) | 0x0F;
+        bytes[1..4].copy_from_slice(&self.segment.to_le_bytes()[..3]);
+        bytes[4..].copy_from_slice(&self.data);
+        bytes
+    }
+
+    /// Session number.
+    pub const fn session(&self) -> u8 {
+        self.session
+    }
+
+    /// Segment number, counted from 1.
+    pub const fn segment(&self) -> u32 {
+        self.segment
+    }
+
+    /// Segment data.
+    pub const fn data(&self) -> &[u8; SEGMENT] {
+        &self.data
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for DataTransfer {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let value = exact::<64>(value)?;
+        let mut data = [0; SEGMENT];
+        data.copy_from_slice(&value[4..]);
+        Ok(Self {
+            session: value[0] >> 4,
+            segment: u32::from_le_bytes([value[1], value[2], value[3], 0x00]),
+            data,
+        })
+    }
+}
+
+/// FD.TP connection management message sent in response to the originator.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Response {
+    Cts(ClearToSend),
+    End(EndOfMessageAck),
+}
+
+impl From<&Response> for [u8; 12] {
+    fn from(value: &Response) -> Self {
+        match value {
+            Response::Cts(cts) => cts.to_bytes(),
+            Response::End(end) => end.to_bytes(),
+        }
+    }
+}
+
+/// An ongoing FD.TP transfer, received into `S`.
+///
+/// Destination specific transfers start by sending [`Transfer::cts`], and
+/// acknowledge the payload when the originator reports the end of the
+/// message. Broadcast transfers never respond.
+#[derive(Debug)]
+pub struct Transfer<S> {
+    pgn: Pgn,
+    session: u8,
+    total_size: u32,
+    rx_segments: u32,
+    /// Segments per CTS, 255 for no limit.
+    max_segments_per_cts: u8,
+    /// Segments received before the current window.
+    window_start: u32,
+    broadcast: bool,
+    aborted: bool,
+    source: u8,
+    storage: S,
+}
+
+impl<S: TransferStorage> Transfer<S> {
+    /// Create a new transfer from a RTS message received from the sender,
+    /// reassembling the payload into `storage`.
+    pub fn with_storage(rts: RequestToSend, storage: S) -> Self {
+        Self {
+            pgn: rts.pgn(),
+            session: rts.session(),
+            total_size: rts.total_size(),
+            rx_segments: 0,
+            max_segments_per_cts: rts.max_segments_per_cts().unwrap_or(255),
+            window_start: 0,
+            broadcast: false,
+            aborted: false,
+            source: 0xFE,
+            storage,
+        }
+    }
+
+    /// Create a new transfer from a RTS message received from the sender,
+    /// checking that `storage` can hold the payload.
+    ///
+    /// Returns the storage if it is too small.
+    pub fn try_with_storage(rts: RequestToSend, storage: S) -> Result<Self, S> {
+        let fits = usize::try_from(rts.total_size()).is_ok_and(|size| storage.capacity() >= size);
+        if !fits {
+            return Err(storage);
+        }
+        Ok(Self::with_storage(rts, storage))
+    }
+
+    /// Create a new transfer from a BAM message received from the sender,
+    /// reassembling the payload into `storage`.
+    pub fn broadcast(bam: BroadcastAnnounce, storage: S) -> Self {
+        Self {
+            pgn: bam.pgn(),
+            session: bam.session(),
+            total_size: bam.total_size(),
+            rx_segments: 0,
+            max_segments_per_cts: 255,
+            window_start: 0,
+            broadcast: true,
+            aborted: false,
+            source: 0xFE,
+            storage,
+        }
+    }
+
+    /// Record `source` as the address of the sender.
+    pub fn with_source(mut self, source: u8) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Address of the sender, `0xFE` until one is recorded.
+    pub fn source(&self) -> u8 {
+        self.source
+    }
+
+    /// Session number.
+    pub fn session(&self) -> u8 {
+        self.session
+    }
+
+    /// Check if the transfer was announced with a BAM message.
+    pub fn is_broadcast(&self) -> bool {
+        self.broadcast
+    }
+
+    /// The CTS granting the next window.
+    ///
+    /// Send it once the transfer was created. The following ones are
+    /// returned by [`Transfer::next`]. `None` for broadcasts and once all
+    /// segments were received.
+    pub fn cts(&self) -> Option<ClearToSend> {
+        let remaining = self.total_segments().checked_sub(self.rx_segments)?;
+        if self.broadcast || self.aborted || remaining == 0 {
+            return None;
+        }
+
+        let segments = u8::try_from(remaining).map_or(self.max_segments_per_cts, |remaining| {
+            remaining.min(self.max_segments_per_cts)
+        });
+        Some(ClearToSend::new(
+            self.session,
+            segments,
+            self.rx_segments + 1,
+            self.pgn,
+        ))
+    }
+
+    /// Feed the transfer with the next data transfer of its session.
+    ///
+    /// Data transfers of other sessions are ignored. Returns the CTS for
+    /// the next window once one was received completely.
+    pub fn next(
+        &mut self,
+        msg: &DataTransfer,
+    ) -> Result<Option<Response>, (Error, ConnectionAbort)> {
+        if msg.session() != self.session {
+            return Ok(None);
+        }
+        if self.aborted || self.rx_segments >= self.total_segments() {
+            return Err((
+                Error::PreviousAbort,
+                self.abort(AbortReason::UnexpectedDataTransfer),
+            ));
+        }
+        if msg.segment() != self.rx_segments + 1 {
+            return Err((Error::Sequence, self.abort(AbortReason::BadSequenceNumber)));
+        }
+
+        let mut result = SEGMENTS
+            .store(
+                &mut self.storage,
+                self.rx_segments as usize,
+                self.total_size as usize,
+                msg.data(),
+            )
+            .map(|_| ());
+        self.rx_segments += 1;
+
+        let window = u32::from(self.max_segments_per_cts);
+        let response = if self.rx_segments == self.total_segments() {
+            result = result.and_then(|_| self.storage.finish());
+            None
+        } else if !self.broadcast
+            && self.max_segments_per_cts != 255
+            && self.rx_segments - self.window_start == window
+        {
+            self.window_start = self.rx_segments;
+            self.cts().map(Response::Cts)
+        } else {
+            None
+        };
+
+        if let Err(e) = result {
+            return Err((e, self.abort(AbortReason::Custom)));
+        }
+        Ok(response)
+    }
+
+    /// Feed the transfer with a connection management message from the
+    /// sender.
+    ///
+    /// Answers the end of message status with the acknowledge once the
+    /// whole payload was stored. Messages of other sessions or PGNs are
+    /// ignored. A peer abort ends the transfer with [`Error::Aborted`].
+    pub fn handle(&mut self, msg: &ControlMessage) -> Result<Option<Response>, Error> {
+        if msg.session() != self.session || msg.pgn() != self.pgn || self.aborted {
+            return Ok(None);
+        }
+
+        match msg {
+            ControlMessage::EndOfMessageStatus(_) if self.is_finished() && !self.broadcast => {
+                Ok(Some(Response::End(EndOfMessageAck::new(
+                    self.session,
+                    self.total_size,
+                    self.pgn,
+                ))))
+            }
+            ControlMessage::ConnectionAbort(abort) => {
+                self.aborted = true;
+                Err(Error::Aborted(abort.reason()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Abort the transfer, returning the connection abort to send unless
+    /// the transfer is a broadcast.
+    pub fn abort(&mut self, reason: AbortReason) -> ConnectionAbort {
+        self.aborted = true;
+        ConnectionAbort::new(self.session, reason, AbortSenderRole::Receiver, self.pgn)
+    }
+
+    /// Check if the whole payload has been stored.
+    pub fn is_finished(&self) -> bool {
+        self.rx_segments >= self.total_segments() && !self.aborted
+    }
+
+    /// Return read-only access to the payload once the transfer is
+    /// complete. Storage that can not be read in place returns `None`.
+    pub fn finished(&self) -> Option<&[u8]> {
+        if self.is_finished() {
+            self.storage.read(self.total_size as usize)
+        } else {
+            None
+        }
+    }
+
+    /// The payload with its PGN and sender, once the transfer is complete.
+    pub fn completed(&self) -> Option<CompletedTransfer<&[u8]>> {
+        Some(CompletedTransfer {
+            pgn: self.pgn,
+            source: self.source,
+            data: self.finished()?,
+        })
+    }
+
+    /// Consume the transfer and hand back its storage for reuse.
+    pub fn into_storage(mut self) -> S {
+        self.storage.reset();
+        self.storage
+    }
+
+    fn total_segments(&self) -> u32 {
+        total_segments(self.total_size)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<S> defmt::Format for Transfer<S> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(
+            f,
+            "fd::Transfer {{ pgn: {}, session: {}, source: {=u8:#x}, total_size: {}, rx_segments: {} }}",
+            self.pgn,
+            self.session,
+            self.source,
+            self.total_size,
+            self.rx_segments
+        )
+    }
+}
+
+/// Progress of an outgoing transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum State {
+    ClearToSend,
+    Sending,
+    EndOfMessageStatus,
+    EndOfMessageAck,
+    Finished,
+    Aborted,
+}
+
+/// An outgoing destination specific FD.TP transfer.
+///
+/// Starts by sending [`OutgoingTransfer::rts`], then sends the segments the
+/// receiver grants with each CTS. After the last one, it sends
+/// [`OutgoingTransfer::end_of_message`] and waits for the acknowledge.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OutgoingTransfer<'a> {
+    data: &'a [u8],
+    rts: RequestToSend,
+    /// Number of the next segment.
+    next_segment: u32,
+    /// Segments left of the window granted by the last CTS.
+    window: u8,
+    state: State,
+}
+
+impl<'a> OutgoingTransfer<'a> {
+    /// Create a new transfer sending `data` as `pgn` in `session`.
+    ///
+    /// Returns `None` if the arguments are rejected by
+    /// [`RequestToSend::new`].
+    pub fn new(
+        data: &'a [u8],
+        session: u8,
+        max_segments_per_cts: Option<u8>,
+        pgn: Pgn,
+    ) -> Option<Self> {
+        let total_size = u32::try_from(data.len()).ok()?;
+        let rts = RequestToSend::new(session, total_size, max_segments_per_cts, pgn)?;
+        Some(Self {
+            data,
+            rts,
+            next_segment: 1,
+            window: 0,
+            state: State::ClearToSend,
+        })
+    }
+
+    /// The RTS message to send to open the session.
+    pub fn rts(&self) -> &RequestToSend {
+        &self.rts
+    }
+
+    /// Check if the receiver acknowledged the whole payload.
+    pub fn is_finished(&self) -> bool {
+        self.state == State::Finished
+    }
+
+    /// Check if the session was aborted by either side.
+    pub fn is_aborted(&self) -> bool {
+        self.state == State::Aborted
+    }
+
+    /// Feed the transfer with a connection management message from the
+    /// receiver.
+    ///
+    /// Messages of other sessions or PGNs are ignored. On failure, the
+    /// connection abort to send is returned alongside the error, unless the
+    /// receiver aborted the session itself.
+    pub fn handle(&mut self, msg: &ControlMessage) -> Result<(), (Error, Option<ConnectionAbort>)> {
+        if msg.session() != self.rts.session() || msg.pgn() != self.rts.pgn() {
+            return Ok(());
+        }
+        if matches!(self.state, State::Finished | State::Aborted) {
+            return Err((Error::PreviousAbort, None));
+        }
+
+        match msg {
+            ControlMessage::ClearToSend(cts) => {
+                if self.state == State::Sending {
+                    return Err(self.abort(Error::Sequence, AbortReason::CtsWhileDataTransfer));
+                }
+                if cts.segments() == 0 {
+                    // the receiver holds the connection open
+                    return Ok(());
+                }
+
+                let total = self.rts.total_segments();
+                let next = cts.next_segment();
+                if next == 0 || next > total {
+                    return Err(self.abort(Error::Sequence, AbortReason::BadSequenceNumber));
+                }
+
+                let remaining = total - next + 1;
+                self.next_segment = next;
+                self.window = u8::try_from(remaining)
+                    .map_or(cts.segments(), |remaining| remaining.min(cts.segments()));
+                self.state = State::Sending;
+            }
+            ControlMessage::EndOfMessageAck(_) if self.state == State::EndOfMessageAck => {
+                self.state = State::Finished;
+            }
+            ControlMessage::ConnectionAbort(abort) => {
+                self.state = State::Aborted;
+                return Err((Error::Aborted(abort.reason()), None));
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Next segment granted by the receiver, if any.
+    ///
+    /// The last segment is padded with `0xFF`.
+    pub fn next_packet(&mut self) -> Option<DataTransfer> {
+        if self.state != State::Sending {
+            return None;
+        }
+
+        let range = SEGMENTS.range((self.next_segment - 1) as usize, self.data.len());
+        let mut segment = [0xFF; SEGMENT];
+        segment[..range.len()].copy_from_slice(&self.data[range]);
+        let packet = DataTransfer::new(self.rts.session(), self.next_segment, segment);
+
+        self.window -= 1;
+        if self.next_segment == self.rts.total_segments() {
+            self.state = State::EndOfMessageStatus;
+        } else {
+            self.next_segment += 1;
+            if self.window == 0 {
+                self.state = State::ClearToSend;
+            }
+        }
+
+        Some(packet)
+    }
+
+    /// The end of message status to send once all segments were sent.
+    pub fn end_of_message(&mut self) -> Option<EndOfMessageStatus> {
+        if self.state != State::EndOfMessageStatus {
+            return None;
+        }
+
+        self.state = State::EndOfMessageAck;
+        Some(EndOfMessageStatus::new(
+            self.rts.session(),
+            self.rts.total_size(),
+            self.rts.pgn(),
+        ))
+    }
+
+    fn abort(&mut self, error: Error, reason: AbortReason) -> (Error, Option<ConnectionAbort>) {
+        self.state = State::Aborted;
+        let abort = ConnectionAbort::new(
+            self.rts.session(),
+            reason,
+            AbortSenderRole::Sender,
+            self.rts.pgn(),
+        );
+        (error, Some(abort))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn messages() {
+        let pgn = Pgn::Other(0xE700);
+        let rts = RequestToSend::new(3, 4000, Some(16), pgn).unwrap();
+        assert_eq!(
+            rts.to_bytes(),
+            [0x30, 0xA0, 0x0F, 0x00, 67, 0, 0, 16, 0x00, 0xE7, 0x00, 0xFF]
+        );
+        assert_eq!(RequestToSend::new(0, MAX_SIZE + 1, None, pgn), None);
+        assert_eq!(RequestToSend::new(0, 100, Some(0), pgn), None);
+
+        let abort = ConnectionAbort::new(3, AbortReason::Timeout, AbortSenderRole::Receiver, pgn);
+        for msg in [
+            ControlMessage::RequestToSend(rts),
+            ControlMessage::ClearToSend(ClearToSend::new(3, 16, 17, pgn)),
+            ControlMessage::EndOfMessageStatus(EndOfMessageStatus::new(3, 4000, pgn)),
+            ControlMessage::EndOfMessageAck(EndOfMessageAck::new(3, 4000, pgn)),
+            ControlMessage::BroadcastAnnounce(BroadcastAnnounce::new(3, 4000, pgn).unwrap()),
+            ControlMessage::ConnectionAbort(abort),
+        ] {
+            assert_eq!(ControlMessage::try_from(&msg.to_bytes()[..]), Ok(msg));
+        }
+        assert!(ControlMessage::try_from(&[0x35; 12][..]).is_err());
+
+        let dt = DataTransfer::new(3, 0x01_0203, [7; SEGMENT]);
+        let bytes = dt.to_bytes();
+        assert_eq!(bytes[..4], [0x3F, 0x03, 0x02, 0x01]);
+        assert_eq!(DataTransfer::try_from(&bytes[..]), Ok(dt));
+    }
+
+    #[test]
+    fn session() {
+        let pgn = Pgn::Other(0xE700);
+        let payload: Vec<u8> = (0..4000).map(|i| i as u8).collect();
+        let mut sender = OutgoingTransfer::new(&payload, 5, Some(16), pgn).unwrap();
+        let mut receiver = Transfer::with_storage(sender.rts().clone(), Vec::new());
+
+        // another session of the same pair is left alone
+        let other = DataTransfer::new(6, 1, [0; SEGMENT]);
+        assert_eq!(receiver.next(&other), Ok(None));
+
+        let mut cts = receiver.cts().unwrap();
+        let mut windows = 0;
+        loop {
+            sender
+                .handle(&ControlMessage::ClearToSend(cts.clone()))
+                .unwrap();
+            windows += 1;
+
+            let mut response = None;
+            while let Some(packet) = sender.next_packet() {
+                response = receiver.next(&packet).unwrap();
+            }
+            match response {
+                Some(Response::Cts(next)) => cts = next,
+                _ => break,
+            }
+        }
+
+        // 67 segments in windows of 16
+        assert_eq!(windows, 5);
+        let eoms = sender.end_of_message().unwrap();
+        let end = receiver
+            .handle(&ControlMessage::EndOfMessageStatus(eoms))
+            .unwrap()
+            .unwrap();
+        let Response::End(end) = end else {
+            panic!("expected an acknowledge");
+        };
+        sender
+            .handle(&ControlMessage::EndOfMessageAck(end))
+            .unwrap();
+        assert!(sender.is_finished());
+        assert_eq!(receiver.finished(), Some(payload.as_slice()));
+    }
+
+    #[test]
+    fn broadcast() {
+        let pgn = Pgn::Other(0xE700);
+        let bam = BroadcastAnnounce::new(1, 100, pgn).unwrap();
+        let mut receiver = Transfer::broadcast(bam, Vec::new());
+        assert_eq!(receiver.cts(), None);
+        assert_eq!(
+            receiver.next(&DataTransfer::new(1, 1, [1; SEGMENT])),
+            Ok(None)
+        );
+        let (error, _) = receiver
+            .next(&DataTransfer::new(1, 3, [2; SEGMENT]))
+            .unwrap_err();
+        assert_eq!(error, Error::Sequence);
+        assert!(!receiver.is_finished());
+    }
+}
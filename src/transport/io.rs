@@ -0,0 +1,208 @@
+//! Streaming transport payloads through [`embedded_io`]
+//!
+//! [`Sink`] writes the packets of a received session as they arrive and
+//! [`Source`] reads the packets of a session to send, so payloads can be
+//! piped between the bus and files, flash or sockets without buffering the
+//! whole message.
+
+use super::assembly::Segments;
+use super::{
+    AbortReason, AbortSenderRole, ConnectionAbort, DataTransfer, Error, RequestToSend, Response,
+    response,
+};
+use embedded_io::{Read, ReadExactError, Write};
+
+/// Error streaming a transport payload.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum StreamError<E> {
+    /// The session is broken.
+    Transport(Error),
+    /// The underlying reader or writer failed.
+    Io(E),
+}
+
+/// Receiving side of a transport session writing its payload to `W`.
+#[derive(Debug)]
+pub struct Sink<W> {
+    rts: RequestToSend,
+    rx_packets: u8,
+    inner: W,
+    abort: bool,
+}
+
+impl<W: Write> Sink<W> {
+    /// Create a new sink from a RTS message received from the sender.
+    pub fn new(rts: RequestToSend, inner: W) -> Self {
+        Self {
+            rts,
+            rx_packets: 0,
+            inner,
+            abort: false,
+        }
+    }
+
+    /// The RTS message this session was started from.
+    pub fn rts(&self) -> &RequestToSend {
+        &self.rts
+    }
+
+    /// Check if the whole payload has been written.
+    pub fn is_finished(&self) -> bool {
+        self.rx_packets >= self.rts.total_packets() && !self.abort
+    }
+
+    /// Unwrap the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Feed the sink with the next data transfer.
+    ///
+    /// The payload bytes of the packet are written before the response is
+    /// returned. The writer is flushed with the last packet.
+    pub fn next(
+        &mut self,
+        msg: DataTransfer,
+    ) -> Result<Option<Response>, (StreamError<W::Error>, ConnectionAbort)> {
+        if self.abort || self.rx_packets >= self.rts.total_packets() {
+            return Err(self.abort(
+                StreamError::Transport(Error::PreviousAbort),
+                AbortReason::UnexpectedDataTransfer,
+            ));
+        }
+
+        if self.rx_packets + 1 != msg.sequence() {
+            return Err(self.abort(
+                StreamError::Transport(Error::Sequence),
+                AbortReason::BadSequenceNumber,
+            ));
+        }
+
+        let range = Segments::TP.range(self.rx_packets.into(), self.rts.total_size().into());
+        let mut result = self.inner.write_all(&msg.data()[..range.len()]);
+
+        self.rx_packets += 1;
+        let response = response(&self.rts, self.rx_packets, 0);
+
+        if matches!(response, Some(Response::End(_))) {
+            result = result.and_then(|_| self.inner.flush());
+        }
+        if let Err(e) = result {
+            return Err(self.abort(StreamError::Io(e), AbortReason::CanceledBySystem));
+        }
+
+        Ok(response)
+    }
+
+    fn abort(
+        &mut self,
+        error: StreamError<W::Error>,
+        reason: AbortReason,
+    ) -> (StreamError<W::Error>, ConnectionAbort) {
+        self.abort = true;
+        let abort = ConnectionAbort::new(reason, AbortSenderRole::Receiver, self.rts.pgn());
+        (error, abort)
+    }
+}
+
+/// Sending side of a transport session reading its payload from `R`.
+///
+/// Yields the data transfer packets in order, padding the last one with
+/// `0xFF`.
+#[derive(Debug)]
+pub struct Source<R> {
+    inner: R,
+    remaining: u16,
+    sequence: u8,
+}
+
+impl<R: Read> Source<R> {
+    /// Create a new source reading `total_size` bytes.
+    pub fn new(inner: R, total_size: u16) -> Self {
+        Self {
+            inner,
+            remaining: total_size,
+            sequence: 0,
+        }
+    }
+
+    /// Bytes not read yet.
+    pub fn remaining(&self) -> u16 {
+        self.remaining
+    }
+
+    /// Unwrap the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Iterator for Source<R> {
+    type Item = Result<DataTransfer, ReadExactError<R::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 || self.sequence == u8::MAX {
+            return None;
+        }
+
+        let len = self.remaining.min(7);
+        let mut data = [0xFF; 7];
+        if let Err(e) = self.inner.read_exact(&mut data[..usize::from(len)]) {
+            self.remaining = 0;
+            return Some(Err(e));
+        }
+
+        self.remaining -= len;
+        self.sequence += 1;
+        Some(Ok(DataTransfer::new(self.sequence, data)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::Pgn;
+
+    #[test]
+    fn round_trip() {
+        let payload: std::vec::Vec<u8> = (0..16).collect();
+        let rts = RequestToSend::try_new(16, Some(2), Pgn::ProprietaryA).unwrap();
+
+        let mut sink = Sink::new(rts, std::vec::Vec::new());
+        let responses: std::vec::Vec<_> = Source::new(payload.as_slice(), 16)
+            .map(|dt| sink.next(dt.unwrap()).unwrap())
+            .collect();
+
+        assert!(matches!(responses[1], Some(Response::Cts(_))));
+        assert!(matches!(responses[2], Some(Response::End(_))));
+        assert!(sink.is_finished());
+        assert_eq!(sink.into_inner(), payload);
+    }
+
+    #[test]
+    fn errors() {
+        let rts = RequestToSend::try_new(9, None, Pgn::ProprietaryA).unwrap();
+        let mut source = Source::new([1, 2, 3].as_slice(), 9);
+        assert!(matches!(
+            source.next(),
+            Some(Err(ReadExactError::UnexpectedEof))
+        ));
+        assert!(source.next().is_none());
+
+        let mut buf = [0u8; 4];
+        let mut sink = Sink::new(rts.clone(), buf.as_mut_slice());
+        let dt = DataTransfer::new(2, [0; 7]);
+        assert!(matches!(
+            sink.next(dt),
+            Err((StreamError::Transport(Error::Sequence), _))
+        ));
+
+        // the writer runs out of space
+        let mut sink = Sink::new(rts, buf.as_mut_slice());
+        let (error, abort) = sink.next(DataTransfer::new(1, [0; 7])).unwrap_err();
+        assert!(matches!(error, StreamError::Io(_)));
+        assert_eq!(abort.reason(), AbortReason::CanceledBySystem);
+        assert!(!sink.is_finished());
+    }
+}
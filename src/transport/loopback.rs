@@ -0,0 +1,285 @@
+//! In-memory loopback of a transport session
+//!
+//! [`Loopback`] connects an [`OutgoingTransfer`] to the receiving
+//! [`Transfer`] through a frame queue, so applications can be tested against
+//! the whole session without hardware. Data transfers can be dropped,
+//! repeated or reordered on the way with [`Fault`]s.
+
+use super::{ClearToSend, ControlMessage, Error, OutgoingTransfer, Transfer, TransferStorage};
+use crate::id::{Id, IdBuilder, Pgn};
+use crate::time::{Duration, Instant};
+use std::collections::VecDeque;
+use std::vec::Vec;
+
+/// Time a frame takes on the bus.
+const FRAME_TIME: Duration = Duration::from_millis(1);
+
+/// Disturbance of a data transfer, applied to the first copy sent with the
+/// sequence number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fault {
+    /// The packet is lost.
+    Drop(u8),
+    /// The packet arrives twice.
+    Duplicate(u8),
+    /// The packet arrives after the one sent next.
+    Swap(u8),
+}
+
+/// A transport session between an originator and a receiver in memory.
+///
+/// Frames carry the addresses recorded on the receiver with
+/// [`Transfer::with_peers`]. Time only advances as frames are delivered and,
+/// once both sides wait, to the earliest deadline, so timeouts happen
+/// without waiting for them.
+#[derive(Debug)]
+pub struct Loopback<'a, 'b, S> {
+    sender: OutgoingTransfer<'a>,
+    receiver: Transfer<'b, S>,
+    faults: Vec<Fault>,
+    frames: VecDeque<(Id, [u8; 8])>,
+    /// Packet held back by a swap.
+    held: Option<(Id, [u8; 8])>,
+    error: Option<Error>,
+    now: Instant,
+}
+
+impl<'a, 'b, S: TransferStorage> Loopback<'a, 'b, S> {
+    /// Connect `sender` to `receiver`, which must be created from the RTS of
+    /// the sender.
+    pub fn new(sender: OutgoingTransfer<'a>, receiver: Transfer<'b, S>) -> Self {
+        Self {
+            sender,
+            receiver,
+            faults: Vec::new(),
+            frames: VecDeque::new(),
+            held: None,
+            error: None,
+            now: Instant::from_millis(0),
+        }
+    }
+
+    /// Disturb the data transfers with `fault`.
+    pub fn with_fault(mut self, fault: Fault) -> Self {
+        self.faults.push(fault);
+        self
+    }
+
+    /// The originator side of the session.
+    pub fn sender(&self) -> &OutgoingTransfer<'a> {
+        &self.sender
+    }
+
+    /// The receiving side of the session.
+    pub fn receiver(&self) -> &Transfer<'b, S> {
+        &self.receiver
+    }
+
+    /// Unwrap the receiving side, as to take its payload.
+    pub fn into_receiver(self) -> Transfer<'b, S> {
+        self.receiver
+    }
+
+    /// Time the session has taken so far.
+    pub fn now(&self) -> Instant {
+        self.now
+    }
+
+    /// Run the session until both sides are done.
+    ///
+    /// Returns the first error of either side. Connection aborts are
+    /// delivered before returning, so both sides end up aborted.
+    pub fn run(&mut self) -> Result<(), Error> {
+        let _ = self.sender.poll(self.now);
+        let _ = self.receiver.poll(self.now);
+        let rts = self.sender.rts();
+        let window = rts
+            .max_packets_per_response()
+            .unwrap_or(rts.total_packets());
+        let cts = ClearToSend::new(Some(window), 1, rts.pgn());
+        self.respond(cts.to_bytes());
+
+        loop {
+            if let Some((id, data)) = self.frames.pop_front() {
+                self.now = self.now + FRAME_TIME;
+                self.deliver(id, &data);
+                continue;
+            }
+
+            if self.error.is_none()
+                && let Some(dt) = self.sender.next_packet()
+            {
+                self.transmit(dt.sequence(), dt.to_bytes());
+                continue;
+            }
+
+            if let Some(frame) = self.held.take() {
+                self.frames.push_back(frame);
+                continue;
+            }
+
+            if let Some(error) = self.error {
+                return Err(error);
+            }
+            if self.sender.is_finished() && self.receiver.is_finished() {
+                return Ok(());
+            }
+
+            let deadline = match (self.sender.deadline(), self.receiver.deadline()) {
+                (Some(a), Some(b)) => a.min(b),
+                (Some(deadline), None) | (None, Some(deadline)) => deadline,
+                (None, None) => return Err(Error::Timeout),
+            };
+            self.now = self.now.max(deadline);
+            self.poll();
+        }
+    }
+
+    /// Hand a data transfer to the bus, applying the faults.
+    fn transmit(&mut self, sequence: u8, data: [u8; 8]) {
+        let Some(id) = self.id(Pgn::TransportProtocolDataTransfer, false) else {
+            return;
+        };
+        let frame = (id, data);
+        let fault = self
+            .faults
+            .iter()
+            .position(|fault| {
+                matches!(fault, Fault::Drop(s) | Fault::Duplicate(s) | Fault::Swap(s) if *s == sequence)
+            })
+            .map(|index| self.faults.remove(index));
+
+        match fault {
+            Some(Fault::Drop(_)) => {}
+            Some(Fault::Duplicate(_)) => {
+                self.frames.push_back(frame);
+                self.frames.push_back(frame);
+            }
+            Some(Fault::Swap(_)) => self.held = Some(frame),
+            None => {
+                self.frames.push_back(frame);
+                if let Some(held) = self.held.take() {
+                    self.frames.push_back(held);
+                }
+            }
+        }
+    }
+
+    /// Deliver a frame to the side it is directed to.
+    fn deliver(&mut self, id: Id, data: &[u8; 8]) {
+        if id.sa() == self.receiver.originator() {
+            match self.receiver.handle_frame(id, data) {
+                Ok(Some(response)) => self.respond((&response).into()),
+                Ok(None) => {}
+                Err((error, abort)) => {
+                    self.error.get_or_insert(error);
+                    if let Some(abort) = abort {
+                        self.respond(abort.to_bytes());
+                    }
+                }
+            }
+        } else if let Ok(msg) = ControlMessage::try_from(&data[..])
+            && let Err((error, abort)) = self.sender.handle(&msg)
+        {
+            self.error.get_or_insert(error);
+            if let Some(abort) = abort {
+                self.request(abort.to_bytes());
+            }
+        }
+        self.poll();
+    }
+
+    /// Advance the timers of both sides, sending the aborts of timeouts.
+    fn poll(&mut self) {
+        if let Err((error, abort)) = self.sender.poll(self.now) {
+            self.error.get_or_insert(error);
+            if let Some(abort) = abort {
+                self.request(abort.to_bytes());
+            }
+        }
+        if let Err((error, abort)) = self.receiver.poll(self.now) {
+            self.error.get_or_insert(error);
+            if let Some(abort) = abort {
+                self.respond(abort.to_bytes());
+            }
+        }
+    }
+
+    /// Queue a connection management frame from the originator.
+    fn request(&mut self, data: [u8; 8]) {
+        if let Some(id) = self.id(Pgn::TransportProtocolConnectionManagement, false) {
+            self.frames.push_back((id, data));
+        }
+    }
+
+    /// Queue a connection management frame from the receiver.
+    fn respond(&mut self, data: [u8; 8]) {
+        if let Some(id) = self.id(Pgn::TransportProtocolConnectionManagement, true) {
+            self.frames.push_back((id, data));
+        }
+    }
+
+    /// Identifier of a frame from the originator, or from the receiver, at
+    /// priority 7.
+    fn id(&self, pgn: Pgn, from_receiver: bool) -> Option<Id> {
+        let (sa, da) = if from_receiver {
+            (self.receiver.responder(), self.receiver.originator())
+        } else {
+            (self.receiver.originator(), self.receiver.responder())
+        };
+        IdBuilder::new().priority(7).pgn(pgn).sa(sa).da(da).build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::T1;
+
+    fn session<'a, 'b>(
+        data: &'a [u8],
+        storage: &'b mut [u8],
+    ) -> (OutgoingTransfer<'a>, Transfer<'b, &'b mut [u8]>) {
+        let sender = OutgoingTransfer::new(data, Some(4), Pgn::ProprietaryA).unwrap();
+        let receiver = Transfer::with_storage(sender.rts().clone(), storage).with_peers(0x10, 0x80);
+        (sender, receiver)
+    }
+
+    #[test]
+    fn clean() {
+        let data: Vec<u8> = (0..40).collect();
+        let mut buf = [0; 40];
+        let (sender, receiver) = session(&data, &mut buf);
+        let mut loopback = Loopback::new(sender, receiver);
+        assert_eq!(loopback.run(), Ok(()));
+        assert!(loopback.sender().is_finished());
+        assert_eq!(loopback.receiver().finished(), Some(data.as_slice()));
+    }
+
+    #[test]
+    fn faults() {
+        let data: Vec<u8> = (0..40).collect();
+
+        let mut buf = [0; 40];
+        let (sender, receiver) = session(&data, &mut buf);
+        let receiver = receiver.with_duplicates_ignored();
+        let mut loopback = Loopback::new(sender, receiver).with_fault(Fault::Duplicate(3));
+        assert_eq!(loopback.run(), Ok(()));
+        assert_eq!(loopback.receiver().stats().duplicates, 1);
+
+        let mut buf = [0; 40];
+        let (sender, receiver) = session(&data, &mut buf);
+        let mut loopback = Loopback::new(sender, receiver).with_fault(Fault::Swap(2));
+        assert_eq!(loopback.run(), Err(Error::Sequence));
+        assert!(loopback.sender().is_aborted());
+
+        // the last packet of a window is lost and the receiver times out
+        let mut buf = [0; 40];
+        let (sender, receiver) = session(&data, &mut buf);
+        let mut loopback = Loopback::new(sender, receiver).with_fault(Fault::Drop(4));
+        assert_eq!(loopback.run(), Err(Error::Timeout));
+        assert!(loopback.now() >= Instant::from_millis(0) + T1);
+        assert!(loopback.sender().is_aborted());
+        assert_eq!(loopback.receiver().stats().aborts_sent, 1);
+    }
+}
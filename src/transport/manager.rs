@@ -0,0 +1,636 @@
+//! Concurrent receive sessions
+//!
+//! [`TransportManager`] reassembles transfers from several originators at
+//! once, each in a storage of its own, and answers them on behalf of a
+//! single address.
+
+use super::{
+    AbortReason, AbortSenderRole, BroadcastAnnounce, ClearToSend, CompletedTransfer,
+    ConnectionAbort, ControlMessage, DataTransferRef, RequestToSend, Transfer, TransferStats,
+    TransferStorage, tp_cm,
+};
+use crate::id::{Id, Pgn};
+use crate::logging::debug;
+use crate::time::Instant;
+
+const GLOBAL: u8 = 0xFF;
+
+/// Pool entry, holding either an idle storage or a session using it.
+#[derive(Debug)]
+enum Slot<'a, S> {
+    Idle(S),
+    Receiving(Transfer<'a, S>),
+    /// Completed by the last frame, released on the next one.
+    Completed(Transfer<'a, S>),
+    /// Only while the entry is being replaced.
+    Empty,
+}
+
+impl<'a, S: TransferStorage> Slot<'a, S> {
+    fn receiving(&self) -> Option<&Transfer<'a, S>> {
+        match self {
+            Slot::Receiving(session) => Some(session),
+            _ => None,
+        }
+    }
+
+    /// Return the storage of a session to the pool, with the final
+    /// counters of the session.
+    fn release(&mut self) -> TransferStats {
+        match core::mem::replace(self, Slot::Empty) {
+            Slot::Receiving(session) | Slot::Completed(session) => {
+                let stats = session.stats();
+                *self = Slot::Idle(session.into_storage());
+                stats
+            }
+            slot => {
+                *self = slot;
+                TransferStats::default()
+            }
+        }
+    }
+}
+
+/// Receives up to `N` transfers at once.
+///
+/// Sessions are keyed by the address of their originator and the address
+/// they are directed to, so each pair of addresses has one session at a
+/// time. Destination specific transfers are accepted for the address of the
+/// manager and broadcast transfers from any originator. A new RTS or BAM
+/// replaces the session between the same pair of addresses, unless
+/// [`TransportManager::with_busy_rejection`] is used. A replaced destination
+/// specific session of another PGN is aborted, with the connection abort
+/// returned by the next call to [`TransportManager::poll`].
+#[derive(Debug)]
+pub struct TransportManager<'a, S, const N: usize> {
+    address: u8,
+    /// Reject a RTS from an originator with a session in progress.
+    exclusive: bool,
+    max_packets_per_cts: u8,
+    max_message_size: u16,
+    /// Counters of the sessions that ended and of rejected requests.
+    stats: TransferStats,
+    slots: [Slot<'a, S>; N],
+    /// Connection abort of the last replaced session, due since the RTS
+    /// replacing it.
+    replaced: Option<(Instant, (Id, [u8; 8]))>,
+}
+
+impl<'a, S: TransferStorage, const N: usize> TransportManager<'a, S, N> {
+    /// Create a new manager receiving transfers directed to `address`, each
+    /// session reassembling into one of `storage`.
+    pub fn new(address: u8, storage: [S; N]) -> Self {
+        Self {
+            address,
+            exclusive: false,
+            max_packets_per_cts: 255,
+            max_message_size: 1785,
+            stats: TransferStats::default(),
+            slots: storage.map(Slot::Idle),
+            replaced: None,
+        }
+    }
+
+    /// Keep one session per originator, rejecting a RTS that arrives while
+    /// its session is in progress with [`AbortReason::MaxConnections`].
+    ///
+    /// The session in progress continues, so the originator has to finish
+    /// or abort it before starting another one.
+    pub fn with_busy_rejection(mut self) -> Self {
+        self.exclusive = true;
+        self
+    }
+
+    /// Limit the number of packets allowed per CTS.
+    ///
+    /// Defaults to the number requested by the originator. A limit of 0 is
+    /// ignored.
+    pub fn with_max_packets_per_cts(mut self, max: u8) -> Self {
+        self.max_packets_per_cts = if max == 0 { 255 } else { max };
+        self
+    }
+
+    /// Reject transfers larger than `size` bytes.
+    ///
+    /// Default is 1785 bytes, the largest transport protocol message.
+    pub fn with_max_message_size(mut self, size: u16) -> Self {
+        self.max_message_size = size;
+        self
+    }
+
+    /// Receive transfers directed to `address` from now on.
+    pub(crate) fn set_address(&mut self, address: u8) {
+        self.address = address;
+    }
+
+    /// Release the message completed by the last call to
+    /// [`TransportManager::handle`].
+    pub(crate) fn release_completed(&mut self) {
+        for slot in &mut self.slots {
+            if matches!(slot, Slot::Completed(_)) {
+                self.stats += slot.release();
+            }
+        }
+    }
+
+    /// Number of sessions in progress.
+    pub fn sessions(&self) -> usize {
+        self.slots.iter().filter_map(Slot::receiving).count()
+    }
+
+    /// Counters summed over all sessions handled so far, including those in
+    /// progress.
+    ///
+    /// Requests rejected without opening a session count as aborts sent.
+    pub fn stats(&self) -> TransferStats {
+        let mut stats = self.stats;
+        for slot in &self.slots {
+            if let Slot::Receiving(session) | Slot::Completed(session) = slot {
+                stats += session.stats();
+            }
+        }
+        stats
+    }
+
+    /// Message completed by the last call to [`TransportManager::handle`].
+    pub fn completed(&self) -> Option<CompletedTransfer<&[u8]>> {
+        self.slots.iter().find_map(|slot| match slot {
+            Slot::Completed(session) => session.completed(),
+            _ => None,
+        })
+    }
+
+    /// Earliest time at which [`TransportManager::poll`] has to be called,
+    /// if any.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.slots
+            .iter()
+            .filter_map(Slot::receiving)
+            .filter_map(|session| session.deadline())
+            .chain(self.replaced.map(|(due, _)| due))
+            .min()
+    }
+
+    /// Handle a received transport protocol frame.
+    ///
+    /// Frames of other PGNs are ignored. Returns the frame to send in
+    /// response, if any.
+    pub fn handle(&mut self, id: Id, data: &[u8], now: Instant) -> Option<(Id, [u8; 8])> {
+        self.release_completed();
+
+        match id.pgn() {
+            Pgn::TransportProtocolConnectionManagement => self.connection_management(id, data, now),
+            Pgn::TransportProtocolDataTransfer => {
+                let dt = DataTransferRef::try_from(data).ok()?;
+                self.data_transfer(id, dt, now)
+            }
+            _ => None,
+        }
+    }
+
+    /// Abort sessions whose originator went silent.
+    ///
+    /// Returns the connection abort of one replaced or timed out session.
+    /// Call again until `None` is returned.
+    pub fn poll(&mut self, now: Instant) -> Option<(Id, [u8; 8])> {
+        if let Some((_, frame)) = self.replaced.take() {
+            return Some(frame);
+        }
+
+        for slot in &mut self.slots {
+            let Slot::Receiving(session) = slot else {
+                continue;
+            };
+            let Err((_, abort)) = session.poll(now) else {
+                continue;
+            };
+
+            debug!(
+                "transport session with {:#04x} timed out",
+                session.originator()
+            );
+            let frame = abort.and_then(|abort| session.abort_frame(&abort));
+            self.stats += slot.release();
+            if frame.is_some() {
+                return frame;
+            }
+        }
+        None
+    }
+
+    fn connection_management(
+        &mut self,
+        id: Id,
+        data: &[u8],
+        now: Instant,
+    ) -> Option<(Id, [u8; 8])> {
+        let sa = id.sa();
+        let da = id.da()?;
+
+        match ControlMessage::try_from(data).ok()? {
+            ControlMessage::BroadcastAnnounce(bam) if da == GLOBAL => {
+                self.broadcast_announce(sa, bam, now);
+                None
+            }
+            ControlMessage::RequestToSend(rts) if da == self.address => {
+                self.request_to_send(sa, rts, now)
+            }
+            ControlMessage::ConnectionAbort(abort) => {
+                let slot = self.slots.iter_mut().find(|slot| {
+                    slot.receiving().is_some_and(|s| {
+                        s.originator() == sa && s.responder() == da && s.rts().pgn() == abort.pgn()
+                    })
+                })?;
+                debug!(
+                    "transport session aborted by {sa:#04x}: {:?}",
+                    abort.reason()
+                );
+                self.stats.aborts_received += 1;
+                self.stats += slot.release();
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn request_to_send(
+        &mut self,
+        originator: u8,
+        rts: RequestToSend,
+        now: Instant,
+    ) -> Option<(Id, [u8; 8])> {
+        let pgn = rts.pgn();
+        let responder = self.address;
+        let abort = |reason| {
+            debug!("rejecting transport session from {originator:#04x}: {reason:?}");
+            let abort = ConnectionAbort::new(reason, AbortSenderRole::Receiver, pgn);
+            tp_cm(responder, originator, abort.to_bytes())
+        };
+
+        if self.exclusive
+            && let Some(session) = self
+                .slots
+                .iter()
+                .filter_map(Slot::receiving)
+                .find(|s| s.originator() == originator && s.responder() == responder)
+        {
+            debug!("rejecting transport session from busy {originator:#04x}");
+            self.stats.aborts_sent += 1;
+            return session.reject(&rts);
+        }
+
+        if let Some(frame) = self.release(originator, responder, pgn) {
+            self.replaced = Some((now, frame));
+        }
+
+        let size = rts.total_size();
+        if !(9..=self.max_message_size).contains(&size)
+            || u16::from(rts.total_packets()) != size.div_ceil(7)
+        {
+            self.stats.aborts_sent += 1;
+            return abort(AbortReason::MessageTooLarge);
+        }
+
+        let Some(slot) = self.slots.iter_mut().find(|s| matches!(s, Slot::Idle(_))) else {
+            self.stats.aborts_sent += 1;
+            return abort(AbortReason::MaxConnections);
+        };
+        let Slot::Idle(storage) = core::mem::replace(slot, Slot::Empty) else {
+            return None;
+        };
+
+        // a window of 0 would hold the session forever
+        let requested = rts
+            .max_packets_per_response()
+            .filter(|&n| n != 0)
+            .unwrap_or(rts.total_packets());
+        let window = requested.min(self.max_packets_per_cts);
+        // the transfer paces the following CTS from its RTS
+        let rts = if window < requested {
+            RequestToSend::try_new(size, Some(window), pgn).unwrap_or(rts)
+        } else {
+            rts
+        };
+        let mut transfer = match Transfer::try_with_storage(rts, storage) {
+            Ok(transfer) => transfer.with_peers(originator, responder),
+            Err(storage) => {
+                *slot = Slot::Idle(storage);
+                self.stats.aborts_sent += 1;
+                return abort(AbortReason::CanceledBySystem);
+            }
+        };
+
+        // starts the timer, which can not expire yet
+        let _ = transfer.poll(now);
+        *slot = Slot::Receiving(transfer);
+
+        let cts = ClearToSend::new(Some(window), 1, pgn);
+        tp_cm(responder, originator, cts.to_bytes())
+    }
+
+    fn broadcast_announce(&mut self, originator: u8, bam: BroadcastAnnounce, now: Instant) {
+        self.release(originator, GLOBAL, bam.pgn());
+
+        if bam.total_size() > self.max_message_size {
+            debug!("ignoring broadcast from {originator:#04x}");
+            return;
+        }
+
+        let size = usize::from(bam.total_size());
+        let Some(slot) = self
+            .slots
+            .iter_mut()
+            .find(|s| matches!(s, Slot::Idle(storage) if storage.capacity() >= size))
+        else {
+            debug!("ignoring broadcast from {originator:#04x}");
+            return;
+        };
+        let Slot::Idle(storage) = core::mem::replace(slot, Slot::Empty) else {
+            return;
+        };
+
+        let mut transfer = Transfer::broadcast(bam, storage).with_source(originator);
+        let _ = transfer.poll(now);
+        *slot = Slot::Receiving(transfer);
+    }
+
+    fn data_transfer(
+        &mut self,
+        id: Id,
+        dt: DataTransferRef<'_>,
+        now: Instant,
+    ) -> Option<(Id, [u8; 8])> {
+        let (sa, da) = (id.sa(), id.da()?);
+        let slot = self.slots.iter_mut().find(|slot| {
+            slot.receiving()
+                .is_some_and(|s| s.originator() == sa && s.responder() == da)
+        })?;
+        let Slot::Receiving(session) = slot else {
+            return None;
+        };
+
+        match session.next_ref(dt) {
+            Ok(response) => {
+                let frame = response
+                    .as_ref()
+                    .and_then(|response| session.response_frame(response));
+                if session.is_finished() {
+                    debug!(
+                        "transport session with {:#04x} complete",
+                        session.originator()
+                    );
+                    if let Slot::Receiving(session) = core::mem::replace(slot, Slot::Empty) {
+                        *slot = Slot::Completed(session);
+                    }
+                } else {
+                    let _ = session.poll(now);
+                }
+                frame
+            }
+            Err((_, abort)) => {
+                debug!(
+                    "aborting transport session with {:#04x}: {:?}",
+                    session.originator(),
+                    abort.reason()
+                );
+                let frame = session.abort_frame(&abort);
+                self.stats += slot.release();
+                frame
+            }
+        }
+    }
+
+    /// End the session between `originator` and `responder`, if any.
+    ///
+    /// Returns the connection abort of a destination specific session of
+    /// another PGN than `pgn`. One of the same PGN is restarted by the
+    /// originator, which would take an abort for the new session.
+    fn release(&mut self, originator: u8, responder: u8, pgn: Pgn) -> Option<(Id, [u8; 8])> {
+        let mut frame = None;
+        for slot in &mut self.slots {
+            let Slot::Receiving(session) = slot else {
+                continue;
+            };
+            if session.originator() != originator || session.responder() != responder {
+                continue;
+            }
+
+            if session.rts().pgn() != pgn {
+                debug!("transport session with {originator:#04x} replaced");
+                let abort = session.abort(AbortReason::CanceledBySystem);
+                frame = session.abort_frame(&abort);
+            }
+            self.stats += slot.release();
+        }
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::IdBuilder;
+    use crate::transport::{DataTransfer, T1, T2};
+
+    fn id(pgn: Pgn, sa: u8, da: u8) -> Id {
+        IdBuilder::new()
+            .priority(7)
+            .pgn(pgn)
+            .sa(sa)
+            .da(da)
+            .build()
+            .unwrap()
+    }
+
+    fn cm(sa: u8, da: u8) -> Id {
+        id(Pgn::TransportProtocolConnectionManagement, sa, da)
+    }
+
+    fn dt(sa: u8, da: u8) -> Id {
+        id(Pgn::TransportProtocolDataTransfer, sa, da)
+    }
+
+    #[test]
+    fn interleaved() {
+        let mut a = [0u8; 16];
+        let mut b = [0u8; 16];
+        let mut manager = TransportManager::new(0x80, [a.as_mut_slice(), b.as_mut_slice()]);
+        let now = Instant::from_millis(0);
+
+        let rts = RequestToSend::try_new(9, None, Pgn::ProprietaryA).unwrap();
+        let (cts_id, cts) = manager
+            .handle(cm(0x10, 0x80), &rts.to_bytes(), now)
+            .unwrap();
+        assert_eq!((cts_id.sa(), cts_id.da()), (0x80, Some(0x10)));
+        assert_eq!(cts[0], 17);
+
+        let bam = BroadcastAnnounce::try_new(9, Pgn::ProprietaryB(0x00)).unwrap();
+        assert_eq!(manager.handle(cm(0x20, GLOBAL), &bam.to_bytes(), now), None);
+        assert_eq!(manager.sessions(), 2);
+
+        // a third originator finds no storage left
+        let (_, abort) = manager
+            .handle(cm(0x30, 0x80), &rts.to_bytes(), now)
+            .unwrap();
+        assert_eq!(
+            ConnectionAbort::try_from(&abort[..]).unwrap().reason(),
+            AbortReason::MaxConnections
+        );
+
+        let packet = |sequence, byte| DataTransfer::new(sequence, [byte; 7]).to_bytes();
+        assert_eq!(manager.handle(dt(0x10, 0x80), &packet(1, 1), now), None);
+        assert_eq!(manager.handle(dt(0x20, GLOBAL), &packet(1, 2), now), None);
+        assert_eq!(manager.handle(dt(0x20, GLOBAL), &packet(2, 2), now), None);
+
+        let broadcast = manager.completed().unwrap();
+        assert_eq!(
+            (broadcast.source, broadcast.data),
+            (0x20, [2; 9].as_slice())
+        );
+
+        let (_, end) = manager.handle(dt(0x10, 0x80), &packet(2, 1), now).unwrap();
+        assert_eq!(end[0], 19);
+        let message = manager.completed().unwrap();
+        assert_eq!(message.pgn, Pgn::ProprietaryA);
+        assert_eq!((message.source, message.data), (0x10, [1; 9].as_slice()));
+
+        manager.handle(dt(0x10, 0x80), &packet(3, 1), now);
+        assert_eq!(manager.completed(), None);
+        assert_eq!(manager.sessions(), 0);
+    }
+
+    #[test]
+    fn busy_rejection() {
+        let mut a = [0u8; 16];
+        let mut b = [0u8; 16];
+        let mut manager =
+            TransportManager::new(0x80, [a.as_mut_slice(), b.as_mut_slice()]).with_busy_rejection();
+        let now = Instant::from_millis(0);
+
+        let rts = RequestToSend::try_new(9, None, Pgn::ProprietaryA).unwrap();
+        manager.handle(cm(0x10, 0x80), &rts.to_bytes(), now);
+        let packet = |sequence| DataTransfer::new(sequence, [1; 7]).to_bytes();
+        manager.handle(dt(0x10, 0x80), &packet(1), now);
+
+        let other = RequestToSend::try_new(16, None, Pgn::ProprietaryB(0x01)).unwrap();
+        let (id, abort) = manager
+            .handle(cm(0x10, 0x80), &other.to_bytes(), now)
+            .unwrap();
+        assert_eq!((id.sa(), id.da()), (0x80, Some(0x10)));
+        let abort = ConnectionAbort::try_from(&abort[..]).unwrap();
+        assert_eq!(abort.reason(), AbortReason::MaxConnections);
+        assert_eq!(abort.pgn(), Pgn::ProprietaryB(0x01));
+        assert_eq!(manager.sessions(), 1);
+
+        // the session in progress is kept
+        manager.handle(dt(0x10, 0x80), &packet(2), now).unwrap();
+        let message = manager.completed().unwrap();
+        assert_eq!((message.source, message.data), (0x10, [1; 9].as_slice()));
+
+        // and the originator may start another one once it is done
+        let (_, cts) = manager
+            .handle(cm(0x10, 0x80), &other.to_bytes(), now)
+            .unwrap();
+        assert_eq!(cts[0], 17);
+    }
+
+    #[test]
+    fn timeouts_and_aborts() {
+        let mut a = [0u8; 16];
+        let mut b = [0u8; 16];
+        let mut manager = TransportManager::new(0x80, [a.as_mut_slice(), b.as_mut_slice()]);
+        let now = Instant::from_millis(0);
+
+        let rts = RequestToSend::try_new(9, None, Pgn::ProprietaryA).unwrap();
+        manager.handle(cm(0x10, 0x80), &rts.to_bytes(), now);
+        manager.handle(cm(0x20, 0x80), &rts.to_bytes(), now);
+        let packet = DataTransfer::new(1, [0; 7]).to_bytes();
+        manager.handle(dt(0x10, 0x80), &packet, Instant::from_millis(500));
+
+        // the second originator aborts its session
+        let abort = ConnectionAbort::new(
+            AbortReason::CanceledBySystem,
+            AbortSenderRole::Sender,
+            Pgn::ProprietaryA,
+        );
+        manager.handle(cm(0x20, 0x80), &abort.to_bytes(), now);
+        assert_eq!(manager.sessions(), 1);
+
+        let deadline = Instant::from_millis(500) + T1;
+        assert_eq!(manager.deadline(), Some(deadline));
+        assert_eq!(manager.poll(Instant::from_millis(600)), None);
+        let (id, abort) = manager.poll(deadline).unwrap();
+        assert_eq!(id.da(), Some(0x10));
+        assert_eq!(
+            ConnectionAbort::try_from(&abort[..]).unwrap().reason(),
+            AbortReason::Timeout
+        );
+        assert_eq!(manager.poll(deadline), None);
+        assert_eq!(manager.sessions(), 0);
+        assert_eq!(manager.deadline(), None);
+
+        let stats = manager.stats();
+        assert_eq!((stats.aborts_sent, stats.aborts_received), (1, 1));
+        assert_eq!((stats.packets, stats.bytes, stats.completed), (1, 7, 0));
+    }
+
+    #[test]
+    fn replaced() {
+        let mut a = [0u8; 16];
+        let mut manager = TransportManager::new(0x80, [a.as_mut_slice()]);
+        let now = Instant::from_millis(0);
+        let packet = |sequence| DataTransfer::new(sequence, [1; 7]).to_bytes();
+
+        // a RTS for the same PGN restarts the session without an abort
+        let rts = RequestToSend::try_new(9, None, Pgn::ProprietaryA).unwrap();
+        manager.handle(cm(0x10, 0x80), &rts.to_bytes(), now);
+        manager.handle(dt(0x10, 0x80), &packet(1), now);
+        let (_, cts) = manager
+            .handle(cm(0x10, 0x80), &rts.to_bytes(), now)
+            .unwrap();
+        assert_eq!(cts[0], 17);
+        assert_eq!(manager.sessions(), 1);
+        assert_eq!(manager.poll(now), None);
+
+        // one for another PGN takes over the storage and aborts the session
+        let later = Instant::from_millis(100);
+        let other = RequestToSend::try_new(16, None, Pgn::ProprietaryB(0x01)).unwrap();
+        let (_, cts) = manager
+            .handle(cm(0x10, 0x80), &other.to_bytes(), later)
+            .unwrap();
+        assert_eq!(cts[0], 17);
+        assert_eq!(manager.sessions(), 1);
+        assert_eq!(manager.deadline(), Some(later));
+
+        let (id, abort) = manager.poll(later).unwrap();
+        assert_eq!((id.sa(), id.da()), (0x80, Some(0x10)));
+        let abort = ConnectionAbort::try_from(&abort[..]).unwrap();
+        assert_eq!(abort.reason(), AbortReason::CanceledBySystem);
+        assert_eq!(abort.pgn(), Pgn::ProprietaryA);
+        assert_eq!(manager.poll(later), None);
+        assert_eq!(manager.deadline(), Some(later + T2));
+        assert_eq!(manager.stats().aborts_sent, 1);
+    }
+
+    #[test]
+    fn zero_window() {
+        let mut a = [0u8; 16];
+        let mut manager = TransportManager::new(0x80, [a.as_mut_slice()]);
+        let now = Instant::from_millis(0);
+
+        // 16 bytes in 3 packets with a window of 0
+        let rts = [16, 16, 0, 3, 0, 0, 0xEF, 0];
+        let (_, cts) = manager.handle(cm(0x10, 0x80), &rts, now).unwrap();
+        let cts = ClearToSend::try_from(&cts[..]).unwrap();
+        assert_eq!(cts.max_packets_per_response(), Some(3));
+
+        for sequence in 1..=2 {
+            let packet = DataTransfer::new(sequence, [sequence; 7]).to_bytes();
+            assert_eq!(manager.handle(dt(0x10, 0x80), &packet, now), None);
+        }
+        let packet = DataTransfer::new(3, [3; 7]).to_bytes();
+        let (_, end) = manager.handle(dt(0x10, 0x80), &packet, now).unwrap();
+        assert_eq!(end[0], 19);
+        assert_eq!(manager.completed().unwrap().data.len(), 16);
+    }
+}
@@ -1,8 +1,45 @@
-use crate::id::Pgn;
+use crate::error::{DecodeError, exact};
+use crate::id::{Id, Pgn};
+
+/// Check the length and multiplexer of a connection management message.
+pub(super) fn raw(value: &[u8], mux: u8) -> Result<&[u8; 8], DecodeError> {
+    match exact::<8>(value)? {
+        raw if raw[0] == mux => Ok(raw),
+        raw => Err(DecodeError::Mux(raw[0])),
+    }
+}
+
+/// Encode a packet limit, 255 standing for no limit.
+const fn no_limit(max_packets_per_response: Option<u8>) -> u8 {
+    match max_packets_per_response {
+        Some(max) => max,
+        None => 255,
+    }
+}
+
+/// PGN carried in the last three bytes of a connection management message.
+pub(super) fn pgn(value: &[u8; 8]) -> Pgn {
+    Pgn::from(u32::from_le_bytes([value[5], value[6], value[7], 0x00]))
+}
+
+/// Invalid request to send arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RequestToSendError {
+    /// Total size below 9 bytes, which fit a single frame.
+    TooShort,
+    /// Total size above 1785 bytes.
+    TooLong,
+    /// Maximum packets per response of 0, which would only allow holds, or
+    /// 255, which is reserved.
+    MaxPacketsPerResponse,
+}
 
 /// Request to send (TP.CM_RTS) message.
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RequestToSend {
     total_size: u16,
     total_packets: u8,
@@ -11,43 +48,74 @@ pub struct RequestToSend {
 }
 
 impl RequestToSend {
-    const MUX: u8 = 16;
+    pub(super) const MUX: u8 = 16;
 
     /// Create a new request to send message.
     ///
-    /// - `total_size` must be between 9 and 1785 bytes.
-    /// - `max_packets_per_response` must be between
+    /// # Panics
+    ///
+    /// Panics if the arguments are rejected by [`RequestToSend::try_new`].
+    #[deprecated(note = "panics on invalid arguments, use `try_new`")]
+    #[allow(clippy::panic)]
     pub fn new(total_size: u16, max_packets_per_response: Option<u8>, pgn: Pgn) -> Self {
-        assert!(total_size <= 1785);
-        assert!(total_size >= 9);
-
-        let total_packets = total_size.div_ceil(7);
-        assert!(total_packets >= 2);
-        assert!(total_packets <= 255);
-        let total_packets = total_packets as u8;
-
-        if let Some(max) = max_packets_per_response {
-            assert!(
-                max < 255,
-                "No limit is designated with `None` for`max_packets_per_response`"
-            );
+        match Self::try_new(total_size, max_packets_per_response, pgn) {
+            Ok(rts) => rts,
+            Err(e) => panic!("invalid request to send: {e:?}"),
         }
+    }
 
-        Self {
+    /// Create a new request to send message.
+    ///
+    /// - `total_size` must be between 9 and 1785 bytes.
+    /// - `max_packets_per_response` must be between 1 and 254, no limit is
+    ///   designated with `None`.
+    pub const fn try_new(
+        total_size: u16,
+        max_packets_per_response: Option<u8>,
+        pgn: Pgn,
+    ) -> Result<Self, RequestToSendError> {
+        if total_size < 9 {
+            return Err(RequestToSendError::TooShort);
+        }
+        if total_size > 1785 {
+            return Err(RequestToSendError::TooLong);
+        }
+        if matches!(max_packets_per_response, Some(0 | 255)) {
+            return Err(RequestToSendError::MaxPacketsPerResponse);
+        }
+
+        Ok(Self {
             total_size,
-            total_packets,
+            // at most 255 packets for 1785 bytes
+            total_packets: total_size.div_ceil(7) as u8,
             max_packets_per_response,
             pgn,
-        }
+        })
+    }
+
+    /// Encode the message.
+    pub const fn to_bytes(&self) -> [u8; 8] {
+        let total_size = self.total_size.to_le_bytes();
+        let pgn = self.pgn.as_raw().to_le_bytes();
+        [
+            Self::MUX,
+            total_size[0],
+            total_size[1],
+            self.total_packets,
+            no_limit(self.max_packets_per_response),
+            pgn[0],
+            pgn[1],
+            pgn[2],
+        ]
     }
 
     /// Total number of bytes in this transfer.
-    pub fn total_size(&self) -> u16 {
+    pub const fn total_size(&self) -> u16 {
         self.total_size
     }
 
     /// Total number of packets in this transfer.
-    pub fn total_packets(&self) -> u8 {
+    pub const fn total_packets(&self) -> u8 {
         self.total_packets
     }
 
@@ -55,60 +123,50 @@ impl RequestToSend {
     /// every TP.CM_CTS message.
     ///
     /// `None` signifies no limit.
-    pub fn max_packets_per_response(&self) -> Option<u8> {
+    pub const fn max_packets_per_response(&self) -> Option<u8> {
         self.max_packets_per_response
     }
 
     /// Tranfer contents PGN.
-    pub fn pgn(&self) -> Pgn {
+    pub const fn pgn(&self) -> Pgn {
         self.pgn
     }
 }
 
 impl From<RequestToSend> for [u8; 8] {
     fn from(val: RequestToSend) -> Self {
-        let total_size = val.total_size.to_le_bytes();
-        let pgn = u32::from(val.pgn).to_le_bytes();
-        [
-            RequestToSend::MUX,
-            total_size[0],
-            total_size[1],
-            val.total_packets,
-            val.max_packets_per_response.unwrap_or(255),
-            pgn[0],
-            pgn[1],
-            pgn[2],
-        ]
+        val.to_bytes()
     }
 }
 
-impl<'a> TryFrom<&'a [u8]> for RequestToSend {
-    type Error = &'a [u8];
-
-    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
-        if value.len() != 8 {
-            return Err(value);
-        }
-
-        if value[0] != Self::MUX {
-            return Err(value);
-        }
-
-        Ok(Self {
+impl RequestToSend {
+    pub(super) fn from_raw(value: &[u8; 8]) -> Self {
+        Self {
             total_size: u16::from_le_bytes([value[1], value[2]]),
             total_packets: value[3],
+            // a window of 0 would never let the sender continue, so it is
+            // read as no limit like 255
             max_packets_per_response: match value[4] {
-                0..255 => Some(value[4]),
-                255 => None,
+                1..255 => Some(value[4]),
+                0 | 255 => None,
             },
-            pgn: Pgn::from(u32::from_le_bytes([value[5], value[6], value[7], 0x00])),
-        })
+            pgn: pgn(value),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for RequestToSend {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        raw(value, Self::MUX).map(Self::from_raw)
     }
 }
 
 /// Clear to send (TP.CM_CTS) message.
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ClearToSend {
     max_packets_per_response: Option<u8>,
     next_sequence: u8,
@@ -119,7 +177,7 @@ impl ClearToSend {
     const MUX: u8 = 17;
 
     /// Create a new CTS message.
-    pub fn new(max_packets_per_response: Option<u8>, next_sequence: u8, pgn: Pgn) -> Self {
+    pub const fn new(max_packets_per_response: Option<u8>, next_sequence: u8, pgn: Pgn) -> Self {
         Self {
             max_packets_per_response,
             next_sequence,
@@ -127,25 +185,30 @@ impl ClearToSend {
         }
     }
 
-    /// Number of packets that can be sent sent.
-    pub fn max_packets_per_response(&self) -> Option<u8> {
-        self.max_packets_per_response
+    /// Create a CTS message holding the connection open without granting
+    /// any packets.
+    ///
+    /// A receiver that is not ready for the packets starting at
+    /// `next_sequence` sends a hold at least every [`TH`](super::TH). The
+    /// sender waits up to [`T4`](super::T4) after each one.
+    pub const fn hold(next_sequence: u8, pgn: Pgn) -> Self {
+        Self::new(Some(0), next_sequence, pgn)
     }
 
-    /// Next sequence number.
-    pub fn next_sequence(&self) -> u8 {
-        self.next_sequence
+    /// Check if the message holds the connection open without granting any
+    /// packets.
+    pub const fn is_hold(&self) -> bool {
+        matches!(self.max_packets_per_response, Some(0))
     }
-}
 
-impl From<&ClearToSend> for [u8; 8] {
-    fn from(value: &ClearToSend) -> Self {
-        let pgn = u32::from(value.pgn).to_le_bytes();
+    /// Encode the message.
+    pub const fn to_bytes(&self) -> [u8; 8] {
+        let pgn = self.pgn.as_raw().to_le_bytes();
 
         [
-            ClearToSend::MUX,
-            value.max_packets_per_response.unwrap_or(255),
-            value.next_sequence,
+            Self::MUX,
+            no_limit(self.max_packets_per_response),
+            self.next_sequence,
             0xFF, // reserved
             0xFF, // reserved
             pgn[0],
@@ -153,36 +216,54 @@ impl From<&ClearToSend> for [u8; 8] {
             pgn[2],
         ]
     }
-}
 
-impl<'a> TryFrom<&'a [u8]> for ClearToSend {
-    type Error = &'a [u8];
+    /// Number of packets that can be sent sent.
+    pub const fn max_packets_per_response(&self) -> Option<u8> {
+        self.max_packets_per_response
+    }
 
-    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
-        if value.len() != 8 {
-            return Err(value);
-        }
+    /// Next sequence number.
+    pub const fn next_sequence(&self) -> u8 {
+        self.next_sequence
+    }
 
-        if value[0] != Self::MUX {
-            return Err(value);
-        }
+    /// Tranfer contents PGN.
+    pub const fn pgn(&self) -> Pgn {
+        self.pgn
+    }
+}
 
-        let pgn = Pgn::from(u32::from_le_bytes([value[5], value[6], value[7], 0x00]));
+impl From<&ClearToSend> for [u8; 8] {
+    fn from(value: &ClearToSend) -> Self {
+        value.to_bytes()
+    }
+}
 
-        Ok(Self {
+impl ClearToSend {
+    fn from_raw(value: &[u8; 8]) -> Self {
+        Self {
             max_packets_per_response: match value[1] {
                 0..255 => Some(value[1]),
                 255 => None,
             },
             next_sequence: value[2],
-            pgn,
-        })
+            pgn: pgn(value),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ClearToSend {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        raw(value, Self::MUX).map(Self::from_raw)
     }
 }
 
 /// End of message acknowledge (TP.CM_EndOfMsgAck) message.
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EndOfMessageAck {
     total_size: u16,
     total_packets: u8,
@@ -193,7 +274,7 @@ impl EndOfMessageAck {
     const MUX: u8 = 19;
 
     /// Creates a new end of message acknowledge message.
-    pub fn new(total_size: u16, total_packets: u8, pgn: Pgn) -> Self {
+    pub const fn new(total_size: u16, total_packets: u8, pgn: Pgn) -> Self {
         Self {
             total_size,
             total_packets,
@@ -201,69 +282,283 @@ impl EndOfMessageAck {
         }
     }
 
+    /// Encode the message.
+    pub const fn to_bytes(&self) -> [u8; 8] {
+        let total_size = self.total_size.to_le_bytes();
+        let pgn = self.pgn.as_raw().to_le_bytes();
+
+        [
+            Self::MUX,
+            total_size[0],
+            total_size[1],
+            self.total_packets,
+            0xFF,
+            pgn[0],
+            pgn[1],
+            pgn[2],
+        ]
+    }
+
     /// Total message size in bytes.
-    pub fn total_size(&self) -> u16 {
+    pub const fn total_size(&self) -> u16 {
         self.total_size
     }
 
     /// Total number of packets transferred.
-    pub fn total_packets(&self) -> u8 {
+    pub const fn total_packets(&self) -> u8 {
         self.total_packets
     }
 
     /// Tranfer contents PGN.
-    pub fn pgn(&self) -> Pgn {
+    pub const fn pgn(&self) -> Pgn {
         self.pgn
     }
 }
 
 impl From<&EndOfMessageAck> for [u8; 8] {
     fn from(value: &EndOfMessageAck) -> Self {
-        let total_size = value.total_size.to_le_bytes();
-        let pgn = u32::from(value.pgn).to_le_bytes();
+        value.to_bytes()
+    }
+}
+
+impl EndOfMessageAck {
+    fn from_raw(value: &[u8; 8]) -> Self {
+        Self {
+            total_size: u16::from_le_bytes([value[1], value[2]]),
+            total_packets: value[3],
+            pgn: pgn(value),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for EndOfMessageAck {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        raw(value, Self::MUX).map(Self::from_raw)
+    }
+}
+
+/// Broadcast announce message (TP.CM_BAM).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BroadcastAnnounce {
+    total_size: u16,
+    total_packets: u8,
+    pgn: Pgn,
+}
+
+impl BroadcastAnnounce {
+    pub(super) const MUX: u8 = 32;
+
+    /// Create a new broadcast announce message.
+    ///
+    /// `total_size` must be between 9 and 1785 bytes.
+    pub const fn try_new(total_size: u16, pgn: Pgn) -> Result<Self, RequestToSendError> {
+        match RequestToSend::try_new(total_size, None, pgn) {
+            Ok(rts) => Ok(Self {
+                total_size,
+                total_packets: rts.total_packets,
+                pgn,
+            }),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Encode the message.
+    pub const fn to_bytes(&self) -> [u8; 8] {
+        let total_size = self.total_size.to_le_bytes();
+        let pgn = self.pgn.as_raw().to_le_bytes();
 
         [
-            EndOfMessageAck::MUX,
+            Self::MUX,
             total_size[0],
             total_size[1],
-            value.total_packets,
-            0xFF,
+            self.total_packets,
+            0xFF, // reserved
             pgn[0],
             pgn[1],
             pgn[2],
         ]
     }
+
+    /// Total number of bytes in this transfer.
+    pub const fn total_size(&self) -> u16 {
+        self.total_size
+    }
+
+    /// Total number of packets in this transfer.
+    pub const fn total_packets(&self) -> u8 {
+        self.total_packets
+    }
+
+    /// Tranfer contents PGN.
+    pub const fn pgn(&self) -> Pgn {
+        self.pgn
+    }
 }
 
-impl<'a> TryFrom<&'a [u8]> for EndOfMessageAck {
-    type Error = &'a [u8];
+impl From<&BroadcastAnnounce> for [u8; 8] {
+    fn from(value: &BroadcastAnnounce) -> Self {
+        value.to_bytes()
+    }
+}
 
-    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
-        if value.len() != 8 {
-            return Err(value);
+impl BroadcastAnnounce {
+    fn from_raw(value: &[u8; 8]) -> Self {
+        Self {
+            total_size: u16::from_le_bytes([value[1], value[2]]),
+            total_packets: value[3],
+            pgn: pgn(value),
         }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for BroadcastAnnounce {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        raw(value, Self::MUX).map(Self::from_raw)
+    }
+}
+
+/// Any connection management (TP.CM) message.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ControlMessage {
+    RequestToSend(RequestToSend),
+    ClearToSend(ClearToSend),
+    EndOfMessageAck(EndOfMessageAck),
+    BroadcastAnnounce(BroadcastAnnounce),
+    ConnectionAbort(ConnectionAbort),
+}
+
+impl<'a> TryFrom<&'a [u8]> for ControlMessage {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        ControlMessageRef::try_from(value).map(Self::from)
+    }
+}
+
+/// Connection management message, interpreted in place.
+///
+/// Checks the multiplexer once and reads fields straight from the frame, for
+/// receive paths that only look at a few of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ControlMessageRef<'a> {
+    raw: &'a [u8; 8],
+}
 
-        if value[0] != Self::MUX {
-            return Err(value);
+/// Kind of a connection management message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ControlKind {
+    RequestToSend,
+    ClearToSend,
+    EndOfMessageAck,
+    BroadcastAnnounce,
+    ConnectionAbort,
+}
+
+impl<'a> ControlMessageRef<'a> {
+    /// Kind of the message.
+    pub fn kind(&self) -> ControlKind {
+        match self.raw[0] {
+            RequestToSend::MUX => ControlKind::RequestToSend,
+            ClearToSend::MUX => ControlKind::ClearToSend,
+            EndOfMessageAck::MUX => ControlKind::EndOfMessageAck,
+            BroadcastAnnounce::MUX => ControlKind::BroadcastAnnounce,
+            _ => ControlKind::ConnectionAbort,
         }
+    }
 
-        let total_size = u16::from_le_bytes([value[1], value[2]]);
+    /// Tranfer contents PGN.
+    pub fn pgn(&self) -> Pgn {
+        pgn(self.raw)
+    }
 
-        let total_packets = value[3];
+    /// Total message size of a RTS, BAM or end of message acknowledge.
+    pub fn total_size(&self) -> Option<u16> {
+        matches!(
+            self.kind(),
+            ControlKind::RequestToSend
+                | ControlKind::EndOfMessageAck
+                | ControlKind::BroadcastAnnounce
+        )
+        .then(|| u16::from_le_bytes([self.raw[1], self.raw[2]]))
+    }
 
-        let pgn = Pgn::from(u32::from_le_bytes([value[5], value[6], value[7], 0x00]));
+    /// Total number of packets of a RTS, BAM or end of message acknowledge.
+    pub fn total_packets(&self) -> Option<u8> {
+        matches!(
+            self.kind(),
+            ControlKind::RequestToSend
+                | ControlKind::EndOfMessageAck
+                | ControlKind::BroadcastAnnounce
+        )
+        .then_some(self.raw[3])
+    }
 
-        Ok(Self {
-            total_size,
-            total_packets,
-            pgn,
-        })
+    /// Next sequence number of a CTS.
+    pub fn next_sequence(&self) -> Option<u8> {
+        (self.kind() == ControlKind::ClearToSend).then_some(self.raw[2])
+    }
+
+    /// Reason of a connection abort.
+    pub fn reason(&self) -> Option<AbortReason> {
+        (self.kind() == ControlKind::ConnectionAbort).then(|| AbortReason::from_raw(self.raw[1]))
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &'a [u8; 8] {
+        self.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ControlMessageRef<'a> {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        const RTS: u8 = RequestToSend::MUX;
+        const CTS: u8 = ClearToSend::MUX;
+        const END: u8 = EndOfMessageAck::MUX;
+        const BAM: u8 = BroadcastAnnounce::MUX;
+        const ABORT: u8 = ConnectionAbort::MUX;
+
+        match exact::<8>(value)? {
+            raw @ [RTS | CTS | END | BAM | ABORT, ..] => Ok(Self { raw }),
+            raw => Err(DecodeError::Mux(raw[0])),
+        }
+    }
+}
+
+impl From<ControlMessageRef<'_>> for ControlMessage {
+    fn from(view: ControlMessageRef<'_>) -> Self {
+        match view.kind() {
+            ControlKind::RequestToSend => Self::RequestToSend(RequestToSend::from_raw(view.raw)),
+            ControlKind::ClearToSend => Self::ClearToSend(ClearToSend::from_raw(view.raw)),
+            ControlKind::EndOfMessageAck => {
+                Self::EndOfMessageAck(EndOfMessageAck::from_raw(view.raw))
+            }
+            ControlKind::BroadcastAnnounce => {
+                Self::BroadcastAnnounce(BroadcastAnnounce::from_raw(view.raw))
+            }
+            ControlKind::ConnectionAbort => {
+                Self::ConnectionAbort(ConnectionAbort::from_raw(view.raw))
+            }
+        }
     }
 }
 
 /// Connection abort (TP.Conn_Abort) message.
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConnectionAbort {
     reason: AbortReason,
     sender_role: AbortSenderRole,
@@ -274,7 +569,7 @@ impl ConnectionAbort {
     const MUX: u8 = 255;
 
     /// Create a new connection abort message.
-    pub fn new(reason: AbortReason, sender_role: AbortSenderRole, pgn: Pgn) -> Self {
+    pub const fn new(reason: AbortReason, sender_role: AbortSenderRole, pgn: Pgn) -> Self {
         Self {
             reason,
             sender_role,
@@ -282,117 +577,173 @@ impl ConnectionAbort {
         }
     }
 
+    /// Encode the message.
+    pub const fn to_bytes(&self) -> [u8; 8] {
+        let pgn = self.pgn.as_raw().to_le_bytes();
+
+        [
+            Self::MUX,
+            self.reason.as_raw(),
+            self.sender_role as u8 | 0b11111100,
+            0xFF,
+            0xFF,
+            pgn[0],
+            pgn[1],
+            pgn[2],
+        ]
+    }
+
+    /// Complete TP.CM frame sent by `sa` to the peer at `da`.
+    pub fn to_frame(&self, sa: u8, da: u8) -> Option<(Id, [u8; 8])> {
+        super::tp_cm(sa, da, self.to_bytes())
+    }
+
     /// Abort reason.
-    pub fn reason(&self) -> AbortReason {
+    pub const fn reason(&self) -> AbortReason {
         self.reason
     }
 
     /// Abort sender role.
-    pub fn sender_role(&self) -> AbortSenderRole {
+    pub const fn sender_role(&self) -> AbortSenderRole {
         self.sender_role
     }
 
     /// Tranfer contents PGN.
-    pub fn pgn(&self) -> Pgn {
+    pub const fn pgn(&self) -> Pgn {
         self.pgn
     }
 }
 
-impl<'a> TryFrom<&'a [u8]> for ConnectionAbort {
-    type Error = &'a [u8];
-
-    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
-        if value.len() != 8 {
-            return Err(value);
+impl ConnectionAbort {
+    fn from_raw(value: &[u8; 8]) -> Self {
+        Self {
+            reason: AbortReason::from_raw(value[1]),
+            sender_role: AbortSenderRole::try_from(value[2] & 0b00000011)
+                .unwrap_or(AbortSenderRole::NotSpecified),
+            pgn: pgn(value),
         }
+    }
+}
 
-        if value[0] != Self::MUX {
-            return Err(value);
-        }
+impl<'a> TryFrom<&'a [u8]> for ConnectionAbort {
+    type Error = DecodeError;
 
-        Ok(Self {
-            reason: AbortReason::try_from(value[1]).unwrap_or(AbortReason::Custom),
-            sender_role: AbortSenderRole::try_from(value[2] & 0b00000011)
-                .unwrap_or(AbortSenderRole::NotSpecified),
-            pgn: Pgn::from(u32::from_le_bytes([value[5], value[6], value[7], 0x00])),
-        })
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        raw(value, Self::MUX).map(Self::from_raw)
     }
 }
 
 impl From<&ConnectionAbort> for [u8; 8] {
     fn from(value: &ConnectionAbort) -> Self {
-        let pgn = u32::from(value.pgn).to_le_bytes();
-
-        [
-            ConnectionAbort::MUX,
-            u8::from(&value.reason),
-            u8::from(&value.sender_role) | 0b11111100,
-            0xFF,
-            0xFF,
-            pgn[0],
-            pgn[1],
-            pgn[2],
-        ]
+        value.to_bytes()
     }
 }
 
 /// Abort reason.
 ///
-/// See J1939™-21 table 6.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+/// See J1939™-21 table 6. Codes 10 to 14 were added with the extended
+/// transport protocol and are shared by the J1939™-22 FD transport protocol.
+/// Codes not listed are kept as [`AbortReason::Other`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AbortReason {
     /// Already in one or more connection managed sessions and cannot support another.
-    MaxConnections = 1,
+    MaxConnections,
     /// System resources were needed for another task, so this connection managed session was terminated.
-    CanceledBySystem = 2,
+    CanceledBySystem,
     /// A timeout occurred, and this is the connection abort to close the session.
-    Timeout = 3,
+    Timeout,
     /// CTS messages received when data transfer is in progress.
-    CtsWhileDataTransfer = 4,
+    CtsWhileDataTransfer,
     /// Maximum retransmit request limit reached.
-    RetransmitLimitReached = 5,
+    RetransmitLimitReached,
     /// Unexpected data transfer packet.
-    UnexpectedDataTransfer = 6,
+    UnexpectedDataTransfer,
     /// Bad sequence number (software cannot recover).
-    BadSequenceNumber = 7,
+    BadSequenceNumber,
     /// Duplicate sequence number (software cannot recover).
-    DuplicateSequenceNumber = 8,
+    DuplicateSequenceNumber,
     /// Total Message Size is greater than 1785 bytes.
-    MessageTooLarge = 9,
+    MessageTooLarge,
+    /// Unexpected ETP.CM_DPO packet.
+    UnexpectedDataPacketOffset,
+    /// Bad ETP.CM_DPO offset.
+    BadDataPacketOffset,
+    /// Unexpected PGN in an ETP.CM_DPO packet.
+    UnexpectedDataPacketOffsetPgn,
+    /// ETP.CM_DPO number of packets is greater than granted by the CTS.
+    DataPacketOffsetExceedsCts,
+    /// Bad PGN in a connection management message.
+    BadPgn,
     /// If a Connection Abort reason is identified that is not listed in the table use code 250.
-    Custom = 250,
+    Custom,
+    /// Code not listed in the table, as used by some OEM stacks.
+    Other(u8),
 }
 
-impl TryFrom<u8> for AbortReason {
-    type Error = u8;
+impl AbortReason {
+    /// Decode a reason code, keeping codes not listed in the table.
+    pub const fn from_raw(raw: u8) -> Self {
+        match raw {
+            1 => Self::MaxConnections,
+            2 => Self::CanceledBySystem,
+            3 => Self::Timeout,
+            4 => Self::CtsWhileDataTransfer,
+            5 => Self::RetransmitLimitReached,
+            6 => Self::UnexpectedDataTransfer,
+            7 => Self::BadSequenceNumber,
+            8 => Self::DuplicateSequenceNumber,
+            9 => Self::MessageTooLarge,
+            10 => Self::UnexpectedDataPacketOffset,
+            11 => Self::BadDataPacketOffset,
+            12 => Self::UnexpectedDataPacketOffsetPgn,
+            13 => Self::DataPacketOffsetExceedsCts,
+            14 => Self::BadPgn,
+            250 => Self::Custom,
+            other => Self::Other(other),
+        }
+    }
 
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            x if x == Self::MaxConnections as u8 => Ok(Self::MaxConnections),
-            x if x == Self::CanceledBySystem as u8 => Ok(Self::CanceledBySystem),
-            x if x == Self::Timeout as u8 => Ok(Self::Timeout),
-            x if x == Self::CtsWhileDataTransfer as u8 => Ok(Self::CtsWhileDataTransfer),
-            x if x == Self::RetransmitLimitReached as u8 => Ok(Self::RetransmitLimitReached),
-            x if x == Self::UnexpectedDataTransfer as u8 => Ok(Self::UnexpectedDataTransfer),
-            x if x == Self::BadSequenceNumber as u8 => Ok(Self::BadSequenceNumber),
-            x if x == Self::DuplicateSequenceNumber as u8 => Ok(Self::DuplicateSequenceNumber),
-            x if x == Self::MessageTooLarge as u8 => Ok(Self::MessageTooLarge),
-            x if x == Self::Custom as u8 => Ok(Self::Custom),
-            _ => Err(value),
+    /// Reason code sent on the bus.
+    pub const fn as_raw(self) -> u8 {
+        match self {
+            Self::MaxConnections => 1,
+            Self::CanceledBySystem => 2,
+            Self::Timeout => 3,
+            Self::CtsWhileDataTransfer => 4,
+            Self::RetransmitLimitReached => 5,
+            Self::UnexpectedDataTransfer => 6,
+            Self::BadSequenceNumber => 7,
+            Self::DuplicateSequenceNumber => 8,
+            Self::MessageTooLarge => 9,
+            Self::UnexpectedDataPacketOffset => 10,
+            Self::BadDataPacketOffset => 11,
+            Self::UnexpectedDataPacketOffsetPgn => 12,
+            Self::DataPacketOffsetExceedsCts => 13,
+            Self::BadPgn => 14,
+            Self::Custom => 250,
+            Self::Other(raw) => raw,
         }
     }
 }
 
+impl From<u8> for AbortReason {
+    fn from(value: u8) -> Self {
+        Self::from_raw(value)
+    }
+}
+
 impl From<&AbortReason> for u8 {
     fn from(value: &AbortReason) -> Self {
-        *value as u8
+        value.as_raw()
     }
 }
 
 /// Abort message sender role.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AbortSenderRole {
     Sender = 0b00,
     Receiver = 0b01,
@@ -420,8 +771,9 @@ impl From<&AbortSenderRole> for u8 {
 }
 
 /// Data transfer (TP.DT) message.
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DataTransfer {
     sequence: u8,
     data: [u8; 7],
@@ -434,44 +786,46 @@ impl DataTransfer {
     ///
     /// Data with less than 7 bytes should have the remaining bytes padded with
     /// 0xFF.
-    pub fn new(sequence: u8, data: [u8; 7]) -> Self {
+    pub const fn new(sequence: u8, data: [u8; 7]) -> Self {
         Self { sequence, data }
     }
 
+    /// Encode the message.
+    pub const fn to_bytes(&self) -> [u8; 8] {
+        [
+            self.sequence,
+            self.data[0],
+            self.data[1],
+            self.data[2],
+            self.data[3],
+            self.data[4],
+            self.data[5],
+            self.data[6],
+        ]
+    }
+
     /// Packet sequence number.
-    pub fn sequence(&self) -> u8 {
+    pub const fn sequence(&self) -> u8 {
         self.sequence
     }
 
     /// Payload data.
-    pub fn data(&self) -> [u8; 7] {
+    pub const fn data(&self) -> [u8; 7] {
         self.data
     }
 }
 
 impl From<&DataTransfer> for [u8; 8] {
     fn from(value: &DataTransfer) -> Self {
-        [
-            value.sequence,
-            value.data[0],
-            value.data[1],
-            value.data[2],
-            value.data[3],
-            value.data[4],
-            value.data[5],
-            value.data[6],
-        ]
+        value.to_bytes()
     }
 }
 
 impl<'a> TryFrom<&'a [u8]> for DataTransfer {
-    type Error = &'a [u8];
+    type Error = DecodeError;
 
     fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
-        if value.len() != 8 {
-            return Err(value);
-        }
-
+        let value = exact::<8>(value)?;
         Ok(Self {
             sequence: value[0],
             data: [
@@ -480,3 +834,51 @@ impl<'a> TryFrom<&'a [u8]> for DataTransfer {
         })
     }
 }
+
+impl From<DataTransferRef<'_>> for DataTransfer {
+    fn from(view: DataTransferRef<'_>) -> Self {
+        Self::new(view.sequence(), *view.data())
+    }
+}
+
+/// Data transfer (TP.DT) message, interpreted in place.
+///
+/// Borrows the payload from the frame instead of copying it, for receive
+/// paths that handle every packet of a transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DataTransferRef<'a> {
+    raw: &'a [u8; 8],
+}
+
+impl<'a> DataTransferRef<'a> {
+    /// Packet sequence number.
+    pub const fn sequence(&self) -> u8 {
+        self.raw[0]
+    }
+
+    /// Payload data.
+    pub const fn data(&self) -> &'a [u8; 7] {
+        let [_, data @ ..] = self.raw;
+        data
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &'a [u8; 8] {
+        self.raw
+    }
+}
+
+impl<'a> From<&'a [u8; 8]> for DataTransferRef<'a> {
+    fn from(raw: &'a [u8; 8]) -> Self {
+        Self { raw }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for DataTransferRef<'a> {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self { raw: exact(value)? })
+    }
+}
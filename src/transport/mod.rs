@@ -1,28 +1,118 @@
 //! Transport protocol (J1939-21)
 
+pub(crate) mod assembly;
+mod checksum;
+pub mod etp;
+pub mod fd;
+#[cfg(feature = "embedded-io")]
+mod io;
+#[cfg(feature = "std")]
+pub mod loopback;
+mod manager;
 mod message;
+mod sender;
+mod stats;
+mod storage;
 
+use crate::id::{Id, IdBuilder, Pgn};
+use crate::time::{Duration, Instant};
+use assembly::Segments;
+pub use checksum::{Checksum, Checksummed, Crc16, Crc32};
+use core::marker::PhantomData;
+#[cfg(feature = "embedded-io")]
+pub use io::{Sink, Source, StreamError};
 use managed::ManagedSlice;
+pub use manager::TransportManager;
 pub use message::{
-    AbortReason, AbortSenderRole, ClearToSend, ConnectionAbort, DataTransfer, EndOfMessageAck,
-    RequestToSend,
+    AbortReason, AbortSenderRole, BroadcastAnnounce, ClearToSend, ConnectionAbort, ControlKind,
+    ControlMessage, ControlMessageRef, DataTransfer, DataTransferRef, EndOfMessageAck,
+    RequestToSend, RequestToSendError,
 };
+pub use sender::{BroadcastTransfer, OutgoingTransfer, Packets};
+pub use stats::TransferStats;
+#[cfg(feature = "embedded-storage")]
+pub use storage::FlashStorage;
+pub use storage::{CallbackStorage, TransferStorage};
 
-#[derive(Debug, Clone, Copy)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+/// Maximum time between two data transfers (T1).
+pub const T1: Duration = Duration::from_millis(750);
+/// Maximum time after sending a CTS until the next data transfer (T2).
+pub const T2: Duration = Duration::from_millis(1250);
+/// Maximum time after sending the last data transfer of a window until the
+/// next CTS or end of message acknowledge (T3).
+pub const T3: Duration = Duration::from_millis(1250);
+/// Maximum time after receiving a CTS holding the connection open until the
+/// next CTS (T4).
+pub const T4: Duration = Duration::from_millis(1050);
+/// Maximum time to respond (Tr).
+pub const TR: Duration = Duration::from_millis(200);
+/// Interval of CTS messages holding the connection open (Th).
+pub const TH: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Error {
     StorageTooSmall,
     Sequence,
     PreviousAbort,
+    /// The storage backend failed.
+    Storage,
+    /// The peer aborted the session.
+    Aborted(AbortReason),
+    /// The peer did not respond in time.
+    Timeout,
+}
+
+/// Any transport protocol error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TransportError {
+    /// Failure of an ongoing session.
+    Session(Error),
+    /// Invalid request to send arguments.
+    RequestToSend(RequestToSendError),
+}
+
+impl From<Error> for TransportError {
+    fn from(value: Error) -> Self {
+        TransportError::Session(value)
+    }
 }
 
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "defmt-1", derive(defmt::Format))]
+impl From<RequestToSendError> for TransportError {
+    fn from(value: RequestToSendError) -> Self {
+        TransportError::RequestToSend(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Response {
     Cts(ClearToSend),
     End(EndOfMessageAck),
 }
 
+impl Response {
+    /// Complete TP.CM frame sent by `sa` to the originator at `da`.
+    pub fn to_frame(&self, sa: u8, da: u8) -> Option<(Id, [u8; 8])> {
+        tp_cm(sa, da, self.into())
+    }
+}
+
+/// Connection management frame from `sa` to `da`.
+fn tp_cm(sa: u8, da: u8, data: [u8; 8]) -> Option<(Id, [u8; 8])> {
+    let id = IdBuilder::new()
+        .priority(7)
+        .pgn(Pgn::TransportProtocolConnectionManagement)
+        .sa(sa)
+        .da(da)
+        .build()?;
+    Some((id, data))
+}
+
 impl From<&Response> for [u8; 8] {
     fn from(value: &Response) -> Self {
         match value {
@@ -32,132 +122,725 @@ impl From<&Response> for [u8; 8] {
     }
 }
 
+/// Transfer flag set once the session was aborted.
+const ABORTED: u8 = 1 << 0;
+/// Transfer flag of broadcast transfers, which never respond.
+const BROADCAST: u8 = 1 << 1;
+/// Transfer flag set until the timer is restarted by [`Transfer::poll`].
+const RESTART: u8 = 1 << 2;
+/// Transfer flag set while the receiver holds the connection open.
+const HOLD: u8 = 1 << 3;
+/// Transfer flag set from a rewind until the first repeated packet arrives.
+const REWIND: u8 = 1 << 4;
+/// Transfer flag to ignore repeated copies of the last packet.
+const DUPLICATES: u8 = 1 << 5;
+/// Transfer flag to keep only what fits into the storage.
+const TRUNCATE: u8 = 1 << 6;
+/// Transfer flag set when the sender aborted the session.
+const PEER_ABORTED: u8 = 1 << 7;
+
+/// Source address of a transfer whose sender was not recorded.
+const NULL_ADDRESS: u8 = 0xFE;
+const GLOBAL_ADDRESS: u8 = 0xFF;
+
+/// Payload of a completed transfer with the group it belongs to.
+///
+/// `data` borrows the payload from [`Transfer::completed`] or holds the
+/// storage it was reassembled into after [`Transfer::into_message`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CompletedTransfer<D> {
+    pub pgn: Pgn,
+    /// Address of the sender.
+    pub source: u8,
+    pub data: D,
+}
+
 /// An ongoing transport-protocol transfer.
+///
+/// The payload is reassembled into `S`, a [`ManagedSlice`] unless another
+/// [`TransferStorage`] is given. The session state besides the storage takes
+/// 24 bytes, so nodes can track many sessions at once.
+///
+/// The transfer times out when the sender goes silent for longer than
+/// [`T1`] between packets, or [`T2`] after a CTS. Its timer runs from the
+/// times passed to [`Transfer::poll`].
 #[derive(Debug)]
-pub struct Transfer<'a> {
-    rts: RequestToSend,
+pub struct Transfer<'a, S = ManagedSlice<'a, u8>> {
+    /// PGN of the payload.
+    pgn: u32,
+    total_size: u16,
+    total_packets: u8,
+    /// Packets per CTS, 255 for no limit.
+    max_packets_per_response: u8,
     rx_packets: u8,
-    storage: ManagedSlice<'a, u8>,
-    abort: bool,
+    /// Packets received before the current window.
+    window_start: u8,
+    flags: u8,
+    source: u8,
+    /// Address the session is directed to, global for broadcasts.
+    destination: u8,
+    /// Ignored copies of the last packet, saturating.
+    duplicates: u8,
+    /// Packets requested again by rewinds, saturating.
+    retransmits: u8,
+    deadline: Instant,
+    storage: S,
+    _storage: PhantomData<&'a mut [u8]>,
 }
 
+const _: () = assert!(core::mem::size_of::<Transfer<'static, ()>>() == 24);
+
 impl<'a> Transfer<'a> {
     /// Create a new transfer from a RTS message received from the sender.
     #[cfg(feature = "alloc")]
     pub fn new(rts: RequestToSend) -> Self {
+        Self::with_storage(rts, Vec::new().into())
+    }
+
+    /// Create a new transfer from a RTS message received from the sender using provided storage.
+    pub fn new_with_storage(rts: RequestToSend, storage: impl Into<ManagedSlice<'a, u8>>) -> Self {
+        Self::with_storage(rts, storage.into())
+    }
+
+    /// Consume the completed transfer, keeping the payload in its storage.
+    ///
+    /// Borrowed storage is narrowed to the payload and owned storage is
+    /// truncated to it. Returns the transfer if it is not complete.
+    pub fn into_message(self) -> Result<CompletedTransfer<ManagedSlice<'a, u8>>, Self> {
+        if self.finished().is_none() {
+            return Err(self);
+        }
+
+        let len = self.stored_size();
+        let data = match self.storage {
+            ManagedSlice::Borrowed(slice) => {
+                let len = len.min(slice.len());
+                ManagedSlice::Borrowed(&mut slice[..len])
+            }
+            #[cfg(feature = "alloc")]
+            ManagedSlice::Owned(mut vec) => {
+                vec.truncate(len);
+                ManagedSlice::Owned(vec)
+            }
+        };
+
+        Ok(CompletedTransfer {
+            pgn: Pgn::from_raw(self.pgn),
+            source: self.source,
+            data,
+        })
+    }
+}
+
+impl<S: TransferStorage> Transfer<'_, S> {
+    /// Create a new transfer from a RTS message received from the sender,
+    /// checking that `storage` can hold the payload.
+    ///
+    /// Returns the storage if it is too small, so nothing is written until
+    /// the whole payload is known to fit.
+    pub fn try_with_storage(rts: RequestToSend, storage: S) -> Result<Self, S> {
+        if storage.capacity() < usize::from(rts.total_size()) {
+            return Err(storage);
+        }
+        Ok(Self::with_storage(rts, storage))
+    }
+
+    /// Create a new transfer from a RTS message received from the sender,
+    /// reassembling the payload into `storage`.
+    ///
+    /// A payload larger than `storage` aborts the transfer with
+    /// [`Error::StorageTooSmall`] at the first packet that does not fit.
+    pub fn with_storage(rts: RequestToSend, storage: S) -> Self {
         Self {
-            rts,
+            pgn: rts.pgn().as_raw(),
+            total_size: rts.total_size(),
+            total_packets: rts.total_packets(),
+            max_packets_per_response: window(&rts),
             rx_packets: 0,
-            storage: Vec::new().into(),
-            abort: false,
+            window_start: 0,
+            flags: RESTART,
+            source: NULL_ADDRESS,
+            destination: NULL_ADDRESS,
+            duplicates: 0,
+            retransmits: 0,
+            deadline: Instant::from_millis(0),
+            storage,
+            _storage: PhantomData,
         }
     }
 
-    /// Create a new transfer from a RTS message received from the sender using provided storage.
-    pub fn new_with_storage(rts: RequestToSend, storage: impl Into<ManagedSlice<'a, u8>>) -> Self {
+    /// Record `source` as the address of the sender.
+    ///
+    /// Transfers report the null address `0xFE` until a sender is recorded.
+    pub fn with_source(mut self, source: u8) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Ignore exact copies of the last packet instead of aborting.
+    ///
+    /// Some senders repeat their last packet. A copy whose payload bytes
+    /// differ from the stored ones, or that can not be compared because the
+    /// storage can not be read in place, still aborts the transfer with
+    /// [`AbortReason::DuplicateSequenceNumber`].
+    pub fn with_duplicates_ignored(mut self) -> Self {
+        self.flags |= DUPLICATES;
+        self
+    }
+
+    /// Accept payloads larger than the storage, keeping only their first
+    /// bytes.
+    ///
+    /// The transfer runs to the end as usual, but bytes past the capacity of
+    /// the storage are dropped and [`Transfer::finished`] returns the ones
+    /// kept. The announced size is still reported by [`Transfer::rts`].
+    pub fn with_truncation(mut self) -> Self {
+        self.flags |= TRUNCATE;
+        self
+    }
+
+    /// Check if bytes of the payload are dropped for lack of storage.
+    pub fn is_truncated(&self) -> bool {
+        self.stored_size() < usize::from(self.total_size)
+    }
+
+    /// Address of the sender.
+    pub fn source(&self) -> u8 {
+        self.source
+    }
+
+    /// Record the addresses of the session, as taken from the RTS frame.
+    ///
+    /// `originator` is the sender, as with [`Transfer::with_source`], and
+    /// `responder` the destination the RTS was sent to. Broadcasts are
+    /// directed to the global address `0xFF` from the start.
+    pub fn with_peers(mut self, originator: u8, responder: u8) -> Self {
+        self.source = originator;
+        self.destination = responder;
+        self
+    }
+
+    /// Address of the sender, which responses are directed to.
+    pub fn originator(&self) -> u8 {
+        self.source
+    }
+
+    /// Address the session is directed to, which responses are sent from.
+    ///
+    /// Destination specific transfers report the null address `0xFE` until
+    /// one is recorded with [`Transfer::with_peers`].
+    pub fn responder(&self) -> u8 {
+        self.destination
+    }
+
+    /// Complete TP.CM frame of `response`, from the responder to the
+    /// originator.
+    pub fn response_frame(&self, response: &Response) -> Option<(Id, [u8; 8])> {
+        response.to_frame(self.destination, self.source)
+    }
+
+    /// Complete TP.CM frame rejecting `rts` with
+    /// [`AbortReason::MaxConnections`], for a new request from the originator
+    /// while this session is in progress.
+    ///
+    /// The transfer itself is left as it is.
+    pub fn reject(&self, rts: &RequestToSend) -> Option<(Id, [u8; 8])> {
+        let abort = ConnectionAbort::new(
+            AbortReason::MaxConnections,
+            AbortSenderRole::Receiver,
+            rts.pgn(),
+        );
+        abort.to_frame(self.destination, self.source)
+    }
+
+    /// Complete TP.CM frame of `abort`, from the responder to the
+    /// originator, unless the transfer is a broadcast.
+    pub fn abort_frame(&self, abort: &ConnectionAbort) -> Option<(Id, [u8; 8])> {
+        if self.is_broadcast() {
+            return None;
+        }
+        abort.to_frame(self.destination, self.source)
+    }
+
+    /// Create a new transfer from a BAM message received from the sender,
+    /// reassembling the payload into `storage`.
+    ///
+    /// Broadcast transfers accept the data transfers as they come and never
+    /// respond. A failing packet ends the transfer like a destination
+    /// specific one, but the returned connection abort must not be sent.
+    pub fn broadcast(bam: BroadcastAnnounce, storage: S) -> Self {
         Self {
-            rts,
+            pgn: bam.pgn().as_raw(),
+            total_size: bam.total_size(),
+            total_packets: bam.total_packets(),
+            max_packets_per_response: 255,
             rx_packets: 0,
-            storage: storage.into(),
-            abort: false,
+            window_start: 0,
+            flags: BROADCAST | RESTART,
+            source: NULL_ADDRESS,
+            destination: GLOBAL_ADDRESS,
+            duplicates: 0,
+            retransmits: 0,
+            deadline: Instant::from_millis(0),
+            storage,
+            _storage: PhantomData,
+        }
+    }
+
+    /// Check if the transfer was announced with a BAM message.
+    pub fn is_broadcast(&self) -> bool {
+        self.flags & BROADCAST != 0
+    }
+
+    /// The RTS message this transfer was started from.
+    ///
+    /// Broadcast transfers report a RTS without packet limit.
+    pub fn rts(&self) -> RequestToSend {
+        let size = self.total_size.to_le_bytes();
+        let pgn = self.pgn.to_le_bytes();
+        RequestToSend::from_raw(&[
+            RequestToSend::MUX,
+            size[0],
+            size[1],
+            self.total_packets,
+            self.max_packets_per_response,
+            pgn[0],
+            pgn[1],
+            pgn[2],
+        ])
+    }
+
+    /// Start over with the session announced by `rts`, reusing the storage.
+    ///
+    /// The storage is reset, which clears owned buffers but keeps their
+    /// allocation. The responder and whether duplicates are ignored or
+    /// payloads truncated carry over. The sender does not and has to be
+    /// recorded again with [`Transfer::with_source`].
+    pub fn reset(&mut self, rts: RequestToSend) {
+        self.storage.reset();
+        self.pgn = rts.pgn().as_raw();
+        self.total_size = rts.total_size();
+        self.total_packets = rts.total_packets();
+        self.max_packets_per_response = window(&rts);
+        self.rx_packets = 0;
+        self.window_start = 0;
+        self.duplicates = 0;
+        self.retransmits = 0;
+        if self.is_broadcast() {
+            self.destination = NULL_ADDRESS;
         }
+        self.flags = (self.flags & (DUPLICATES | TRUNCATE)) | RESTART;
+        self.source = NULL_ADDRESS;
+    }
+
+    /// Consume the transfer and hand back its storage for reuse.
+    ///
+    /// The storage is reset, which clears owned buffers.
+    pub fn into_storage(mut self) -> S {
+        self.storage.reset();
+        self.storage
+    }
+
+    /// Check if the whole payload has been stored.
+    pub fn is_finished(&self) -> bool {
+        self.rx_packets >= self.total_packets && self.flags & ABORTED == 0
     }
 
     /// Return read-only acess to the internal buffer.
     ///
-    /// The contents of this buffer are only valid after the transfer is complete.
+    /// The contents of this buffer are only valid after the transfer is
+    /// complete. Storage that can not be read in place returns `None`.
     pub fn finished(&self) -> Option<&[u8]> {
-        if self.rx_packets >= self.rts.total_packets() && !self.abort {
-            Some(&self.storage[..self.rts.total_size() as usize])
+        if self.is_finished() {
+            self.storage.read(self.stored_size())
         } else {
             None
         }
     }
 
+    /// The payload with its PGN and sender, once the transfer is complete.
+    ///
+    /// Storage that can not be read in place returns `None`, as with
+    /// [`Transfer::finished`].
+    pub fn completed(&self) -> Option<CompletedTransfer<&[u8]>> {
+        Some(CompletedTransfer {
+            pgn: Pgn::from_raw(self.pgn),
+            source: self.source,
+            data: self.finished()?,
+        })
+    }
+
     /// Feed the transfer with the next data transfer.
     pub fn next(
         &mut self,
         msg: DataTransfer,
     ) -> Result<Option<Response>, (Error, ConnectionAbort)> {
-        if self.abort {
+        self.receive(msg.sequence(), &msg.data())
+    }
+
+    /// Feed the transfer with a data transfer borrowed from the frame.
+    ///
+    /// Behaves like [`Transfer::next`] without copying the payload first.
+    pub fn next_ref(
+        &mut self,
+        msg: DataTransferRef<'_>,
+    ) -> Result<Option<Response>, (Error, ConnectionAbort)> {
+        self.receive(msg.sequence(), msg.data())
+    }
+
+    fn receive(
+        &mut self,
+        sequence: u8,
+        data: &[u8; 7],
+    ) -> Result<Option<Response>, (Error, ConnectionAbort)> {
+        if self.flags & ABORTED != 0 {
             return Err((
                 Error::PreviousAbort,
-                ConnectionAbort::new(
-                    AbortReason::UnexpectedDataTransfer,
-                    AbortSenderRole::Receiver,
-                    self.rts.pgn(),
-                ),
+                self.abort(AbortReason::UnexpectedDataTransfer),
             ));
         }
 
-        if msg.sequence() != self.rx_packets + 1 {
-            self.abort = true;
+        if self.flags & DUPLICATES != 0 && self.rx_packets > 0 && sequence == self.rx_packets {
+            if self.is_duplicate(data) {
+                self.duplicates = self.duplicates.saturating_add(1);
+                return Ok(None);
+            }
             return Err((
                 Error::Sequence,
-                ConnectionAbort::new(
-                    AbortReason::BadSequenceNumber,
-                    AbortSenderRole::Receiver,
-                    self.rts.pgn(),
-                ),
+                self.abort(AbortReason::DuplicateSequenceNumber),
             ));
         }
 
-        match &mut self.storage {
-            #[cfg(feature = "alloc")]
-            ManagedSlice::Owned(vec) => {
-                vec.extend_from_slice(&msg.data());
-                vec.truncate(self.rts.total_size() as usize);
-            }
-            ManagedSlice::Borrowed(slice) => {
-                let Some(chunk) = slice.chunks_mut(7).nth(self.rx_packets as usize) else {
-                    self.abort = true;
-                    return Err((
-                        Error::StorageTooSmall,
-                        ConnectionAbort::new(
-                            AbortReason::Custom,
-                            AbortSenderRole::Receiver,
-                            self.rts.pgn(),
-                        ),
-                    ));
-                };
-                chunk.clone_from_slice(&msg.data()[..chunk.len()]);
-            }
+        let expected = self.rx_packets.checked_add(1);
+        if self.flags & REWIND != 0 && expected.is_some_and(|next| sequence > next) {
+            // the rest of the window sent before the rewind
+            return Ok(None);
+        }
+
+        if expected != Some(sequence) {
+            return Err((Error::Sequence, self.abort(AbortReason::BadSequenceNumber)));
         }
 
+        let stored_size = self.stored_size();
+        let mut result = Segments::TP
+            .store(&mut self.storage, self.rx_packets.into(), stored_size, data)
+            .map(|_| ());
+
         self.rx_packets += 1;
+        self.flags = (self.flags | RESTART) & !(HOLD | REWIND);
+        let response = if self.is_broadcast() {
+            None
+        } else {
+            response(&self.rts(), self.rx_packets, self.window_start)
+        };
+
+        if self.rx_packets == self.total_packets {
+            result = result.and_then(|_| self.storage.finish());
+        }
+        if let Err(e) = result {
+            return Err((e, self.abort(AbortReason::Custom)));
+        }
+
+        Ok(response)
+    }
+
+    /// Abort the transfer, as when the application shuts down or needs the
+    /// storage for something else.
+    ///
+    /// Returns the connection abort to send, unless the transfer is a
+    /// broadcast. Data transfers that still arrive are rejected with
+    /// [`Error::PreviousAbort`].
+    pub fn abort(&mut self, reason: AbortReason) -> ConnectionAbort {
+        self.flags |= ABORTED;
+        ConnectionAbort::new(reason, AbortSenderRole::Receiver, Pgn::from_raw(self.pgn))
+    }
+
+    /// Counters of the session so far.
+    pub fn stats(&self) -> TransferStats {
+        let aborted = self.flags & ABORTED != 0;
+        let peer_aborted = self.flags & PEER_ABORTED != 0;
+        let packets = u32::from(self.rx_packets) + u32::from(self.retransmits);
+        TransferStats {
+            packets,
+            bytes: (u32::from(self.rx_packets) * 7).min(self.total_size.into()),
+            duplicates: self.duplicates.into(),
+            retransmits: self.retransmits.into(),
+            aborts_sent: (aborted && !peer_aborted && !self.is_broadcast()).into(),
+            aborts_received: peer_aborted.into(),
+            completed: self.is_finished().into(),
+        }
+    }
+
+    /// Feed the transfer with a received frame of any PGN.
+    ///
+    /// Data transfers are passed to [`Transfer::next`]. A connection abort
+    /// for the PGN of the transfer ends it with [`Error::Aborted`]. A CTS for
+    /// it, which only the receiver sends, aborts it. Other frames are
+    /// ignored, as are all frames from other senders once one was recorded
+    /// with [`Transfer::with_source`]. The destination is not checked.
+    ///
+    /// On failure, the connection abort to send is returned alongside the
+    /// error, unless the sender aborted the session itself or the transfer
+    /// is a broadcast.
+    pub fn handle_frame(
+        &mut self,
+        id: Id,
+        data: &[u8; 8],
+    ) -> Result<Option<Response>, (Error, Option<ConnectionAbort>)> {
+        if self.source != NULL_ADDRESS && id.sa() != self.source {
+            return Ok(None);
+        }
 
-        if self.rx_packets == self.rts.total_packets() {
-            return Ok(Some(Response::End(EndOfMessageAck::new(
-                self.rts.total_size(),
-                self.rts.total_packets(),
-                self.rts.pgn(),
-            ))));
-        }
-
-        if let Some(packets_per_response) = self.rts.max_packets_per_response() {
-            // send cts on nth data transfer
-            if msg.sequence() % packets_per_response == 0 {
-                return Ok(Some(Response::Cts(ClearToSend::new(
-                    self.rts.max_packets_per_response(),
-                    self.rx_packets + 1,
-                    self.rts.pgn(),
-                ))));
+        let result = match id.pgn() {
+            Pgn::TransportProtocolDataTransfer => self.next_ref(data.into()),
+            Pgn::TransportProtocolConnectionManagement => {
+                let Ok(msg) = ControlMessageRef::try_from(&data[..]) else {
+                    return Ok(None);
+                };
+                let ended = self.flags & ABORTED != 0 || self.rx_packets >= self.total_packets;
+                if ended || msg.pgn().as_raw() != self.pgn {
+                    return Ok(None);
+                }
+
+                match msg.kind() {
+                    ControlKind::ConnectionAbort => {
+                        self.flags |= ABORTED | PEER_ABORTED;
+                        let reason = msg.reason().unwrap_or(AbortReason::Custom);
+                        return Err((Error::Aborted(reason), None));
+                    }
+                    ControlKind::ClearToSend => Err((
+                        Error::Sequence,
+                        self.abort(AbortReason::CtsWhileDataTransfer),
+                    )),
+                    _ => return Ok(None),
+                }
             }
+            _ => return Ok(None),
+        };
+
+        let broadcast = self.is_broadcast();
+        result.map_err(|(error, abort)| (error, (!broadcast).then_some(abort)))
+    }
+
+    /// Hold the connection open instead of granting the next packets.
+    ///
+    /// Returns the CTS to send in place of the one granting the next window,
+    /// or `None` unless the transfer waits for a CTS. While held, the sender
+    /// waits up to [`T4`] after each hold, so the hold has to be sent again
+    /// by [`Transfer::deadline`], which is [`TH`] after [`Transfer::poll`]
+    /// restarted the timer. [`Transfer::resume`] grants the packets.
+    pub fn hold(&mut self) -> Option<ClearToSend> {
+        let ended = self.flags & ABORTED != 0 || self.rx_packets >= self.total_packets;
+        if ended || self.is_broadcast() || !self.awaits_cts() {
+            return None;
+        }
+
+        self.flags |= HOLD | RESTART;
+        Some(ClearToSend::hold(
+            self.rx_packets + 1,
+            Pgn::from_raw(self.pgn),
+        ))
+    }
+
+    /// Check if the receiver holds the connection open.
+    pub fn is_held(&self) -> bool {
+        self.flags & HOLD != 0
+    }
+
+    /// Stop holding the connection open.
+    ///
+    /// Returns the CTS granting the next window, or `None` unless the
+    /// connection was held.
+    pub fn resume(&mut self) -> Option<ClearToSend> {
+        if !self.is_held() || self.flags & ABORTED != 0 {
+            return None;
+        }
+
+        self.flags = (self.flags | RESTART) & !HOLD;
+        Some(ClearToSend::new(
+            self.rts().max_packets_per_response(),
+            self.rx_packets + 1,
+            Pgn::from_raw(self.pgn),
+        ))
+    }
+
+    /// Request the packets from `next_sequence` on again.
+    ///
+    /// Returns the CTS rewinding the sender, to send in place of the one
+    /// granting the next window, as senders abort on a CTS while sending.
+    /// Returns `None` unless `next_sequence` was received already, the
+    /// transfer is still receiving and its storage is
+    /// [rewritable](TransferStorage::rewritable). Packets of the current window that are
+    /// still underway are ignored until the first repeated one arrives.
+    pub fn rewind(&mut self, next_sequence: u8) -> Option<ClearToSend> {
+        let ended = self.flags & ABORTED != 0 || self.rx_packets >= self.total_packets;
+        if ended
+            || self.is_broadcast()
+            || !self.storage.rewritable()
+            || next_sequence == 0
+            || next_sequence > self.rx_packets
+        {
+            return None;
+        }
+
+        let repeated = self.rx_packets - (next_sequence - 1);
+        self.retransmits = self.retransmits.saturating_add(repeated);
+        self.rx_packets = next_sequence - 1;
+        self.window_start = self.rx_packets;
+        self.flags = (self.flags | REWIND | RESTART) & !HOLD;
+        Some(ClearToSend::new(
+            self.rts().max_packets_per_response(),
+            next_sequence,
+            Pgn::from_raw(self.pgn),
+        ))
+    }
+
+    /// Advance the timer of the transfer to `now`.
+    ///
+    /// Call after the session was opened and after each packet, with the
+    /// time it was received, to restart the timer, and again once
+    /// [`Transfer::deadline`] has passed. A sender that went silent aborts
+    /// the transfer with [`Error::Timeout`], returned with the connection
+    /// abort to send unless the transfer is a broadcast, which must not be
+    /// aborted. A held connection does not time out.
+    pub fn poll(&mut self, now: Instant) -> Result<(), (Error, Option<ConnectionAbort>)> {
+        if self.flags & ABORTED != 0 || self.rx_packets >= self.total_packets {
+            return Ok(());
+        }
+
+        if self.flags & RESTART != 0 {
+            self.flags &= !RESTART;
+            self.deadline = now + self.timeout();
+            return Ok(());
+        }
+
+        if now < self.deadline || self.is_held() {
+            return Ok(());
         }
 
-        Ok(None)
+        let abort = self.abort(AbortReason::Timeout);
+        Err((Error::Timeout, (!self.is_broadcast()).then_some(abort)))
+    }
+
+    /// Time at which the transfer times out, unless the next packet arrives
+    /// first.
+    ///
+    /// While the connection is held, the time by which the next hold is due.
+    ///
+    /// `None` once the transfer ended, or while the timer waits to be
+    /// restarted by [`Transfer::poll`].
+    pub fn deadline(&self) -> Option<Instant> {
+        let running = self.flags & (ABORTED | RESTART) == 0;
+        (running && self.rx_packets < self.total_packets).then_some(self.deadline)
+    }
+
+    /// Time allowed until the next packet.
+    fn timeout(&self) -> Duration {
+        if self.is_held() {
+            TH
+        } else if self.awaits_cts() && !self.is_broadcast() {
+            T2
+        } else {
+            T1
+        }
+    }
+
+    /// Check if `msg` repeats the payload bytes of the last packet.
+    fn is_duplicate(&self, data: &[u8; 7]) -> bool {
+        let range = Segments::TP.range(usize::from(self.rx_packets) - 1, self.stored_size());
+        let stored = self
+            .storage
+            .read(range.end)
+            .and_then(|p| p.get(range.clone()));
+        stored.is_some_and(|stored| data.get(..range.len()) == Some(stored))
+    }
+
+    /// Bytes of the payload written to the storage.
+    fn stored_size(&self) -> usize {
+        let total = usize::from(self.total_size);
+        if self.flags & TRUNCATE != 0 {
+            total.min(self.storage.capacity())
+        } else {
+            total
+        }
+    }
+
+    /// Check if the next packet starts a window granted by a CTS.
+    #[allow(clippy::manual_is_multiple_of)]
+    fn awaits_cts(&self) -> bool {
+        let window = self.max_packets_per_response;
+        let received = self.rx_packets - self.window_start;
+        received == 0 || (window != 255 && window != 0 && received % window == 0)
+    }
+}
+
+impl<S: TransferStorage, C: Checksum> Transfer<'_, Checksummed<S, C>> {
+    /// Checksum of the payload, once the whole payload has been stored.
+    pub fn checksum(&self) -> Option<C::Output> {
+        self.is_finished().then(|| self.storage.checksum())
+    }
+}
+
+/// Packets per CTS of the session announced by `rts`, 255 for no limit.
+///
+/// A RTS built without [`RequestToSend::try_new`], as by deserializing, may
+/// ask for a window of 0, which is taken as no limit.
+fn window(rts: &RequestToSend) -> u8 {
+    match rts.max_packets_per_response() {
+        Some(0) | None => 255,
+        Some(n) => n,
+    }
+}
+
+/// Response due after `rx_packets` packets of the session announced by `rts`,
+/// the first `window_start` of which were received before the current window.
+#[allow(clippy::manual_is_multiple_of)]
+fn response(rts: &RequestToSend, rx_packets: u8, window_start: u8) -> Option<Response> {
+    if rx_packets == rts.total_packets() {
+        return Some(Response::End(EndOfMessageAck::new(
+            rts.total_size(),
+            rts.total_packets(),
+            rts.pgn(),
+        )));
+    }
+
+    // send cts on nth data transfer
+    let packets_per_response = rts.max_packets_per_response().filter(|&n| n != 0)?;
+    ((rx_packets - window_start) % packets_per_response == 0).then(|| {
+        Response::Cts(ClearToSend::new(
+            Some(packets_per_response),
+            rx_packets.saturating_add(1),
+            rts.pgn(),
+        ))
+    })
+}
+
+#[cfg(feature = "defmt")]
+impl<S> defmt::Format for Transfer<'_, S> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(
+            f,
+            "Transfer {{ pgn: {}, source: {=u8:#x}, total_size: {}, rx_packets: {}, flags: {=u8:#x} }}",
+            self.pgn,
+            self.source,
+            self.total_size,
+            self.rx_packets,
+            self.flags
+        )
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::id::Pgn;
+    use crate::id::{IdBuilder, Pgn};
 
     #[test]
     fn transmission() {
-        let rts = message::RequestToSend::new(16, Some(2), Pgn::ProprietaryA);
-        let mut transfer = Transfer::new(rts);
+        let rts = message::RequestToSend::try_new(16, Some(2), Pgn::ProprietaryA).unwrap();
+        let mut transfer = Transfer::new(rts.clone());
+        assert_eq!(transfer.rts().to_bytes(), rts.to_bytes());
 
         // send first data transfer
         let dt = message::DataTransfer::try_from([1, 1, 2, 3, 4, 5, 6, 7].as_ref()).unwrap();
@@ -180,4 +863,585 @@ mod tests {
             &[1, 2, 3, 4, 5, 6, 7, 1, 2, 3, 4, 5, 6, 7, 1, 2]
         );
     }
+
+    #[test]
+    fn zero_window() {
+        // a window of 0 from the bus is read as no limit
+        let rts = RequestToSend::try_from(&[16, 16, 0, 3, 0, 0, 0xEF, 0][..]).unwrap();
+        assert_eq!(rts.max_packets_per_response(), None);
+        let mut transfer = Transfer::new(rts);
+        assert_eq!(transfer.next(DataTransfer::new(1, [1; 7])), Ok(None));
+        assert_eq!(transfer.next(DataTransfer::new(2, [2; 7])), Ok(None));
+        let end = transfer.next(DataTransfer::new(3, [3; 7])).unwrap();
+        assert!(matches!(end, Some(Response::End(_))));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn zero_window_deserialized() {
+        let rts: RequestToSend = serde_json::from_str(
+            r#"{"total_size":16,"total_packets":3,"max_packets_per_response":0,"pgn":"ProprietaryA"}"#,
+        )
+        .unwrap();
+        assert_eq!(rts.max_packets_per_response(), Some(0));
+        let mut transfer = Transfer::new(rts);
+        assert_eq!(transfer.next(DataTransfer::new(1, [1; 7])), Ok(None));
+        assert_eq!(transfer.rts().max_packets_per_response(), None);
+    }
+
+    #[test]
+    fn completed_message() {
+        let rts = RequestToSend::try_new(9, None, Pgn::ProprietaryA).unwrap();
+        let mut buf = [0; 16];
+        let mut transfer = Transfer::new_with_storage(rts.clone(), &mut buf[..]).with_source(0x80);
+        transfer.next(DataTransfer::new(1, [1; 7])).unwrap();
+        assert_eq!(transfer.completed(), None);
+        let mut transfer = transfer.into_message().unwrap_err();
+
+        transfer.next(DataTransfer::new(2, [2; 7])).unwrap();
+        let expected = [1, 1, 1, 1, 1, 1, 1, 2, 2];
+        let message = transfer.completed().unwrap();
+        assert_eq!((message.pgn, message.source), (Pgn::ProprietaryA, 0x80));
+        assert_eq!(message.data, expected);
+
+        let message = transfer.into_message().unwrap();
+        assert_eq!(message.source, 0x80);
+        assert_eq!(&*message.data, expected);
+
+        // owned storage is truncated to the payload
+        let mut transfer = Transfer::new(rts);
+        transfer.next(DataTransfer::new(1, [1; 7])).unwrap();
+        transfer.next(DataTransfer::new(2, [2; 7])).unwrap();
+        let message = transfer.into_message().unwrap();
+        assert_eq!(message.source, 0xFE);
+        assert!(matches!(message.data, ManagedSlice::Owned(vec) if vec == expected));
+    }
+
+    #[test]
+    fn broadcast() {
+        let bam = BroadcastAnnounce::try_new(10, Pgn::Other(65226)).unwrap();
+        assert_eq!(bam.to_bytes(), [32, 10, 0, 2, 0xFF, 0xCA, 0xFE, 0x00]);
+        assert_eq!(
+            BroadcastAnnounce::try_from(&bam.to_bytes()[..]),
+            Ok(bam.clone())
+        );
+        assert!(RequestToSend::try_from(&bam.to_bytes()[..]).is_err());
+
+        let mut buf = [0; 10];
+        let mut transfer = Transfer::broadcast(bam, buf.as_mut_slice());
+        assert!(transfer.is_broadcast());
+        assert_eq!(transfer.next(DataTransfer::new(1, [1; 7])), Ok(None));
+        assert_eq!(transfer.next(DataTransfer::new(2, [2; 7])), Ok(None));
+        assert_eq!(
+            transfer.finished(),
+            Some([1, 1, 1, 1, 1, 1, 1, 2, 2, 2].as_slice())
+        );
+
+        let bam = BroadcastAnnounce::try_new(10, Pgn::Other(65226)).unwrap();
+        let mut transfer = Transfer::broadcast(bam, buf.as_mut_slice());
+        assert!(transfer.next(DataTransfer::new(2, [2; 7])).is_err());
+        assert!(!transfer.is_finished());
+    }
+
+    #[test]
+    fn timeouts() {
+        let rts = RequestToSend::try_new(20, Some(2), Pgn::ProprietaryA).unwrap();
+        let mut transfer = Transfer::new(rts);
+        assert_eq!(transfer.deadline(), None);
+        transfer.poll(Instant::from_millis(0)).unwrap();
+        assert_eq!(transfer.deadline(), Some(Instant::from_millis(0) + T2));
+
+        transfer.next(DataTransfer::new(1, [0; 7])).unwrap();
+        transfer.poll(Instant::from_millis(100)).unwrap();
+        assert_eq!(transfer.deadline(), Some(Instant::from_millis(100) + T1));
+
+        // the second packet is answered with a CTS
+        transfer.next(DataTransfer::new(2, [0; 7])).unwrap();
+        transfer.poll(Instant::from_millis(200)).unwrap();
+        assert_eq!(transfer.deadline(), Some(Instant::from_millis(200) + T2));
+
+        let (error, abort) = transfer.poll(Instant::from_millis(200) + T2).unwrap_err();
+        assert_eq!(error, Error::Timeout);
+        assert_eq!(abort.unwrap().reason(), AbortReason::Timeout);
+        assert_eq!(transfer.deadline(), None);
+        assert!(transfer.next(DataTransfer::new(3, [0; 7])).is_err());
+    }
+
+    #[test]
+    fn broadcast_timeout() {
+        let bam = BroadcastAnnounce::try_new(20, Pgn::ProprietaryA).unwrap();
+        let mut buf = [0; 20];
+        let mut transfer = Transfer::broadcast(bam, buf.as_mut_slice());
+        transfer.poll(Instant::from_millis(0)).unwrap();
+        transfer.next(DataTransfer::new(1, [0; 7])).unwrap();
+        transfer.poll(Instant::from_millis(50)).unwrap();
+        assert_eq!(transfer.deadline(), Some(Instant::from_millis(50) + T1));
+
+        // a broadcast ends without a connection abort
+        let result = transfer.poll(Instant::from_millis(50) + T1);
+        assert_eq!(result, Err((Error::Timeout, None)));
+        assert_eq!(transfer.deadline(), None);
+        assert_eq!(transfer.stats().aborts_sent, 0);
+    }
+
+    #[test]
+    fn frames() {
+        let id = |pgn, sa, da| {
+            IdBuilder::new()
+                .priority(7)
+                .pgn(pgn)
+                .sa(sa)
+                .da(da)
+                .build()
+                .unwrap()
+        };
+        let dt = |sa| id(Pgn::TransportProtocolDataTransfer, sa, 0x80);
+        let cm = id(Pgn::TransportProtocolConnectionManagement, 0x10, 0x80);
+        let other = id(Pgn::ProprietaryA, 0x10, 0x80);
+
+        let rts = RequestToSend::try_new(9, None, Pgn::ProprietaryA).unwrap();
+        let mut transfer = Transfer::new(rts.clone()).with_source(0x10);
+        let packet = DataTransfer::new(1, [1; 7]).to_bytes();
+        assert_eq!(transfer.handle_frame(other, &packet), Ok(None));
+        assert_eq!(transfer.handle_frame(dt(0x20), &packet), Ok(None));
+        assert_eq!(transfer.handle_frame(dt(0x10), &packet), Ok(None));
+        assert_eq!(transfer.handle_frame(cm, &rts.to_bytes()), Ok(None));
+
+        // CTS for another PGN, then one from the sender of the transfer
+        let cts = ClearToSend::new(None, 2, Pgn::Other(65226));
+        assert_eq!(transfer.handle_frame(cm, &cts.to_bytes()), Ok(None));
+        let cts = ClearToSend::new(None, 2, Pgn::ProprietaryA);
+        let (error, abort) = transfer.handle_frame(cm, &cts.to_bytes()).unwrap_err();
+        assert_eq!(error, Error::Sequence);
+        assert_eq!(abort.unwrap().reason(), AbortReason::CtsWhileDataTransfer);
+
+        let mut transfer = Transfer::new(rts).with_source(0x10);
+        let abort = ConnectionAbort::new(
+            AbortReason::Timeout,
+            AbortSenderRole::Sender,
+            Pgn::ProprietaryA,
+        );
+        assert_eq!(
+            transfer.handle_frame(cm, &abort.to_bytes()),
+            Err((Error::Aborted(AbortReason::Timeout), None))
+        );
+        assert_eq!(transfer.handle_frame(cm, &abort.to_bytes()), Ok(None));
+        assert!(transfer.handle_frame(dt(0x10), &packet).is_err());
+
+        // broadcasts never send aborts
+        let bam = BroadcastAnnounce::try_new(9, Pgn::ProprietaryA).unwrap();
+        let mut buf = [0; 9];
+        let mut transfer = Transfer::broadcast(bam, buf.as_mut_slice()).with_source(0x10);
+        let packet = DataTransfer::new(2, [1; 7]).to_bytes();
+        assert_eq!(
+            transfer.handle_frame(id(Pgn::TransportProtocolDataTransfer, 0x10, 0xFF), &packet),
+            Err((Error::Sequence, None))
+        );
+    }
+
+    #[test]
+    fn abort() {
+        let rts = RequestToSend::try_new(9, None, Pgn::ProprietaryA).unwrap();
+        let mut transfer = Transfer::new(rts);
+        transfer.poll(Instant::from_millis(0)).unwrap();
+        transfer.next(DataTransfer::new(1, [1; 7])).unwrap();
+
+        let abort = transfer.abort(AbortReason::CanceledBySystem);
+        assert_eq!(
+            abort.to_bytes(),
+            [255, 2, 0xFD, 0xFF, 0xFF, 0x00, 0xEF, 0x00]
+        );
+        assert_eq!(transfer.deadline(), None);
+        let (error, _) = transfer.next(DataTransfer::new(2, [2; 7])).unwrap_err();
+        assert_eq!(error, Error::PreviousAbort);
+        assert!(!transfer.is_finished());
+    }
+
+    #[test]
+    fn duplicates() {
+        let rts = RequestToSend::try_new(9, None, Pgn::ProprietaryA).unwrap();
+        let mut transfer = Transfer::new(rts.clone());
+        transfer.next(DataTransfer::new(1, [1; 7])).unwrap();
+        let (_, abort) = transfer.next(DataTransfer::new(1, [1; 7])).unwrap_err();
+        assert_eq!(abort.reason(), AbortReason::BadSequenceNumber);
+
+        let mut transfer = Transfer::new(rts.clone()).with_duplicates_ignored();
+        transfer.next(DataTransfer::new(1, [1; 7])).unwrap();
+        assert_eq!(transfer.next(DataTransfer::new(1, [1; 7])), Ok(None));
+        let last = DataTransfer::new(2, [2, 2, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert!(transfer.next(last.clone()).unwrap().is_some());
+
+        // padding of a repeated last packet is not compared
+        let repeated = DataTransfer::new(2, [2, 2, 0, 0, 0, 0, 0]);
+        assert_eq!(transfer.next(repeated), Ok(None));
+        assert!(transfer.is_finished());
+
+        let mut transfer = Transfer::new(rts).with_duplicates_ignored();
+        transfer.next(DataTransfer::new(1, [1; 7])).unwrap();
+        let (error, abort) = transfer.next(DataTransfer::new(1, [3; 7])).unwrap_err();
+        assert_eq!(error, Error::Sequence);
+        assert_eq!(abort.reason(), AbortReason::DuplicateSequenceNumber);
+    }
+
+    #[test]
+    fn response_frames() {
+        let cts = Response::Cts(ClearToSend::new(Some(2), 3, Pgn::ProprietaryA));
+        let (id, data) = cts.to_frame(0x80, 0x10).unwrap();
+        let expected = IdBuilder::new()
+            .priority(7)
+            .pgn(Pgn::TransportProtocolConnectionManagement)
+            .sa(0x80)
+            .da(0x10)
+            .build()
+            .unwrap();
+        assert_eq!(id.as_raw(), expected.as_raw());
+        assert_eq!(data, [17, 2, 3, 0xFF, 0xFF, 0x00, 0xEF, 0x00]);
+
+        let abort = ConnectionAbort::new(
+            AbortReason::Timeout,
+            AbortSenderRole::Receiver,
+            Pgn::ProprietaryA,
+        );
+        let (id, data) = abort.to_frame(0x80, 0x10).unwrap();
+        assert_eq!(id.as_raw(), expected.as_raw());
+        assert_eq!(data, abort.to_bytes());
+    }
+
+    #[test]
+    fn truncation() {
+        let mut buf = [0; 10];
+        let rts = RequestToSend::try_new(20, None, Pgn::ProprietaryA).unwrap();
+        let mut transfer = Transfer::new_with_storage(rts.clone(), &mut buf[..]).with_truncation();
+        assert!(transfer.is_truncated());
+        transfer.next(DataTransfer::new(1, [1; 7])).unwrap();
+        transfer.next(DataTransfer::new(2, [2; 7])).unwrap();
+        assert_eq!(transfer.finished(), None);
+        let response = transfer.next(DataTransfer::new(3, [3; 7])).unwrap();
+        assert!(matches!(response, Some(Response::End(end)) if end.total_size() == 20));
+        assert_eq!(transfer.rts().total_size(), 20);
+        assert_eq!(
+            transfer.finished(),
+            Some([1, 1, 1, 1, 1, 1, 1, 2, 2, 2].as_slice())
+        );
+        assert_eq!(transfer.into_message().unwrap().data.len(), 10);
+
+        // without it, the first packet that does not fit aborts
+        let mut buf = [0; 10];
+        let mut transfer = Transfer::new_with_storage(rts, &mut buf[..]);
+        transfer.next(DataTransfer::new(1, [1; 7])).unwrap();
+        let (err, _) = transfer.next(DataTransfer::new(2, [2; 7])).unwrap_err();
+        assert_eq!(err, Error::StorageTooSmall);
+    }
+
+    #[test]
+    fn peers() {
+        let mut buf = [0; 9];
+        let rts = RequestToSend::try_new(9, Some(1), Pgn::ProprietaryA).unwrap();
+        let mut transfer = Transfer::new_with_storage(rts, &mut buf[..]).with_peers(0x10, 0x80);
+        assert_eq!((transfer.originator(), transfer.responder()), (0x10, 0x80));
+
+        let response = transfer
+            .next(DataTransfer::new(1, [0; 7]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            transfer.response_frame(&response),
+            response.to_frame(0x80, 0x10)
+        );
+        let abort = transfer.abort(AbortReason::Timeout);
+        assert_eq!(transfer.abort_frame(&abort), abort.to_frame(0x80, 0x10));
+
+        let mut buf = [0; 9];
+        let bam = BroadcastAnnounce::try_new(9, Pgn::ProprietaryA).unwrap();
+        let transfer = Transfer::broadcast(bam, buf.as_mut_slice()).with_source(0x10);
+        assert_eq!((transfer.originator(), transfer.responder()), (0x10, 0xFF));
+        assert_eq!(transfer.abort_frame(&abort), None);
+    }
+
+    #[test]
+    fn reset() {
+        let mut buf = [0; 16];
+        let rts = RequestToSend::try_new(9, None, Pgn::ProprietaryA).unwrap();
+        let mut transfer = Transfer::new_with_storage(rts, &mut buf[..]).with_source(0x10);
+        transfer.next(DataTransfer::new(2, [0; 7])).unwrap_err();
+
+        let rts = RequestToSend::try_new(10, Some(1), Pgn::ProprietaryB(0x20)).unwrap();
+        transfer.reset(rts.clone());
+        assert_eq!(transfer.rts(), rts);
+        assert_eq!(transfer.source(), 0xFE);
+        assert!(
+            transfer
+                .next(DataTransfer::new(1, [1; 7]))
+                .unwrap()
+                .is_some()
+        );
+        transfer.next(DataTransfer::new(2, [2; 7])).unwrap();
+        assert_eq!(
+            transfer.finished(),
+            Some([1, 1, 1, 1, 1, 1, 1, 2, 2, 2].as_slice())
+        );
+    }
+
+    #[test]
+    fn hold() {
+        let rts = RequestToSend::try_new(20, Some(2), Pgn::ProprietaryA).unwrap();
+        let mut transfer = Transfer::new(rts);
+        transfer.next(DataTransfer::new(1, [0; 7])).unwrap();
+        assert_eq!(transfer.hold(), None);
+        assert_eq!(transfer.resume(), None);
+
+        // hold instead of the CTS for the second window
+        transfer.next(DataTransfer::new(2, [0; 7])).unwrap();
+        let hold = transfer.hold().unwrap();
+        assert!(hold.is_hold());
+        assert_eq!(hold.next_sequence(), 3);
+        assert_eq!(hold.to_bytes()[1], 0);
+        transfer.poll(Instant::from_millis(0)).unwrap();
+        assert_eq!(transfer.deadline(), Some(Instant::from_millis(0) + TH));
+
+        // the sender is not timed out while held
+        transfer.poll(Instant::from_millis(0) + T2).unwrap();
+        assert!(transfer.is_held());
+
+        let cts = transfer.resume().unwrap();
+        assert!(!cts.is_hold());
+        assert_eq!(
+            (cts.max_packets_per_response(), cts.next_sequence()),
+            (Some(2), 3)
+        );
+        transfer.poll(Instant::from_millis(2000)).unwrap();
+        assert_eq!(transfer.deadline(), Some(Instant::from_millis(2000) + T2));
+        assert!(transfer.next(DataTransfer::new(3, [0; 7])).is_ok());
+    }
+
+    #[test]
+    fn rewind() {
+        let rts = RequestToSend::try_new(30, Some(3), Pgn::ProprietaryA).unwrap();
+        let mut transfer = Transfer::new(rts);
+        assert_eq!(transfer.rewind(1), None);
+        for sequence in 1..=3 {
+            transfer
+                .next(DataTransfer::new(sequence, [sequence; 7]))
+                .unwrap();
+        }
+
+        // packet 2 was corrupted, ask for it again in place of the next CTS
+        let cts = transfer.rewind(2).unwrap();
+        assert_eq!(
+            (cts.max_packets_per_response(), cts.next_sequence()),
+            (Some(3), 2)
+        );
+        assert_eq!(transfer.rewind(3), None);
+
+        // a packet still underway is ignored
+        assert_eq!(transfer.next(DataTransfer::new(4, [0; 7])), Ok(None));
+        transfer.next(DataTransfer::new(2, [2; 7])).unwrap();
+        transfer.next(DataTransfer::new(3, [3; 7])).unwrap();
+        let response = transfer.next(DataTransfer::new(4, [4; 7])).unwrap();
+        assert!(matches!(response, Some(Response::Cts(cts)) if cts.next_sequence() == 5));
+
+        // once repeating, the sequence is checked again
+        assert!(transfer.next(DataTransfer::new(6, [0; 7])).is_err());
+    }
+
+    #[test]
+    fn stats() {
+        let mut buf = [0; 30];
+        let rts = RequestToSend::try_new(30, Some(3), Pgn::ProprietaryA).unwrap();
+        let mut transfer =
+            Transfer::new_with_storage(rts.clone(), &mut buf[..]).with_duplicates_ignored();
+        for sequence in 1..=3 {
+            transfer
+                .next(DataTransfer::new(sequence, [sequence; 7]))
+                .unwrap();
+        }
+        transfer.next(DataTransfer::new(3, [3; 7])).unwrap();
+        transfer.rewind(2).unwrap();
+        for sequence in 2..=5 {
+            transfer
+                .next(DataTransfer::new(sequence, [sequence; 7]))
+                .unwrap();
+        }
+        assert_eq!(
+            transfer.stats(),
+            TransferStats {
+                packets: 7,
+                bytes: 30,
+                duplicates: 1,
+                retransmits: 2,
+                completed: 1,
+                ..Default::default()
+            }
+        );
+
+        let abort = ConnectionAbort::new(
+            AbortReason::Timeout,
+            AbortSenderRole::Sender,
+            Pgn::ProprietaryA,
+        );
+        let mut buf = [0; 30];
+        let mut transfer = Transfer::new_with_storage(rts, &mut buf[..]);
+        let (id, data) = abort.to_frame(0x10, 0x80).unwrap();
+        transfer.handle_frame(id, &data).unwrap_err();
+        assert_eq!(
+            (
+                transfer.stats().aborts_received,
+                transfer.stats().aborts_sent
+            ),
+            (1, 0)
+        );
+    }
+
+    #[test]
+    fn inconsistent_rts() {
+        // 100 bytes announced in a single packet
+        let rts =
+            message::RequestToSend::try_from([16, 100, 0, 1, 0xFF, 0x00, 0xEF, 0x00].as_ref())
+                .unwrap();
+        let mut transfer = Transfer::new(rts);
+        let dt = message::DataTransfer::try_from([1, 1, 2, 3, 4, 5, 6, 7].as_ref()).unwrap();
+        assert!(transfer.next(dt).unwrap().is_some());
+        assert_eq!(transfer.finished(), None);
+
+        // no packets announced, sequence numbers must not overflow
+        let rts = message::RequestToSend::try_from([16, 0, 0, 0, 0xFF, 0x00, 0xEF, 0x00].as_ref())
+            .unwrap();
+        let mut transfer = Transfer::new(rts);
+        for sequence in 1..=255 {
+            let dt = message::DataTransfer::new(sequence, [0; 7]);
+            assert!(transfer.next(dt).is_ok());
+        }
+        let dt = message::DataTransfer::new(0, [0; 7]);
+        assert!(transfer.next(dt).is_err());
+    }
+
+    #[test]
+    fn control_message_view() {
+        let raw = [16, 9, 0, 2, 0xFF, 0x00, 0xEF, 0x00];
+        let view = ControlMessageRef::try_from(&raw[..]).unwrap();
+        assert_eq!(view.kind(), ControlKind::RequestToSend);
+        assert_eq!(view.pgn(), Pgn::ProprietaryA);
+        assert_eq!(view.total_size(), Some(9));
+        assert_eq!(view.next_sequence(), None);
+        assert!(matches!(
+            ControlMessage::from(view),
+            ControlMessage::RequestToSend(rts) if rts.total_packets() == 2
+        ));
+
+        let abort: [u8; 8] = (&ConnectionAbort::new(
+            AbortReason::Timeout,
+            AbortSenderRole::Receiver,
+            Pgn::ProprietaryA,
+        ))
+            .into();
+        let view = ControlMessageRef::try_from(&abort[..]).unwrap();
+        assert_eq!(view.reason(), Some(AbortReason::Timeout));
+        assert_eq!(view.total_size(), None);
+
+        // codes not listed in the table are kept
+        let oem = [255, 42, 0xFD, 0xFF, 0xFF, 0x00, 0xEF, 0x00];
+        let view = ControlMessageRef::try_from(&oem[..]).unwrap();
+        assert_eq!(view.reason(), Some(AbortReason::Other(42)));
+        let abort = ConnectionAbort::try_from(&oem[..]).unwrap();
+        assert_eq!(abort.to_bytes(), oem);
+        assert_eq!(AbortReason::from(250), AbortReason::Custom);
+        assert_eq!(u8::from(&AbortReason::MessageTooLarge), 9);
+        for raw in 10..=14 {
+            let reason = AbortReason::from_raw(raw);
+            assert!(!matches!(reason, AbortReason::Other(_)));
+            assert_eq!(reason.as_raw(), raw);
+        }
+        assert_eq!(AbortReason::from(15), AbortReason::Other(15));
+
+        let bam = BroadcastAnnounce::try_new(20, Pgn::ProprietaryA).unwrap();
+        let raw = bam.to_bytes();
+        assert_eq!(raw, [32, 20, 0, 3, 0xFF, 0x00, 0xEF, 0x00]);
+        let view = ControlMessageRef::try_from(&raw[..]).unwrap();
+        assert_eq!(view.kind(), ControlKind::BroadcastAnnounce);
+        assert_eq!(
+            (view.total_size(), view.total_packets()),
+            (Some(20), Some(3))
+        );
+        assert_eq!(
+            ControlMessage::try_from(&raw[..]),
+            Ok(ControlMessage::BroadcastAnnounce(bam))
+        );
+        assert!(RequestToSend::try_from(&raw[..]).is_err());
+
+        assert!(ControlMessageRef::try_from(&[20, 0, 0, 0, 0, 0, 0, 0][..]).is_err());
+        assert!(ControlMessageRef::try_from(&raw[..7]).is_err());
+    }
+
+    #[test]
+    fn data_transfer_view() {
+        let raw = [1, 1, 2, 3, 4, 5, 6, 7];
+        let view = DataTransferRef::from(&raw);
+        assert_eq!(view.sequence(), 1);
+        assert_eq!(view.data(), &[1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(
+            DataTransfer::from(view),
+            DataTransfer::new(1, [1, 2, 3, 4, 5, 6, 7])
+        );
+        assert!(DataTransferRef::try_from(&raw[..7]).is_err());
+
+        let mut buf = [0; 9];
+        let rts = RequestToSend::try_new(9, None, Pgn::ProprietaryA).unwrap();
+        let mut transfer = Transfer::new_with_storage(rts, &mut buf[..]);
+        transfer.next_ref(view).unwrap();
+        transfer
+            .next_ref((&[2, 8, 9, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]).into())
+            .unwrap();
+        assert_eq!(
+            transfer.finished(),
+            Some([1, 2, 3, 4, 5, 6, 7, 8, 9].as_slice())
+        );
+    }
+
+    #[test]
+    fn const_frames() {
+        const ABORT: [u8; 8] = ConnectionAbort::new(
+            AbortReason::Timeout,
+            AbortSenderRole::Receiver,
+            Pgn::from_raw(0xEF00),
+        )
+        .to_bytes();
+        const RTS: [u8; 8] = match RequestToSend::try_new(9, None, Pgn::ProprietaryA) {
+            Ok(rts) => rts.to_bytes(),
+            Err(_) => [0; 8],
+        };
+
+        assert_eq!(ABORT, [255, 3, 0xFD, 0xFF, 0xFF, 0x00, 0xEF, 0x00]);
+        assert_eq!(RTS, [16, 9, 0, 2, 0xFF, 0x00, 0xEF, 0x00]);
+        assert_eq!(
+            DataTransfer::new(1, [2; 7]).to_bytes(),
+            <[u8; 8]>::from(&DataTransfer::new(1, [2; 7]))
+        );
+    }
+
+    #[test]
+    fn rts_arguments() {
+        let pgn = Pgn::ProprietaryA;
+        assert_eq!(
+            RequestToSend::try_new(1785, Some(16), pgn)
+                .unwrap()
+                .total_packets(),
+            255
+        );
+        assert_eq!(
+            RequestToSend::try_new(8, None, pgn).unwrap_err(),
+            RequestToSendError::TooShort
+        );
+        assert_eq!(
+            RequestToSend::try_new(1786, None, pgn).unwrap_err(),
+            RequestToSendError::TooLong
+        );
+        assert_eq!(
+            RequestToSend::try_new(9, Some(255), pgn).unwrap_err(),
+            RequestToSendError::MaxPacketsPerResponse
+        );
+        assert_eq!(
+            RequestToSend::try_new(9, Some(0), pgn).unwrap_err(),
+            RequestToSendError::MaxPacketsPerResponse
+        );
+    }
 }
@@ -0,0 +1,538 @@
+//! Originator side of transport sessions
+
+use super::assembly::Segments;
+use super::{
+    AbortReason, AbortSenderRole, BroadcastAnnounce, ConnectionAbort, ControlMessage, DataTransfer,
+    Error, RequestToSend, RequestToSendError, T3, T4,
+};
+use crate::id::Pgn;
+use crate::time::{Duration, Instant};
+
+/// Default time between the packets of a broadcast transfer.
+const BAM_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Progress of an outgoing transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum State {
+    /// Waiting for the receiver to grant packets.
+    ClearToSend,
+    /// Sending the packets granted by the last CTS.
+    Sending,
+    /// All packets sent, waiting for the end of message acknowledge.
+    EndOfMessageAck,
+    Finished,
+    Aborted,
+}
+
+/// An outgoing transport-protocol transfer.
+///
+/// Starts by sending [`OutgoingTransfer::rts`], then sends the packets the
+/// receiver grants with each CTS until it acknowledges the whole payload.
+///
+/// The transfer times out when the receiver does not respond within [`T3`]
+/// of the RTS or the last packet of a window, or within [`T4`] of a CTS
+/// holding the connection open. Its timer runs from the times passed to
+/// [`OutgoingTransfer::poll`].
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OutgoingTransfer<'a> {
+    data: &'a [u8],
+    rts: RequestToSend,
+    /// Sequence number of the next packet.
+    next_sequence: u8,
+    /// Packets left of the window granted by the last CTS.
+    window: u8,
+    state: State,
+    deadline: Instant,
+    /// Timeout to start at the next poll.
+    restart: Option<Duration>,
+}
+
+impl<'a> OutgoingTransfer<'a> {
+    /// Create a new transfer sending `data` as `pgn`.
+    ///
+    /// `max_packets_per_response` is announced to the receiver as the largest
+    /// window the sender supports.
+    pub fn new(
+        data: &'a [u8],
+        max_packets_per_response: Option<u8>,
+        pgn: Pgn,
+    ) -> Result<Self, RequestToSendError> {
+        let total_size = u16::try_from(data.len()).map_err(|_| RequestToSendError::TooLong)?;
+        let rts = RequestToSend::try_new(total_size, max_packets_per_response, pgn)?;
+
+        Ok(Self {
+            data,
+            rts,
+            next_sequence: 1,
+            window: 0,
+            state: State::ClearToSend,
+            deadline: Instant::from_millis(0),
+            restart: Some(T3),
+        })
+    }
+
+    /// The RTS message to send to open the session.
+    pub fn rts(&self) -> &RequestToSend {
+        &self.rts
+    }
+
+    /// Check if the receiver acknowledged the whole payload.
+    pub fn is_finished(&self) -> bool {
+        self.state == State::Finished
+    }
+
+    /// Check if the session was aborted by either side.
+    pub fn is_aborted(&self) -> bool {
+        self.state == State::Aborted
+    }
+
+    /// Feed the transfer with a connection management message from the
+    /// receiver.
+    ///
+    /// Messages for other PGNs are ignored. On failure, the connection abort
+    /// to send is returned alongside the error, unless the receiver aborted
+    /// the session itself.
+    pub fn handle(&mut self, msg: &ControlMessage) -> Result<(), (Error, Option<ConnectionAbort>)> {
+        let pgn = match msg {
+            ControlMessage::RequestToSend(_) | ControlMessage::BroadcastAnnounce(_) => {
+                return Ok(());
+            }
+            ControlMessage::ClearToSend(cts) => cts.pgn(),
+            ControlMessage::EndOfMessageAck(end) => end.pgn(),
+            ControlMessage::ConnectionAbort(abort) => abort.pgn(),
+        };
+        if pgn != self.rts.pgn() {
+            return Ok(());
+        }
+
+        if matches!(self.state, State::Finished | State::Aborted) {
+            return Err((Error::PreviousAbort, None));
+        }
+
+        match msg {
+            ControlMessage::ClearToSend(cts) => {
+                if self.state == State::Sending {
+                    return Err(self.abort(Error::Sequence, AbortReason::CtsWhileDataTransfer));
+                }
+
+                if cts.is_hold() {
+                    // the sequence number of a hold is not binding
+                    self.restart = Some(T4);
+                    return Ok(());
+                }
+
+                let total_packets = self.rts.total_packets();
+                let next = cts.next_sequence();
+                if next == 0 || next > total_packets {
+                    return Err(self.abort(Error::Sequence, AbortReason::BadSequenceNumber));
+                }
+
+                // never more than announced in the RTS, even if granted
+                let remaining = total_packets - next + 1;
+                let limit = self.rts.max_packets_per_response().unwrap_or(255);
+                self.next_sequence = next;
+                self.window = cts
+                    .max_packets_per_response()
+                    .map_or(remaining, |max| max.min(remaining))
+                    .min(limit);
+                self.state = State::Sending;
+                self.restart = None;
+            }
+            ControlMessage::EndOfMessageAck(_) => {
+                if self.state == State::EndOfMessageAck {
+                    self.state = State::Finished;
+                }
+            }
+            ControlMessage::ConnectionAbort(abort) => {
+                self.state = State::Aborted;
+                return Err((Error::Aborted(abort.reason()), None));
+            }
+            ControlMessage::RequestToSend(_) | ControlMessage::BroadcastAnnounce(_) => {}
+        }
+
+        Ok(())
+    }
+
+    /// The packets granted by the last CTS.
+    ///
+    /// The iterator ends with the window. It is empty until the receiver
+    /// grants packets, and while it holds the connection open.
+    pub fn packets(&mut self) -> Packets<'_, 'a> {
+        Packets { transfer: self }
+    }
+
+    /// Number of packets left of the window granted by the last CTS.
+    pub fn granted(&self) -> u8 {
+        if self.state == State::Sending {
+            self.window
+        } else {
+            0
+        }
+    }
+
+    /// Next packet granted by the receiver, if any.
+    ///
+    /// The last packet is padded with `0xFF`.
+    pub fn next_packet(&mut self) -> Option<DataTransfer> {
+        if self.state != State::Sending {
+            return None;
+        }
+
+        let packet = packet(
+            self.data,
+            usize::from(self.next_sequence - 1),
+            self.next_sequence,
+        );
+
+        self.window -= 1;
+        if self.next_sequence == self.rts.total_packets() {
+            self.state = State::EndOfMessageAck;
+            self.restart = Some(T3);
+        } else {
+            self.next_sequence += 1;
+            if self.window == 0 {
+                self.state = State::ClearToSend;
+                self.restart = Some(T3);
+            }
+        }
+
+        Some(packet)
+    }
+
+    /// Advance the timer of the transfer to `now`.
+    ///
+    /// Call after sending the RTS and each packet, and after handling each
+    /// message from the receiver, to restart the timer, and again once
+    /// [`OutgoingTransfer::deadline`] has passed. A receiver that went
+    /// silent aborts the transfer with [`Error::Timeout`].
+    pub fn poll(&mut self, now: Instant) -> Result<(), (Error, Option<ConnectionAbort>)> {
+        if !matches!(self.state, State::ClearToSend | State::EndOfMessageAck) {
+            return Ok(());
+        }
+
+        if let Some(timeout) = self.restart.take() {
+            self.deadline = now + timeout;
+            return Ok(());
+        }
+
+        if now < self.deadline {
+            return Ok(());
+        }
+        Err(self.abort(Error::Timeout, AbortReason::Timeout))
+    }
+
+    /// Time at which the transfer times out, unless the receiver responds
+    /// first.
+    ///
+    /// `None` while sending, once the transfer ended, or while the timer
+    /// waits to be restarted by [`OutgoingTransfer::poll`].
+    pub fn deadline(&self) -> Option<Instant> {
+        let waiting = matches!(self.state, State::ClearToSend | State::EndOfMessageAck);
+        (waiting && self.restart.is_none()).then_some(self.deadline)
+    }
+
+    /// Continue the transfer from `data`, which must hold the same payload.
+    ///
+    /// Lets the owner of a reused buffer keep the transfer between calls.
+    pub(crate) fn rebind<'b>(self, data: &'b [u8]) -> OutgoingTransfer<'b> {
+        OutgoingTransfer {
+            data,
+            rts: self.rts,
+            next_sequence: self.next_sequence,
+            window: self.window,
+            state: self.state,
+            deadline: self.deadline,
+            restart: self.restart,
+        }
+    }
+
+    fn abort(&mut self, error: Error, reason: AbortReason) -> (Error, Option<ConnectionAbort>) {
+        self.state = State::Aborted;
+        let abort = ConnectionAbort::new(reason, AbortSenderRole::Sender, self.rts.pgn());
+        (error, Some(abort))
+    }
+}
+
+/// Iterator over the packets of an [`OutgoingTransfer`] granted by the last
+/// CTS, returned by [`OutgoingTransfer::packets`].
+#[derive(Debug)]
+pub struct Packets<'t, 'a> {
+    transfer: &'t mut OutgoingTransfer<'a>,
+}
+
+impl Iterator for Packets<'_, '_> {
+    type Item = DataTransfer;
+
+    fn next(&mut self) -> Option<DataTransfer> {
+        self.transfer.next_packet()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let granted = usize::from(self.transfer.granted());
+        (granted, Some(granted))
+    }
+}
+
+impl ExactSizeIterator for Packets<'_, '_> {}
+
+/// Data transfer `sequence` carrying segment `index` of `data`, padded with
+/// `0xFF`.
+pub(super) fn packet(data: &[u8], index: usize, sequence: u8) -> DataTransfer {
+    let range = Segments::TP.range(index, data.len());
+    let mut packet = [0xFF; 7];
+    packet[..range.len()].copy_from_slice(&data[range]);
+    DataTransfer::new(sequence, packet)
+}
+
+/// An outgoing broadcast transfer.
+///
+/// Starts by sending the BAM message returned by
+/// [`BroadcastTransfer::announce`], then sends the packets as they become due.
+/// J1939-21 requires 50 to 200 ms between the frames of a broadcast transfer.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BroadcastTransfer<'a> {
+    data: &'a [u8],
+    bam: BroadcastAnnounce,
+    interval: Duration,
+    /// Sequence number of the next packet.
+    next_sequence: u8,
+    /// Time the next packet is due, `None` until announced.
+    due: Option<Instant>,
+}
+
+impl<'a> BroadcastTransfer<'a> {
+    /// Create a new broadcast transfer sending `data` as `pgn`.
+    ///
+    /// Packets are sent 50 ms apart.
+    pub fn new(data: &'a [u8], pgn: Pgn) -> Result<Self, RequestToSendError> {
+        let total_size = u16::try_from(data.len()).map_err(|_| RequestToSendError::TooLong)?;
+        let bam = BroadcastAnnounce::try_new(total_size, pgn)?;
+
+        Ok(Self {
+            data,
+            bam,
+            interval: BAM_INTERVAL,
+            next_sequence: 1,
+            due: None,
+        })
+    }
+
+    /// Send packets `interval` apart.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// The BAM message to send at `now`, starting the transfer.
+    ///
+    /// The first packet is due one interval later.
+    pub fn announce(&mut self, now: Instant) -> BroadcastAnnounce {
+        self.due = Some(now + self.interval);
+        self.bam.clone()
+    }
+
+    /// Time at which the next packet is due, if announced and not finished.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.due.filter(|_| !self.is_finished())
+    }
+
+    /// Check if all packets have been sent.
+    pub fn is_finished(&self) -> bool {
+        self.next_sequence > self.bam.total_packets()
+    }
+
+    /// Next packet, if it is due at `now`.
+    pub fn next_packet(&mut self, now: Instant) -> Option<DataTransfer> {
+        if self.deadline().is_none_or(|due| now < due) {
+            return None;
+        }
+
+        let packet = packet(
+            self.data,
+            usize::from(self.next_sequence - 1),
+            self.next_sequence,
+        );
+        self.next_sequence += 1;
+        self.due = Some(now + self.interval);
+        Some(packet)
+    }
+
+    /// Continue the transfer from `data`, which must hold the same payload.
+    ///
+    /// Lets the owner of a reused buffer keep the transfer between calls.
+    pub(crate) fn rebind<'b>(self, data: &'b [u8]) -> BroadcastTransfer<'b> {
+        BroadcastTransfer {
+            data,
+            bam: self.bam,
+            interval: self.interval,
+            next_sequence: self.next_sequence,
+            due: self.due,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{ClearToSend, EndOfMessageAck, Response, Transfer};
+
+    #[test]
+    fn round_trip() {
+        let payload: [u8; 20] = core::array::from_fn(|i| i as u8);
+        let mut sender = OutgoingTransfer::new(&payload, Some(2), Pgn::ProprietaryA).unwrap();
+        assert_eq!(sender.next_packet(), None);
+
+        let mut receiver = Transfer::new(sender.rts().clone());
+        let cts = ClearToSend::new(Some(2), 1, Pgn::ProprietaryA);
+        sender.handle(&ControlMessage::ClearToSend(cts)).unwrap();
+
+        while !sender.is_finished() {
+            let packet = sender.next_packet().unwrap();
+            match receiver.next(packet).unwrap() {
+                Some(Response::Cts(cts)) => {
+                    assert_eq!(sender.next_packet(), None);
+                    sender.handle(&ControlMessage::ClearToSend(cts)).unwrap();
+                }
+                Some(Response::End(end)) => {
+                    sender
+                        .handle(&ControlMessage::EndOfMessageAck(end))
+                        .unwrap();
+                }
+                None => {}
+            }
+        }
+
+        assert_eq!(receiver.finished(), Some(payload.as_slice()));
+        assert_eq!(sender.next_packet(), None);
+    }
+
+    #[test]
+    fn broadcast() {
+        let payload: [u8; 10] = core::array::from_fn(|i| i as u8);
+        let mut sender = BroadcastTransfer::new(&payload, Pgn::ProprietaryA)
+            .unwrap()
+            .with_interval(Duration::from_millis(100));
+        assert_eq!(sender.deadline(), None);
+        assert_eq!(sender.next_packet(Instant::from_millis(1000)), None);
+
+        let bam = sender.announce(Instant::from_millis(0));
+        let mut buf = [0; 10];
+        let mut receiver = Transfer::broadcast(bam, buf.as_mut_slice());
+
+        assert_eq!(sender.deadline(), Some(Instant::from_millis(100)));
+        assert_eq!(sender.next_packet(Instant::from_millis(99)), None);
+        let first = sender.next_packet(Instant::from_millis(100)).unwrap();
+        assert_eq!(receiver.next(first), Ok(None));
+
+        // the interval runs from the last packet
+        assert_eq!(sender.next_packet(Instant::from_millis(150)), None);
+        let last = sender.next_packet(Instant::from_millis(200)).unwrap();
+        assert_eq!(last.data(), [7, 8, 9, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(receiver.next(last), Ok(None));
+
+        assert!(sender.is_finished());
+        assert_eq!(sender.deadline(), None);
+        assert_eq!(receiver.finished(), Some(payload.as_slice()));
+    }
+
+    #[test]
+    fn paced() {
+        let pgn = Pgn::ProprietaryA;
+        let payload = [0; 50];
+        let mut sender = OutgoingTransfer::new(&payload, Some(3), pgn).unwrap();
+        assert_eq!(sender.packets().count(), 0);
+
+        // a receiver granting more than announced gets the announced window
+        let cts = ClearToSend::new(Some(5), 1, pgn);
+        sender.handle(&ControlMessage::ClearToSend(cts)).unwrap();
+        let packets = sender.packets();
+        assert_eq!(packets.len(), 3);
+        let sequences: std::vec::Vec<u8> = packets.map(|p| p.sequence()).collect();
+        assert_eq!(sequences, [1, 2, 3]);
+        assert_eq!(sender.packets().next(), None);
+
+        let hold = ClearToSend::hold(4, pgn);
+        sender.handle(&ControlMessage::ClearToSend(hold)).unwrap();
+        assert_eq!(sender.packets().len(), 0);
+
+        let cts = ClearToSend::new(None, 4, pgn);
+        sender.handle(&ControlMessage::ClearToSend(cts)).unwrap();
+        assert_eq!(sender.packets().count(), 3);
+    }
+
+    #[test]
+    fn timeouts() {
+        let pgn = Pgn::ProprietaryA;
+        let mut sender = OutgoingTransfer::new(&[0; 9], None, pgn).unwrap();
+        assert_eq!(sender.deadline(), None);
+        sender.poll(Instant::from_millis(0)).unwrap();
+        assert_eq!(sender.deadline(), Some(Instant::from_millis(0) + T3));
+
+        // hold the connection open
+        let hold = ControlMessage::ClearToSend(ClearToSend::hold(0xFF, pgn));
+        sender.handle(&hold).unwrap();
+        sender.poll(Instant::from_millis(100)).unwrap();
+        assert_eq!(sender.deadline(), Some(Instant::from_millis(100) + T4));
+        assert_eq!(sender.next_packet(), None);
+
+        // each hold restarts the timer
+        sender.handle(&hold).unwrap();
+        sender.poll(Instant::from_millis(600)).unwrap();
+        assert_eq!(sender.deadline(), Some(Instant::from_millis(600) + T4));
+
+        let cts = ControlMessage::ClearToSend(ClearToSend::new(None, 1, pgn));
+        sender.handle(&cts).unwrap();
+        sender.poll(Instant::from_millis(200)).unwrap();
+        assert_eq!(sender.deadline(), None);
+        sender.next_packet().unwrap();
+        sender.next_packet().unwrap();
+        sender.poll(Instant::from_millis(300)).unwrap();
+
+        let (error, abort) = sender.poll(Instant::from_millis(300) + T3).unwrap_err();
+        assert_eq!(error, Error::Timeout);
+        assert_eq!(abort.unwrap().reason(), AbortReason::Timeout);
+        assert!(sender.is_aborted());
+        assert_eq!(sender.deadline(), None);
+    }
+
+    #[test]
+    fn errors() {
+        let pgn = Pgn::ProprietaryA;
+        assert_eq!(
+            OutgoingTransfer::new(&[0; 8], None, pgn).unwrap_err(),
+            RequestToSendError::TooShort
+        );
+
+        // CTS beyond the last packet
+        let mut sender = OutgoingTransfer::new(&[0; 9], None, pgn).unwrap();
+        let (error, abort) = sender
+            .handle(&ControlMessage::ClearToSend(ClearToSend::new(None, 3, pgn)))
+            .unwrap_err();
+        assert_eq!(error, Error::Sequence);
+        assert_eq!(abort.unwrap().reason(), AbortReason::BadSequenceNumber);
+        assert!(sender.is_aborted());
+
+        // CTS while sending
+        let mut sender = OutgoingTransfer::new(&[0; 9], None, pgn).unwrap();
+        let cts = ControlMessage::ClearToSend(ClearToSend::new(None, 1, pgn));
+        sender.handle(&cts).unwrap();
+        let (_, abort) = sender.handle(&cts).unwrap_err();
+        assert_eq!(abort.unwrap().reason(), AbortReason::CtsWhileDataTransfer);
+
+        // acknowledge before all packets were sent and abort from the receiver
+        let mut sender = OutgoingTransfer::new(&[0; 9], None, pgn).unwrap();
+        let end = EndOfMessageAck::new(9, 2, pgn);
+        sender
+            .handle(&ControlMessage::EndOfMessageAck(end))
+            .unwrap();
+        assert!(!sender.is_finished());
+        let abort = ConnectionAbort::new(AbortReason::Timeout, AbortSenderRole::Receiver, pgn);
+        assert_eq!(
+            sender.handle(&ControlMessage::ConnectionAbort(abort)),
+            Err((Error::Aborted(AbortReason::Timeout), None))
+        );
+    }
+}
@@ -0,0 +1,41 @@
+//! Transport-layer health counters
+//!
+//! [`Transfer::stats`](super::Transfer::stats) reports the counters of one
+//! session and [`TransportManager::stats`](super::TransportManager::stats)
+//! the sum over all sessions it handled, so gateways can publish how well
+//! the bus carries multi-packet messages.
+
+use core::ops::AddAssign;
+
+/// Counters of one or more receive sessions.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransferStats {
+    /// Data transfers stored, including those sent again after a rewind.
+    pub packets: u32,
+    /// Payload bytes received so far.
+    pub bytes: u32,
+    /// Repeated copies of the last packet that were ignored.
+    pub duplicates: u32,
+    /// Packets requested again with a rewind.
+    pub retransmits: u32,
+    /// Connection aborts sent to the originator.
+    pub aborts_sent: u32,
+    /// Connection aborts received from the originator.
+    pub aborts_received: u32,
+    /// Sessions that received their whole payload.
+    pub completed: u32,
+}
+
+impl AddAssign for TransferStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.packets = self.packets.saturating_add(rhs.packets);
+        self.bytes = self.bytes.saturating_add(rhs.bytes);
+        self.duplicates = self.duplicates.saturating_add(rhs.duplicates);
+        self.retransmits = self.retransmits.saturating_add(rhs.retransmits);
+        self.aborts_sent = self.aborts_sent.saturating_add(rhs.aborts_sent);
+        self.aborts_received = self.aborts_received.saturating_add(rhs.aborts_received);
+        self.completed = self.completed.saturating_add(rhs.completed);
+    }
+}
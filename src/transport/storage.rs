@@ -0,0 +1,508 @@
+//! Storage for reassembled transport payloads
+//!
+//! A [`Transfer`](super::Transfer) hands the payload bytes of each packet to
+//! a [`TransferStorage`] as they arrive. Besides RAM buffers, payloads can be
+//! written straight to flash with [`FlashStorage`], so firmware images do not
+//! need a staging buffer the size of the image, or handed to a callback with
+//! [`CallbackStorage`].
+
+use super::Error;
+use core::fmt;
+use managed::ManagedSlice;
+
+/// Destination of the payload of a transfer.
+///
+/// Packets are written in order, each one at the payload offset of its first
+/// byte, starting over from an earlier one after
+/// [`Transfer::rewind`](super::Transfer::rewind) if the storage is
+/// [rewritable](TransferStorage::rewritable). Padding of the last packet is not written. A write that does not
+/// fit must fail without storing any of its bytes.
+pub trait TransferStorage {
+    /// Largest payload the storage can hold.
+    fn capacity(&self) -> usize;
+
+    /// Store `data` at byte `offset` of the payload.
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), Error>;
+
+    /// The first `len` bytes of the payload, if they can be read in place.
+    fn read(&self, len: usize) -> Option<&[u8]>;
+
+    /// Complete the payload after its last packet was written.
+    fn finish(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Discard the payload so the storage can be reused.
+    fn reset(&mut self) {}
+
+    /// Whether bytes written already can be written again, as a rewound
+    /// transfer does.
+    fn rewritable(&self) -> bool {
+        true
+    }
+}
+
+impl<T: TransferStorage + ?Sized> TransferStorage for &mut T {
+    fn capacity(&self) -> usize {
+        (**self).capacity()
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), Error> {
+        (**self).write(offset, data)
+    }
+
+    fn read(&self, len: usize) -> Option<&[u8]> {
+        (**self).read(len)
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        (**self).finish()
+    }
+
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+
+    fn rewritable(&self) -> bool {
+        (**self).rewritable()
+    }
+}
+
+impl TransferStorage for [u8] {
+    fn capacity(&self) -> usize {
+        self.len()
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), Error> {
+        self.get_mut(offset..offset + data.len())
+            .ok_or(Error::StorageTooSmall)?
+            .copy_from_slice(data);
+        Ok(())
+    }
+
+    fn read(&self, len: usize) -> Option<&[u8]> {
+        self.get(..len)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl TransferStorage for Vec<u8> {
+    fn capacity(&self) -> usize {
+        usize::MAX
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), Error> {
+        let end = offset + data.len();
+        if self.len() < end {
+            self.resize(end, 0);
+        }
+        self[offset..end].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn read(&self, len: usize) -> Option<&[u8]> {
+        self.get(..len)
+    }
+
+    fn reset(&mut self) {
+        self.clear();
+    }
+}
+
+impl TransferStorage for ManagedSlice<'_, u8> {
+    fn capacity(&self) -> usize {
+        match self {
+            #[cfg(feature = "alloc")]
+            ManagedSlice::Owned(vec) => TransferStorage::capacity(vec),
+            ManagedSlice::Borrowed(slice) => slice.capacity(),
+        }
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), Error> {
+        match self {
+            #[cfg(feature = "alloc")]
+            ManagedSlice::Owned(vec) => vec.write(offset, data),
+            ManagedSlice::Borrowed(slice) => slice.write(offset, data),
+        }
+    }
+
+    fn read(&self, len: usize) -> Option<&[u8]> {
+        self.get(..len)
+    }
+
+    fn reset(&mut self) {
+        #[cfg(feature = "alloc")]
+        if let ManagedSlice::Owned(vec) = self {
+            vec.clear();
+        }
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<const N: usize> TransferStorage for heapless::Vec<u8, N> {
+    fn capacity(&self) -> usize {
+        N
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), Error> {
+        let end = offset + data.len();
+        if self.len() < end {
+            self.resize(end, 0).map_err(|_| Error::StorageTooSmall)?;
+        }
+        self[offset..end].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn read(&self, len: usize) -> Option<&[u8]> {
+        self.get(..len)
+    }
+
+    fn reset(&mut self) {
+        self.clear();
+    }
+}
+
+/// Payload storage handing each chunk to a callback instead of keeping it.
+///
+/// The callback is called with the payload offset and bytes of every packet,
+/// in order, before the transfer responds to it. An error from the callback
+/// aborts the transfer. Chunks handed over can not be taken back, so the
+/// transfer can not be rewound. Nothing is kept, so
+/// [`Transfer::finished`](super::Transfer::finished) returns `None` and
+/// completion is checked with
+/// [`Transfer::is_finished`](super::Transfer::is_finished).
+pub struct CallbackStorage<F> {
+    capacity: usize,
+    f: F,
+}
+
+impl<F: FnMut(usize, &[u8]) -> Result<(), Error>> CallbackStorage<F> {
+    /// Create a new storage accepting payloads of up to `capacity` bytes.
+    pub fn new(capacity: usize, f: F) -> Self {
+        Self { capacity, f }
+    }
+
+    /// Unwrap the callback.
+    pub fn into_inner(self) -> F {
+        self.f
+    }
+}
+
+impl<F> fmt::Debug for CallbackStorage<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallbackStorage")
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: FnMut(usize, &[u8]) -> Result<(), Error>> TransferStorage for CallbackStorage<F> {
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), Error> {
+        if offset + data.len() > self.capacity {
+            return Err(Error::StorageTooSmall);
+        }
+        (self.f)(offset, data)
+    }
+
+    fn read(&self, _len: usize) -> Option<&[u8]> {
+        None
+    }
+
+    fn rewritable(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "embedded-storage")]
+pub use flash::FlashStorage;
+
+#[cfg(feature = "embedded-storage")]
+mod flash {
+    use super::{Error, TransferStorage};
+    use embedded_storage::nor_flash::NorFlash;
+
+    /// Size of the buffer collecting writes into whole flash words.
+    const STAGE_LEN: usize = 64;
+
+    /// Payload storage in a region of NOR flash.
+    ///
+    /// Sectors are erased as the payload reaches them and bytes are
+    /// programmed in whole flash words, the last one padded with `0xFF`.
+    /// Programmed bytes can not be written again, so the transfer can not be
+    /// rewound.
+    /// The payload can not be read in place, so
+    /// [`Transfer::finished`](super::super::Transfer::finished) returns `None`
+    /// and completion is checked with
+    /// [`Transfer::is_finished`](super::super::Transfer::is_finished).
+    #[derive(Debug)]
+    pub struct FlashStorage<F> {
+        flash: F,
+        offset: u32,
+        len: u32,
+        erased: u32,
+        written: u32,
+        stage: [u8; STAGE_LEN],
+        stage_len: usize,
+    }
+
+    impl<F: NorFlash> FlashStorage<F> {
+        /// Create a new storage for `len` bytes at flash `offset`.
+        ///
+        /// Returns `None` if the region is not made of whole erase sectors or
+        /// the write size of `flash` does not divide 64 bytes.
+        pub fn new(flash: F, offset: u32, len: u32) -> Option<Self> {
+            let erase = F::ERASE_SIZE as u32;
+            if !offset.is_multiple_of(erase)
+                || !len.is_multiple_of(erase)
+                || !STAGE_LEN.is_multiple_of(F::WRITE_SIZE)
+            {
+                return None;
+            }
+
+            Some(Self {
+                flash,
+                offset,
+                len,
+                erased: 0,
+                written: 0,
+                stage: [0xFF; STAGE_LEN],
+                stage_len: 0,
+            })
+        }
+
+        /// Payload bytes received so far.
+        pub fn written(&self) -> u32 {
+            self.written
+        }
+
+        /// Release the flash.
+        pub fn into_inner(self) -> F {
+            self.flash
+        }
+
+        /// Program the staged bytes, padding the last word with `0xFF`.
+        fn program(&mut self) -> Result<(), Error> {
+            if self.stage_len == 0 {
+                return Ok(());
+            }
+
+            let start = self.written - self.stage_len as u32;
+            let len = self.stage_len.next_multiple_of(F::WRITE_SIZE);
+            let end = start + len as u32;
+            if end > self.erased {
+                let to = end.next_multiple_of(F::ERASE_SIZE as u32).min(self.len);
+                self.flash
+                    .erase(self.offset + self.erased, self.offset + to)
+                    .map_err(|_| Error::Storage)?;
+                self.erased = to;
+            }
+
+            self.stage[self.stage_len..len].fill(0xFF);
+            self.stage_len = 0;
+            self.flash
+                .write(self.offset + start, &self.stage[..len])
+                .map_err(|_| Error::Storage)
+        }
+    }
+
+    impl<F: NorFlash> TransferStorage for FlashStorage<F> {
+        fn capacity(&self) -> usize {
+            self.len as usize
+        }
+
+        fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), Error> {
+            if offset != self.written as usize {
+                return Err(Error::Storage);
+            }
+            if offset + data.len() > self.len as usize {
+                return Err(Error::StorageTooSmall);
+            }
+
+            for byte in data {
+                self.stage[self.stage_len] = *byte;
+                self.stage_len += 1;
+                self.written += 1;
+
+                if self.stage_len == STAGE_LEN {
+                    self.program()?;
+                }
+            }
+
+            Ok(())
+        }
+
+        fn read(&self, _len: usize) -> Option<&[u8]> {
+            None
+        }
+
+        fn finish(&mut self) -> Result<(), Error> {
+            self.program()
+        }
+
+        fn rewritable(&self) -> bool {
+            false
+        }
+
+        fn reset(&mut self) {
+            self.erased = 0;
+            self.written = 0;
+            self.stage_len = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::Pgn;
+    use crate::transport::{DataTransfer, RequestToSend, Transfer};
+
+    fn feed<S: TransferStorage>(transfer: &mut Transfer<'_, S>, payload: &[u8]) {
+        for (i, chunk) in payload.chunks(7).enumerate() {
+            let mut data = [0xFF; 7];
+            data[..chunk.len()].copy_from_slice(chunk);
+            transfer.next(DataTransfer::new(i as u8 + 1, data)).unwrap();
+        }
+    }
+
+    #[test]
+    fn heapless_and_slices() {
+        let payload: std::vec::Vec<u8> = (0..20).collect();
+        let rts = RequestToSend::try_new(20, None, Pgn::ProprietaryA).unwrap();
+
+        let mut buf = [0u8; 20];
+        let mut transfer = Transfer::with_storage(rts.clone(), &mut buf[..]);
+        feed(&mut transfer, &payload);
+        assert_eq!(transfer.finished(), Some(payload.as_slice()));
+
+        let mut transfer = Transfer::with_storage(rts.clone(), std::vec::Vec::new());
+        feed(&mut transfer, &payload);
+        assert_eq!(transfer.finished(), Some(payload.as_slice()));
+        assert!(transfer.into_storage().is_empty());
+
+        #[cfg(feature = "heapless")]
+        {
+            let mut transfer = Transfer::with_storage(rts.clone(), heapless::Vec::<u8, 32>::new());
+            feed(&mut transfer, &payload);
+            assert_eq!(transfer.finished(), Some(payload.as_slice()));
+        }
+
+        let mut buf = [0u8; 10];
+        assert!(Transfer::try_with_storage(rts.clone(), &mut buf[..]).is_err());
+
+        // a packet that does not fit leaves the storage untouched
+        let mut transfer = Transfer::with_storage(rts, &mut buf[..]);
+        transfer.next(DataTransfer::new(1, [1; 7])).unwrap();
+        let (error, _) = transfer.next(DataTransfer::new(2, [2; 7])).unwrap_err();
+        assert_eq!(error, Error::StorageTooSmall);
+        assert!(!transfer.is_finished());
+        assert_eq!(buf, [1, 1, 1, 1, 1, 1, 1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn callback() {
+        let payload: std::vec::Vec<u8> = (0..20).collect();
+        let rts = RequestToSend::try_new(20, Some(2), Pgn::ProprietaryA).unwrap();
+
+        let mut received = std::vec::Vec::new();
+        let storage = CallbackStorage::new(1785, |offset, data: &[u8]| {
+            received.push((offset, data.len()));
+            Ok(())
+        });
+        let mut transfer = Transfer::with_storage(rts.clone(), storage);
+        feed(&mut transfer, &payload);
+        assert!(transfer.is_finished());
+        assert_eq!(transfer.finished(), None);
+        assert_eq!(received, [(0, 7), (7, 7), (14, 6)]);
+
+        let storage = CallbackStorage::new(1785, |offset, _: &[u8]| match offset {
+            0 => Ok(()),
+            _ => Err(Error::StorageTooSmall),
+        });
+        let mut transfer = Transfer::with_storage(rts.clone(), storage);
+        transfer.next(DataTransfer::new(1, [0; 7])).unwrap();
+        let (_, abort) = transfer.next(DataTransfer::new(2, [0; 7])).unwrap_err();
+        assert_eq!(abort.reason(), crate::transport::AbortReason::Custom);
+
+        // chunks handed to the callback can not be rewound
+        let storage = CallbackStorage::new(1785, |_, _: &[u8]| Ok(()));
+        let mut transfer = Transfer::with_storage(rts, storage);
+        transfer.next(DataTransfer::new(1, [0; 7])).unwrap();
+        assert_eq!(transfer.rewind(1), None);
+        assert!(transfer.next(DataTransfer::new(2, [0; 7])).is_ok());
+    }
+
+    #[cfg(feature = "embedded-storage")]
+    #[test]
+    fn flash() {
+        use embedded_storage::nor_flash::{ErrorType, NorFlash, NorFlashErrorKind, ReadNorFlash};
+
+        struct Ram([u8; 256]);
+
+        impl ErrorType for Ram {
+            type Error = NorFlashErrorKind;
+        }
+
+        impl ReadNorFlash for Ram {
+            const READ_SIZE: usize = 1;
+
+            fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+                let offset = offset as usize;
+                bytes.copy_from_slice(&self.0[offset..offset + bytes.len()]);
+                Ok(())
+            }
+
+            fn capacity(&self) -> usize {
+                self.0.len()
+            }
+        }
+
+        impl NorFlash for Ram {
+            const WRITE_SIZE: usize = 4;
+            const ERASE_SIZE: usize = 32;
+
+            fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+                self.0[from as usize..to as usize].fill(0xFF);
+                Ok(())
+            }
+
+            fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+                assert_eq!(offset as usize % Self::WRITE_SIZE, 0);
+                assert_eq!(bytes.len() % Self::WRITE_SIZE, 0);
+                let offset = offset as usize;
+                for (cell, byte) in self.0[offset..offset + bytes.len()].iter_mut().zip(bytes) {
+                    // programming only clears bits
+                    *cell &= *byte;
+                }
+                Ok(())
+            }
+        }
+
+        assert!(FlashStorage::new(Ram([0; 256]), 0x10, 0x40).is_none());
+
+        let payload: std::vec::Vec<u8> = (0..100).collect();
+        let rts = RequestToSend::try_new(100, Some(4), Pgn::ProprietaryA).unwrap();
+        let storage = FlashStorage::new(Ram([0; 256]), 0x20, 0x80).unwrap();
+        let mut transfer = Transfer::with_storage(rts.clone(), storage);
+        transfer.next(DataTransfer::new(1, [0; 7])).unwrap();
+        // programmed bytes can not be rewound
+        assert_eq!(transfer.rewind(1), None);
+
+        let storage = FlashStorage::new(Ram([0; 256]), 0x20, 0x80).unwrap();
+        let mut transfer = Transfer::with_storage(rts, storage);
+        feed(&mut transfer, &payload);
+        assert!(transfer.is_finished());
+        assert_eq!(transfer.finished(), None);
+
+        let flash = transfer.into_storage().into_inner();
+        assert_eq!(flash.0[..0x20], [0; 0x20]);
+        assert_eq!(flash.0[0x20..0x84], payload[..]);
+        // the rest of the last sector is erased
+        assert_eq!(flash.0[0x84..0xA0], [0xFF; 0x1C]);
+        assert_eq!(flash.0[0xA0..], [0; 0x60]);
+    }
+}
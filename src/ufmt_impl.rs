@@ -0,0 +1,210 @@
+//! [`ufmt`] implementations for identifiers, signals and slots
+//!
+//! Scaled values are printed with up to three decimals without going through
+//! `core::fmt`, for targets where its code size is not affordable.
+
+use crate::id::{Id, Pgn};
+use crate::signal::*;
+use crate::slot::{SaeBM, Slot};
+use ufmt::{Formatter, uDebug, uDisplay, uWrite, uwrite};
+
+/// Write `value` as an 8 digit upper case hexadecimal number.
+fn write_hex<W: uWrite + ?Sized>(f: &mut Formatter<'_, W>, value: u32) -> Result<(), W::Error> {
+    const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+    let mut buf = [0u8; 8];
+    for (i, digit) in buf.iter_mut().enumerate() {
+        *digit = DIGITS[(value >> (28 - i * 4)) as usize & 0xF];
+    }
+
+    // only ASCII digits were written
+    f.write_str(core::str::from_utf8(&buf).unwrap_or_default())
+}
+
+/// Write `value` rounded to three decimals, without trailing zeros.
+fn write_decimal<W: uWrite + ?Sized>(f: &mut Formatter<'_, W>, value: f32) -> Result<(), W::Error> {
+    if value.is_nan() {
+        return f.write_str("NaN");
+    }
+
+    let milli = (value.abs() as f64 * 1000.0 + 0.5) as u64;
+    if value < 0.0 && milli != 0 {
+        f.write_str("-")?;
+    }
+    uwrite!(f, "{}", milli / 1000)?;
+
+    let mut frac = milli % 1000;
+    if frac != 0 {
+        let mut digits = 3;
+        while frac.is_multiple_of(10) {
+            frac /= 10;
+            digits -= 1;
+        }
+        f.write_str(".")?;
+        let width = if frac < 10 {
+            1
+        } else if frac < 100 {
+            2
+        } else {
+            3
+        };
+        for _ in width..digits {
+            f.write_str("0")?;
+        }
+        uwrite!(f, "{}", frac)?;
+    }
+
+    Ok(())
+}
+
+/// Write the state of a signal that carries no valid value.
+fn write_state<T: Signal, W: uWrite + ?Sized>(
+    f: &mut Formatter<'_, W>,
+    signal: &T,
+) -> Result<(), W::Error> {
+    if signal.is_indicator() {
+        f.write_str("indicator")
+    } else if signal.is_error() {
+        f.write_str("error")
+    } else {
+        f.write_str("n/a")
+    }
+}
+
+/// Display a slot as its scaled value and unit.
+#[doc(hidden)]
+pub fn fmt_slot<P, S, W>(slot: &S, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+where
+    P: Signal,
+    S: Slot<P>,
+    W: uWrite + ?Sized,
+{
+    match slot.as_f32() {
+        Some(value) => {
+            write_decimal(f, value)?;
+            if !S::UNIT.is_empty() {
+                f.write_str(" ")?;
+                f.write_str(S::UNIT)?;
+            }
+            Ok(())
+        }
+        None => write_state(f, &slot.parameter()),
+    }
+}
+
+impl uDebug for Id {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.write_str("Id(0x")?;
+        write_hex(f, self.as_raw())?;
+        f.write_str(")")
+    }
+}
+
+impl uDisplay for Id {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        write_hex(f, self.as_raw())
+    }
+}
+
+impl uDebug for Pgn {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        uwrite!(f, "Pgn({})", u32::from(*self))
+    }
+}
+
+impl uDisplay for Pgn {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        uwrite!(f, "{}", u32::from(*self))
+    }
+}
+
+macro_rules! signal_ufmt {
+    ($($type:ident),*) => {
+        $(
+            impl uDebug for $type {
+                fn fmt<W: uWrite + ?Sized>(
+                    &self,
+                    f: &mut Formatter<'_, W>,
+                ) -> Result<(), W::Error> {
+                    uwrite!(f, "{}({})", stringify!($type), self.to_raw())
+                }
+            }
+
+            impl uDisplay for $type {
+                fn fmt<W: uWrite + ?Sized>(
+                    &self,
+                    f: &mut Formatter<'_, W>,
+                ) -> Result<(), W::Error> {
+                    match self.value() {
+                        Some(value) => uwrite!(f, "{}", value),
+                        None => write_state(f, self),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+signal_ufmt!(
+    Param4, Param8, Param10, Param12, Param16, Param20, Param24, Param28, Param32
+);
+
+impl<T: Signal + Copy + uDebug> uDebug for SaeBM<T> {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        uwrite!(f, "SaeBM({:?})", self.parameter())
+    }
+}
+
+impl<T: Signal + Copy + uDisplay> uDisplay for SaeBM<T> {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        self.parameter().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slot::{SaeEV01, SaeTP02};
+    use std::string::String;
+
+    fn display<T: uDisplay>(value: &T) -> String {
+        let mut s = String::new();
+        ufmt::uwrite!(s, "{}", value).unwrap();
+        s
+    }
+
+    fn debug<T: uDebug>(value: &T) -> String {
+        let mut s = String::new();
+        ufmt::uwrite!(s, "{:?}", value).unwrap();
+        s
+    }
+
+    #[test]
+    fn identifiers() {
+        let id = Id::new(0x18FEF500);
+        assert_eq!(display(&id), "18FEF500");
+        assert_eq!(debug(&id), "Id(0x18FEF500)");
+        assert_eq!(display(&id.pgn()), "65269");
+        assert_eq!(debug(&id.pgn()), "Pgn(65269)");
+    }
+
+    #[test]
+    fn signals() {
+        assert_eq!(display(&Param8::from(42)), "42");
+        assert_eq!(display(&Param8::from(0xFE)), "error");
+        assert_eq!(display(&Param8::from(0xFF)), "n/a");
+        assert_eq!(debug(&Param16::from(0xFB00)), "Param16(64256)");
+    }
+
+    #[test]
+    fn slots() {
+        assert_eq!(display(&SaeEV01::new(Param16::from(264))), "13.2 V");
+        assert_eq!(display(&SaeTP02::new(Param16::from(8736))), "0 °C");
+        assert_eq!(display(&SaeTP02::new(Param16::from(8705))), "-0.969 °C");
+        assert_eq!(display(&SaeTP02::new(Param16::from(0xFFFF))), "n/a");
+        assert_eq!(
+            debug(&SaeEV01::new(Param16::from(264))),
+            "SaeEV01(Param16(264))"
+        );
+    }
+}
@@ -0,0 +1,450 @@
+//! Virtual Terminal core messages (ISO 11783-6)
+//!
+//! Messages between a working set and a Virtual Terminal share two PGNs,
+//! [`Pgn::VtToEcu`] and [`Pgn::EcuToVt`], multiplexed by the function code in
+//! the first byte. Object pools larger than 8 bytes are sent with the
+//! transport protocol.
+
+use crate::error::{DecodeError, at_least, exact};
+use crate::{Id, Pgn};
+use core::hash::{Hash, Hasher};
+
+/// Object identifier of a null object.
+pub const NULL_OBJECT: u16 = 0xFFFF;
+
+/// Function code multiplexing the VT PGNs.
+#[derive(Debug, Clone, Copy, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Function {
+    ObjectPoolTransfer,
+    EndOfObjectPool,
+    VtStatus,
+    WorkingSetMaintenance,
+    Other(u8),
+}
+
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        // Cast to underlying value to compare
+        u8::from(*self) == u8::from(*other)
+    }
+}
+
+impl Hash for Function {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        u8::from(*self).hash(state);
+    }
+}
+
+impl From<Function> for u8 {
+    fn from(value: Function) -> Self {
+        match value {
+            Function::ObjectPoolTransfer => 0x11,
+            Function::EndOfObjectPool => 0x12,
+            Function::VtStatus => 0xFE,
+            Function::WorkingSetMaintenance => 0xFF,
+            Function::Other(v) => v,
+        }
+    }
+}
+
+impl From<u8> for Function {
+    fn from(value: u8) -> Self {
+        match value {
+            0x11 => Self::ObjectPoolTransfer,
+            0x12 => Self::EndOfObjectPool,
+            0xFE => Self::VtStatus,
+            0xFF => Self::WorkingSetMaintenance,
+            v => Self::Other(v),
+        }
+    }
+}
+
+/// Function code of a VT to ECU or ECU to VT message.
+///
+/// Returns `None` for other PGNs or empty messages.
+pub fn function(id: Id, data: &[u8]) -> Option<Function> {
+    match id.pgn() {
+        Pgn::VtToEcu | Pgn::EcuToVt => data.first().map(|f| Function::from(*f)),
+        _ => None,
+    }
+}
+
+/// Check the function code of an 8 byte message.
+fn check(value: &[u8], function: Function) -> Result<[u8; 8], DecodeError> {
+    let raw = *exact::<8>(value)?;
+    if Function::from(raw[0]) != function {
+        return Err(DecodeError::Mux(raw[0]));
+    }
+    Ok(raw)
+}
+
+/// VT Status, sent by the VT once per second.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VtStatus {
+    raw: [u8; 8],
+}
+
+impl VtStatus {
+    /// Create a new VT status message.
+    pub fn new(
+        active_working_set: u8,
+        data_mask: u16,
+        soft_key_mask: u16,
+        busy: u8,
+        executing: u8,
+    ) -> Self {
+        let mut raw = [0xFF; 8];
+        raw[0] = Function::VtStatus.into();
+        raw[1] = active_working_set;
+        raw[2..4].copy_from_slice(&data_mask.to_le_bytes());
+        raw[4..6].copy_from_slice(&soft_key_mask.to_le_bytes());
+        raw[6] = busy;
+        raw[7] = executing;
+        Self { raw }
+    }
+
+    /// Source address of the active working set master.
+    pub fn active_working_set(&self) -> u8 {
+        self.raw[1]
+    }
+
+    /// Object identifier of the visible data or alarm mask of the active
+    /// working set.
+    pub fn data_mask(&self) -> u16 {
+        u16::from_le_bytes([self.raw[2], self.raw[3]])
+    }
+
+    /// Object identifier of the visible soft key mask of the active working
+    /// set.
+    pub fn soft_key_mask(&self) -> u16 {
+        u16::from_le_bytes([self.raw[4], self.raw[5]])
+    }
+
+    /// VT busy codes.
+    pub fn busy(&self) -> u8 {
+        self.raw[6]
+    }
+
+    /// Function code of the command being executed.
+    pub fn executing(&self) -> u8 {
+        self.raw[7]
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&VtStatus> for [u8; 8] {
+    fn from(value: &VtStatus) -> Self {
+        value.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for VtStatus {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let raw = check(value, Function::VtStatus)?;
+        Ok(Self { raw })
+    }
+}
+
+/// Working Set Maintenance, sent by a working set to the VT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorkingSetMaintenance {
+    raw: [u8; 8],
+}
+
+impl WorkingSetMaintenance {
+    /// Create a new working set maintenance message.
+    ///
+    /// `initiating` is set on the first message sent to a VT.
+    pub fn new(initiating: bool, version: u8) -> Self {
+        let mut raw = [0xFF; 8];
+        raw[0] = Function::WorkingSetMaintenance.into();
+        raw[1] = 0b11111110 | initiating as u8;
+        raw[2] = version;
+        Self { raw }
+    }
+
+    /// Check if the working set is initiating maintenance.
+    pub fn initiating(&self) -> bool {
+        self.raw[1] & 1 == 1
+    }
+
+    /// VT version of the working set.
+    pub fn version(&self) -> u8 {
+        self.raw[2]
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&WorkingSetMaintenance> for [u8; 8] {
+    fn from(value: &WorkingSetMaintenance) -> Self {
+        value.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for WorkingSetMaintenance {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let raw = check(value, Function::WorkingSetMaintenance)?;
+        Ok(Self { raw })
+    }
+}
+
+/// WSM - Working Set Master (ISO 11783-7)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorkingSetMaster {
+    raw: [u8; 8],
+}
+
+impl WorkingSetMaster {
+    /// Create a new working set master message.
+    pub fn new(members: u8) -> Self {
+        let mut raw = [0xFF; 8];
+        raw[0] = members;
+        Self { raw }
+    }
+
+    /// Number of members of the working set, including the master.
+    pub fn members(&self) -> u8 {
+        self.raw[0]
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&WorkingSetMaster> for [u8; 8] {
+    fn from(value: &WorkingSetMaster) -> Self {
+        value.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for WorkingSetMaster {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            raw: *exact(value)?,
+        })
+    }
+}
+
+/// Object Pool Transfer, sent by a working set to the VT.
+///
+/// Usually sent as a transport protocol payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ObjectPoolTransfer<'a> {
+    raw: &'a [u8],
+}
+
+impl<'a> ObjectPoolTransfer<'a> {
+    /// Encode `pool` into `buf`.
+    ///
+    /// Returns `None` if `buf` is too small.
+    pub fn encode(pool: &[u8], buf: &'a mut [u8]) -> Option<Self> {
+        let len = pool.len() + 1;
+
+        *buf.first_mut()? = Function::ObjectPoolTransfer.into();
+        buf.get_mut(1..len)?.copy_from_slice(pool);
+
+        Some(Self { raw: &buf[..len] })
+    }
+
+    /// Object pool data.
+    pub fn pool(&self) -> &'a [u8] {
+        &self.raw[1..]
+    }
+
+    /// Raw message bytes.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ObjectPoolTransfer<'a> {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        match at_least(value, 1)?[0] {
+            f if Function::from(f) == Function::ObjectPoolTransfer => Ok(Self { raw: value }),
+            f => Err(DecodeError::Mux(f)),
+        }
+    }
+}
+
+/// End of Object Pool, sent by a working set once the pool is transferred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EndOfObjectPool {
+    raw: [u8; 8],
+}
+
+impl EndOfObjectPool {
+    /// Create a new end of object pool message.
+    pub fn new() -> Self {
+        let mut raw = [0xFF; 8];
+        raw[0] = Function::EndOfObjectPool.into();
+        Self { raw }
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl Default for EndOfObjectPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<&EndOfObjectPool> for [u8; 8] {
+    fn from(value: &EndOfObjectPool) -> Self {
+        value.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for EndOfObjectPool {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let raw = check(value, Function::EndOfObjectPool)?;
+        Ok(Self { raw })
+    }
+}
+
+/// End of Object Pool response, sent by the VT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EndOfObjectPoolResponse {
+    raw: [u8; 8],
+}
+
+impl EndOfObjectPoolResponse {
+    /// Create a new end of object pool response.
+    ///
+    /// Pass [`NULL_OBJECT`] for the object identifiers if there is no error.
+    pub fn new(error_codes: u8, parent: u16, object: u16, pool_error_codes: u8) -> Self {
+        let mut raw = [0xFF; 8];
+        raw[0] = Function::EndOfObjectPool.into();
+        raw[1] = error_codes;
+        raw[2..4].copy_from_slice(&parent.to_le_bytes());
+        raw[4..6].copy_from_slice(&object.to_le_bytes());
+        raw[6] = pool_error_codes;
+        Self { raw }
+    }
+
+    /// Check if the object pool was accepted.
+    pub fn is_ok(&self) -> bool {
+        self.raw[1] == 0
+    }
+
+    /// Error codes, zero if there are no errors.
+    pub fn error_codes(&self) -> u8 {
+        self.raw[1]
+    }
+
+    /// Parent object identifier of the faulty object.
+    pub fn parent(&self) -> u16 {
+        u16::from_le_bytes([self.raw[2], self.raw[3]])
+    }
+
+    /// Object identifier of the faulty object.
+    pub fn object(&self) -> u16 {
+        u16::from_le_bytes([self.raw[4], self.raw[5]])
+    }
+
+    /// Object pool error codes.
+    pub fn pool_error_codes(&self) -> u8 {
+        self.raw[6]
+    }
+
+    /// Raw message bytes.
+    pub const fn as_bytes(&self) -> &[u8; 8] {
+        &self.raw
+    }
+}
+
+impl From<&EndOfObjectPoolResponse> for [u8; 8] {
+    fn from(value: &EndOfObjectPoolResponse) -> Self {
+        value.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for EndOfObjectPoolResponse {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let raw = check(value, Function::EndOfObjectPool)?;
+        Ok(Self { raw })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IdBuilder;
+
+    #[test]
+    fn vt_status() {
+        let raw = [0xFE, 0x26, 0xE8, 0x03, 0xFF, 0xFF, 0x00, 0xFF];
+        let status = VtStatus::try_from(&raw[..]).unwrap();
+        assert_eq!(status.active_working_set(), 0x26);
+        assert_eq!(status.data_mask(), 1000);
+        assert_eq!(status.soft_key_mask(), NULL_OBJECT);
+        assert_eq!(<[u8; 8]>::from(&status), raw);
+        assert_eq!(VtStatus::new(0x26, 1000, NULL_OBJECT, 0, 0xFF), status);
+
+        let id = IdBuilder::new()
+            .pgn(Pgn::VtToEcu)
+            .sa(0x26)
+            .da(0xFF)
+            .build()
+            .unwrap();
+        assert_eq!(function(id, &raw), Some(Function::VtStatus));
+        assert!(WorkingSetMaintenance::try_from(&raw[..]).is_err());
+    }
+
+    #[test]
+    fn object_pool() {
+        let wsm = WorkingSetMaintenance::new(true, 4);
+        assert_eq!(<[u8; 8]>::from(&wsm)[..3], [0xFF, 0xFF, 4]);
+
+        let mut buf = [0; 16];
+        let transfer = ObjectPoolTransfer::encode(&[1, 2, 3], &mut buf).unwrap();
+        assert_eq!(transfer.as_bytes(), &[0x11, 1, 2, 3]);
+        assert_eq!(
+            ObjectPoolTransfer::try_from(transfer.as_bytes())
+                .unwrap()
+                .pool(),
+            &[1, 2, 3]
+        );
+
+        let response = EndOfObjectPoolResponse::new(0, NULL_OBJECT, NULL_OBJECT, 0);
+        assert!(response.is_ok());
+    }
+}